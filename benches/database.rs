@@ -0,0 +1,54 @@
+//! Benchmarks for the most common operations on large databases, run with
+//! `cargo bench`.
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use std::hint::black_box;
+use tinydb::Database;
+
+/// Amount of items used for each benchmark.
+const ITEMS: u64 = 100_000;
+
+/// Compares adding items one at a time with the bulk fast path.
+fn adding(c: &mut Criterion) {
+    let mut group = c.benchmark_group("add");
+
+    group.bench_function("add_item", |b| {
+        b.iter_batched(
+            || Database::new("Bench", None, false),
+            |mut db| {
+                for item in 0..ITEMS {
+                    db.add_item(item).unwrap();
+                }
+
+                db
+            },
+            BatchSize::LargeInput,
+        )
+    });
+    group.bench_function("add_items_unchecked", |b| {
+        b.iter_batched(
+            || Database::new("Bench", None, false),
+            |mut db| {
+                db.add_items_unchecked(0..ITEMS).unwrap();
+                db
+            },
+            BatchSize::LargeInput,
+        )
+    });
+    group.finish();
+}
+
+/// Compares a full scan with a hashed lookup.
+fn querying(c: &mut Criterion) {
+    let mut db = Database::new("Bench", None, false);
+
+    db.add_items_unchecked(0..ITEMS).unwrap();
+
+    c.bench_function("query_item", |b| {
+        b.iter(|| db.query_item(|n| n, black_box(ITEMS / 2)).unwrap())
+    });
+    c.bench_function("contains", |b| b.iter(|| db.contains(black_box(&(ITEMS / 2)))));
+}
+
+criterion_group!(benches, adding, querying);
+criterion_main!(benches);
@@ -0,0 +1,287 @@
+//! Contains opt-in tracking of when each item inside of a [crate::Database]
+//! was last accessed, see [crate::Database::track_access].
+
+use crate::compat::{HashMap, Mutex, Vec};
+#[cfg(feature = "std")]
+use crate::meta::from_millis;
+use crate::meta::now_millis;
+use crate::{error, Database};
+use alloc::sync::Arc;
+use core::{fmt, hash};
+#[cfg(feature = "std")]
+use std::time::SystemTime;
+
+/// Clones an item, captured when access starts being tracked so that [Clone]
+/// isn't required everywhere.
+type Cloner<T> = Arc<dyn Fn(&T) -> T + Send + Sync>;
+
+/// When each item was last accessed, both as a tick of a counter so accesses
+/// within the same millisecond are still ordered and as a timestamp.
+struct Accesses<T> {
+    next: u64,
+    items: HashMap<T, (u64, u64)>,
+}
+
+/// Last accesses of each item once [Database::track_access] has been called.
+///
+/// This is behind a [Mutex] as it's updated from queries which only borrow
+/// the database. Accesses aren't saved when dumping and never take part in
+/// comparisons between databases.
+pub(crate) struct AccessTracker<T> {
+    cloner: Option<Cloner<T>>,
+    accesses: Mutex<Accesses<T>>,
+}
+
+impl<T: hash::Hash + Eq> AccessTracker<T> {
+    /// Checks if accesses are being tracked.
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.cloner.is_some()
+    }
+
+    /// Starts keeping track of an item which has just been added, which
+    /// counts as accessing it.
+    pub(crate) fn insert(&mut self, item: &T) {
+        if let Some(cloner) = &self.cloner {
+            let accesses = self.accesses.get_mut().unwrap();
+            let tick = accesses.next;
+
+            accesses.next += 1;
+            accesses.items.insert(cloner(item), (tick, now_millis()));
+        }
+    }
+
+    /// Forgets an item which has just been removed.
+    pub(crate) fn remove(&mut self, item: &T) {
+        self.accesses.get_mut().unwrap().items.remove(item);
+    }
+
+    /// Marks an item as accessed right now, returning `false` if it isn't
+    /// being tracked.
+    pub(crate) fn accessed(&self, item: &T) -> bool {
+        if self.cloner.is_none() {
+            return false;
+        }
+
+        let mut accesses = self.accesses.lock().unwrap();
+        let tick = accesses.next;
+
+        match accesses.items.get_mut(item) {
+            Some(entry) => *entry = (tick, now_millis()),
+            None => return false,
+        }
+
+        accesses.next += 1;
+        true
+    }
+
+    /// Copies of up to `n` items sorted by when they were last accessed.
+    pub(crate) fn by_recency(&self, n: usize, most_recent_first: bool) -> Vec<T> {
+        let cloner = match &self.cloner {
+            Some(cloner) => cloner,
+            None => return Vec::new(),
+        };
+        let accesses = self.accesses.lock().unwrap();
+        let mut items: Vec<(&T, u64)> = accesses
+            .items
+            .iter()
+            .map(|(item, (tick, _))| (item, *tick))
+            .collect();
+
+        if most_recent_first {
+            items.sort_by_key(|(_, tick)| core::cmp::Reverse(*tick));
+        } else {
+            items.sort_by_key(|(_, tick)| *tick);
+        }
+
+        items.into_iter().take(n).map(|(item, _)| cloner(item)).collect()
+    }
+}
+
+impl<T> Default for AccessTracker<T> {
+    fn default() -> Self {
+        AccessTracker {
+            cloner: None,
+            accesses: Mutex::new(Accesses {
+                next: 0,
+                items: HashMap::new(),
+            }),
+        }
+    }
+}
+
+impl<T: hash::Hash + Eq> Clone for AccessTracker<T> {
+    fn clone(&self) -> Self {
+        let cloner = match &self.cloner {
+            Some(cloner) => cloner,
+            None => return AccessTracker::default(),
+        };
+        let accesses = self.accesses.lock().unwrap();
+
+        AccessTracker {
+            cloner: Some(Arc::clone(cloner)),
+            accesses: Mutex::new(Accesses {
+                next: accesses.next,
+                items: accesses
+                    .items
+                    .iter()
+                    .map(|(item, access)| (cloner(item), *access))
+                    .collect(),
+            }),
+        }
+    }
+}
+
+impl<T> PartialEq for AccessTracker<T> {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl<T> Eq for AccessTracker<T> {}
+
+impl<T> fmt::Debug for AccessTracker<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AccessTracker")
+            .field("enabled", &self.cloner.is_some())
+            .finish()
+    }
+}
+
+impl<T: hash::Hash + Eq> Database<T> {
+    /// Starts tracking when each item was last accessed, which happens when
+    /// it's added, found by [Database::query_item], [Database::contains] or
+    /// [Database::get], or marked using [Database::touch]. Calling this again
+    /// does nothing.
+    ///
+    /// Items already inside of the database count as accessed right now.
+    /// Accesses are runtime-only, so they are not saved when dumping.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tinydb::Database;
+    ///
+    /// fn main() {
+    ///     let mut recent = Database::new("access_test", None, false);
+    ///
+    ///     recent.track_access();
+    ///
+    ///     for file in &["main.rs", "lib.rs", "error.rs"] {
+    ///         recent.add_item(file.to_string()).unwrap();
+    ///     }
+    ///
+    ///     recent.touch(&String::from("main.rs")).unwrap();
+    ///
+    ///     assert_eq!(recent.most_recently_used(1), vec!["main.rs"]);
+    ///     assert_eq!(recent.least_recently_used(1), vec!["lib.rs"]);
+    /// }
+    /// ```
+    pub fn track_access(&mut self)
+    where
+        T: Clone + 'static,
+    {
+        if self.access.is_enabled() {
+            return;
+        }
+
+        self.access.cloner = Some(Arc::new(T::clone));
+
+        for item in self.items.iter() {
+            self.access.insert(item);
+        }
+    }
+
+    /// Stops tracking accesses, unless they're needed for a capacity limit
+    /// using [crate::capacity::EvictionPolicy::EvictLeastRecentlyQueried].
+    pub fn untrack_access(&mut self) {
+        if !self.capacity.needs_access() {
+            self.access = AccessTracker::default();
+        }
+    }
+
+    /// Marks an item as accessed right now, without having to query for it.
+    ///
+    /// # Errors
+    ///
+    /// Will return [error::DatabaseError::ItemNotFound] if the item isn't
+    /// inside of the database or accesses aren't being tracked with
+    /// [Database::track_access].
+    pub fn touch(&self, item: &T) -> Result<(), error::DatabaseError> {
+        if self.is_expired(item) || !self.access.accessed(item) {
+            return Err(error::DatabaseError::ItemNotFound);
+        }
+
+        Ok(())
+    }
+
+    /// Gets when an item was last accessed, or [Option::None] if it isn't
+    /// inside of the database or accesses aren't being tracked.
+    #[cfg(feature = "std")]
+    pub fn last_accessed(&self, item: &T) -> Option<SystemTime> {
+        self.access
+            .accesses
+            .lock()
+            .unwrap()
+            .items
+            .get(item)
+            .map(|(_, millis)| from_millis(*millis))
+    }
+
+    /// Gets up to `n` items which have gone the longest without being
+    /// accessed, starting with the least recent. Nothing is returned if
+    /// accesses aren't being tracked with [Database::track_access].
+    pub fn least_recently_used(&self, n: usize) -> Vec<&T> {
+        self.recency_to_items(self.access.by_recency(n, false))
+    }
+
+    /// Gets up to `n` of the most recently accessed items, starting with the
+    /// most recent, which is ideal for "recent items" lists.
+    pub fn most_recently_used(&self, n: usize) -> Vec<&T> {
+        self.recency_to_items(self.access.by_recency(n, true))
+    }
+
+    /// Swaps copies of tracked items for the items themselves, skipping any
+    /// which have expired.
+    fn recency_to_items(&self, copies: Vec<T>) -> Vec<&T> {
+        copies
+            .iter()
+            .filter_map(|copy| self.items.get(copy))
+            .filter(|item| !self.is_expired(item))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests queries counting as accesses and removed items being forgotten
+    #[test]
+    fn access_tracking() -> Result<(), error::DatabaseError> {
+        let mut db = Database::new("Access test", None, false);
+
+        db.add_item(1)?;
+        db.add_item(2)?;
+
+        assert!(db.touch(&1).is_err());
+
+        db.track_access();
+        db.add_item(3)?;
+        db.query_item(|n| n, 1)?;
+
+        assert!(db.contains(&2));
+        assert_eq!(db.least_recently_used(3), vec![&3, &1, &2]);
+
+        db.remove_item(&3)?;
+        db.touch(&1)?;
+
+        assert_eq!(db.most_recently_used(5), vec![&1, &2]);
+        assert!(db.last_accessed(&1).is_some());
+        assert!(db.last_accessed(&3).is_none());
+
+        db.untrack_access();
+
+        assert!(db.least_recently_used(1).is_empty());
+        Ok(())
+    }
+}
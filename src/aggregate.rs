@@ -0,0 +1,281 @@
+//! Contains one-off aggregations over all items of a [Database], such as
+//! counting, summing or grouping by a field. Unlike [Database::track_stat] these walk every
+//! item when called, so they don't need registering up-front.
+//!
+//! Aggregates which are read often can instead be registered with
+//! [Database::register_aggregate], which folds every change into them as it
+//! happens.
+
+use crate::compat::{Box, HashMap, String, Vec};
+use crate::{events, Database};
+use core::any::Any;
+use core::iter::Sum;
+use core::{fmt, hash};
+
+/// Folds a change into the value of an aggregate, which is downcast back to
+/// its real type inside.
+type Fold<T> = Box<dyn FnMut(&mut dyn Any, events::ChangeEvent<&T>) + Send + Sync>;
+
+/// A single aggregate registered with [Database::register_aggregate].
+struct Aggregate<T> {
+    value: Box<dyn Any + Send + Sync>,
+    fold: Fold<T>,
+}
+
+/// All aggregates registered to a database, kept up-to-date on each
+/// mutation.
+///
+/// Like listeners, folds can't be copied so aggregates are never cloned
+/// alongside the database and never take part in comparisons.
+pub(crate) struct Aggregates<T> {
+    aggregates: HashMap<String, Aggregate<T>>,
+}
+
+impl<T> Aggregates<T> {
+    /// Feeds a change into every aggregate.
+    pub(crate) fn emit(&mut self, event: events::ChangeEvent<&T>) {
+        for aggregate in self.aggregates.values_mut() {
+            (aggregate.fold)(aggregate.value.as_mut(), event.clone());
+        }
+    }
+}
+
+impl<T> Default for Aggregates<T> {
+    fn default() -> Self {
+        Aggregates {
+            aggregates: HashMap::new(),
+        }
+    }
+}
+
+impl<T> Clone for Aggregates<T> {
+    fn clone(&self) -> Self {
+        Aggregates::default()
+    }
+}
+
+impl<T> fmt::Debug for Aggregates<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.aggregates.keys()).finish()
+    }
+}
+
+impl<T: hash::Hash + Eq> Database<T> {
+    /// Counts the items matching `pred`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tinydb::Database;
+    ///
+    /// fn main() {
+    ///     let mut db = Database::new("aggregate_test", None, false);
+    ///
+    ///     for age in &[25, 34, 3000000] {
+    ///         db.add_item(*age).unwrap();
+    ///     }
+    ///
+    ///     assert_eq!(db.count_where(|age| *age < 100), 2);
+    ///     assert_eq!(db.min_by_key(|age| *age), Some(&25));
+    ///     assert_eq!(db.max_by_key(|age| *age), Some(&3000000));
+    ///     assert_eq!(db.sum_by(|age| *age as u64), 3000059);
+    /// }
+    /// ```
+    pub fn count_where(&self, mut pred: impl FnMut(&T) -> bool) -> usize {
+        self.live_items().filter(|item| pred(item)).count()
+    }
+
+    /// Gets the item with the smallest key given by `extractor`, or
+    /// [Option::None] if the database is empty. If several items share the
+    /// smallest key, any of them may be returned.
+    pub fn min_by_key<K: Ord>(&self, mut extractor: impl FnMut(&T) -> K) -> Option<&T> {
+        self.live_items().min_by_key(|item| extractor(item))
+    }
+
+    /// Gets the item with the largest key given by `extractor`, or
+    /// [Option::None] if the database is empty. If several items share the
+    /// largest key, any of them may be returned.
+    pub fn max_by_key<K: Ord>(&self, mut extractor: impl FnMut(&T) -> K) -> Option<&T> {
+        self.live_items().max_by_key(|item| extractor(item))
+    }
+
+    /// Sums the values given by `extractor` for every item, which is `0` for
+    /// an empty database.
+    pub fn sum_by<S: Sum<S>>(&self, extractor: impl FnMut(&T) -> S) -> S {
+        self.live_items().map(extractor).sum()
+    }
+
+    /// Buckets items by the key given by `extractor`, where each bucket holds
+    /// every item with that key in no particular order.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tinydb::Database;
+    ///
+    /// fn main() {
+    ///     let mut db = Database::new("group_test", None, false);
+    ///
+    ///     for (buffer, language) in &[("main.rs", "rust"), ("lib.rs", "rust"), ("README.md", "markdown")] {
+    ///         db.add_item((buffer.to_string(), language.to_string())).unwrap();
+    ///     }
+    ///
+    ///     let by_language = db.group_by(|(_, language)| language.clone());
+    ///
+    ///     assert_eq!(by_language["rust"].len(), 2);
+    ///     assert_eq!(by_language["markdown"][0].0, "README.md");
+    /// }
+    /// ```
+    pub fn group_by<K: hash::Hash + Eq>(
+        &self,
+        mut extractor: impl FnMut(&T) -> K,
+    ) -> HashMap<K, Vec<&T>> {
+        let mut groups: HashMap<K, Vec<&T>> = HashMap::new();
+
+        for item in self.live_items() {
+            groups.entry(extractor(item)).or_default().push(item);
+        }
+
+        groups
+    }
+
+    /// Registers an aggregate under `name` which starts at `init` and has
+    /// every change folded into it as it happens, replacing any aggregate
+    /// already registered under that name. Items already inside of the
+    /// database are folded in as [events::ChangeEvent::Added] straight away.
+    ///
+    /// Aggregates aren't saved when dumping or carried over when cloning, so
+    /// they have to be registered again after loading. Expired items only
+    /// count as removed once they're purged.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tinydb::events::ChangeEvent;
+    /// use tinydb::Database;
+    ///
+    /// fn main() {
+    ///     let mut db = Database::new("register_aggregate_test", None, false);
+    ///
+    ///     db.add_item((String::from("Lister"), 25)).unwrap();
+    ///     db.register_aggregate("total_age", 0, |total: &mut u32, event| match event {
+    ///         ChangeEvent::Added((_, age)) => *total += age,
+    ///         ChangeEvent::Removed((_, age)) => *total -= age,
+    ///     });
+    ///     db.add_item((String::from("Rimmer"), 34)).unwrap();
+    ///
+    ///     assert_eq!(db.get_aggregate::<u32>("total_age"), Some(&59));
+    /// }
+    /// ```
+    pub fn register_aggregate<A: Send + Sync + 'static>(
+        &mut self,
+        name: impl Into<String>,
+        mut init: A,
+        mut fold: impl FnMut(&mut A, events::ChangeEvent<&T>) + Send + Sync + 'static,
+    ) {
+        for item in self.items.iter() {
+            for _ in 0..self.dupes.counts.get(item).copied().unwrap_or(1) {
+                fold(&mut init, events::ChangeEvent::Added(item));
+            }
+        }
+
+        let aggregate = Aggregate {
+            value: Box::new(init),
+            fold: Box::new(move |value, event| {
+                if let Some(value) = value.downcast_mut() {
+                    fold(value, event);
+                }
+            }),
+        };
+
+        self.aggregates.aggregates.insert(name.into(), aggregate);
+    }
+
+    /// Gets the current value of an aggregate registered with
+    /// [Database::register_aggregate], or [Option::None] if there isn't one
+    /// with this name and type.
+    pub fn get_aggregate<A: 'static>(&self, name: &str) -> Option<&A> {
+        self.aggregates.aggregates.get(name)?.value.downcast_ref()
+    }
+
+    /// Unregisters an aggregate, returning if it was registered.
+    pub fn unregister_aggregate(&mut self, name: &str) -> bool {
+        self.aggregates.aggregates.remove(name).is_some()
+    }
+
+    /// Iterates over all items which haven't expired.
+    fn live_items(&self) -> impl Iterator<Item = &T> {
+        self.items.iter().filter(move |item| !self.is_expired(item))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    /// Tests aggregates ignoring expired items
+    #[test]
+    fn aggregate_skip_expired() {
+        let mut db = Database::new("Aggregate test", None, false);
+
+        db.add_item(1).unwrap();
+        db.add_item(2).unwrap();
+        db.add_item_with_ttl(100, Duration::from_millis(0)).unwrap();
+        std::thread::sleep(Duration::from_millis(5));
+
+        assert_eq!(db.count_where(|_| true), 2);
+        assert_eq!(db.max_by_key(|item| *item), Some(&2));
+        assert_eq!(db.sum_by(|item| *item), 3);
+        assert_eq!(db.min_by_key(|item| -item), Some(&2));
+    }
+
+    /// Tests registered aggregates following removals and updates
+    #[test]
+    fn aggregate_registered() {
+        let mut db = Database::new("Aggregate test", None, false);
+
+        db.add_item(1).unwrap();
+        db.register_aggregate("sum", 0, |sum: &mut i32, event| match event {
+            events::ChangeEvent::Added(item) => *sum += item,
+            events::ChangeEvent::Removed(item) => *sum -= item,
+        });
+        db.add_item(5).unwrap();
+        db.update_item(&1, 10).unwrap();
+        db.remove_item(&5).unwrap();
+
+        assert_eq!(db.get_aggregate::<i32>("sum"), Some(&10));
+        assert_eq!(db.get_aggregate::<u64>("sum"), None);
+        assert_eq!(db.clone().get_aggregate::<i32>("sum"), None);
+        assert!(db.unregister_aggregate("sum"));
+        assert_eq!(db.get_aggregate::<i32>("sum"), None);
+    }
+
+    /// Tests grouping into buckets which cover every item
+    #[test]
+    fn aggregate_group_by() {
+        let mut db = Database::new("Aggregate test", None, false);
+
+        for i in 0..10 {
+            db.add_item(i).unwrap();
+        }
+
+        let groups = db.group_by(|item| item % 3);
+
+        assert_eq!(groups.len(), 3);
+        assert_eq!(groups[&0].len(), 4);
+        assert_eq!(groups.values().map(Vec::len).sum::<usize>(), 10);
+        assert!(groups[&2].iter().all(|item| *item % 3 == 2));
+    }
+
+    /// Tests aggregates over an empty database
+    #[test]
+    fn aggregate_empty() {
+        let db: Database<u8> = Database::new("Aggregate test", None, false);
+
+        assert_eq!(db.count_where(|_| true), 0);
+        assert_eq!(db.min_by_key(|item| *item), None);
+        assert_eq!(db.sum_by(|item| *item as u32), 0);
+        assert!(db.group_by(|item| *item).is_empty());
+    }
+}
@@ -0,0 +1,179 @@
+//! Contains [Autosave], which periodically dumps a shared database from a
+//! background thread, see [crate::Database::start_autosave].
+
+use crate::{error, Database};
+use serde::Serialize;
+use std::hash;
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// Dumps a shared database from a background thread whenever it has unsaved
+/// changes, with one last dump once stopped so nothing is lost on exit. The
+/// thread is stopped once this is dropped.
+///
+/// Failed dumps are tried again on the next interval. Use [Autosave::stop]
+/// rather than dropping this to find out if the last dump failed, or if the
+/// database couldn't be dumped at all because its mutex was poisoned.
+#[derive(Debug)]
+pub struct Autosave {
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<Result<(), error::DatabaseError>>>,
+}
+
+impl Autosave {
+    /// Stops autosaving, waiting for the background thread to make its last
+    /// dump and returning the result of it.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error from the last dump, or
+    /// [error::DatabaseError::IOError] if the database's mutex was poisoned or
+    /// the background thread panicked.
+    pub fn stop(mut self) -> Result<(), error::DatabaseError> {
+        self.finish()
+    }
+
+    /// Tells the background thread to stop and waits for it.
+    fn finish(&mut self) -> Result<(), error::DatabaseError> {
+        self.stop.store(true, Ordering::SeqCst);
+
+        match self.handle.take() {
+            Some(handle) => {
+                handle.thread().unpark();
+                handle
+                    .join()
+                    .unwrap_or_else(|_| Err(io::Error::other("autosave thread panicked").into()))
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for Autosave {
+    fn drop(&mut self) {
+        self.finish().ok();
+    }
+}
+
+/// Dumps a database if it has changed since it was last dumped.
+fn save_if_dirty<T: hash::Hash + Eq + Serialize>(
+    shared: &Mutex<Database<T>>,
+) -> Result<(), error::DatabaseError> {
+    let db = shared
+        .lock()
+        .map_err(|_| io::Error::other("database mutex was poisoned"))?;
+
+    match db.is_dirty() {
+        true => db.dump_db().map(|_| ()),
+        false => Ok(()),
+    }
+}
+
+impl<T: hash::Hash + Eq + Serialize> Database<T> {
+    /// Starts dumping a shared database every `interval` from a background
+    /// thread, skipping dumps whilst nothing has changed. See [Autosave] for
+    /// more infomation.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tinydb::Database;
+    /// use std::sync::{Arc, Mutex};
+    /// use std::time::Duration;
+    ///
+    /// fn main() {
+    ///     let db: Database<String> = Database::new("autosave_test", None, false);
+    ///     let shared = Arc::new(Mutex::new(db));
+    ///     let autosave = Database::start_autosave(&shared, Duration::from_secs(30));
+    ///
+    ///     shared.lock().unwrap().add_item(String::from("Kochanski")).unwrap();
+    ///     autosave.stop().unwrap();
+    ///
+    ///     assert!(!shared.lock().unwrap().is_dirty());
+    ///     # std::fs::remove_file("autosave_test.tinydb").unwrap();
+    /// }
+    /// ```
+    pub fn start_autosave(shared: &Arc<Mutex<Self>>, interval: Duration) -> Autosave
+    where
+        T: Send + Sync + 'static,
+    {
+        let shared = Arc::clone(shared);
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = Arc::clone(&stop);
+        let handle = thread::spawn(move || {
+            while !thread_stop.load(Ordering::SeqCst) {
+                thread::park_timeout(interval);
+
+                if !thread_stop.load(Ordering::SeqCst) {
+                    save_if_dirty(&shared).ok();
+                }
+            }
+
+            save_if_dirty(&shared)
+        });
+
+        Autosave {
+            stop,
+            handle: Some(handle),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// Tests changes being dumped in the background without waiting to stop
+    #[test]
+    fn autosave_interval() -> Result<(), error::DatabaseError> {
+        let path = PathBuf::from("autosave_interval.tinydb");
+        let db = Database::new("Autosave test", path.clone(), false);
+        let shared = Arc::new(Mutex::new(db));
+        let autosave = Database::start_autosave(&shared, Duration::from_millis(10));
+
+        shared.lock().unwrap().add_item(1u8)?;
+
+        for _ in 0..500 {
+            if !shared.lock().unwrap().is_dirty() {
+                break;
+            }
+
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        assert!(!shared.lock().unwrap().is_dirty());
+        assert!(path.exists());
+
+        drop(autosave);
+        std::fs::remove_file(path)?;
+        Ok(())
+    }
+
+    /// Tests a poisoned database being reported rather than silently never
+    /// dumped
+    #[test]
+    fn autosave_poisoned() {
+        let db = Database::new("Autosave poison test", None, false);
+        let shared = Arc::new(Mutex::new(db));
+        let autosave = Database::start_autosave(&shared, Duration::from_secs(60));
+        let poisoner = Arc::clone(&shared);
+
+        thread::spawn(move || {
+            let mut db = poisoner.lock().unwrap();
+
+            db.add_item(1u8).unwrap();
+            panic!("poisoning the database");
+        })
+        .join()
+        .ok();
+
+        assert!(matches!(
+            autosave.stop(),
+            Err(error::DatabaseError::IOError(_))
+        ));
+    }
+}
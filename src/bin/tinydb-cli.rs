@@ -0,0 +1,290 @@
+//! Command line tool for looking inside of, checking and converting `.tinydb`
+//! files, built when the `cli` feature is enabled.
+//!
+//! Items are encoded with bincode which doesn't describe itself, so commands
+//! which read items need to be told what type they are with `--type`.
+
+use serde::{de::DeserializeOwned, Serialize};
+use std::env;
+use std::hash::Hash;
+use std::io::{self, prelude::*};
+use std::process;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tinydb::doc::Doc;
+use tinydb::expr::Expr;
+use tinydb::Database;
+
+/// Help shown when the arguments given don't make sense.
+const USAGE: &str = "usage: tinydb-cli <command> [args] [--type <type>]
+
+commands:
+    inspect <dump>                 show the header of a dump
+    verify <dump>                  check a dump and every item in it loads
+    export <dump> <out.ndjson>     write items out as newline-delimited json
+    import <in.ndjson> <dump>      add items from newline-delimited json
+    diff <dump> <other>            show items which are only in one dump
+    shell <dump>                   query and edit a dump interactively
+
+shell commands:
+    list [where <expr>]            show items, optionally only matching ones
+    count [where <expr>]           count items, optionally only matching ones
+    delete where <expr>            remove matching items
+    save                           dump changes, also done on quit
+    quit
+
+expressions look like `age > 30 and name == \"Lister\"`, with `.` being the
+item itself such as `. >= 10`
+
+types (default string):
+    doc, string, bytes, bool, u32, u64, i32, i64
+
+doc is for databases of tinydb::doc::Doc, which can hold json of any shape";
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    if let Err(message) = run(args) {
+        eprintln!("error: {}", message);
+        process::exit(1);
+    }
+}
+
+/// Splits the `--type` option out of the arguments and runs the command.
+fn run(mut args: Vec<String>) -> Result<(), String> {
+    let item_type = match args.iter().position(|arg| arg == "--type") {
+        Some(pos) if pos + 1 < args.len() => {
+            let item_type = args.remove(pos + 1);
+
+            args.remove(pos);
+            item_type
+        }
+        Some(_) => return Err(String::from("--type needs a value")),
+        None => String::from("string"),
+    };
+
+    match args.split_first() {
+        Some((command, rest)) if command == "inspect" => inspect(rest),
+        Some((command, rest)) => match item_type.as_str() {
+            "doc" => typed::<Doc>(command, rest),
+            "string" => typed::<String>(command, rest),
+            "bytes" => typed::<Vec<u8>>(command, rest),
+            "bool" => typed::<bool>(command, rest),
+            "u32" => typed::<u32>(command, rest),
+            "u64" => typed::<u64>(command, rest),
+            "i32" => typed::<i32>(command, rest),
+            "i64" => typed::<i64>(command, rest),
+            other => Err(format!("unknown type `{}`\n\n{}", other, USAGE)),
+        },
+        None => Err(String::from(USAGE)),
+    }
+}
+
+/// Gets exactly `N` positional arguments for a command.
+fn positional<const N: usize>(args: &[String]) -> Result<[&str; N], String> {
+    if args.len() != N {
+        return Err(String::from(USAGE));
+    }
+
+    let mut found = [""; N];
+
+    for (slot, arg) in found.iter_mut().zip(args) {
+        *slot = arg;
+    }
+
+    Ok(found)
+}
+
+/// Prints what can be read from a dump without knowing its item type.
+fn inspect(args: &[String]) -> Result<(), String> {
+    let [path] = positional(args)?;
+    let info = tinydb::inspect(path).map_err(|e| e.to_string())?;
+
+    println!("label:        {}", info.label);
+    println!("save path:    {:?}", info.save_path);
+    println!("strict dupes: {}", info.strict_dupes);
+    println!("items:        {}", info.items);
+
+    match info.format_version {
+        Some(version) => println!("format:       {}", version),
+        None => println!("format:       headerless"),
+    }
+
+    println!("archived:     {}", info.archived);
+    println!("signed:       {}", info.signed);
+    println!("size:         {} bytes", info.size);
+    println!("created:      {}", unix_time(info.created_at));
+    println!("last dumped:  {}", unix_time(info.last_dumped));
+
+    match info.schema {
+        Some(schema) => println!("schema:       {:016x}", schema),
+        None => println!("schema:       unknown"),
+    }
+
+    Ok(())
+}
+
+/// Formats a timestamp from a dump as seconds since the unix epoch.
+fn unix_time(time: Option<SystemTime>) -> String {
+    match time.and_then(|time| time.duration_since(UNIX_EPOCH).ok()) {
+        Some(since) => format!("{} (unix time)", since.as_secs()),
+        None => String::from("unknown"),
+    }
+}
+
+/// Runs a command which needs to know the type of items.
+fn typed<T>(command: &str, args: &[String]) -> Result<(), String>
+where
+    T: Hash + Eq + Serialize + DeserializeOwned,
+{
+    match command {
+        "verify" => {
+            let [path] = positional(args)?;
+            let db: Database<T> = load(path)?;
+
+            println!("ok, {} items", db.items.len());
+        }
+        "export" => {
+            let [path, out] = positional(args)?;
+            let db: Database<T> = load(path)?;
+
+            db.export_ndjson(out).map_err(|e| e.to_string())?;
+            println!("exported {} items to {}", db.items.len(), out);
+        }
+        "import" => {
+            let [input, path] = positional(args)?;
+            let mut db: Database<T> =
+                Database::auto_from(path, false).map_err(|e| e.to_string())?;
+            let report = db.import_ndjson(input).map_err(|e| e.to_string())?;
+
+            for error in &report.errors {
+                eprintln!("line {}: {}", error.line, error.message);
+            }
+
+            db.dump_db().map_err(|e| e.to_string())?;
+            println!("imported {} items into {}", report.added, path);
+        }
+        "diff" => {
+            let [path, other] = positional(args)?;
+            let (db, other): (Database<T>, Database<T>) = (load(path)?, load(other)?);
+            let diff = db.diff(&other);
+
+            for item in &diff.only_in_self {
+                println!("- {}", show(item));
+            }
+
+            for item in &diff.only_in_other {
+                println!("+ {}", show(item));
+            }
+
+            println!("{} items in common", diff.common.len());
+        }
+        "shell" => {
+            let [path] = positional(args)?;
+            let mut db: Database<T> = Database::from(path).map_err(|e| e.to_string())?;
+
+            shell(&mut db)?;
+        }
+        other => return Err(format!("unknown command `{}`\n\n{}", other, USAGE)),
+    }
+
+    Ok(())
+}
+
+/// Reads shell commands from stdin until `quit` or the end of input, dumping
+/// any changes before returning.
+fn shell<T>(db: &mut Database<T>) -> Result<(), String>
+where
+    T: Hash + Eq + Serialize,
+{
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+
+    loop {
+        print!("{}> ", db.label);
+        io::stdout().flush().map_err(|e| e.to_string())?;
+
+        let line = match lines.next() {
+            Some(line) => line.map_err(|e| e.to_string())?,
+            None => break,
+        };
+        let (command, rest) = match line.trim().split_once(' ') {
+            Some((command, rest)) => (command, rest.trim()),
+            None => (line.trim(), ""),
+        };
+
+        match shell_command(db, command, rest) {
+            Ok(true) => (),
+            Ok(false) => break,
+            Err(message) => eprintln!("error: {}", message),
+        }
+    }
+
+    if db.is_dirty() {
+        db.dump_db().map_err(|e| e.to_string())?;
+    }
+
+    Ok(())
+}
+
+/// Runs a single shell command, returning `false` once the shell should quit.
+fn shell_command<T>(db: &mut Database<T>, command: &str, rest: &str) -> Result<bool, String>
+where
+    T: Hash + Eq + Serialize,
+{
+    let expr = match rest.strip_prefix("where ") {
+        Some(expr) => Some(Expr::parse(expr).map_err(|e| e.to_string())?),
+        None if rest.is_empty() => None,
+        None => return Err(format!("expected `where`, found `{}`", rest)),
+    };
+
+    match (command, expr) {
+        ("", _) => (),
+        ("list", Some(expr)) => {
+            for item in db.query_expr(&expr) {
+                println!("{}", show(item));
+            }
+        }
+        ("list", None) => {
+            for item in db.ordered() {
+                println!("{}", show(item));
+            }
+        }
+        ("count", Some(expr)) => println!("{}", db.query_expr(&expr).len()),
+        ("count", None) => println!("{}", db.items.len()),
+        ("delete", Some(expr)) => {
+            let removed = db.remove_where_expr(&expr).map_err(|e| e.to_string())?;
+
+            println!("deleted {} items", removed);
+        }
+        ("delete", None) => return Err(String::from("delete needs a `where` expression")),
+        ("save", _) => {
+            let report = db.dump_db().map_err(|e| e.to_string())?;
+
+            println!(
+                "saved {} items ({} bytes) to {}",
+                report.items,
+                report.bytes,
+                report.path.display()
+            );
+        }
+        ("quit", _) | ("exit", _) => return Ok(false),
+        (other, _) => return Err(format!("unknown command `{}`", other)),
+    }
+
+    Ok(true)
+}
+
+/// Writes an item as JSON for showing to the user.
+fn show<T: Serialize>(item: &T) -> String {
+    Doc::from_item(item)
+        .map(|doc| doc.to_json())
+        .unwrap_or_else(|e| e.to_string())
+}
+
+/// Loads a dump without locking it, so dumps in use can still be looked at.
+fn load<T>(path: &str) -> Result<Database<T>, String>
+where
+    T: Hash + Eq + DeserializeOwned,
+{
+    Database::from_unlocked(path).map_err(|e| e.to_string())
+}
@@ -0,0 +1,264 @@
+//! Contains a store of raw bytes kept next to a [crate::Database] rather than
+//! inside of its items, see [crate::Database::put_blob].
+//!
+//! Blobs are saved into a directory named after the dump with `.blobs` on
+//! the end, such as `photos.tinydb.blobs`, with a file per blob. Only blobs
+//! which changed are written when dumping so large attachments don't make
+//! every dump slower.
+
+use crate::{error, Database};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::hash;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Blobs which have been put or removed since the last dump, where
+/// [Option::None] means the blob was removed.
+///
+/// This is behind a [Mutex] as pending blobs are written from
+/// [Database::dump_db] which only borrows the database. Pending blobs never
+/// take part in comparisons between databases.
+#[derive(Default)]
+pub(crate) struct Blobs {
+    pending: Mutex<HashMap<String, Option<Vec<u8>>>>,
+}
+
+impl Blobs {
+    /// Writes every pending blob into `dir`, forgetting them afterwards.
+    pub(crate) fn flush(&self, dir: &Path) -> Result<(), error::DatabaseError> {
+        let mut pending = self.pending.lock().unwrap();
+
+        if pending.is_empty() {
+            return Ok(());
+        }
+
+        fs::create_dir_all(dir)?;
+
+        for (key, bytes) in pending.iter() {
+            let path = blob_path(dir, key);
+
+            match bytes {
+                Some(bytes) => fs::write(path, bytes)?,
+                None => match fs::remove_file(path) {
+                    Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e.into()),
+                    _ => (),
+                },
+            }
+        }
+
+        pending.clear();
+        Ok(())
+    }
+}
+
+impl Clone for Blobs {
+    fn clone(&self) -> Self {
+        Blobs {
+            pending: Mutex::new(self.pending.lock().unwrap().clone()),
+        }
+    }
+}
+
+impl PartialEq for Blobs {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for Blobs {}
+
+impl fmt::Debug for Blobs {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Blobs")
+            .field("pending", &self.pending.lock().unwrap().len())
+            .finish()
+    }
+}
+
+/// Gets the path of a blob, hex-encoding its key so any key is a valid file
+/// name.
+fn blob_path(dir: &Path, key: &str) -> PathBuf {
+    let name: String = key.bytes().map(|byte| format!("{:02x}", byte)).collect();
+
+    dir.join(name)
+}
+
+/// Decodes a blob's key from its file name, returning [Option::None] for
+/// files which weren't made by [blob_path].
+fn blob_key(name: &str) -> Option<String> {
+    if !name.len().is_multiple_of(2) {
+        return None;
+    }
+
+    let bytes = (0..name.len())
+        .step_by(2)
+        .map(|ind| u8::from_str_radix(name.get(ind..ind + 2)?, 16).ok())
+        .collect::<Option<Vec<u8>>>()?;
+
+    String::from_utf8(bytes).ok()
+}
+
+impl<T: hash::Hash + Eq> Database<T> {
+    /// Stores raw bytes under `key`, replacing any blob already there. Items
+    /// can then refer to the blob by its key rather than holding the bytes
+    /// themselves.
+    ///
+    /// Blobs are written next to the dump the next time this database is
+    /// dumped, and aren't included in [Database::to_bytes] or
+    /// [Database::dump_to_writer]. Keys become file names once hex-encoded,
+    /// so they should be kept under 128 bytes long.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tinydb::Database;
+    ///
+    /// fn main() {
+    ///     let mut db = Database::new("blob_test", None, false);
+    ///
+    ///     db.put_blob("avatar.png", vec![137, 80, 78, 71]).unwrap();
+    ///     db.add_item(String::from("avatar.png")).unwrap();
+    ///     db.dump_db().unwrap();
+    ///
+    ///     let loaded: Database<String> = Database::from("blob_test.tinydb").unwrap();
+    ///
+    ///     assert_eq!(loaded.get_blob("avatar.png").unwrap(), Some(vec![137, 80, 78, 71]));
+    ///     # std::fs::remove_file("blob_test.tinydb").unwrap();
+    ///     # std::fs::remove_dir_all("blob_test.tinydb.blobs").unwrap();
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Will return [error::DatabaseError::Archived] or
+    /// [error::DatabaseError::ReadOnly] if this database can't be mutated.
+    pub fn put_blob(
+        &mut self,
+        key: impl Into<String>,
+        bytes: impl Into<Vec<u8>>,
+    ) -> Result<(), error::DatabaseError> {
+        self.check_mutable()?;
+        self.meta.modified();
+        self.blobs
+            .pending
+            .get_mut()
+            .unwrap()
+            .insert(key.into(), Some(bytes.into()));
+
+        Ok(())
+    }
+
+    /// Gets a copy of the blob stored under `key`, reading it from disk if
+    /// it has already been dumped.
+    pub fn get_blob(&self, key: &str) -> Result<Option<Vec<u8>>, error::DatabaseError> {
+        if let Some(pending) = self.blobs.pending.lock().unwrap().get(key) {
+            return Ok(pending.clone());
+        }
+
+        match fs::read(blob_path(&self.blobs_dir(), key)) {
+            Ok(bytes) => Ok(Some(bytes)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Removes the blob stored under `key`, returning `false` if there wasn't
+    /// one. The blob is deleted from disk the next time this database is
+    /// dumped.
+    ///
+    /// # Errors
+    ///
+    /// Will return [error::DatabaseError::Archived] or
+    /// [error::DatabaseError::ReadOnly] if this database can't be mutated.
+    pub fn remove_blob(&mut self, key: &str) -> Result<bool, error::DatabaseError> {
+        self.check_mutable()?;
+
+        let existed = self.get_blob(key)?.is_some();
+
+        if existed {
+            self.meta.modified();
+            self.blobs
+                .pending
+                .get_mut()
+                .unwrap()
+                .insert(key.to_string(), None);
+        }
+
+        Ok(existed)
+    }
+
+    /// Gets the keys of every blob, in no particular order.
+    pub fn blob_keys(&self) -> Result<Vec<String>, error::DatabaseError> {
+        let pending = self.blobs.pending.lock().unwrap();
+        let mut keys: Vec<String> = pending
+            .iter()
+            .filter(|(_, bytes)| bytes.is_some())
+            .map(|(key, _)| key.clone())
+            .collect();
+
+        let entries = match fs::read_dir(self.blobs_dir()) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(keys),
+            Err(e) => return Err(e.into()),
+        };
+
+        for entry in entries {
+            let key = entry?.file_name().to_str().and_then(blob_key);
+
+            if let Some(key) = key {
+                if !pending.contains_key(&key) {
+                    keys.push(key);
+                }
+            }
+        }
+
+        Ok(keys)
+    }
+
+    /// Gets the directory blobs are saved into.
+    pub(crate) fn blobs_dir(&self) -> PathBuf {
+        let mut dir = self.smart_path_get().into_os_string();
+
+        dir.push(".blobs");
+        PathBuf::from(dir)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests blobs only touching disk once dumped and removals being saved
+    #[test]
+    fn blobs_persist() -> Result<(), error::DatabaseError> {
+        let path = PathBuf::from("blobs_persist.tinydb");
+        let mut db: Database<u8> = Database::new("Blob test", path.clone(), false);
+
+        db.put_blob("first", *b"one")?;
+        db.put_blob("second/with slashes", *b"two")?;
+
+        assert!(!db.blobs_dir().exists());
+        assert!(db.is_dirty());
+
+        db.dump_db()?;
+        db.remove_blob("first")?;
+
+        assert!(!db.remove_blob("missing")?);
+        assert_eq!(db.get_blob("first")?, None);
+        assert_eq!(db.blob_keys()?, vec![String::from("second/with slashes")]);
+
+        db.dump_db()?;
+
+        let loaded: Database<u8> = Database::from_unlocked(path.clone())?;
+
+        assert_eq!(loaded.get_blob("second/with slashes")?, Some(b"two".to_vec()));
+        assert_eq!(loaded.blob_keys()?.len(), 1);
+
+        fs::remove_dir_all(loaded.blobs_dir())?;
+        fs::remove_file(path)?;
+        Ok(())
+    }
+}
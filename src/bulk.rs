@@ -0,0 +1,114 @@
+//! Contains fast paths for loading large amounts of items into a
+//! [crate::Database], which skip the per-item work done by
+//! [crate::Database::add_item] where possible.
+//!
+//! The `benches` directory measures these against the regular methods, run
+//! them with `cargo bench`.
+
+use crate::Database;
+use core::hash;
+
+impl<T: hash::Hash + Eq> Database<T> {
+    /// Reserves space for at least `additional` more items, avoiding repeated
+    /// reallocation when the amount of items about to be added is known.
+    pub fn reserve(&mut self, additional: usize) {
+        self.items.reserve(additional);
+    }
+
+    /// Adds many items at once without checking [Database::dupe_policy],
+    /// returning how many were actually added. Duplicates are silently
+    /// dropped, as with [crate::dupes::DupePolicy::Ignore].
+    ///
+    /// When nothing else needs to hear about each item (no statistics,
+    /// indexes, ids, listeners, history, capacity limit or access tracking),
+    /// items are moved straight into [Database::items], which is several
+    /// times faster for hundreds of thousands of items. Otherwise this falls
+    /// back to adding them one by one so everything stays in sync.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tinydb::Database;
+    ///
+    /// fn main() {
+    ///     let mut db = Database::new("bulk_test", None, true);
+    ///
+    ///     let added = db.add_items_unchecked((0..100_000).chain(0..10)).unwrap();
+    ///
+    ///     assert_eq!(added, 100_000);
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Will return [crate::error::DatabaseError::Archived] if the database has
+    /// been archived.
+    pub fn add_items_unchecked(
+        &mut self,
+        items: impl IntoIterator<Item = T>,
+    ) -> Result<usize, crate::error::DatabaseError> {
+        self.check_mutable()?;
+        self.history.begin_step();
+
+        let items = items.into_iter();
+        let before = self.items.len();
+
+        self.items.reserve(items.size_hint().0);
+
+        if self.needs_bookkeeping() {
+            for item in items {
+                self.insert_item(item);
+            }
+
+            return Ok(self.items.len() - before);
+        }
+
+        self.items.extend(items);
+
+        let added = self.items.len() - before;
+
+        if added != 0 {
+            self.meta.modified_by(added);
+            self.counters.inserted(added as u64);
+        }
+
+        Ok(added)
+    }
+
+    /// Checks if anything has to be kept in sync on each added item, meaning
+    /// [Database::add_items_unchecked] can't skip straight to
+    /// [Database::items].
+    fn needs_bookkeeping(&self) -> bool {
+        !self.stats.is_empty()
+            || !self.indexes.is_empty()
+            || !self.listeners.is_empty()
+            || self.handles.is_enabled()
+            || self.history.is_enabled()
+            || self.capacity.is_enabled()
+            || self.access.is_enabled()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{error, stats};
+
+    /// Tests both the direct path and the fallback keeping statistics in sync
+    #[test]
+    fn bulk_add() -> Result<(), error::DatabaseError> {
+        let mut db = Database::new("Bulk test", None, true);
+
+        assert_eq!(db.add_items_unchecked(vec![1, 2, 2, 3])?, 3);
+        assert!(db.contains(&2));
+        assert_eq!(db.unsaved_changes(), 3);
+
+        db.track_stat("sum", stats::Stat::Sum, |n: &i32| *n as f64);
+
+        assert_eq!(db.add_items_unchecked(vec![3, 4])?, 1);
+        assert_eq!(db.stat("sum").unwrap().sum, 10.0);
+        assert_eq!(db.stats().inserts, 4);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,164 @@
+//! Contains caching the results of queries until a database changes, see
+//! [crate::Database::query_cache].
+
+use crate::Database;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Results of queries stored under keys of type `K`, which are all thrown
+/// away once the database they came from changes. Created with
+/// [Database::query_cache].
+///
+/// Any added or removed item clears every result, after which the next call
+/// to [QueryCache::get_or_query] for each key runs its query again. Pinning
+/// items or them expiring doesn't count as a change, so queries which depend
+/// on expiries shouldn't be cached.
+pub struct QueryCache<K, V> {
+    results: HashMap<K, V>,
+    stale: Arc<AtomicBool>,
+}
+
+impl<K: hash::Hash + Eq, V> QueryCache<K, V> {
+    /// Gets the result cached under `key`, running `query` on `db` to make it
+    /// first if there isn't one or the database has changed since it was
+    /// cached. `db` should be the database this cache was created from.
+    pub fn get_or_query<T: hash::Hash + Eq>(
+        &mut self,
+        db: &Database<T>,
+        key: K,
+        query: impl FnOnce(&Database<T>) -> V,
+    ) -> &V {
+        if self.stale.swap(false, Ordering::Relaxed) {
+            self.results.clear();
+        }
+
+        self.results.entry(key).or_insert_with(|| query(db))
+    }
+
+    /// Throws away every cached result, as if the database had changed.
+    pub fn invalidate(&mut self) {
+        self.results.clear();
+    }
+
+    /// Checks if the database has changed since results were last cached,
+    /// meaning they'll be thrown away on the next call to
+    /// [QueryCache::get_or_query].
+    pub fn is_stale(&self) -> bool {
+        self.stale.load(Ordering::Relaxed)
+    }
+
+    /// Gets how many results are cached, including any which are stale.
+    pub fn len(&self) -> usize {
+        self.results.len()
+    }
+
+    /// Checks if no results are cached.
+    pub fn is_empty(&self) -> bool {
+        self.results.is_empty()
+    }
+}
+
+impl<K, V> fmt::Debug for QueryCache<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("QueryCache")
+            .field("results", &self.results.len())
+            .field("stale", &self.stale.load(Ordering::Relaxed))
+            .finish()
+    }
+}
+
+impl<T: hash::Hash + Eq> Database<T> {
+    /// Creates a [QueryCache] holding results of queries on this database
+    /// until it changes, so hot paths such as a UI drawing every frame don't
+    /// scan unchanged items again. Results are owned, such as counts or
+    /// cloned items, as they outlive any one borrow of the database.
+    ///
+    /// The cache is registered like a listener from [Database::on_change], so
+    /// it isn't saved when dumping or carried over when cloning and is
+    /// unregistered once dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tinydb::Database;
+    ///
+    /// fn main() {
+    ///     let mut db = Database::new("query_cache_test", None, false);
+    ///     let mut cache = db.query_cache();
+    ///
+    ///     db.add_item((String::from("Lister"), 25)).unwrap();
+    ///
+    ///     let adults = cache.get_or_query(&db, "adults", |db| {
+    ///         db.project(|(_, age): &(String, u32)| *age)
+    ///             .into_iter()
+    ///             .filter(|age| *age >= 18)
+    ///             .count()
+    ///     });
+    ///
+    ///     assert_eq!(*adults, 1);
+    /// }
+    /// ```
+    pub fn query_cache<K, V>(&mut self) -> QueryCache<K, V> {
+        let stale = Arc::new(AtomicBool::new(false));
+        let listener_stale = Arc::clone(&stale);
+
+        self.listeners.push(Box::new(move |_| {
+            listener_stale.store(true, Ordering::Relaxed);
+            Arc::strong_count(&listener_stale) > 1
+        }));
+
+        QueryCache {
+            results: HashMap::new(),
+            stale,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error;
+    use std::sync::atomic::AtomicUsize;
+
+    /// Tests results only being queried again after a change, per key
+    #[test]
+    fn cache_invalidation() -> Result<(), error::DatabaseError> {
+        static QUERIED: AtomicUsize = AtomicUsize::new(0);
+
+        fn count_over(db: &Database<u32>, cache: &mut QueryCache<u32, usize>, min: u32) -> usize {
+            *cache.get_or_query(db, min, |db| {
+                QUERIED.fetch_add(1, Ordering::Relaxed);
+                db.project(|item| *item)
+                    .into_iter()
+                    .filter(|item| *item > min)
+                    .count()
+            })
+        }
+
+        let mut db = Database::new("Cache test", None, false);
+        let mut cache = db.query_cache();
+
+        db.add_item(1)?;
+        db.add_item(2)?;
+
+        assert_eq!(count_over(&db, &mut cache, 0), 2);
+        assert_eq!(count_over(&db, &mut cache, 0), 2);
+        assert_eq!(count_over(&db, &mut cache, 1), 1);
+
+        db.remove_item(&2)?;
+
+        assert!(cache.is_stale());
+        assert_eq!(count_over(&db, &mut cache, 0), 1);
+        assert_eq!(QUERIED.load(Ordering::Relaxed), 3);
+        assert_eq!(cache.len(), 1);
+
+        drop(cache);
+        db.add_item(3)?;
+
+        assert!(db.listeners.is_empty());
+        Ok(())
+    }
+}
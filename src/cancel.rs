@@ -0,0 +1,129 @@
+//! Contains [CancelToken], used to stop long dumps or loads partway through,
+//! see [crate::Database::dump_db_cancellable].
+
+use crate::{error, lock, metrics, Database};
+use serde::{de::DeserializeOwned, Serialize};
+use std::hash;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// A flag which can be set from another thread to cancel a dump or load, for
+/// example when the user quits whilst a large database is still loading.
+///
+/// Clones share the same flag, so one can be handed to the thread doing the
+/// work whilst another is kept to cancel it.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    /// Creates a token which hasn't been cancelled yet.
+    pub fn new() -> Self {
+        CancelToken::default()
+    }
+
+    /// Cancels every operation using this token or any of its clones.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// Checks if [CancelToken::cancel] has been called.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+impl<T: hash::Hash + Eq + Serialize> Database<T> {
+    /// Dumps this database like [Database::dump_db], stopping if `token` is
+    /// cancelled whilst it's still being encoded. The token is checked
+    /// roughly every 64KiB.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tinydb::cancel::CancelToken;
+    /// use tinydb::error::DatabaseError;
+    /// use tinydb::Database;
+    ///
+    /// fn main() {
+    ///     let mut db = Database::new("cancel_test", None, false);
+    ///     let token = CancelToken::new();
+    ///
+    ///     db.add_item(String::from("Holly")).unwrap();
+    ///     token.cancel();
+    ///
+    ///     assert_eq!(db.dump_db_cancellable(&token), Err(DatabaseError::Cancelled));
+    ///     assert!(!std::path::Path::new("cancel_test.tinydb").exists());
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Will return [error::DatabaseError::Cancelled] if `token` was cancelled
+    /// before encoding finished, in which case the existing dump is left
+    /// untouched. Otherwise this fails in the same ways as
+    /// [Database::dump_db].
+    pub fn dump_db_cancellable(
+        &self,
+        token: &CancelToken,
+    ) -> Result<metrics::DumpReport, error::DatabaseError> {
+        self.write_dump(None, Some(&mut |_, _| !token.is_cancelled()))
+    }
+}
+
+impl<T: hash::Hash + Eq + DeserializeOwned> Database<T> {
+    /// Creates a database from a `.tinydb` file like [Database::from],
+    /// stopping if `token` is cancelled whilst it's still being decoded. The
+    /// token is checked roughly every 64KiB.
+    ///
+    /// # Errors
+    ///
+    /// Will return [error::DatabaseError::Cancelled] if `token` was cancelled
+    /// before decoding finished. Otherwise this fails in the same ways as
+    /// [Database::from].
+    pub fn from_cancellable(
+        path: impl Into<PathBuf>,
+        token: &CancelToken,
+    ) -> Result<Self, error::DatabaseError> {
+        let path = path.into();
+        let lock = lock::FileLock::acquire(&path)?;
+        let mut decoded = Database::load(path, Some(&mut |_, _| !token.is_cancelled()))?;
+
+        decoded.lock = lock::HeldLock(Some(Arc::new(lock)));
+        Ok(decoded)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests cancelled loads failing and uncancelled ones going ahead
+    #[test]
+    fn cancel_load() -> Result<(), error::DatabaseError> {
+        let path = PathBuf::from("cancel_load.tinydb");
+        let mut db = Database::new("Cancel test", path.clone(), false);
+        let token = CancelToken::new();
+
+        for id in 0..20_000u64 {
+            db.add_item(id)?;
+        }
+
+        db.dump_db_cancellable(&token)?;
+
+        let loaded: Database<u64> = Database::from_cancellable(path.clone(), &token)?;
+
+        assert_eq!(loaded, db);
+        drop(loaded);
+
+        token.clone().cancel();
+
+        assert_eq!(
+            Database::<u64>::from_cancellable(path.clone(), &token),
+            Err(error::DatabaseError::Cancelled)
+        );
+
+        std::fs::remove_file(path)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,332 @@
+//! Contains an optional limit on how many items a [crate::Database] can hold,
+//! allowing it to be used as a bounded cache, see
+//! [crate::Database::set_capacity].
+
+use crate::compat::{HashMap, RandomState, Vec};
+use crate::snapshot::SharedState;
+use crate::Database;
+use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
+use core::fmt;
+use core::hash::{self, BuildHasher};
+
+/// What to do when adding an item to a database which is already holding as
+/// many items as its [Database::set_capacity] limit allows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EvictionPolicy {
+    /// Refuses to add the item, returning
+    /// [crate::error::DatabaseError::CapacityReached].
+    Reject,
+
+    /// Removes the item which was added the longest time ago.
+    EvictOldest,
+
+    /// Removes the item which was least recently accessed, which enables
+    /// [Database::track_access].
+    EvictLeastRecentlyQueried,
+}
+
+/// Clones an item, captured when the limit is set so that [Clone] isn't
+/// required everywhere.
+type Cloner<T> = Arc<dyn Fn(&T) -> T + Send + Sync>;
+
+/// The limit set with [Database::set_capacity] alongside when each item was
+/// added, as ticks of a counter, to find the oldest item.
+///
+/// Items are kept in the order they were added so the oldest can be found
+/// straight away. Their ticks are looked up by the hash of each item, so only
+/// one copy of every item is kept.
+///
+/// The limit isn't saved when dumping and never takes part in comparisons
+/// between databases.
+pub(crate) struct Capacity<T> {
+    cloner: Option<Cloner<T>>,
+    limit: usize,
+    policy: EvictionPolicy,
+    next: u64,
+    order: BTreeMap<u64, T>,
+    ticks: HashMap<u64, Vec<u64>>,
+    hasher: RandomState,
+}
+
+impl<T: hash::Hash + Eq> Capacity<T> {
+    /// Checks if a limit has been set.
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.cloner.is_some()
+    }
+
+    /// Checks if another item can't be added without going over the limit
+    /// whilst using [EvictionPolicy::Reject].
+    pub(crate) fn rejects(&self, len: usize) -> bool {
+        self.cloner.is_some() && self.policy == EvictionPolicy::Reject && len >= self.limit
+    }
+
+    /// Checks if an item should be evicted to get back under the limit.
+    pub(crate) fn overflows(&self, len: usize) -> bool {
+        self.cloner.is_some() && self.policy != EvictionPolicy::Reject && len > self.limit
+    }
+
+    /// Checks if the limit relies on [Database::track_access].
+    pub(crate) fn needs_access(&self) -> bool {
+        self.cloner.is_some() && self.policy == EvictionPolicy::EvictLeastRecentlyQueried
+    }
+
+    /// Starts keeping track of an item which has just been added.
+    pub(crate) fn insert(&mut self, item: &T) {
+        if let Some(cloner) = &self.cloner {
+            let tick = self.next;
+
+            self.next += 1;
+            self.order.insert(tick, cloner(item));
+            self.ticks
+                .entry(self.hasher.hash_one(item))
+                .or_default()
+                .push(tick);
+        }
+    }
+
+    /// Forgets an item which has just been removed.
+    pub(crate) fn remove(&mut self, item: &T) {
+        let hash = self.hasher.hash_one(item);
+        let ticks = match self.ticks.get_mut(&hash) {
+            Some(ticks) => ticks,
+            None => return,
+        };
+        let order = &self.order;
+
+        if let Some(pos) = ticks
+            .iter()
+            .position(|tick| order.get(tick).is_some_and(|added| added == item))
+        {
+            self.order.remove(&ticks.swap_remove(pos));
+        }
+
+        if ticks.is_empty() {
+            self.ticks.remove(&hash);
+        }
+    }
+
+    /// Stops tracking the item which was added the longest time ago,
+    /// returning it so it can be evicted.
+    fn pop_oldest(&mut self) -> Option<T> {
+        let (tick, item) = self.order.pop_first()?;
+        let hash = self.hasher.hash_one(&item);
+
+        if let Some(ticks) = self.ticks.get_mut(&hash) {
+            ticks.retain(|other| *other != tick);
+
+            if ticks.is_empty() {
+                self.ticks.remove(&hash);
+            }
+        }
+
+        Some(item)
+    }
+}
+
+impl<T> Default for Capacity<T> {
+    fn default() -> Self {
+        Capacity {
+            cloner: None,
+            limit: 0,
+            policy: EvictionPolicy::Reject,
+            next: 0,
+            order: BTreeMap::new(),
+            ticks: HashMap::new(),
+            hasher: RandomState::default(),
+        }
+    }
+}
+
+// [RandomState] is only `Copy` without the `std` feature.
+#[allow(clippy::clone_on_copy)]
+impl<T: hash::Hash + Eq> Clone for Capacity<T> {
+    fn clone(&self) -> Self {
+        let cloner = match &self.cloner {
+            Some(cloner) => cloner,
+            None => return Capacity::default(),
+        };
+
+        Capacity {
+            cloner: Some(Arc::clone(cloner)),
+            limit: self.limit,
+            policy: self.policy,
+            next: self.next,
+            order: self
+                .order
+                .iter()
+                .map(|(tick, item)| (*tick, cloner(item)))
+                .collect(),
+            ticks: self.ticks.clone(),
+            hasher: self.hasher.clone(),
+        }
+    }
+}
+
+impl<T> PartialEq for Capacity<T> {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl<T> Eq for Capacity<T> {}
+
+impl<T> fmt::Debug for Capacity<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Capacity")
+            .field("limit", &self.cloner.as_ref().map(|_| self.limit))
+            .field("policy", &self.policy)
+            .finish()
+    }
+}
+
+impl<T: hash::Hash + Eq> Database<T> {
+    /// Limits how many items this database can hold, with `policy` deciding
+    /// what happens when adding an item to a full database. If there are
+    /// already more items than the limit, items are evicted straight away
+    /// unless using [EvictionPolicy::Reject].
+    ///
+    /// The limit is runtime-only, so it isn't saved when dumping and has to
+    /// be set again after loading. Items already inside of the database are
+    /// treated as if they were added in an arbitrary order.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tinydb::Database;
+    /// use tinydb::capacity::EvictionPolicy;
+    ///
+    /// fn main() {
+    ///     let mut cache = Database::new("capacity_test", None, false);
+    ///
+    ///     cache.set_capacity(2, EvictionPolicy::EvictOldest);
+    ///
+    ///     for page in &["home", "about", "contact"] {
+    ///         cache.add_item(page.to_string()).unwrap();
+    ///     }
+    ///
+    ///     assert_eq!(cache.items.len(), 2);
+    ///     assert!(!cache.contains(&String::from("home")));
+    /// }
+    /// ```
+    pub fn set_capacity(&mut self, limit: usize, policy: EvictionPolicy)
+    where
+        T: Clone + 'static,
+    {
+        self.capacity = SharedState::new(Capacity {
+            cloner: Some(Arc::new(T::clone)),
+            limit,
+            policy,
+            ..Capacity::default()
+        });
+
+        for item in self.items.iter() {
+            self.capacity.insert(item);
+        }
+
+        if policy == EvictionPolicy::EvictLeastRecentlyQueried {
+            self.track_access();
+        }
+
+        self.evict_overflow();
+    }
+
+    /// Removes the limit set with [Database::set_capacity].
+    pub fn clear_capacity(&mut self) {
+        self.capacity = SharedState::default();
+    }
+
+    /// Gets the limit set with [Database::set_capacity], if any.
+    pub fn capacity(&self) -> Option<usize> {
+        self.capacity.cloner.as_ref().map(|_| self.capacity.limit)
+    }
+
+    /// Evicts items until this database is back under its limit.
+    pub(crate) fn evict_overflow(&mut self) {
+        while self.capacity.overflows(self.items.len()) {
+            let victim = if self.capacity.needs_access() {
+                self.access.by_recency(1, false).pop()
+            } else {
+                self.capacity.pop_oldest()
+            };
+
+            match victim {
+                Some(victim) => self.take_item(&victim),
+                None => break,
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error;
+
+    /// Tests each policy once the limit is reached
+    #[test]
+    fn capacity_policies() -> Result<(), error::DatabaseError> {
+        let mut db = Database::new("Capacity test", None, false);
+
+        db.set_capacity(2, EvictionPolicy::Reject);
+        db.add_item(1)?;
+        db.add_item(2)?;
+
+        assert!(matches!(
+            db.add_item(3),
+            Err(error::DatabaseError::CapacityReached)
+        ));
+
+        db.update_item(&2, 3)?;
+        db.set_capacity(2, EvictionPolicy::EvictLeastRecentlyQueried);
+        db.add_item(4)?;
+
+        assert_eq!(db.items.len(), 2);
+        assert!(db.contains(&4));
+
+        let kept = *db.items.iter().find(|n| **n != 4).unwrap();
+
+        assert!(db.contains(&kept));
+
+        db.add_item(5)?;
+
+        assert!(db.contains(&kept) && db.contains(&5));
+
+        db.set_capacity(1, EvictionPolicy::EvictOldest);
+
+        assert_eq!(db.items.len(), 1);
+
+        db.clear_capacity();
+        db.add_item(6)?;
+
+        assert_eq!(db.items.len(), 2);
+        Ok(())
+    }
+
+    /// Tests the oldest items being evicted in the order they were added
+    #[test]
+    fn evict_oldest_order() -> Result<(), error::DatabaseError> {
+        let mut db = Database::new("Evict oldest test", None, false);
+
+        db.set_capacity(3, EvictionPolicy::EvictOldest);
+
+        for value in 0..3 {
+            db.add_item(value)?;
+        }
+
+        db.remove_item(&1)?;
+        db.add_item(1)?;
+        db.add_item(3)?;
+
+        assert!(!db.contains(&0));
+
+        db.add_item(4)?;
+
+        assert!(!db.contains(&2));
+        assert!(db.contains(&1) && db.contains(&3) && db.contains(&4));
+        assert_eq!(db.capacity.order.len(), 3);
+        assert_eq!(db.capacity.ticks.len(), 3);
+
+        Ok(())
+    }
+}
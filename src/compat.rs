@@ -0,0 +1,112 @@
+//! Contains the parts of the standard library used by the in-memory core of
+//! tinydb, which come from `alloc`, [hashbrown] and [spin] instead when the
+//! `std` feature is disabled.
+//!
+//! Everything here has the same name and signatures as its standard library
+//! counterpart, so the core reads the same either way.
+
+pub(crate) use alloc::boxed::Box;
+pub(crate) use alloc::string::{String, ToString};
+pub(crate) use alloc::vec::Vec;
+
+#[cfg(not(feature = "std"))]
+pub(crate) use hashbrown::{hash_set, HashMap, HashSet};
+#[cfg(feature = "std")]
+pub(crate) use std::collections::{HashMap, HashSet};
+
+// Only used by the standard backend of [crate::items::Items].
+#[cfg(all(feature = "std", not(feature = "im")))]
+pub(crate) use std::collections::hash_set;
+
+/// Default hasher of [HashMap] and [HashSet], which is randomly seeded.
+#[cfg(feature = "std")]
+pub(crate) type RandomState = std::collections::hash_map::RandomState;
+
+/// Default hasher of [HashMap] and [HashSet], which is randomly seeded.
+#[cfg(not(feature = "std"))]
+pub(crate) type RandomState = hashbrown::DefaultHashBuilder;
+
+#[cfg(feature = "std")]
+pub(crate) use std::sync::{Mutex, OnceLock};
+
+#[cfg(not(feature = "std"))]
+pub(crate) use self::no_std::{Mutex, OnceLock};
+
+/// Stand-in for [std::path::PathBuf] without the `std` feature, which can't
+/// be created as there's no filesystem to save to. This lets
+/// [crate::Database::save_path] always be given as [None].
+#[cfg(not(feature = "std"))]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub enum PathBuf {}
+
+#[cfg(feature = "std")]
+pub(crate) use std::path::PathBuf;
+
+/// Spinning versions of the locks used by the core, as there are no threads
+/// to park without the `std` feature.
+#[cfg(not(feature = "std"))]
+mod no_std {
+    use core::convert::Infallible;
+    use core::fmt;
+
+    /// A [spin::Mutex] which never poisons, with the same signatures as
+    /// [std::sync::Mutex].
+    #[derive(Default)]
+    pub(crate) struct Mutex<T>(spin::Mutex<T>);
+
+    impl<T> Mutex<T> {
+        pub(crate) fn new(value: T) -> Self {
+            Mutex(spin::Mutex::new(value))
+        }
+
+        pub(crate) fn lock(&self) -> Result<spin::MutexGuard<'_, T>, Infallible> {
+            Ok(self.0.lock())
+        }
+
+        pub(crate) fn get_mut(&mut self) -> Result<&mut T, Infallible> {
+            Ok(self.0.get_mut())
+        }
+    }
+
+    impl<T: fmt::Debug> fmt::Debug for Mutex<T> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_tuple("Mutex").field(&*self.0.lock()).finish()
+        }
+    }
+
+    /// A [spin::Once] with the same signatures as [std::sync::OnceLock].
+    pub(crate) struct OnceLock<T>(spin::Once<T>);
+
+    impl<T> OnceLock<T> {
+        pub(crate) fn new() -> Self {
+            OnceLock(spin::Once::new())
+        }
+
+        pub(crate) fn get(&self) -> Option<&T> {
+            self.0.get()
+        }
+
+        pub(crate) fn get_or_init(&self, init: impl FnOnce() -> T) -> &T {
+            self.0.call_once(init)
+        }
+    }
+
+    impl<T> From<T> for OnceLock<T> {
+        fn from(value: T) -> Self {
+            OnceLock(spin::Once::initialized(value))
+        }
+    }
+}
+
+/// Natural logarithm of `value`.
+#[cfg(feature = "std")]
+pub(crate) fn ln(value: f64) -> f64 {
+    value.ln()
+}
+
+/// Natural logarithm of `value`, from [libm] as `core` has no floating point
+/// maths.
+#[cfg(not(feature = "std"))]
+pub(crate) fn ln(value: f64) -> f64 {
+    libm::log(value)
+}
@@ -0,0 +1,223 @@
+//! Contains [ConfigStore], a database which always holds exactly one value
+//! such as the settings of an application.
+
+use crate::{error, metrics, Database};
+use serde::{de::DeserializeOwned, Serialize};
+use std::hash;
+use std::path::PathBuf;
+
+/// A database holding exactly one value, for things like app settings which
+/// would otherwise need [Database::single] and manual emptiness checks
+/// everywhere. This is dumped and loaded just like a [Database], so the same
+/// dump can be opened with either.
+///
+/// # Examples
+///
+/// ```rust
+/// use serde::{Serialize, Deserialize};
+/// use tinydb::config::ConfigStore;
+///
+/// #[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+/// struct Settings {
+///     dark_mode: bool,
+///     volume: u8,
+/// }
+///
+/// fn main() {
+///     let default = Settings { dark_mode: false, volume: 50 };
+///     let mut settings = ConfigStore::load_or("config_test.tinydb", || default).unwrap();
+///
+///     settings.update(|settings| settings.volume = 80).unwrap();
+///     settings.dump().unwrap();
+///     drop(settings);
+///
+///     let settings: ConfigStore<Settings> = ConfigStore::from("config_test.tinydb").unwrap();
+///
+///     assert_eq!(settings.get().unwrap().volume, 80);
+///     # std::fs::remove_file("config_test.tinydb").unwrap();
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigStore<T: hash::Hash + Eq> {
+    db: Database<T>,
+}
+
+impl<T: hash::Hash + Eq> ConfigStore<T> {
+    /// Creates a store holding `value`, with the same `label` and `save_path`
+    /// as [Database::new].
+    pub fn new(label: impl Into<String>, save_path: impl Into<Option<PathBuf>>, value: T) -> Self {
+        let mut db = Database::new(label, save_path, false);
+
+        db.insert_item(value);
+        ConfigStore { db }
+    }
+
+    /// Wraps a database which holds exactly one item.
+    ///
+    /// # Errors
+    ///
+    /// Will return [error::DatabaseError::ItemNotFound] if the database is
+    /// empty or [error::DatabaseError::MultipleFound] if there is more than
+    /// one item.
+    pub fn from_db(db: Database<T>) -> Result<Self, error::DatabaseError> {
+        db.single()?;
+
+        Ok(ConfigStore { db })
+    }
+
+    /// Gets the value being stored.
+    ///
+    /// # Errors
+    ///
+    /// Fails in the same ways as [ConfigStore::from_db] if the database
+    /// underneath was changed using [ConfigStore::db_mut] so it no longer
+    /// holds exactly one item.
+    pub fn get(&self) -> Result<&T, error::DatabaseError> {
+        self.db.single()
+    }
+
+    /// Replaces the value being stored, returning the old one. The old value
+    /// is put back if the new one can't be added.
+    ///
+    /// # Errors
+    ///
+    /// Will return [error::DatabaseError::ConstraintViolation] if the new
+    /// value is rejected by [Database::add_constraint], the same errors as
+    /// [Database::add_item] if the store can't be changed or the same errors
+    /// as [ConfigStore::get] if it doesn't hold exactly one value.
+    pub fn set(&mut self, value: T) -> Result<T, error::DatabaseError> {
+        self.db.check_mutable()?;
+        self.db.constraints.check(&value)?;
+        self.db.single()?;
+        self.db.history.begin_step();
+
+        let mut old = self.db.take_where(|_| true);
+
+        if !self.db.insert_item(value) {
+            for item in old {
+                self.db.insert_item(item);
+            }
+
+            return Err(error::DatabaseError::CapacityReached);
+        }
+
+        old.pop().ok_or(error::DatabaseError::ItemNotFound)
+    }
+
+    /// Changes the value being stored using `modify`, which is given a copy
+    /// so the old value is kept if the new one is rejected.
+    ///
+    /// # Errors
+    ///
+    /// Fails in the same ways as [ConfigStore::set].
+    pub fn update(&mut self, modify: impl FnOnce(&mut T)) -> Result<(), error::DatabaseError>
+    where
+        T: Clone,
+    {
+        let mut value = self.get()?.clone();
+
+        modify(&mut value);
+        self.set(value)?;
+
+        Ok(())
+    }
+
+    /// Gets the database underneath, for anything not covered here such as
+    /// [Database::add_constraint].
+    pub fn db(&self) -> &Database<T> {
+        &self.db
+    }
+
+    /// Gets the database underneath mutably. Any changes to it have to leave
+    /// it holding exactly one item, otherwise methods of this store will
+    /// return errors until it does again.
+    pub fn db_mut(&mut self) -> &mut Database<T> {
+        &mut self.db
+    }
+
+    /// Unwraps the database underneath.
+    pub fn into_db(self) -> Database<T> {
+        self.db
+    }
+}
+
+impl<T: hash::Hash + Eq + Serialize> ConfigStore<T> {
+    /// Dumps the store using [Database::dump_db].
+    pub fn dump(&self) -> Result<metrics::DumpReport, error::DatabaseError> {
+        self.db.dump_db()
+    }
+}
+
+impl<T: hash::Hash + Eq + DeserializeOwned> ConfigStore<T> {
+    /// Loads a store from a `.tinydb` file using [Database::from].
+    ///
+    /// # Errors
+    ///
+    /// Fails in the same ways as [Database::from] and [ConfigStore::from_db].
+    pub fn from(path: impl Into<PathBuf>) -> Result<Self, error::DatabaseError> {
+        ConfigStore::from_db(Database::from(path)?)
+    }
+
+    /// Loads a store from a `.tinydb` file like [Database::auto_from] does,
+    /// holding the value made by `default` if there isn't a dump yet or it's
+    /// empty.
+    ///
+    /// # Errors
+    ///
+    /// Fails in the same ways as [Database::auto_from] and
+    /// [ConfigStore::from_db].
+    pub fn load_or(
+        path: impl Into<PathBuf>,
+        default: impl FnOnce() -> T,
+    ) -> Result<Self, error::DatabaseError> {
+        let mut db = Database::auto_from(path, false)?;
+
+        if db.items.is_empty() {
+            db.add_item(default())?;
+        }
+
+        ConfigStore::from_db(db)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests values being swapped out and rejected ones keeping the old value
+    #[test]
+    fn config_set_update() -> Result<(), error::DatabaseError> {
+        let mut store = ConfigStore::new("Config test", None, 1u32);
+
+        store
+            .db_mut()
+            .add_constraint(|value: &u32| match *value < 100 {
+                true => Ok(()),
+                false => Err(String::from("too big")),
+            });
+
+        assert_eq!(store.set(2)?, 1);
+        assert!(store.update(|value| *value *= 100).is_err());
+        assert_eq!(store.get(), Ok(&2));
+        assert_eq!(store.db().items.len(), 1);
+
+        store.db_mut().add_item(3)?;
+
+        assert_eq!(store.get(), Err(error::DatabaseError::MultipleFound(2)));
+        assert_eq!(store.set(4), Err(error::DatabaseError::MultipleFound(2)));
+
+        store.db_mut().remove_item(&3)?;
+
+        assert_eq!(store.get(), Ok(&2));
+
+        let mut db = store.into_db();
+
+        db.add_item(3)?;
+
+        assert_eq!(
+            ConfigStore::from_db(db),
+            Err(error::DatabaseError::MultipleFound(2))
+        );
+        Ok(())
+    }
+}
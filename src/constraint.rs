@@ -0,0 +1,128 @@
+//! Contains validation of items before they're added or updated, see
+//! [Database::add_constraint].
+
+use crate::compat::{String, Vec};
+use crate::{error, Database};
+use alloc::sync::Arc;
+use core::{fmt, hash};
+
+/// Validator for items, returning why an item is invalid.
+type Validator<T> = Arc<dyn Fn(&T) -> Result<(), String> + Send + Sync>;
+
+/// All constraints added to a database.
+///
+/// Constraints aren't saved when dumping so they never take part in
+/// comparisons between databases, but they are carried over when cloning.
+pub(crate) struct Constraints<T> {
+    validators: Vec<Validator<T>>,
+}
+
+impl<T> Constraints<T> {
+    /// Checks an item against every constraint, stopping at the first which
+    /// it violates.
+    pub(crate) fn check(&self, item: &T) -> Result<(), error::DatabaseError> {
+        self.validators
+            .iter()
+            .try_for_each(|validator| validator(item))
+            .map_err(error::DatabaseError::ConstraintViolation)
+    }
+}
+
+impl<T> Default for Constraints<T> {
+    fn default() -> Self {
+        Constraints {
+            validators: Vec::new(),
+        }
+    }
+}
+
+impl<T> Clone for Constraints<T> {
+    fn clone(&self) -> Self {
+        Constraints {
+            validators: self.validators.clone(),
+        }
+    }
+}
+
+impl<T> fmt::Debug for Constraints<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Constraints({})", self.validators.len())
+    }
+}
+
+impl<T: hash::Hash + Eq> Database<T> {
+    /// Adds a constraint which every item has to pass before it's added or
+    /// used as a replacement, returning why an item is invalid otherwise.
+    /// Constraints are checked in the order they were added.
+    ///
+    /// Items already inside of the database aren't checked, nor are items
+    /// added with [Database::add_items_unchecked] or put back by undoing,
+    /// restoring or reloading. Constraints aren't saved when dumping, so they
+    /// have to be added again after loading.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tinydb::error::DatabaseError;
+    /// use tinydb::Database;
+    ///
+    /// fn main() {
+    ///     let mut db = Database::new("constraint_test", None, false);
+    ///
+    ///     db.add_constraint(|(_, age): &(String, u32)| match *age < 200 {
+    ///         true => Ok(()),
+    ///         false => Err(String::from("age must be below 200")),
+    ///     });
+    ///     db.add_item((String::from("Lister"), 25)).unwrap();
+    ///
+    ///     assert_eq!(
+    ///         db.add_item((String::from("Lister"), 3000000)),
+    ///         Err(DatabaseError::ConstraintViolation(String::from("age must be below 200")))
+    ///     );
+    /// }
+    /// ```
+    pub fn add_constraint(
+        &mut self,
+        validator: impl Fn(&T) -> Result<(), String> + Send + Sync + 'static,
+    ) {
+        self.constraints.validators.push(Arc::new(validator));
+    }
+
+    /// Removes every constraint added with [Database::add_constraint].
+    pub fn clear_constraints(&mut self) {
+        self.constraints.validators.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests failed updates and modifications leaving items untouched
+    #[test]
+    fn constraint_update() -> Result<(), error::DatabaseError> {
+        let mut db = Database::new("Constraint test", None, false);
+
+        db.add_item(2)?;
+        db.add_item(4)?;
+        db.add_constraint(|item: &i32| match item % 2 {
+            0 => Ok(()),
+            _ => Err(format!("{} is odd", item)),
+        });
+
+        assert_eq!(
+            db.update_item(&2, 3),
+            Err(error::DatabaseError::ConstraintViolation(String::from(
+                "3 is odd"
+            )))
+        );
+        assert!(db.modify_where(|_| true, |item| item + 1).is_err());
+        assert!(db.contains(&2) && db.contains(&4));
+
+        db.clear_constraints();
+        db.update_item(&2, 3)?;
+
+        assert!(db.contains(&3));
+        Ok(())
+    }
+}
@@ -0,0 +1,331 @@
+//! Contains exporting and importing struct-shaped items as CSV, so tinydb
+//! contents can be used by spreadsheets and other non-Rust tooling. This is
+//! only available with the `csv` feature enabled.
+//!
+//! Each field of an item becomes a column, with headers taken from the field
+//! names. Only flat items can be exported, as nested structs, sequences and
+//! maps have no natural CSV representation.
+
+use crate::import::{ImportError, ImportReport};
+use crate::value::Value;
+use crate::{error, Database};
+use serde::{de::DeserializeOwned, Serialize};
+use std::fs::{self, File};
+use std::hash;
+use std::io::prelude::*;
+use std::io::BufWriter;
+use std::path::PathBuf;
+
+impl<T: hash::Hash + Eq + Serialize> Database<T> {
+    /// Exports all items to a CSV file at `path`, with a header row made from
+    /// the field names of the items. Items are written in the same order as
+    /// [Database::ordered] and any existing file is overwritten.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use serde::{Deserialize, Serialize};
+    /// use tinydb::Database;
+    ///
+    /// #[derive(Hash, Eq, PartialEq, Serialize, Deserialize)]
+    /// struct Crew {
+    ///     name: String,
+    ///     rank: Option<u8>,
+    /// }
+    ///
+    /// fn main() {
+    ///     let mut db = Database::new("crew", None, false);
+    ///
+    ///     db.add_item(Crew { name: String::from("Lister"), rank: None }).unwrap();
+    ///     db.export_csv("crew.csv").unwrap();
+    ///
+    ///     assert_eq!(std::fs::read_to_string("crew.csv").unwrap(), "name,rank\nLister,\n");
+    ///     # std::fs::remove_file("crew.csv").unwrap();
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns [error::DatabaseError::ConversionError] if an item isn't a
+    /// struct, has a nested field or has different fields to the others.
+    pub fn export_csv(&self, path: impl Into<PathBuf>) -> Result<(), error::DatabaseError> {
+        let mut rows = Vec::with_capacity(self.items.len());
+        let mut headers: Option<Vec<String>> = None;
+
+        for item in self.ordered().filter(|item| !self.is_expired(item)) {
+            let fields = match Value::from_item(item) {
+                Ok(Value::Map(fields)) => fields,
+                Ok(_) => {
+                    return Err(error::DatabaseError::ConversionError(String::from(
+                        "only struct-shaped items can be exported to CSV",
+                    )))
+                }
+                Err(err) => return Err(error::DatabaseError::ConversionError(err.0)),
+            };
+            let names: Vec<String> = fields.iter().map(|(name, _)| name.clone()).collect();
+
+            match &headers {
+                Some(headers) if *headers != names => {
+                    return Err(error::DatabaseError::ConversionError(String::from(
+                        "all items must have the same fields to be exported to CSV",
+                    )))
+                }
+                Some(_) => (),
+                None => headers = Some(names),
+            }
+
+            rows.push(
+                fields
+                    .into_iter()
+                    .map(|(name, value)| cell(&name, value))
+                    .collect::<Result<Vec<String>, error::DatabaseError>>()?,
+            );
+        }
+
+        let mut writer = BufWriter::new(File::create(path.into())?);
+
+        for row in headers.into_iter().chain(rows) {
+            let line: Vec<String> = row.iter().map(|field| escape(field)).collect();
+
+            writer.write_all(line.join(",").as_bytes())?;
+            writer.write_all(b"\n")?;
+        }
+
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+impl<T: hash::Hash + Eq + DeserializeOwned> Database<T> {
+    /// Imports items from a CSV file at `path` made by [Database::export_csv]
+    /// or any other tool, matching columns to fields using the header row.
+    ///
+    /// Cells are parsed into whatever type their field has and empty cells
+    /// become [Option::None]. Rows which can't be read are skipped and
+    /// reported alongside their line number, as are duplicates if
+    /// [Database::strict_dupes] is enabled. Other duplicates are handled using
+    /// [Database::dupe_policy].
+    ///
+    /// # Errors
+    ///
+    /// Returns [error::DatabaseError::Archived] if this database is archived
+    /// or [error::DatabaseError::IOError] if the file can't be read.
+    pub fn import_csv(
+        &mut self,
+        path: impl Into<PathBuf>,
+    ) -> Result<ImportReport, error::DatabaseError> {
+        self.check_mutable()?;
+        self.history.begin_step();
+
+        let text = fs::read_to_string(path.into())?;
+        let mut records = parse(&text).into_iter();
+        let mut report = ImportReport::default();
+        let headers = match records.next() {
+            Some((_, Ok(headers))) => headers,
+            Some((line, Err(message))) => {
+                report.errors.push(ImportError { line, message });
+                return Ok(report);
+            }
+            None => return Ok(report),
+        };
+
+        for (line, record) in records {
+            let result = record.and_then(|fields| {
+                if fields.len() != headers.len() {
+                    return Err(format!(
+                        "expected {} fields but found {}",
+                        headers.len(),
+                        fields.len()
+                    ));
+                }
+
+                let row = headers
+                    .iter()
+                    .cloned()
+                    .zip(fields.into_iter().map(Value::String))
+                    .collect();
+
+                Value::Map(row)
+                    .into_item::<T>(true)
+                    .map_err(|err| err.to_string())
+            });
+
+            match result {
+                Ok(item) => match self.insert_by_policy(item) {
+                    Ok(true) => report.added += 1,
+                    Ok(false) => (),
+                    Err(_) => report.errors.push(ImportError {
+                        line,
+                        message: String::from("duplicate item"),
+                    }),
+                },
+                Err(message) => report.errors.push(ImportError { line, message }),
+            }
+        }
+
+        Ok(report)
+    }
+}
+
+/// Converts a field into the text of its cell, leaving [Value::Null] empty.
+fn cell(name: &str, value: Value) -> Result<String, error::DatabaseError> {
+    match value {
+        Value::Null => Ok(String::new()),
+        Value::Bool(value) => Ok(value.to_string()),
+        Value::Int(value) => Ok(value.to_string()),
+        Value::UInt(value) => Ok(value.to_string()),
+        Value::Float(value) => Ok(value.to_string()),
+        Value::String(value) => Ok(value),
+        _ => Err(error::DatabaseError::ConversionError(format!(
+            "nested field `{}` can't be exported to CSV",
+            name
+        ))),
+    }
+}
+
+/// Quotes a cell if it contains anything which would otherwise be misread.
+fn escape(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) || field.starts_with(' ') || field.ends_with(' ') {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Parses CSV text into records alongside the line each starts on, where
+/// quoted cells may contain commas, doubled quotes and newlines.
+fn parse(text: &str) -> Vec<(usize, Result<Vec<String>, String>)> {
+    let mut records = Vec::new();
+    let mut chars = text.chars().peekable();
+    let mut line = 1;
+
+    while chars.peek().is_some() {
+        let start = line;
+        let mut fields = Vec::new();
+        let mut field = String::new();
+        let mut quoted = false;
+        let mut error = None;
+
+        while let Some(c) = chars.next() {
+            match c {
+                '"' if quoted && chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' if quoted => quoted = false,
+                '"' if field.is_empty() => quoted = true,
+                ',' if !quoted => fields.push(std::mem::take(&mut field)),
+                '\r' if !quoted && chars.peek() == Some(&'\n') => (),
+                '\n' if !quoted => {
+                    line += 1;
+                    break;
+                }
+                c => {
+                    if c == '\n' {
+                        line += 1;
+                    }
+
+                    field.push(c);
+                }
+            }
+        }
+
+        if quoted {
+            error = Some(String::from("unterminated quoted field"));
+        }
+
+        fields.push(field);
+
+        if fields.len() == 1 && fields[0].is_empty() && error.is_none() {
+            continue;
+        }
+
+        records.push((start, error.map_or(Ok(fields), Err)));
+    }
+
+    records
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    /// Struct-shaped item to export
+    #[derive(Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
+    struct Row {
+        name: String,
+        age: u32,
+        nickname: Option<String>,
+    }
+
+    /// Tests items surviving a round-trip through CSV
+    #[test]
+    fn csv_round_trip() -> Result<(), error::DatabaseError> {
+        let path = PathBuf::from("csv_round_trip.csv");
+        let mut db = Database::new("CSV test", None, false);
+
+        db.add_item(Row {
+            name: String::from("Rimmer, Arnold \"Judas\""),
+            age: 34,
+            nickname: None,
+        })?;
+        db.add_item(Row {
+            name: String::from("Cat"),
+            age: 3000000,
+            nickname: Some(String::from("Multi\nline")),
+        })?;
+        db.export_csv(path.clone())?;
+
+        let mut loaded = Database::new("CSV test", None, false);
+        let report = loaded.import_csv(path.clone())?;
+
+        assert!(report.is_ok());
+        assert_eq!(report.added, 2);
+        assert_eq!(loaded.items, db.items);
+
+        fs::remove_file(path)?;
+        Ok(())
+    }
+
+    /// Tests bad rows being reported with their line numbers
+    #[test]
+    fn csv_import_errors() -> Result<(), error::DatabaseError> {
+        let path = PathBuf::from("csv_import_errors.csv");
+
+        fs::write(
+            &path,
+            "age,name,nickname\n10,\"Two\nlines\",\nnot a number,Kryten,\n20,Holly\n30,Kochanski,Kris\n",
+        )?;
+
+        let mut db: Database<Row> = Database::new("CSV test", None, false);
+        let report = db.import_csv(path.clone())?;
+
+        assert_eq!(report.added, 2);
+        assert_eq!(
+            report.errors.iter().map(|err| err.line).collect::<Vec<_>>(),
+            vec![4, 5]
+        );
+        assert!(db.contains(&Row {
+            name: String::from("Two\nlines"),
+            age: 10,
+            nickname: None
+        }));
+
+        fs::remove_file(path)?;
+        Ok(())
+    }
+
+    /// Tests nested items being refused
+    #[test]
+    fn csv_export_nested() {
+        let mut db = Database::new("CSV test", None, false);
+
+        db.add_item(vec![1, 2, 3]).unwrap();
+
+        match db.export_csv("csv_export_nested.csv") {
+            Err(error::DatabaseError::ConversionError(_)) => (),
+            _ => panic!("non-struct item was exported"),
+        }
+    }
+}
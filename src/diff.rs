@@ -0,0 +1,139 @@
+//! Contains diffing of two databases, useful for sync tooling and for asserting
+//! what changed between two snapshots in tests.
+
+use crate::{error, Database};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::hash;
+use std::path::Path;
+
+/// Differences between two databases, returned from [Database::diff].
+///
+/// Diffs borrow the items of both databases, use [DbDiff::cloned] to get an
+/// owned copy which can outlive them.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DbDiff<T> {
+    /// Items which are only in the database [Database::diff] was called on.
+    pub only_in_self: Vec<T>,
+
+    /// Items which are only in the other database.
+    pub only_in_other: Vec<T>,
+
+    /// Items which are in both databases.
+    pub common: Vec<T>,
+}
+
+impl<T> DbDiff<T> {
+    /// Checks if both databases contained exactly the same items.
+    pub fn is_empty(&self) -> bool {
+        self.only_in_self.is_empty() && self.only_in_other.is_empty()
+    }
+}
+
+impl<T: Clone> DbDiff<&T> {
+    /// Clones all items so the diff no longer borrows either database.
+    pub fn cloned(&self) -> DbDiff<T> {
+        DbDiff {
+            only_in_self: self
+                .only_in_self
+                .iter()
+                .map(|item| (*item).clone())
+                .collect(),
+            only_in_other: self
+                .only_in_other
+                .iter()
+                .map(|item| (*item).clone())
+                .collect(),
+            common: self.common.iter().map(|item| (*item).clone()).collect(),
+        }
+    }
+}
+
+impl<T: hash::Hash + Eq> Database<T> {
+    /// Compares the items of this database to another, reporting which are
+    /// only in one of them and which are in both.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tinydb::Database;
+    ///
+    /// fn main() {
+    ///     let mut before: Database<u32> = Database::new("before", None, false);
+    ///     let mut after: Database<u32> = Database::new("after", None, false);
+    ///
+    ///     before.add_item(1).unwrap();
+    ///     before.add_item(2).unwrap();
+    ///     after.add_item(2).unwrap();
+    ///     after.add_item(3).unwrap();
+    ///
+    ///     let diff = before.diff(&after);
+    ///
+    ///     assert_eq!(diff.only_in_self, vec![&1]);
+    ///     assert_eq!(diff.only_in_other, vec![&3]);
+    ///     assert_eq!(diff.common, vec![&2]);
+    /// }
+    /// ```
+    pub fn diff<'a>(&'a self, other: &'a Database<T>) -> DbDiff<&'a T> {
+        let mut diff = DbDiff {
+            only_in_self: Vec::new(),
+            only_in_other: Vec::new(),
+            common: Vec::new(),
+        };
+
+        for item in self.items.iter() {
+            if other.items.contains(item) {
+                diff.common.push(item);
+            } else {
+                diff.only_in_self.push(item);
+            }
+        }
+
+        diff.only_in_other
+            .extend(other.items.iter().filter(|item| !self.items.contains(item)));
+
+        diff
+    }
+
+    /// Compares the items of this database to a dump, see [Database::diff].
+    ///
+    /// The dump is read without being locked and, as the loaded database
+    /// doesn't outlive this call, the diff is returned with owned items.
+    pub fn diff_file(&self, path: impl AsRef<Path>) -> Result<DbDiff<T>, error::DatabaseError>
+    where
+        T: Clone + DeserializeOwned,
+    {
+        let other = Database::from_unlocked(path.as_ref())?;
+
+        Ok(self.diff(&other).cloned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// Tests diffing against a dump and serializing the result
+    #[test]
+    fn diff_dump() -> Result<(), error::DatabaseError> {
+        let path = PathBuf::from("diff_dump.tinydb");
+        let mut db = Database::new("Diff test", path.clone(), false);
+
+        db.add_item(String::from("kept"))?;
+        db.add_item(String::from("removed"))?;
+        db.dump_db()?;
+        db.remove_item(&String::from("removed"))?;
+        db.add_item(String::from("added"))?;
+
+        let diff = db.diff_file(&path)?;
+        let encoded = bincode::serialize(&diff)?;
+
+        assert!(!diff.is_empty());
+        assert_eq!(diff.only_in_self, vec![String::from("added")]);
+        assert_eq!(diff.only_in_other, vec![String::from("removed")]);
+        assert_eq!(bincode::deserialize::<DbDiff<String>>(&encoded)?, diff);
+
+        std::fs::remove_file(path)?;
+        Ok(())
+    }
+}
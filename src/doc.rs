@@ -0,0 +1,445 @@
+//! Contains [Doc], a dynamic document for storing data of unknown shape
+//! without a compile-time schema, alongside the [DocDatabase] built on it.
+
+use crate::value::Value;
+use crate::{error, json, Database};
+use serde::de::{self, DeserializeOwned};
+use serde::ser::{SerializeMap, SerializeSeq};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::{fmt, hash};
+
+/// A database of [Doc]s, for tools which work on data of any shape. Queries
+/// look into documents with dotted paths, see [Database::query].
+pub type DocDatabase = Database<Doc>;
+
+/// A self-describing document, similar to a JSON value but keeping the
+/// distinction between signed, unsigned and floating point numbers.
+///
+/// Documents are dumped with a tag before every value so they can be loaded
+/// back from bincode, whilst formats which describe themselves such as JSON
+/// see them as plain values.
+///
+/// Equality is exact, so `Int(1)`, `UInt(1)` and `Float(1.0)` are different
+/// documents and maps only equal others with fields in the same order. Floats
+/// are compared by their bits so documents can be hashed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(remote = "Self")]
+pub enum Doc {
+    /// Unit or an empty option.
+    Null,
+
+    /// A boolean.
+    Bool(bool),
+
+    /// A signed integer.
+    Int(i64),
+
+    /// An unsigned integer.
+    UInt(u64),
+
+    /// A floating point number.
+    Float(f64),
+
+    /// A string or character.
+    String(String),
+
+    /// Raw bytes.
+    Bytes(Vec<u8>),
+
+    /// A sequence, tuple or list.
+    Seq(Vec<Doc>),
+
+    /// A struct or map, keeping the order fields were added in.
+    Map(Vec<(String, Doc)>),
+}
+
+impl Doc {
+    /// Converts any serializable item into a document.
+    ///
+    /// # Errors
+    ///
+    /// Returns [error::DatabaseError::ConversionError] if the item refuses
+    /// to be serialized.
+    pub fn from_item<T: Serialize + ?Sized>(item: &T) -> Result<Self, error::DatabaseError> {
+        Value::from_item(item)
+            .map(Doc::from)
+            .map_err(|err| error::DatabaseError::ConversionError(err.0))
+    }
+
+    /// Converts this document into any deserializable item.
+    ///
+    /// # Errors
+    ///
+    /// Returns [error::DatabaseError::ConversionError] if this document
+    /// doesn't match the shape of `T`.
+    pub fn into_item<T: DeserializeOwned>(self) -> Result<T, error::DatabaseError> {
+        Value::from(self)
+            .into_item(false)
+            .map_err(|err| error::DatabaseError::ConversionError(err.0))
+    }
+
+    /// Parses a JSON document.
+    ///
+    /// # Errors
+    ///
+    /// Returns [error::DatabaseError::ConversionError] describing the problem
+    /// if `text` isn't valid JSON.
+    pub fn from_json(text: &str) -> Result<Self, error::DatabaseError> {
+        json::parse(text)
+            .map(Doc::from)
+            .map_err(error::DatabaseError::ConversionError)
+    }
+
+    /// Writes this document as compact JSON on a single line. This is also
+    /// how documents are displayed.
+    pub fn to_json(&self) -> String {
+        json::write(&Value::from(self.clone()))
+    }
+
+    /// Gets the part of this document at a dotted path, where each part is
+    /// either the name of a field or the index of an item in a sequence. An
+    /// empty path or `.` gets the whole document.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tinydb::doc::Doc;
+    ///
+    /// fn main() {
+    ///     let doc = Doc::from_json(r#"{"user": {"name": "Kochanski", "ranks": [1, 2]}}"#).unwrap();
+    ///
+    ///     assert_eq!(doc.get("user.name"), Some(&Doc::from("Kochanski")));
+    ///     assert_eq!(doc.get("user.ranks.1"), Some(&Doc::UInt(2)));
+    ///     assert_eq!(doc.get("user.age"), None);
+    /// }
+    /// ```
+    pub fn get(&self, path: &str) -> Option<&Doc> {
+        if path.is_empty() || path == "." {
+            return Some(self);
+        }
+
+        path.split('.').try_fold(self, |doc, field| match doc {
+            Doc::Map(fields) => fields
+                .iter()
+                .find(|(name, _)| name == field)
+                .map(|(_, doc)| doc),
+            Doc::Seq(items) => items.get(field.parse::<usize>().ok()?),
+            _ => None,
+        })
+    }
+
+    /// Checks if two documents are equal, allowing numbers of different types
+    /// to match if they have the same value.
+    fn loosely_eq(&self, other: &Doc) -> bool {
+        match (self, other) {
+            (Doc::Int(a), Doc::UInt(b)) | (Doc::UInt(b), Doc::Int(a)) => *a as i128 == *b as i128,
+            (Doc::Float(a), Doc::Int(b)) | (Doc::Int(b), Doc::Float(a)) => *a == *b as f64,
+            (Doc::Float(a), Doc::UInt(b)) | (Doc::UInt(b), Doc::Float(a)) => *a == *b as f64,
+            (a, b) => a == b,
+        }
+    }
+}
+
+impl PartialEq for Doc {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Doc::Null, Doc::Null) => true,
+            (Doc::Bool(a), Doc::Bool(b)) => a == b,
+            (Doc::Int(a), Doc::Int(b)) => a == b,
+            (Doc::UInt(a), Doc::UInt(b)) => a == b,
+            (Doc::Float(a), Doc::Float(b)) => a.to_bits() == b.to_bits(),
+            (Doc::String(a), Doc::String(b)) => a == b,
+            (Doc::Bytes(a), Doc::Bytes(b)) => a == b,
+            (Doc::Seq(a), Doc::Seq(b)) => a == b,
+            (Doc::Map(a), Doc::Map(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Doc {}
+
+impl hash::Hash for Doc {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+
+        match self {
+            Doc::Null => (),
+            Doc::Bool(v) => v.hash(state),
+            Doc::Int(v) => v.hash(state),
+            Doc::UInt(v) => v.hash(state),
+            Doc::Float(v) => v.to_bits().hash(state),
+            Doc::String(v) => v.hash(state),
+            Doc::Bytes(v) => v.hash(state),
+            Doc::Seq(v) => v.hash(state),
+            Doc::Map(v) => v.hash(state),
+        }
+    }
+}
+
+impl fmt::Display for Doc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_json())
+    }
+}
+
+impl From<Value> for Doc {
+    fn from(value: Value) -> Self {
+        match value {
+            Value::Null => Doc::Null,
+            Value::Bool(v) => Doc::Bool(v),
+            Value::Int(v) => Doc::Int(v),
+            Value::UInt(v) => Doc::UInt(v),
+            Value::Float(v) => Doc::Float(v),
+            Value::String(v) => Doc::String(v),
+            Value::Bytes(v) => Doc::Bytes(v),
+            Value::Seq(items) => Doc::Seq(items.into_iter().map(Doc::from).collect()),
+            Value::Map(fields) => Doc::Map(
+                fields
+                    .into_iter()
+                    .map(|(name, value)| (name, Doc::from(value)))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl From<Doc> for Value {
+    fn from(doc: Doc) -> Self {
+        match doc {
+            Doc::Null => Value::Null,
+            Doc::Bool(v) => Value::Bool(v),
+            Doc::Int(v) => Value::Int(v),
+            Doc::UInt(v) => Value::UInt(v),
+            Doc::Float(v) => Value::Float(v),
+            Doc::String(v) => Value::String(v),
+            Doc::Bytes(v) => Value::Bytes(v),
+            Doc::Seq(items) => Value::Seq(items.into_iter().map(Value::from).collect()),
+            Doc::Map(fields) => Value::Map(
+                fields
+                    .into_iter()
+                    .map(|(name, doc)| (name, Value::from(doc)))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// Implements [From] for types which map straight onto a [Doc] variant.
+macro_rules! doc_from {
+    ($($from:ty => $variant:ident),* $(,)?) => {
+        $(
+            impl From<$from> for Doc {
+                fn from(value: $from) -> Self {
+                    Doc::$variant(value.into())
+                }
+            }
+        )*
+    };
+}
+
+doc_from! {
+    bool => Bool,
+    i32 => Int,
+    i64 => Int,
+    u32 => UInt,
+    u64 => UInt,
+    f64 => Float,
+    &str => String,
+    String => String,
+    Vec<Doc> => Seq,
+}
+
+impl Serialize for Doc {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if !serializer.is_human_readable() {
+            return Doc::serialize(self, serializer);
+        }
+
+        match self {
+            Doc::Null => serializer.serialize_unit(),
+            Doc::Bool(v) => serializer.serialize_bool(*v),
+            Doc::Int(v) => serializer.serialize_i64(*v),
+            Doc::UInt(v) => serializer.serialize_u64(*v),
+            Doc::Float(v) => serializer.serialize_f64(*v),
+            Doc::String(v) => serializer.serialize_str(v),
+            Doc::Bytes(v) => serializer.serialize_bytes(v),
+            Doc::Seq(items) => {
+                let mut seq = serializer.serialize_seq(Some(items.len()))?;
+
+                for item in items {
+                    seq.serialize_element(item)?;
+                }
+
+                seq.end()
+            }
+            Doc::Map(fields) => {
+                let mut map = serializer.serialize_map(Some(fields.len()))?;
+
+                for (name, doc) in fields {
+                    map.serialize_entry(name, doc)?;
+                }
+
+                map.end()
+            }
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Doc {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match deserializer.is_human_readable() {
+            true => deserializer.deserialize_any(DocVisitor),
+            false => Doc::deserialize(deserializer),
+        }
+    }
+}
+
+/// Builds a [Doc] from formats which describe themselves.
+struct DocVisitor;
+
+impl<'de> de::Visitor<'de> for DocVisitor {
+    type Value = Doc;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "any value")
+    }
+
+    fn visit_unit<E>(self) -> Result<Doc, E> {
+        Ok(Doc::Null)
+    }
+
+    fn visit_none<E>(self) -> Result<Doc, E> {
+        Ok(Doc::Null)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Doc, D::Error> {
+        <Doc as Deserialize>::deserialize(deserializer)
+    }
+
+    fn visit_bool<E>(self, v: bool) -> Result<Doc, E> {
+        Ok(Doc::Bool(v))
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<Doc, E> {
+        Ok(Doc::Int(v))
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<Doc, E> {
+        Ok(Doc::UInt(v))
+    }
+
+    fn visit_f64<E>(self, v: f64) -> Result<Doc, E> {
+        Ok(Doc::Float(v))
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Doc, E> {
+        Ok(Doc::String(v.into()))
+    }
+
+    fn visit_string<E>(self, v: String) -> Result<Doc, E> {
+        Ok(Doc::String(v))
+    }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<Doc, E> {
+        Ok(Doc::Bytes(v.into()))
+    }
+
+    fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<Doc, E> {
+        Ok(Doc::Bytes(v))
+    }
+
+    fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> Result<Doc, A::Error> {
+        let mut items = Vec::new();
+
+        while let Some(item) = seq.next_element()? {
+            items.push(item);
+        }
+
+        Ok(Doc::Seq(items))
+    }
+
+    fn visit_map<A: de::MapAccess<'de>>(self, mut map: A) -> Result<Doc, A::Error> {
+        let mut fields = Vec::new();
+
+        while let Some(field) = map.next_entry()? {
+            fields.push(field);
+        }
+
+        Ok(Doc::Map(fields))
+    }
+}
+
+impl Database<Doc> {
+    /// Finds every document with a value at a dotted path, see [Doc::get]
+    /// for how paths are written. Numbers match whatever their type, so
+    /// `30` finds documents where the value was parsed as an unsigned
+    /// integer too. Documents are returned in the same order as
+    /// [Database::ordered].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tinydb::doc::{Doc, DocDatabase};
+    ///
+    /// fn main() {
+    ///     let mut db = DocDatabase::new("doc_test", None, false);
+    ///
+    ///     db.add_item(Doc::from_json(r#"{"user": {"name": "Lister", "age": 30}}"#).unwrap()).unwrap();
+    ///     db.add_item(Doc::from_json(r#"{"user": {"name": "Cat", "age": 27}}"#).unwrap()).unwrap();
+    ///
+    ///     let found = db.query("user.age", 30);
+    ///
+    ///     assert_eq!(found.len(), 1);
+    ///     assert_eq!(found[0].get("user.name"), Some(&Doc::from("Lister")));
+    /// }
+    /// ```
+    pub fn query(&self, path: &str, value: impl Into<Doc>) -> Vec<&Doc> {
+        let value = value.into();
+
+        self.ordered()
+            .filter(|doc| !self.is_expired(doc))
+            .filter(|doc| doc.get(path).is_some_and(|found| found.loosely_eq(&value)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// Tests documents surviving a dump and being plain values in JSON
+    #[test]
+    fn doc_roundtrip() -> Result<(), error::DatabaseError> {
+        let path = PathBuf::from("doc_roundtrip.tinydb");
+        let ndjson = PathBuf::from("doc_roundtrip.ndjson");
+        let mut db = DocDatabase::new("Doc test", path.clone(), false);
+        let doc =
+            Doc::from_json(r#"{"name": "Holly", "iq": 6000, "bytes": [1, -2.5], "x": null}"#)?;
+
+        db.add_item(doc.clone())?;
+        db.add_item(Doc::from_item(&(String::from("Rimmer"), 1u8))?)?;
+        db.dump_db()?;
+
+        let loaded: DocDatabase = Database::from(path.clone())?;
+
+        assert_eq!(loaded, db);
+        assert_eq!(loaded.query("iq", 6000.0), vec![&doc]);
+        assert_eq!(loaded.query(".", 1), Vec::<&Doc>::new());
+        assert_eq!(loaded.query("1", 1), loaded.query("0", "Rimmer"));
+
+        loaded.export_ndjson(ndjson.clone())?;
+
+        assert!(std::fs::read_to_string(&ndjson)?.contains(&doc.to_json()));
+        assert_eq!(
+            doc.into_item::<std::collections::HashMap<String, Doc>>()?["iq"],
+            Doc::UInt(6000)
+        );
+
+        drop(loaded);
+        std::fs::remove_file(path)?;
+        std::fs::remove_file(ndjson)?;
+        Ok(())
+    }
+}
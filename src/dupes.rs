@@ -0,0 +1,458 @@
+//! Contains [DupePolicy], which decides what happens when adding an item
+//! identical to one already inside of a [crate::Database].
+
+use crate::compat::{HashMap, PathBuf, String, Vec};
+use crate::{error, events, Database};
+use core::hash;
+use serde::{Deserialize, Serialize};
+
+/// What to do when adding an item which is equal to one already inside of the
+/// database, set using [Database::set_dupe_policy].
+///
+/// Items are compared using their [Eq] implementation, so types which only
+/// compare a few key fields can use [DupePolicy::Replace] to update records
+/// by key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DupePolicy {
+    /// Returns [error::DatabaseError::DupeFound], the same as enabling
+    /// [Database::strict_dupes].
+    Error,
+
+    /// Keeps the existing item and silently drops the new one, the same as
+    /// disabling [Database::strict_dupes].
+    Ignore,
+
+    /// Replaces the existing item with the new one, dropping any pin or
+    /// expiry the existing item had.
+    Replace,
+
+    /// Keeps the existing item but counts how many times it has been added,
+    /// see [Database::count_of]. Removing the item only lowers its count
+    /// until the last copy is removed.
+    Count,
+}
+
+/// Policy used for duplicates alongside how many times each item has been
+/// added whilst using [DupePolicy::Count].
+///
+/// This is saved alongside the database so counts survive a dump.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound(
+    serialize = "T: hash::Hash + Eq + Serialize",
+    deserialize = "T: hash::Hash + Eq + Deserialize<'de>"
+))]
+pub(crate) struct Duplicates<T: hash::Hash + Eq> {
+    /// Policy for duplicates which aren't an error, as errors are decided by
+    /// [Database::strict_dupes].
+    pub(crate) policy: DupePolicy,
+
+    /// Items added more than once, with how many times they've been added.
+    pub(crate) counts: HashMap<T, usize>,
+}
+
+impl<T: hash::Hash + Eq> Duplicates<T> {
+    /// Makes sure every counted item is still inside of the database and has
+    /// been added at least twice, see [crate::testing::check_invariants].
+    #[cfg(feature = "testing")]
+    pub(crate) fn check_consistent(&self, contains: &dyn Fn(&T) -> bool) -> Result<(), String> {
+        match self
+            .counts
+            .iter()
+            .all(|(item, count)| *count > 1 && contains(item))
+        {
+            true => Ok(()),
+            false => Err(String::from(
+                "a duplicate count is out of sync with the items",
+            )),
+        }
+    }
+
+    /// Converts every counted item into another type.
+    #[cfg(feature = "std")]
+    pub(crate) fn map<U: hash::Hash + Eq>(self, convert: &mut impl FnMut(T) -> U) -> Duplicates<U> {
+        Duplicates {
+            policy: self.policy,
+            counts: self
+                .counts
+                .into_iter()
+                .map(|(item, count)| (convert(item), count))
+                .collect(),
+        }
+    }
+}
+
+impl<T: hash::Hash + Eq> Default for Duplicates<T> {
+    fn default() -> Self {
+        Duplicates {
+            policy: DupePolicy::Ignore,
+            counts: HashMap::new(),
+        }
+    }
+}
+
+impl<T: hash::Hash + Eq> Database<T> {
+    /// Creates an empty database using [DupePolicy::Count], so it works as a
+    /// multiset where adding an item again raises its count and removing it
+    /// lowers it. This is handy for tallying how often things happen.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tinydb::Database;
+    ///
+    /// fn main() {
+    ///     let mut words = Database::multiset("multiset_test", None);
+    ///
+    ///     for word in "the cat sat on the mat".split(' ') {
+    ///         words.add_item(word.to_string()).unwrap();
+    ///     }
+    ///
+    ///     assert_eq!(words.count_of(&String::from("the")), 2);
+    ///     assert_eq!(words.total_count(), 6);
+    ///     assert_eq!(words.most_common(1), vec![(&String::from("the"), 2)]);
+    /// }
+    /// ```
+    pub fn multiset(label: impl Into<String>, save_path: impl Into<Option<PathBuf>>) -> Self {
+        let mut db = Database::new(label, save_path, false);
+
+        db.set_dupe_policy(DupePolicy::Count);
+        db
+    }
+
+    /// Sets what happens when adding an item which is already inside of the
+    /// database, which also updates [Database::strict_dupes] to match.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tinydb::Database;
+    /// use tinydb::dupes::DupePolicy;
+    ///
+    /// fn main() {
+    ///     let mut db = Database::new("dupe_policy_test", None, false);
+    ///
+    ///     db.set_dupe_policy(DupePolicy::Count);
+    ///     db.add_item(String::from("Lister")).unwrap();
+    ///     db.add_item(String::from("Lister")).unwrap();
+    ///
+    ///     assert_eq!(db.count_of(&String::from("Lister")), 2);
+    ///
+    ///     db.remove_item(&String::from("Lister")).unwrap();
+    ///
+    ///     assert!(db.contains(&String::from("Lister")));
+    /// }
+    /// ```
+    pub fn set_dupe_policy(&mut self, policy: DupePolicy) {
+        self.strict_dupes = policy == DupePolicy::Error;
+        self.dupes.policy = policy;
+    }
+
+    /// Gets what happens when adding an item which is already inside of the
+    /// database, see [Database::set_dupe_policy].
+    pub fn dupe_policy(&self) -> DupePolicy {
+        match self.dupes.policy {
+            _ if self.strict_dupes => DupePolicy::Error,
+            DupePolicy::Error => DupePolicy::Ignore,
+            policy => policy,
+        }
+    }
+
+    /// Gets how many times an item has been added, which is only ever more
+    /// than `1` whilst using [DupePolicy::Count]. Returns `0` if the item
+    /// isn't inside of the database.
+    pub fn count_of(&self, item: &T) -> usize {
+        if !self.contains(item) {
+            return 0;
+        }
+
+        self.dupes.counts.get(item).copied().unwrap_or(1)
+    }
+
+    /// Iterates over every item alongside how many times it has been added,
+    /// see [Database::count_of].
+    pub fn counts(&self) -> impl Iterator<Item = (&T, usize)> {
+        self.items
+            .iter()
+            .filter(move |item| !self.is_expired(item))
+            .map(move |item| (item, self.dupes.counts.get(item).copied().unwrap_or(1)))
+    }
+
+    /// Adds up how many times every item has been added, which is the same
+    /// as the amount of items unless using [DupePolicy::Count].
+    pub fn total_count(&self) -> usize {
+        self.counts().map(|(_, count)| count).sum()
+    }
+
+    /// Gets the `amount` items which have been added the most times alongside
+    /// their counts, most common first. Items with the same count are in an
+    /// arbitrary order.
+    pub fn most_common(&self, amount: usize) -> Vec<(&T, usize)> {
+        let mut counted: Vec<(&T, usize)> = self.counts().collect();
+
+        counted.sort_unstable_by(|(_, a), (_, b)| b.cmp(a));
+        counted.truncate(amount);
+        counted
+    }
+
+    /// Removes an item entirely regardless of how many times it has been
+    /// added, returning its count. Soft-deletion with
+    /// [Database::set_soft_delete] applies like it does to
+    /// [Database::remove_item].
+    ///
+    /// # Errors
+    ///
+    /// Will return [error::DatabaseError::ItemNotFound] if the item isn't
+    /// inside of the database, or [error::DatabaseError::Archived] if the
+    /// database has been archived.
+    pub fn remove_all(&mut self, item: &T) -> Result<usize, error::DatabaseError> {
+        self.check_mutable()?;
+
+        let count = self.count_of(item);
+
+        if count == 0 {
+            return Err(error::DatabaseError::ItemNotFound);
+        }
+
+        self.history.begin_step();
+        self.discard_item(item)?;
+        Ok(count)
+    }
+
+    /// Adds an item following the current [DupePolicy], returning `false` if
+    /// it was a duplicate which didn't replace anything.
+    pub(crate) fn insert_by_policy(&mut self, item: T) -> Result<bool, error::DatabaseError> {
+        self.check_policy(&item)?;
+
+        Ok(self.insert_checked(item))
+    }
+
+    /// Checks if an item would be accepted by [Database::insert_by_policy]
+    /// without adding it, so a rejected item can be handed back.
+    pub(crate) fn check_policy(&self, item: &T) -> Result<(), error::DatabaseError> {
+        self.constraints.check(item)?;
+
+        let expired = self.items.get(item).is_some_and(|old| self.is_expired(old));
+
+        if expired || !self.items.contains(item) {
+            if self.capacity.rejects(self.items.len() - expired as usize) {
+                return Err(error::DatabaseError::CapacityReached);
+            }
+
+            return self.indexes.check_unique(item, &[], &[]);
+        }
+
+        match self.dupe_policy() {
+            DupePolicy::Error => Err(error::DatabaseError::DupeFound),
+            _ => Ok(()),
+        }
+    }
+
+    /// Adds an item which has already passed [Database::check_policy]. An
+    /// expired copy of the item is purged first, so it's never treated as a
+    /// duplicate.
+    pub(crate) fn insert_checked(&mut self, item: T) -> bool {
+        if self
+            .items
+            .get(&item)
+            .is_some_and(|old| self.is_expired(old))
+        {
+            self.take_item(&item);
+        }
+
+        if !self.items.contains(&item) {
+            return self.insert_item(item);
+        }
+
+        match self.dupe_policy() {
+            DupePolicy::Error | DupePolicy::Ignore => false,
+            DupePolicy::Replace => {
+                self.take_item(&item);
+                self.insert_item(item)
+            }
+            DupePolicy::Count => {
+                self.count_up(item, 1);
+                false
+            }
+        }
+    }
+
+    /// Raises the count of an item which is already inside of the database
+    /// by `by`, with `item` kept as the copy the count is stored under.
+    pub(crate) fn count_up(&mut self, item: T, by: usize) {
+        for _ in 0..by {
+            self.record_count(events::ChangeEvent::Added(&item));
+        }
+
+        *self.dupes.counts.entry(item).or_insert(1) += by;
+    }
+
+    /// Lowers the count of an item added more than once with
+    /// [DupePolicy::Count], returning `false` if it was the last copy.
+    pub(crate) fn uncount(&mut self, item: &T) -> bool {
+        match self.dupes.counts.get_mut(item) {
+            Some(count) if *count > 2 => *count -= 1,
+            Some(_) => {
+                self.dupes.counts.remove(item);
+            }
+            None => return false,
+        }
+
+        self.record_count(events::ChangeEvent::Removed(item));
+        true
+    }
+
+    /// Records a copy of an item being added or removed whilst the item
+    /// itself stays inside of the database, so history, the journal,
+    /// listeners and aggregates see counts change like any other mutation.
+    pub(crate) fn record_count(&mut self, event: events::ChangeEvent<&T>) {
+        self.meta.modified();
+        self.version.bump();
+        self.journal.recount(event.item());
+        self.history.record(event.clone());
+        self.listeners.emit(event.clone());
+        self.aggregates.emit(event);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A record which is only compared by its key
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    struct Setting {
+        key: String,
+        value: i32,
+    }
+
+    impl PartialEq for Setting {
+        fn eq(&self, other: &Self) -> bool {
+            self.key == other.key
+        }
+    }
+
+    impl Eq for Setting {}
+
+    impl hash::Hash for Setting {
+        fn hash<H: hash::Hasher>(&self, state: &mut H) {
+            self.key.hash(state);
+        }
+    }
+
+    /// Tests each policy when adding the same key twice
+    #[test]
+    fn dupe_policies() -> Result<(), error::DatabaseError> {
+        let setting = |value| Setting {
+            key: String::from("volume"),
+            value,
+        };
+        let mut db = Database::new("Dupe policy test", None, false);
+
+        assert_eq!(db.dupe_policy(), DupePolicy::Ignore);
+
+        db.add_item(setting(1))?;
+        db.add_item(setting(2))?;
+
+        assert_eq!(db.query_item(|s| &s.key, String::from("volume"))?.value, 1);
+
+        db.set_dupe_policy(DupePolicy::Replace);
+        db.add_item(setting(3))?;
+
+        assert_eq!(db.query_item(|s| &s.key, String::from("volume"))?.value, 3);
+
+        db.set_dupe_policy(DupePolicy::Error);
+
+        assert!(db.strict_dupes);
+        assert!(db.add_item(setting(4)).is_err());
+
+        db.set_dupe_policy(DupePolicy::Count);
+        db.add_item(setting(5))?;
+        db.add_item(setting(6))?;
+
+        assert_eq!(db.count_of(&setting(0)), 3);
+
+        db.remove_item(&setting(0))?;
+        db.remove_item(&setting(0))?;
+
+        assert_eq!(db.count_of(&setting(0)), 1);
+
+        db.remove_item(&setting(0))?;
+
+        assert_eq!(db.count_of(&setting(0)), 0);
+
+        Ok(())
+    }
+
+    /// Tests counts being reported and removed all at once
+    #[test]
+    fn multiset_counts() -> Result<(), error::DatabaseError> {
+        let mut db = Database::multiset("Multiset test", None);
+
+        for item in [1u8, 2, 2, 3, 3, 3] {
+            db.add_item(item)?;
+        }
+
+        assert_eq!(db.total_count(), 6);
+        assert_eq!(db.most_common(2), vec![(&3, 3), (&2, 2)]);
+        assert_eq!(db.remove_all(&3)?, 3);
+        assert_eq!(db.remove_all(&3), Err(error::DatabaseError::ItemNotFound));
+        assert_eq!(db.total_count(), 3);
+        assert!(!db.dupes.counts.contains_key(&3));
+
+        Ok(())
+    }
+
+    /// Tests counts being kept and merged by [Database::modify_where]
+    #[test]
+    fn modify_counted() -> Result<(), error::DatabaseError> {
+        let mut db = Database::multiset("Modify counted test", None);
+
+        for item in [1u8, 1, 1, 2, 20] {
+            db.add_item(item)?;
+        }
+
+        db.modify_where(|n| *n < 10, |n| n * 10)?;
+
+        assert_eq!(db.count_of(&10), 3);
+        assert_eq!(db.count_of(&20), 2);
+        assert_eq!(db.total_count(), 5);
+
+        Ok(())
+    }
+
+    /// Tests count changes reaching aggregates, listeners and history
+    #[test]
+    fn count_events() -> Result<(), error::DatabaseError> {
+        let mut db = Database::multiset("Count events test", None);
+
+        db.enable_history(8);
+        db.register_aggregate("sum", 0u32, |sum: &mut u32, event| match event {
+            events::ChangeEvent::Added(n) => *sum += n,
+            events::ChangeEvent::Removed(n) => *sum -= n,
+        });
+
+        let receiver = db.subscribe();
+
+        db.add_item(5u32)?;
+        db.add_item(5)?;
+        db.add_item(5)?;
+
+        assert_eq!(db.get_aggregate::<u32>("sum"), Some(&15));
+        assert_eq!(receiver.try_iter().count(), 3);
+
+        db.remove_item(&5)?;
+
+        assert_eq!(db.get_aggregate::<u32>("sum"), Some(&10));
+        assert!(db.undo()?);
+        assert_eq!(db.count_of(&5), 3);
+
+        db.remove_all(&5)?;
+
+        assert_eq!(db.get_aggregate::<u32>("sum"), Some(&0));
+        assert!(db.undo()?);
+        assert_eq!(db.count_of(&5), 3);
+        assert_eq!(db.get_aggregate::<u32>("sum"), Some(&15));
+
+        Ok(())
+    }
+}
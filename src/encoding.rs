@@ -0,0 +1,295 @@
+//! Contains [Encoding], which picks how bincode lays out dumps, see
+//! [crate::Database::set_encoding].
+//!
+//! The layout used is recorded in the flags of each dump's header, so dumps
+//! are always read back the way they were written no matter how the database
+//! loading them is set up.
+
+use crate::limits::{self, LoadLimits};
+use crate::{error, format, progress, Database};
+use bincode::Options;
+use serde::de::{Deserialize, DeserializeOwned};
+use serde::Serialize;
+use std::hash;
+use std::io::Read;
+
+/// Runs `$body` with `$options` bound to the bincode options matching an
+/// [Encoding], as each combination of options is its own type.
+macro_rules! with_options {
+    ($encoding:expr, $limit:expr, |$options:ident| $body:expr) => {{
+        let base = bincode::DefaultOptions::new()
+            .allow_trailing_bytes()
+            .with_limit($limit);
+
+        match ($encoding.varint, $encoding.big_endian) {
+            (false, false) => {
+                let $options = base.with_fixint_encoding().with_little_endian();
+                $body
+            }
+            (false, true) => {
+                let $options = base.with_fixint_encoding().with_big_endian();
+                $body
+            }
+            (true, false) => {
+                let $options = base.with_varint_encoding().with_little_endian();
+                $body
+            }
+            (true, true) => {
+                let $options = base.with_varint_encoding().with_big_endian();
+                $body
+            }
+        }
+    }};
+}
+
+/// How bincode lays out a dump written by [Database::dump_db] and the other
+/// ways of dumping.
+///
+/// The default is the legacy layout every dump was written in before this
+/// could be changed, with integers at their full width in little endian, so
+/// dumps stay readable by older versions of tinydb. Loading always uses the
+/// layout a dump was written in, meaning these settings only have to be
+/// picked on the side which dumps.
+///
+/// # Examples
+///
+/// ```rust
+/// use tinydb::encoding::Encoding;
+///
+/// let encoding = Encoding {
+///     max_size: Some(64 * 1024 * 1024),
+///     ..Encoding::compact()
+/// };
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Encoding {
+    /// Writes integers using as few bytes as their value needs, which
+    /// shrinks dumps of mostly small numbers and lengths a lot. Older
+    /// versions of tinydb can't read these dumps.
+    pub varint: bool,
+
+    /// Writes integers in big endian rather than little endian, for matching
+    /// other tools reading dumps. Older versions of tinydb can't read these
+    /// dumps.
+    pub big_endian: bool,
+
+    /// Largest encoded database in bytes, not counting the header or any
+    /// footers. Dumping fails with [error::DatabaseError::BincodeError]
+    /// rather than writing anything larger. Limits on loading are set with
+    /// [LoadLimits] instead.
+    pub max_size: Option<u64>,
+}
+
+impl Encoding {
+    /// Creates the smallest encoding, using [Encoding::varint].
+    pub fn compact() -> Self {
+        Encoding {
+            varint: true,
+            ..Encoding::default()
+        }
+    }
+
+    /// Checks if this is the legacy layout, which is the only one older
+    /// versions of tinydb and headerless dumps use.
+    pub fn is_legacy(&self) -> bool {
+        !self.varint && !self.big_endian
+    }
+
+    /// Gets the header flags recording this encoding.
+    pub(crate) fn flags(&self) -> u8 {
+        let mut flags = 0;
+
+        if self.varint {
+            flags |= format::FLAG_VARINT;
+        }
+
+        if self.big_endian {
+            flags |= format::FLAG_BIG_ENDIAN;
+        }
+
+        flags
+    }
+
+    /// Gets the encoding recorded in the flags of a header.
+    pub(crate) fn from_flags(flags: u8) -> Self {
+        Encoding {
+            varint: flags & format::FLAG_VARINT != 0,
+            big_endian: flags & format::FLAG_BIG_ENDIAN != 0,
+            max_size: None,
+        }
+    }
+
+    /// Gets the byte limit given to bincode.
+    fn limit(&self) -> u64 {
+        self.max_size.unwrap_or(u64::MAX)
+    }
+
+    /// Encodes a value like [progress::serialize] using this encoding.
+    pub(crate) fn serialize<V: Serialize>(
+        &self,
+        value: &V,
+        progress: progress::Progress<'_>,
+    ) -> Result<Vec<u8>, error::DatabaseError> {
+        with_options!(self, self.limit(), |options| progress::serialize(
+            value, options, progress
+        ))
+    }
+
+    /// Decodes a value like [progress::deserialize] using this encoding, or
+    /// within `limits` if given.
+    pub(crate) fn deserialize<V: DeserializeOwned>(
+        &self,
+        bytes: &[u8],
+        progress: progress::Progress<'_>,
+        limits: Option<&LoadLimits>,
+    ) -> Result<V, error::DatabaseError> {
+        match limits {
+            Some(limits) => {
+                let limit = self.limit().min(bytes.len() as u64);
+
+                with_options!(self, limit, |options| limits::deserialize(
+                    bytes, options, limits
+                ))
+            }
+            None => with_options!(self, self.limit(), |options| progress::deserialize(
+                bytes, options, progress
+            )),
+        }
+    }
+
+    /// Decodes a value borrowing from `bytes` using this encoding.
+    pub(crate) fn deserialize_borrowed<'de, V: Deserialize<'de>>(
+        &self,
+        bytes: &'de [u8],
+    ) -> Result<V, error::DatabaseError> {
+        with_options!(
+            self,
+            self.limit(),
+            |options| Ok(options.deserialize(bytes)?)
+        )
+    }
+
+    /// Decodes a single value from the start of `reader` using this
+    /// encoding, leaving the rest to be read.
+    pub(crate) fn deserialize_from<V: DeserializeOwned>(
+        &self,
+        reader: impl Read,
+    ) -> Result<V, error::DatabaseError> {
+        with_options!(self, self.limit(), |options| Ok(
+            options.deserialize_from(reader)?
+        ))
+    }
+}
+
+impl<T: hash::Hash + Eq> Database<T> {
+    /// Sets how bincode lays out this database when dumping, which is the
+    /// legacy layout by default. Databases loaded from a dump start with the
+    /// layout it was written in. This setting is not saved when dumping, but
+    /// the layout each dump uses is recorded inside of it.
+    ///
+    /// Segmented dumps made with [Database::dump_segmented] always use the
+    /// legacy layout.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tinydb::encoding::Encoding;
+    /// use tinydb::Database;
+    ///
+    /// fn main() {
+    ///     let mut db = Database::new("encoding_test", None, false);
+    ///
+    ///     for num in 0..1000u64 {
+    ///         db.add_item(num).unwrap();
+    ///     }
+    ///
+    ///     let legacy = db.to_bytes().unwrap();
+    ///
+    ///     db.set_encoding(Encoding::compact());
+    ///
+    ///     let compact = db.to_bytes().unwrap();
+    ///     let loaded: Database<u64> = Database::from_bytes(&compact).unwrap();
+    ///
+    ///     assert!(compact.len() < legacy.len() / 2);
+    ///     assert_eq!(loaded.items, db.items);
+    ///     assert_eq!(loaded.encoding(), Encoding::compact());
+    /// }
+    /// ```
+    pub fn set_encoding(&mut self, encoding: Encoding) {
+        self.encoding = encoding;
+    }
+
+    /// Gets how bincode lays out this database when dumping, see
+    /// [Database::set_encoding].
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests every layout being read back from its header, including with
+    /// limits, and the size limit stopping large dumps
+    #[test]
+    fn encodings_roundtrip() -> Result<(), error::DatabaseError> {
+        let mut db = Database::new("Encoding test", None, false);
+
+        for item in 0..500u32 {
+            db.add_item(item * 1000)?;
+        }
+
+        for (varint, big_endian) in [(false, false), (false, true), (true, false), (true, true)] {
+            let encoding = Encoding {
+                varint,
+                big_endian,
+                max_size: None,
+            };
+
+            db.set_encoding(encoding);
+
+            let dump = db.to_bytes()?;
+            let loaded: Database<u32> = Database::from_bytes(&dump)?;
+            let limited: Database<u32> =
+                Database::from_bytes_with_limits(&dump, LoadLimits::default())?;
+
+            assert_eq!(loaded.items, db.items);
+            assert_eq!(limited.items, db.items);
+            assert_eq!(loaded.encoding(), encoding);
+            assert_eq!(Encoding::from_flags(encoding.flags()), encoding);
+        }
+
+        db.set_encoding(Encoding {
+            max_size: Some(100),
+            ..Encoding::default()
+        });
+
+        assert!(matches!(
+            db.to_bytes(),
+            Err(error::DatabaseError::BincodeError(_))
+        ));
+        Ok(())
+    }
+
+    /// Tests dumps with flags from a newer version failing to load rather
+    /// than being decoded with the wrong layout
+    #[test]
+    fn unknown_flags_rejected() -> Result<(), error::DatabaseError> {
+        let mut db = Database::new("Unknown flags test", None, false);
+
+        db.add_item(7u32)?;
+        db.set_encoding(Encoding::compact());
+
+        let mut dump = db.to_bytes()?;
+
+        dump[format::HEADER_LEN - 1] |= 0b1000_0000;
+
+        assert!(matches!(
+            Database::<u32>::from_bytes(&dump),
+            Err(error::DatabaseError::UnrecognizedFormat { detected: Some(_) })
+        ));
+        assert_eq!(format::read_flags(&dump), None);
+        Ok(())
+    }
+}
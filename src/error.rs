@@ -1,7 +1,16 @@
 //! Contains various items related to errors inside of TinyDB.
 
+use crate::compat::String;
+use core::fmt;
+#[cfg(feature = "std")]
+use std::path::PathBuf;
+
 /// An error enum for the possible faliure states of the [crate::Database] structure.
+///
+/// New variants may be added in minor releases, so matches need a wildcard
+/// arm.
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum DatabaseError {
     /// When the item queried for was not found
     ItemNotFound,
@@ -15,20 +24,510 @@ pub enum DatabaseError {
     SavePathRequired,
 
     /// Misc [std::io::Error] that could not be properly handled.
+    #[cfg(feature = "std")]
     IOError(std::io::Error),
 
-    /// When the database could not be found. This is typically raised inside of
-    /// [crate::Database::from] when it tries to retrieve the path to the database.
-    DatabaseNotFound,
+    /// When the database could not be found, containing the path which was
+    /// looked at. This is typically raised inside of [crate::Database::from]
+    /// when it tries to retrieve the path to the database. For dumps saved to
+    /// a [crate::persist::PersistTarget] this contains the key instead.
+    #[cfg(feature = "std")]
+    DatabaseNotFound(PathBuf),
 
     /// When the given database name to an assumption-making function like
     /// [crate::Database::auto_from] does not have a valid file stem or could not
     /// convert from an [std::ffi::OsString] to a [String].
     BadDbName,
+
+    /// When a file given to [crate::Database::from] isn't a tinydb dump which
+    /// can be read by this version, for example a JSON export or a dump from a
+    /// newer format version. The `detected` field contains a guess at what the
+    /// file actually is, if one could be made.
+    UnrecognizedFormat { detected: Option<String> },
+
+    /// When trying to mutate a database finalized with
+    /// [crate::Database::archive], or dumping over an archived dump.
+    Archived,
+
+    /// When trying to mutate or dump a database opened with
+    /// [crate::Database::open_readonly].
+    ReadOnly,
+
+    /// When an archived dump doesn't match the checksum it was saved with,
+    /// meaning it has been changed or corrupted since being archived.
+    ChecksumMismatch,
+
+    /// When a dump loaded with [crate::Database::from_verified] wasn't signed
+    /// using [crate::Database::dump_signed].
+    SignatureMissing,
+
+    /// When the signature of a dump loaded with
+    /// [crate::Database::from_verified] doesn't match its contents, meaning it
+    /// has been tampered with or was signed by someone else.
+    SignatureInvalid,
+
+    /// When adding an item to a database which is already holding as many
+    /// items as [crate::Database::set_capacity] allows, whilst using
+    /// [crate::capacity::EvictionPolicy::Reject].
+    CapacityReached,
+
+    /// When a dump is locked by another database, which opened it with
+    /// [crate::Database::from] or [crate::Database::auto_from] in this process
+    /// or another one. Locks are released once the process holding them
+    /// exits, even if it crashed.
+    Locked,
+
+    /// When querying an index which hasn't been created, such as with
+    /// [crate::Database::search_text].
+    IndexNotFound,
+
+    /// When an item was updated with [crate::Database::update_if_revision]
+    /// but has been changed since the `expected` revision was read, being at
+    /// the `found` revision instead.
+    RevisionConflict { expected: u64, found: u64 },
+
+    /// When items can't be converted to another format, for example exporting
+    /// items which aren't shaped like a struct to CSV.
+    ConversionError(String),
+
+    /// When a query expression given to [crate::expr::Expr::parse] isn't
+    /// valid, containing a description of the problem.
+    BadExpr(String),
+
+    /// When an item is rejected by a constraint added with
+    /// [crate::Database::add_constraint], containing why it was rejected.
+    ConstraintViolation(String),
+
+    /// When an item would share its key with another item in a unique index
+    /// created with [crate::Database::add_unique_index], containing the name
+    /// of the index.
+    UniqueViolation { index: String },
+
+    /// When a database shared with [crate::net::serve] failed in a way which
+    /// can't be sent back as one of the other variants, containing a
+    /// description of the error from the server.
+    Remote(String),
+
+    /// When a dump was written to [crate::Database::save_path] but couldn't
+    /// be written to some of the paths added with
+    /// [crate::Database::add_mirror], containing each of those paths
+    /// alongside why it failed.
+    #[cfg(feature = "std")]
+    MirrorsFailed(Vec<(PathBuf, DatabaseError)>),
+
+    /// When a database was expected to hold a single item with
+    /// [crate::Database::single], containing how many it actually holds.
+    MultipleFound(usize),
+
+    /// When dumping or loading was stopped partway through using a
+    /// [crate::cancel::CancelToken], in which case nothing was written.
+    Cancelled,
+
+    /// When a dump loaded with [crate::Database::from_with_limits] goes over
+    /// one of its [crate::limits::LoadLimits], containing which limit.
+    LimitExceeded(String),
+
+    /// When dumping a database without a [crate::Database::save_path] whose
+    /// [crate::Database::label] can't be used as a file name, such as an
+    /// empty label or one reserved on Windows like `con`, containing the
+    /// label.
+    InvalidLabel(String),
+
+    /// When dumping into a directory which doesn't exist whilst
+    /// [crate::Database::set_create_dirs] is disabled, containing the
+    /// missing directory.
+    #[cfg(feature = "std")]
+    MissingDirectory(PathBuf),
+
+    /// When dumping over a file which isn't a dump of this database whilst
+    /// using [crate::overwrite::OverwritePolicy::Error], containing its path.
+    #[cfg(feature = "std")]
+    DumpExists(PathBuf),
+
+    /// When a directory a dump goes into didn't exist and couldn't be
+    /// created, for example because part of `path` is a file or isn't
+    /// writable.
+    #[cfg(feature = "std")]
+    CreateDirFailed {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    /// Misc [bincode::Error] from encoding or decoding a database, typically
+    /// due to a corrupted dump or one made with a different item type.
+    #[cfg(feature = "std")]
+    BincodeError(bincode::Error),
+
+    /// When an io or decoding error happened whilst loading or dumping, with
+    /// `context` describing what was being done. Use [DatabaseError::root]
+    /// to get the error which caused it.
+    #[cfg(feature = "std")]
+    Context {
+        context: ErrorContext,
+        source: Box<DatabaseError>,
+    },
+}
+
+/// What a database was doing when an error happened, see
+/// [DatabaseError::Context].
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ErrorContext {
+    /// The operation which failed, such as `"load"` or `"dump"`.
+    pub operation: &'static str,
+
+    /// Path of the dump involved, if there was one.
+    pub path: Option<PathBuf>,
+
+    /// Label of the database involved, if it was known yet.
+    pub label: Option<String>,
+}
+
+impl DatabaseError {
+    /// Gets the error which caused this one, skipping over any
+    /// [DatabaseError::Context] so it can be matched on directly.
+    pub fn root(&self) -> &DatabaseError {
+        match self {
+            #[cfg(feature = "std")]
+            DatabaseError::Context { source, .. } => source.root(),
+            other => other,
+        }
+    }
+
+    /// Gets what was being done when this error happened, if it was an io or
+    /// decoding error from loading or dumping.
+    #[cfg(feature = "std")]
+    pub fn context(&self) -> Option<&ErrorContext> {
+        match self {
+            DatabaseError::Context { context, .. } => Some(context),
+            _ => None,
+        }
+    }
+
+    /// Checks if this is a variant holding data which [PartialEq] compares,
+    /// rather than only which variant it is.
+    fn holds_data(&self) -> bool {
+        use DatabaseError::*;
+
+        #[cfg(feature = "std")]
+        if matches!(
+            self,
+            IOError(_)
+                | BincodeError(_)
+                | MissingDirectory(_)
+                | DumpExists(_)
+                | DatabaseNotFound(_)
+                | CreateDirFailed { .. }
+                | MirrorsFailed(_)
+                | Context { .. }
+        ) {
+            return true;
+        }
+
+        matches!(
+            self,
+            UnrecognizedFormat { .. }
+                | RevisionConflict { .. }
+                | ConversionError(_)
+                | BadExpr(_)
+                | ConstraintViolation(_)
+                | UniqueViolation { .. }
+                | Remote(_)
+                | MultipleFound(_)
+                | LimitExceeded(_)
+                | InvalidLabel(_)
+        )
+    }
+
+    /// Attaches context to io and decoding errors, leaving other errors as
+    /// they are as they don't depend on which file was involved.
+    #[cfg(feature = "std")]
+    pub(crate) fn with_context(
+        self,
+        operation: &'static str,
+        path: Option<PathBuf>,
+        label: Option<&str>,
+    ) -> Self {
+        match self {
+            DatabaseError::IOError(_) | DatabaseError::BincodeError(_) => DatabaseError::Context {
+                context: ErrorContext {
+                    operation,
+                    path,
+                    label: label.map(String::from),
+                },
+                source: Box::new(self),
+            },
+            other => other,
+        }
+    }
+}
+
+impl fmt::Display for DatabaseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DatabaseError::ItemNotFound => write!(f, "item not found"),
+            DatabaseError::DupeFound => write!(f, "duplicate item found whilst strict_dupes is on"),
+            DatabaseError::SavePathRequired => write!(f, "database has no save path"),
+            #[cfg(feature = "std")]
+            DatabaseError::IOError(e) => write!(f, "io error: {}", e),
+            #[cfg(feature = "std")]
+            DatabaseError::DatabaseNotFound(path) => {
+                write!(f, "database dump not found at {}", path.display())
+            }
+            DatabaseError::BadDbName => write!(f, "database name isn't a valid file stem"),
+            DatabaseError::UnrecognizedFormat {
+                detected: Some(detected),
+            } => {
+                write!(f, "not a readable tinydb dump, looks like {}", detected)
+            }
+            DatabaseError::UnrecognizedFormat { detected: None } => {
+                write!(f, "not a readable tinydb dump")
+            }
+            DatabaseError::Archived => write!(f, "database is archived"),
+            DatabaseError::ReadOnly => write!(f, "database is read-only"),
+            DatabaseError::ChecksumMismatch => {
+                write!(f, "archived dump doesn't match its checksum")
+            }
+            DatabaseError::SignatureMissing => write!(f, "dump isn't signed"),
+            DatabaseError::SignatureInvalid => write!(f, "dump signature is invalid"),
+            DatabaseError::CapacityReached => write!(f, "database is at capacity"),
+            DatabaseError::Locked => write!(f, "dump is locked by another database"),
+            DatabaseError::IndexNotFound => write!(f, "index not found"),
+            DatabaseError::RevisionConflict { expected, found } => write!(
+                f,
+                "item is at revision {} rather than the expected {}",
+                found, expected
+            ),
+            DatabaseError::ConversionError(e) => write!(f, "conversion error: {}", e),
+            DatabaseError::BadExpr(e) => write!(f, "invalid expression: {}", e),
+            DatabaseError::ConstraintViolation(e) => write!(f, "constraint violated: {}", e),
+            DatabaseError::UniqueViolation { index } => {
+                write!(f, "key is already used in unique index {:?}", index)
+            }
+            DatabaseError::Remote(e) => write!(f, "remote error: {}", e),
+            DatabaseError::MultipleFound(found) => {
+                write!(f, "expected a single item but found {}", found)
+            }
+            DatabaseError::Cancelled => write!(f, "operation was cancelled"),
+            DatabaseError::LimitExceeded(e) => write!(f, "limit exceeded: {}", e),
+            DatabaseError::InvalidLabel(label) => {
+                write!(f, "label `{}` can't be used as a file name", label)
+            }
+            #[cfg(feature = "std")]
+            DatabaseError::MissingDirectory(path) => {
+                write!(f, "directory `{}` doesn't exist", path.display())
+            }
+            #[cfg(feature = "std")]
+            DatabaseError::DumpExists(path) => {
+                write!(f, "`{}` already exists", path.display())
+            }
+            #[cfg(feature = "std")]
+            DatabaseError::CreateDirFailed { path, source } => write!(
+                f,
+                "couldn't create directory `{}`: {}",
+                path.display(),
+                source
+            ),
+            #[cfg(feature = "std")]
+            DatabaseError::MirrorsFailed(failed) => {
+                write!(f, "dumped but {} mirror(s) failed", failed.len())?;
+
+                for (path, e) in failed {
+                    write!(f, ", {:?}: {}", path, e)?;
+                }
+
+                Ok(())
+            }
+            #[cfg(feature = "std")]
+            DatabaseError::BincodeError(e) => write!(f, "bincode error: {}", e),
+            #[cfg(feature = "std")]
+            DatabaseError::Context { context, source } => {
+                write!(f, "{} failed: {}", context, source)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl fmt::Display for ErrorContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.operation)?;
+
+        if let Some(path) = &self.path {
+            write!(f, " of {:?}", path)?;
+        }
+
+        if let Some(label) = &self.label {
+            write!(f, " for database {:?}", label)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl core::error::Error for DatabaseError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            #[cfg(feature = "std")]
+            DatabaseError::IOError(e) => Some(e),
+            #[cfg(feature = "std")]
+            DatabaseError::BincodeError(e) => Some(e),
+            #[cfg(feature = "std")]
+            DatabaseError::CreateDirFailed { source, .. } => Some(source),
+            #[cfg(feature = "std")]
+            DatabaseError::Context { source, .. } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+/// Compares errors for use in assertions. As [std::io::Error] and
+/// [bincode::Error] can't be compared themselves, io errors are equal if their
+/// [std::io::ErrorKind] matches and bincode errors if their messages do.
+impl PartialEq for DatabaseError {
+    fn eq(&self, other: &Self) -> bool {
+        use DatabaseError::*;
+
+        match (self, other) {
+            #[cfg(feature = "std")]
+            (IOError(a), IOError(b)) => a.kind() == b.kind(),
+            #[cfg(feature = "std")]
+            (BincodeError(a), BincodeError(b)) => a.to_string() == b.to_string(),
+            (UnrecognizedFormat { detected: a }, UnrecognizedFormat { detected: b }) => a == b,
+            (
+                RevisionConflict {
+                    expected: a_expected,
+                    found: a_found,
+                },
+                RevisionConflict {
+                    expected: b_expected,
+                    found: b_found,
+                },
+            ) => a_expected == b_expected && a_found == b_found,
+            (ConversionError(a), ConversionError(b)) => a == b,
+            (BadExpr(a), BadExpr(b)) => a == b,
+            (ConstraintViolation(a), ConstraintViolation(b)) => a == b,
+            (UniqueViolation { index: a }, UniqueViolation { index: b }) => a == b,
+            (Remote(a), Remote(b)) => a == b,
+            (MultipleFound(a), MultipleFound(b)) => a == b,
+            (LimitExceeded(a), LimitExceeded(b)) => a == b,
+            (InvalidLabel(a), InvalidLabel(b)) => a == b,
+            #[cfg(feature = "std")]
+            (MissingDirectory(a), MissingDirectory(b)) => a == b,
+            #[cfg(feature = "std")]
+            (DumpExists(a), DumpExists(b)) => a == b,
+            #[cfg(feature = "std")]
+            (DatabaseNotFound(a), DatabaseNotFound(b)) => a == b,
+            #[cfg(feature = "std")]
+            (
+                CreateDirFailed {
+                    path: a_path,
+                    source: a_source,
+                },
+                CreateDirFailed {
+                    path: b_path,
+                    source: b_source,
+                },
+            ) => a_path == b_path && a_source.kind() == b_source.kind(),
+            #[cfg(feature = "std")]
+            (MirrorsFailed(a), MirrorsFailed(b)) => a == b,
+            #[cfg(feature = "std")]
+            (
+                Context {
+                    context: a_context,
+                    source: a_source,
+                },
+                Context {
+                    context: b_context,
+                    source: b_source,
+                },
+            ) => a_context == b_context && a_source == b_source,
+            (a, b) => {
+                !a.holds_data() && core::mem::discriminant(a) == core::mem::discriminant(b)
+            }
+        }
+    }
 }
 
+#[cfg(feature = "std")]
 impl From<std::io::Error> for DatabaseError {
     fn from(e: std::io::Error) -> Self {
         DatabaseError::IOError(e)
     }
 }
+
+#[cfg(feature = "std")]
+impl From<bincode::Error> for DatabaseError {
+    fn from(e: bincode::Error) -> Self {
+        DatabaseError::BincodeError(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::error::Error;
+    use std::io;
+
+    /// Tests io errors being displayed, chained and compared by kind
+    #[test]
+    fn io_error_traits() {
+        let error = DatabaseError::from(io::Error::new(io::ErrorKind::NotFound, "gone"));
+
+        assert_eq!(error.to_string(), "io error: gone");
+        assert_eq!(error.source().unwrap().to_string(), "gone");
+        assert_eq!(
+            error,
+            DatabaseError::IOError(io::ErrorKind::NotFound.into())
+        );
+        assert_ne!(error, DatabaseError::IOError(io::ErrorKind::Other.into()));
+        assert_ne!(error, DatabaseError::ItemNotFound);
+    }
+
+    /// Tests context only being attached to io and decoding errors
+    #[test]
+    fn error_context() {
+        let error = DatabaseError::from(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            "permission denied",
+        ))
+        .with_context("dump", Some(PathBuf::from("db.tinydb")), Some("Users"));
+
+        assert_eq!(
+            error.to_string(),
+            "dump of \"db.tinydb\" for database \"Users\" failed: io error: permission denied"
+        );
+        assert_eq!(error.context().unwrap().operation, "dump");
+        assert_eq!(
+            error.root(),
+            &DatabaseError::IOError(io::ErrorKind::PermissionDenied.into())
+        );
+        assert_eq!(
+            DatabaseError::Locked.with_context("load", None, None),
+            DatabaseError::Locked
+        );
+    }
+
+    /// Tests missing dumps saying which path was looked at
+    #[test]
+    fn not_found_path() {
+        let path = PathBuf::from("not_found_path.tinydb");
+        let error = crate::Database::<u8>::from_unlocked(path.clone()).unwrap_err();
+
+        assert_eq!(error, DatabaseError::DatabaseNotFound(path));
+        assert_eq!(
+            error.to_string(),
+            "database dump not found at not_found_path.tinydb"
+        );
+    }
+
+    /// Tests errors working with `?` into a boxed error
+    #[test]
+    fn boxed_error() {
+        fn fails() -> Result<(), Box<dyn Error>> {
+            Err(DatabaseError::ItemNotFound)?
+        }
+
+        assert_eq!(fails().unwrap_err().to_string(), "item not found");
+    }
+}
@@ -0,0 +1,129 @@
+//! Contains change events which are emitted from a [crate::Database] whenever
+//! it is mutated, see [crate::Database::on_change] and
+//! [crate::Database::subscribe].
+
+use crate::compat::{Box, Vec};
+use core::fmt;
+
+/// A single mutation which happened to a [crate::Database].
+///
+/// Updates done with [crate::Database::update_item] are emitted as a
+/// [ChangeEvent::Removed] of the old item followed by a [ChangeEvent::Added]
+/// of the new one.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ChangeEvent<T> {
+    /// An item was added to the database.
+    Added(T),
+
+    /// An item was removed from the database.
+    Removed(T),
+}
+
+impl<T> ChangeEvent<T> {
+    /// Gets the item this event is about, regardless of the kind of event.
+    pub fn item(&self) -> &T {
+        match self {
+            ChangeEvent::Added(item) => item,
+            ChangeEvent::Removed(item) => item,
+        }
+    }
+
+    /// Borrows the item inside of this event whilst keeping the kind of event.
+    pub fn as_ref(&self) -> ChangeEvent<&T> {
+        match self {
+            ChangeEvent::Added(item) => ChangeEvent::Added(item),
+            ChangeEvent::Removed(item) => ChangeEvent::Removed(item),
+        }
+    }
+
+    /// Converts the item inside of this event whilst keeping the kind of event.
+    pub fn map<U>(self, op: impl FnOnce(T) -> U) -> ChangeEvent<U> {
+        match self {
+            ChangeEvent::Added(item) => ChangeEvent::Added(op(item)),
+            ChangeEvent::Removed(item) => ChangeEvent::Removed(op(item)),
+        }
+    }
+}
+
+/// A registered listener, returning `false` once it no longer wants events.
+type Listener<T> = Box<dyn FnMut(ChangeEvent<&T>) -> bool + Send + Sync>;
+
+/// All listeners currently registered to a database.
+///
+/// Listeners are tied to the exact database they were registered on, so they
+/// are never cloned alongside it and never take part in comparisons.
+pub(crate) struct Listeners<T> {
+    listeners: Vec<Listener<T>>,
+}
+
+impl<T> Listeners<T> {
+    /// Registers a new listener.
+    pub(crate) fn push(&mut self, listener: Listener<T>) {
+        self.listeners.push(listener);
+    }
+
+    /// Checks if no listeners are registered.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.listeners.is_empty()
+    }
+
+    /// Sends an event to every listener, dropping those which have hung up.
+    pub(crate) fn emit(&mut self, event: ChangeEvent<&T>) {
+        self.listeners
+            .retain_mut(|listener| listener(event.clone()));
+    }
+}
+
+impl<T> Default for Listeners<T> {
+    fn default() -> Self {
+        Listeners {
+            listeners: Vec::new(),
+        }
+    }
+}
+
+impl<T> Clone for Listeners<T> {
+    fn clone(&self) -> Self {
+        Listeners::default()
+    }
+}
+
+impl<T> fmt::Debug for Listeners<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Listeners({})", self.listeners.len())
+    }
+}
+
+impl<T> PartialEq for Listeners<T> {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl<T> Eq for Listeners<T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests that listeners returning `false` are dropped after the event
+    #[test]
+    fn listener_hangup() {
+        let mut listeners: Listeners<i32> = Listeners::default();
+
+        listeners.push(Box::new(|_| false));
+        listeners.push(Box::new(|_| true));
+        listeners.emit(ChangeEvent::Added(&5));
+
+        assert_eq!(listeners.listeners.len(), 1);
+    }
+
+    /// Tests [ChangeEvent::map] keeping the kind of event
+    #[test]
+    fn event_map() {
+        assert_eq!(
+            ChangeEvent::Removed(&4).map(|n| n * 2),
+            ChangeEvent::Removed(8)
+        );
+    }
+}
@@ -0,0 +1,207 @@
+//! Contains an opt-in debug mode recording how each query found its items,
+//! see [crate::Database::set_explain].
+
+use crate::compat::{Mutex, String, ToString};
+use crate::Database;
+use core::hash;
+
+/// How a query got to the items it looked at.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Access {
+    /// Every item was looked at until the query was done, which gets slower
+    /// as the database grows.
+    FullScan,
+
+    /// The item was found directly by hashing it, such as with
+    /// [Database::contains].
+    Lookup,
+
+    /// Items were found using the index with this name, only looking at those
+    /// the index picked out.
+    Index(String),
+}
+
+/// How the last query made on a database was carried out, returned from
+/// [Database::last_plan].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct QueryPlan {
+    /// Name of the method which made the query, e.g. `query_item`.
+    pub query: &'static str,
+
+    /// If an index or a full scan was used.
+    pub access: Access,
+
+    /// Amount of items looked at, including any which didn't match. Queries
+    /// which stop at the first match only count the items up to it.
+    pub items_scanned: usize,
+
+    /// Amount of items the query gave back.
+    pub matched: usize,
+}
+
+/// The plan of the last query made whilst explaining is enabled.
+///
+/// This is behind a [Mutex] as it's updated from queries which only borrow
+/// the database. Plans aren't saved when dumping or carried over when
+/// cloning.
+#[derive(Debug, Default)]
+pub(crate) struct Explainer {
+    last: Option<Mutex<Option<QueryPlan>>>,
+}
+
+impl Explainer {
+    /// Records the plan made by `plan` as the last one, doing nothing if
+    /// explaining is disabled so plans cost nothing to make otherwise.
+    pub(crate) fn record(&self, plan: impl FnOnce() -> QueryPlan) {
+        if let Some(last) = &self.last {
+            *last.lock().unwrap() = Some(plan());
+        }
+    }
+}
+
+impl Clone for Explainer {
+    fn clone(&self) -> Self {
+        Explainer::default()
+    }
+}
+
+impl<T: hash::Hash + Eq> Database<T> {
+    /// Enables or disables recording how each query finds its items, which
+    /// can then be read with [Database::last_plan] to see why a query is
+    /// slow. This is disabled by default and isn't saved when dumping.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tinydb::explain::Access;
+    /// use tinydb::Database;
+    ///
+    /// fn main() {
+    ///     let mut db = Database::new("explain_test", None, false);
+    ///
+    ///     for num in 0..100u32 {
+    ///         db.add_item(num).unwrap();
+    ///     }
+    ///
+    ///     db.set_explain(true);
+    ///     db.find_by(|num| num, 50);
+    ///
+    ///     let plan = db.last_plan().unwrap();
+    ///
+    ///     assert_eq!(plan.access, Access::FullScan);
+    ///     assert_eq!((plan.items_scanned, plan.matched), (100, 1));
+    ///
+    ///     db.create_range_index("nums", |num: &u32| *num as f64);
+    ///     db.query_range("nums", 10..20).unwrap();
+    ///
+    ///     let plan = db.last_plan().unwrap();
+    ///
+    ///     assert_eq!(plan.access, Access::Index(String::from("nums")));
+    ///     assert_eq!((plan.items_scanned, plan.matched), (10, 10));
+    /// }
+    /// ```
+    pub fn set_explain(&mut self, enabled: bool) {
+        self.explainer.last = if enabled {
+            Some(self.explainer.last.take().unwrap_or_default())
+        } else {
+            None
+        };
+    }
+
+    /// Gets how the last query was carried out, or [None] if no query has
+    /// been made since [Database::set_explain] was enabled.
+    pub fn last_plan(&self) -> Option<QueryPlan> {
+        self.explainer
+            .last
+            .as_ref()
+            .and_then(|last| last.lock().unwrap().clone())
+    }
+
+    /// Records a query as having scanned through items.
+    pub(crate) fn explain_scan(&self, query: &'static str, items_scanned: usize, matched: usize) {
+        self.explainer.record(|| QueryPlan {
+            query,
+            access: Access::FullScan,
+            items_scanned,
+            matched,
+        })
+    }
+
+    /// Records a query as having looked up a single item by its hash.
+    pub(crate) fn explain_lookup(&self, query: &'static str, found: bool) {
+        self.explainer.record(|| QueryPlan {
+            query,
+            access: Access::Lookup,
+            items_scanned: found as usize,
+            matched: found as usize,
+        })
+    }
+
+    /// Records a query as having used the index called `name`, which picked
+    /// out `items_scanned` items.
+    pub(crate) fn explain_index(
+        &self,
+        query: &'static str,
+        name: &str,
+        items_scanned: usize,
+        matched: usize,
+    ) {
+        self.explainer.record(|| QueryPlan {
+            query,
+            access: Access::Index(name.to_string()),
+            items_scanned,
+            matched,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index::TextMatch;
+
+    /// Tests plans of early stopping scans, lookups and text indexes, and
+    /// nothing being recorded once disabled
+    #[test]
+    fn explain_plans() {
+        let mut db = Database::new("Explain test", None, false);
+
+        for name in &["Lister", "Rimmer", "Cat"] {
+            db.add_item(name.to_string()).unwrap();
+        }
+
+        db.create_text_index("names", |name: &String| name.clone());
+        db.query_item(|name: &String| name, "Rimmer").unwrap();
+
+        assert!(db.last_plan().is_none());
+
+        db.set_explain(true);
+        db.contains_by(|name: &String| name, "Cat");
+
+        let plan = db.last_plan().unwrap();
+
+        assert_eq!(plan.query, "contains_by");
+        assert_eq!(plan.matched, 1);
+        assert!(plan.items_scanned >= 1 && plan.items_scanned <= 3);
+
+        db.contains(&String::from("Kryten"));
+
+        assert_eq!(db.last_plan().unwrap().access, Access::Lookup);
+
+        db.search_text("names", "lister", TextMatch::Any).unwrap();
+
+        assert_eq!(
+            db.last_plan().unwrap(),
+            QueryPlan {
+                query: "search_text",
+                access: Access::Index(String::from("names")),
+                items_scanned: 1,
+                matched: 1,
+            }
+        );
+
+        db.set_explain(false);
+
+        assert!(db.last_plan().is_none());
+    }
+}
@@ -0,0 +1,305 @@
+//! Contains [Expr], a small text query language for filtering items by their
+//! serialized fields, used by the interactive shell of `tinydb-cli` and
+//! anywhere else queries come from users rather than code.
+
+use crate::value::Value;
+use crate::{error, json, meta, Database};
+use serde::Serialize;
+use std::cmp::Ordering;
+use std::hash;
+
+/// A parsed query expression such as `age > 30 and name == "Lister"`.
+///
+/// Each comparison is made up of a field, an operator and a value. Fields are
+/// dotted paths into items as they'd be serialized, so `ship.crew` looks at
+/// the `crew` field of a `ship` field and `tags.0` at the first of `tags`,
+/// with `.` being the item itself. Operators are `==`, `!=`, `>`, `>=`, `<`
+/// and `<=`. Values are written as JSON, with bare words being taken as
+/// strings, and comparisons are joined together with `and`.
+///
+/// Numbers compare with each other whatever their type, whilst values of
+/// different kinds never match anything apart from `!=`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expr {
+    conditions: Vec<Condition>,
+}
+
+/// A single comparison within an [Expr].
+#[derive(Debug, Clone, PartialEq)]
+struct Condition {
+    path: Vec<String>,
+    op: Op,
+    value: Value,
+}
+
+/// Operator of a [Condition].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Eq,
+    Ne,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+}
+
+impl Expr {
+    /// Parses an expression, see [Expr] for what can be written.
+    ///
+    /// # Errors
+    ///
+    /// Returns [error::DatabaseError::BadExpr] describing the problem if the
+    /// expression isn't valid.
+    pub fn parse(text: &str) -> Result<Self, error::DatabaseError> {
+        let tokens = tokenize(text)?;
+        let mut tokens = tokens.iter().map(String::as_str);
+        let mut conditions = Vec::new();
+
+        loop {
+            let path = tokens.next().ok_or_else(|| bad("expected a field"))?;
+            let op = match tokens.next() {
+                Some("==") => Op::Eq,
+                Some("!=") => Op::Ne,
+                Some(">") => Op::Gt,
+                Some(">=") => Op::Ge,
+                Some("<") => Op::Lt,
+                Some("<=") => Op::Le,
+                Some(other) => return Err(bad(&format!("unknown operator `{}`", other))),
+                None => return Err(bad("expected an operator")),
+            };
+            let value = tokens.next().ok_or_else(|| bad("expected a value"))?;
+
+            conditions.push(Condition {
+                path: match path {
+                    "." => Vec::new(),
+                    path => path.split('.').map(String::from).collect(),
+                },
+                op,
+                value: json::parse(value).unwrap_or_else(|_| Value::String(value.into())),
+            });
+
+            match tokens.next() {
+                Some("and") => (),
+                Some(other) => return Err(bad(&format!("expected `and`, found `{}`", other))),
+                None => break,
+            }
+        }
+
+        Ok(Expr { conditions })
+    }
+
+    /// Checks if an item matches every comparison of this expression. Items
+    /// which can't be serialized never match.
+    pub fn matches<T: Serialize + ?Sized>(&self, item: &T) -> bool {
+        let value = match Value::from_item(item) {
+            Ok(value) => value,
+            Err(_) => return false,
+        };
+
+        self.conditions.iter().all(|condition| {
+            let found = condition
+                .path
+                .iter()
+                .try_fold(&value, |value, field| lookup(value, field));
+            let ordering = found.and_then(|found| compare(found, &condition.value));
+
+            match condition.op {
+                Op::Eq => ordering == Some(Ordering::Equal),
+                Op::Ne => ordering != Some(Ordering::Equal),
+                Op::Gt => ordering == Some(Ordering::Greater),
+                Op::Ge => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+                Op::Lt => ordering == Some(Ordering::Less),
+                Op::Le => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+            }
+        })
+    }
+}
+
+/// Creates a [error::DatabaseError::BadExpr].
+fn bad(message: &str) -> error::DatabaseError {
+    error::DatabaseError::BadExpr(message.into())
+}
+
+/// Splits an expression on whitespace, keeping quoted strings together.
+fn tokenize(text: &str) -> Result<Vec<String>, error::DatabaseError> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+
+        let mut token = String::new();
+
+        if c == '"' {
+            token.push(c);
+            chars.next();
+
+            loop {
+                match chars.next() {
+                    Some('\\') => {
+                        token.push('\\');
+                        token.extend(chars.next());
+                    }
+                    Some('"') => break token.push('"'),
+                    Some(c) => token.push(c),
+                    None => return Err(bad("unterminated string")),
+                }
+            }
+        } else {
+            while let Some(&c) = chars.peek().filter(|c| !c.is_whitespace()) {
+                token.push(c);
+                chars.next();
+            }
+        }
+
+        tokens.push(token);
+    }
+
+    Ok(tokens)
+}
+
+/// Gets a field of a map or an index of a sequence.
+fn lookup<'a>(value: &'a Value, field: &str) -> Option<&'a Value> {
+    match value {
+        Value::Map(fields) => fields
+            .iter()
+            .find(|(name, _)| name == field)
+            .map(|(_, value)| value),
+        Value::Seq(items) => items.get(field.parse::<usize>().ok()?),
+        _ => None,
+    }
+}
+
+/// Orders two values, returning [Option::None] if they can't be compared.
+fn compare(a: &Value, b: &Value) -> Option<Ordering> {
+    match (a, b) {
+        (Value::Int(a), Value::Int(b)) => Some(a.cmp(b)),
+        (Value::UInt(a), Value::UInt(b)) => Some(a.cmp(b)),
+        (Value::Int(a), Value::UInt(b)) => Some((*a as i128).cmp(&(*b as i128))),
+        (Value::UInt(a), Value::Int(b)) => Some((*a as i128).cmp(&(*b as i128))),
+        (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+        (Value::Bool(a), Value::Bool(b)) => Some(a.cmp(b)),
+        (Value::Null, Value::Null) => Some(Ordering::Equal),
+        (a, b) => match (as_float(a), as_float(b)) {
+            (Some(a), Some(b)) => a.partial_cmp(&b),
+            _ if a == b => Some(Ordering::Equal),
+            _ => None,
+        },
+    }
+}
+
+/// Gets a number as a float, for comparing numbers of different types.
+fn as_float(value: &Value) -> Option<f64> {
+    match value {
+        Value::Int(value) => Some(*value as f64),
+        Value::UInt(value) => Some(*value as f64),
+        Value::Float(value) => Some(*value),
+        _ => None,
+    }
+}
+
+impl<T: hash::Hash + Eq + Serialize> Database<T> {
+    /// Finds every item matching a query expression, in the same order as
+    /// [Database::ordered]. See [Expr] for how expressions are written.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use serde::Serialize;
+    /// use tinydb::expr::Expr;
+    /// use tinydb::Database;
+    ///
+    /// #[derive(Debug, Hash, Eq, PartialEq, Serialize)]
+    /// struct Crew {
+    ///     name: String,
+    ///     age: u32,
+    /// }
+    ///
+    /// fn main() {
+    ///     let mut db = Database::new("expr_test", None, false);
+    ///
+    ///     db.add_item(Crew { name: String::from("Lister"), age: 25 }).unwrap();
+    ///     db.add_item(Crew { name: String::from("Rimmer"), age: 34 }).unwrap();
+    ///
+    ///     let expr = Expr::parse("age > 30").unwrap();
+    ///
+    ///     assert_eq!(db.query_expr(&expr)[0].name, "Rimmer");
+    /// }
+    /// ```
+    pub fn query_expr(&self, expr: &Expr) -> Vec<&T> {
+        let found: Vec<&T> = self
+            .ordered()
+            .filter(|item| !self.is_expired(item) && expr.matches(*item))
+            .collect();
+
+        self.explain_scan("query_expr", self.items.len(), found.len());
+        found
+    }
+
+    /// Removes every item matching a query expression, returning how many
+    /// were removed. Removed items are kept aside if soft-deletion has been
+    /// enabled with [Database::set_soft_delete], like [Database::remove_item].
+    pub fn remove_where_expr(&mut self, expr: &Expr) -> Result<usize, error::DatabaseError> {
+        self.check_mutable()?;
+        self.history.begin_step();
+
+        let now = meta::now_millis();
+        let expiries = std::mem::take(&mut self.expiries);
+        let taken = self.take_where(|item| !expiries.is_expired(item, now) && expr.matches(item));
+        let removed = taken.len();
+
+        self.expiries = expiries;
+
+        for item in taken.iter() {
+            self.expiries.remove(item);
+        }
+
+        if self.trash.enabled {
+            self.trash.items.extend(taken);
+        }
+
+        Ok(removed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests nested fields, mixed numbers and conjunctions
+    #[test]
+    fn expr_matching() -> Result<(), error::DatabaseError> {
+        let mut db = Database::new("Expr test", None, false);
+
+        db.add_item((String::from("Lister"), 25u16, vec![2]))?;
+        db.add_item((String::from("Kryten"), 3000u16, vec![]))?;
+        db.add_item((String::from("Cat"), 30u16, vec![-2]))?;
+
+        let found = |expr: &str| -> Result<usize, error::DatabaseError> {
+            Ok(db.query_expr(&Expr::parse(expr)?).len())
+        };
+
+        assert_eq!(found("1 >= 25")?, 3);
+        assert_eq!(found("1 > 25 and 0 != Cat")?, 1);
+        assert_eq!(found("0 == \"Lister\" and 2.0 > 1.5")?, 1);
+        assert_eq!(found("2.0 < 0")?, 1);
+        assert_eq!(found("2 == []")?, 1);
+        assert_eq!(found("0 > Cat")?, 2);
+        assert_eq!(found("0 > 5")?, 0);
+        assert!(matches!(
+            Expr::parse("1 =~ 25"),
+            Err(error::DatabaseError::BadExpr(_))
+        ));
+        assert!(Expr::parse("0 == \"Lister").is_err());
+        assert!(Expr::parse("1 > 25 or").is_err());
+
+        let removed = db.remove_where_expr(&Expr::parse("1 < 30")?)?;
+
+        assert_eq!(removed, 1);
+        assert_eq!(db.items.len(), 2);
+        Ok(())
+    }
+}
@@ -0,0 +1,446 @@
+//! Contains the on-disk format of dumped databases, which is a small header of
+//! magic bytes, a format version and flags followed by a fixed-size [Info]
+//! block and the bincode-encoded database. Archived dumps also end with a
+//! checksum footer of the encoded database and signed dumps end with a
+//! signature of everything before it.
+
+use crate::error;
+use std::io::prelude::*;
+
+/// Magic bytes which all dumps made since format versioning begin with.
+pub(crate) const MAGIC: &[u8; 6] = b"TINYDB";
+
+/// Current version of the dump format, bumped whenever the layout of the
+/// dumped database changes.
+pub(crate) const FORMAT_VERSION: u16 = 1;
+
+/// Length of the header written by [write_header].
+pub(crate) const HEADER_LEN: usize = MAGIC.len() + 3;
+
+/// Length of the block written by [Info::write].
+pub(crate) const INFO_LEN: usize = 32;
+
+/// Length of the checksum footer written by [write_footer].
+pub(crate) const FOOTER_LEN: usize = 8;
+
+/// Header flag set for dumps made with [crate::Database::archive], which also
+/// means the dump ends with a checksum footer.
+pub(crate) const FLAG_ARCHIVED: u8 = 0b0000_0001;
+
+/// Header flag set for dumps made with [crate::Database::dump_signed], which
+/// means the dump ends with a signature followed by its length as a [u32].
+pub(crate) const FLAG_SIGNED: u8 = 0b0000_0010;
+
+/// Header flag set for dumps encoded with [crate::encoding::Encoding::varint].
+pub(crate) const FLAG_VARINT: u8 = 0b0000_0100;
+
+/// Header flag set for dumps encoded with
+/// [crate::encoding::Encoding::big_endian].
+pub(crate) const FLAG_BIG_ENDIAN: u8 = 0b0000_1000;
+
+/// Header flag set for dumps with an [Info] block between the header and the
+/// encoded database, which every dump made by [crate::Database::dump_db] and
+/// the other ways of dumping has.
+pub(crate) const FLAG_INFO: u8 = 0b0001_0000;
+
+/// Every header flag this version of tinydb understands. Dumps with any other
+/// flag set were written by a newer version and can't be read correctly, so
+/// they're rejected rather than decoded as if the flag wasn't there.
+pub(crate) const KNOWN_FLAGS: u8 =
+    FLAG_ARCHIVED | FLAG_SIGNED | FLAG_VARINT | FLAG_BIG_ENDIAN | FLAG_INFO;
+
+/// Details about a dump which are written before the encoded database, so
+/// they can be read without knowing the type of its items, see
+/// [crate::inspect]. Timestamps are in milliseconds since the unix epoch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Info {
+    pub(crate) created_at: u64,
+    pub(crate) last_modified: u64,
+    pub(crate) last_dumped: u64,
+    pub(crate) schema: u64,
+}
+
+impl Info {
+    /// Writes this block, which is always [INFO_LEN] bytes long.
+    pub(crate) fn write(&self, writer: &mut impl Write) -> Result<(), error::DatabaseError> {
+        for field in [
+            self.created_at,
+            self.last_modified,
+            self.last_dumped,
+            self.schema,
+        ] {
+            writer.write_all(&field.to_le_bytes())?;
+        }
+
+        Ok(())
+    }
+
+    /// Reads a block from the start of `bytes`, returning [Option::None] if
+    /// there aren't enough of them.
+    pub(crate) fn read(bytes: &[u8]) -> Option<Info> {
+        let field = |index: usize| {
+            let mut field = [0; 8];
+
+            field.copy_from_slice(bytes.get(index * 8..index * 8 + 8)?);
+            Some(u64::from_le_bytes(field))
+        };
+
+        Some(Info {
+            created_at: field(0)?,
+            last_modified: field(1)?,
+            last_dumped: field(2)?,
+            schema: field(3)?,
+        })
+    }
+}
+
+/// Writes the header for the current format version with the given flags.
+pub(crate) fn write_header(writer: &mut impl Write, flags: u8) -> Result<(), error::DatabaseError> {
+    writer.write_all(MAGIC)?;
+    writer.write_all(&FORMAT_VERSION.to_le_bytes())?;
+    writer.write_all(&[flags])?;
+
+    Ok(())
+}
+
+/// Writes the checksum footer for an encoded database.
+pub(crate) fn write_footer(
+    writer: &mut impl Write,
+    payload: &[u8],
+) -> Result<(), error::DatabaseError> {
+    writer.write_all(&checksum(payload).to_le_bytes())?;
+
+    Ok(())
+}
+
+/// Writes the signature of everything written before it, followed by its
+/// length.
+pub(crate) fn write_signature(
+    writer: &mut impl Write,
+    signature: &[u8],
+) -> Result<(), error::DatabaseError> {
+    writer.write_all(signature)?;
+    writer.write_all(&(signature.len() as u32).to_le_bytes())?;
+
+    Ok(())
+}
+
+/// A signature found at the end of a dump alongside the message it signs.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct Signed<'a> {
+    /// Everything in the dump before the signature.
+    pub(crate) message: &'a [u8],
+
+    /// The signature itself.
+    pub(crate) signature: &'a [u8],
+}
+
+/// Kind of stream found after sniffing a header using [read_header].
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum Payload<'a> {
+    /// A dump of the current format version, without the header or footers.
+    Current {
+        flags: u8,
+        payload: &'a [u8],
+        signed: Option<Signed<'a>>,
+    },
+
+    /// A dump from before format versioning which has no header at all.
+    Headerless(&'a [u8]),
+}
+
+/// Reads the header from the start of a dump, returning the payload after it.
+///
+/// # Errors
+///
+/// Returns [error::DatabaseError::UnrecognizedFormat] if the stream is from a
+/// different version of tinydb or is something else entirely, such as a JSON
+/// export, or sets flags which aren't known. If the dump is archived and its
+/// footer doesn't match,
+/// [error::DatabaseError::ChecksumMismatch] is returned. Signatures are only
+/// split out here and have to be verified by the caller.
+pub(crate) fn read_header(stream: &[u8]) -> Result<Payload<'_>, error::DatabaseError> {
+    if !stream.starts_with(MAGIC) {
+        return match sniff(stream) {
+            Some(detected) => Err(error::DatabaseError::UnrecognizedFormat {
+                detected: Some(detected),
+            }),
+            None => Ok(Payload::Headerless(stream)),
+        };
+    }
+
+    if stream.len() < HEADER_LEN {
+        return Err(error::DatabaseError::UnrecognizedFormat {
+            detected: Some(String::from("truncated tinydb header")),
+        });
+    }
+
+    let version = u16::from_le_bytes([stream[MAGIC.len()], stream[MAGIC.len() + 1]]);
+
+    if version != FORMAT_VERSION {
+        return Err(error::DatabaseError::UnrecognizedFormat {
+            detected: Some(format!("tinydb format version {}", version)),
+        });
+    }
+
+    let flags = stream[HEADER_LEN - 1];
+
+    if flags & !KNOWN_FLAGS != 0 {
+        return Err(error::DatabaseError::UnrecognizedFormat {
+            detected: Some(format!("tinydb dump with unknown flags {:#010b}", flags)),
+        });
+    }
+
+    let mut payload = &stream[HEADER_LEN..];
+    let mut signed = None;
+
+    if flags & FLAG_SIGNED != 0 {
+        let (message, signature) = split_signature(stream)?;
+
+        payload = &message[HEADER_LEN..];
+        signed = Some(Signed { message, signature });
+    }
+
+    if flags & FLAG_ARCHIVED != 0 {
+        if payload.len() < FOOTER_LEN {
+            return Err(error::DatabaseError::ChecksumMismatch);
+        }
+
+        let (body, footer) = payload.split_at(payload.len() - FOOTER_LEN);
+        let mut expected = [0; FOOTER_LEN];
+
+        expected.copy_from_slice(footer);
+
+        if checksum(body) != u64::from_le_bytes(expected) {
+            return Err(error::DatabaseError::ChecksumMismatch);
+        }
+
+        payload = body;
+    }
+
+    if flags & FLAG_INFO != 0 {
+        if payload.len() < INFO_LEN {
+            return Err(error::DatabaseError::UnrecognizedFormat {
+                detected: Some(String::from("truncated tinydb header")),
+            });
+        }
+
+        payload = &payload[INFO_LEN..];
+    }
+
+    Ok(Payload::Current {
+        flags,
+        payload,
+        signed,
+    })
+}
+
+/// Splits the signature off the end of a signed dump.
+fn split_signature(stream: &[u8]) -> Result<(&[u8], &[u8]), error::DatabaseError> {
+    if stream.len() < HEADER_LEN + 4 {
+        return Err(error::DatabaseError::SignatureInvalid);
+    }
+
+    let (rest, length) = stream.split_at(stream.len() - 4);
+    let length = u32::from_le_bytes([length[0], length[1], length[2], length[3]]) as usize;
+
+    if rest.len() < HEADER_LEN + length {
+        return Err(error::DatabaseError::SignatureInvalid);
+    }
+
+    Ok(rest.split_at(rest.len() - length))
+}
+
+/// Reads only the flags from the header of a dump, returning [Option::None]
+/// if the stream doesn't start with a header of the current format version or
+/// sets flags which aren't known.
+pub(crate) fn read_flags(stream: &[u8]) -> Option<u8> {
+    if stream.len() >= HEADER_LEN
+        && stream.starts_with(MAGIC)
+        && stream[MAGIC.len()..HEADER_LEN - 1] == FORMAT_VERSION.to_le_bytes()
+        && stream[HEADER_LEN - 1] & !KNOWN_FLAGS == 0
+    {
+        Some(stream[HEADER_LEN - 1])
+    } else {
+        None
+    }
+}
+
+/// Gets where the encoded database starts in a dump with the given header
+/// flags.
+pub(crate) fn payload_offset(flags: u8) -> usize {
+    match flags & FLAG_INFO {
+        0 => HEADER_LEN,
+        _ => HEADER_LEN + INFO_LEN,
+    }
+}
+
+/// Checksums an encoded database using 64-bit FNV-1a, which is plenty to
+/// catch accidental changes to an archived dump.
+pub(crate) fn checksum(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Guesses what a stream without the tinydb magic bytes is, returning
+/// [Option::None] if it could be a headerless tinydb dump.
+fn sniff(stream: &[u8]) -> Option<String> {
+    const SIGNATURES: &[(&[u8], &str)] = &[
+        (b"SQLite format 3\0", "sqlite database"),
+        (b"PK\x03\x04", "zip archive"),
+        (b"\x1f\x8b", "gzip archive"),
+        (b"\xef\xbb\xbf", "utf-8 text"),
+    ];
+
+    if stream.is_empty() {
+        return Some(String::from("empty file"));
+    }
+
+    for (signature, name) in SIGNATURES {
+        if stream.starts_with(signature) {
+            return Some(String::from(*name));
+        }
+    }
+
+    let first = stream.iter().find(|byte| !byte.is_ascii_whitespace());
+
+    match first {
+        Some(b'{') | Some(b'[') if !stream.contains(&0) && std::str::from_utf8(stream).is_ok() => {
+            Some(String::from("json"))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests a header round-trip
+    #[test]
+    fn header_round_trip() -> Result<(), error::DatabaseError> {
+        let mut stream = Vec::new();
+
+        write_header(&mut stream, 0)?;
+        stream.extend_from_slice(&[1, 2, 3]);
+
+        assert_eq!(
+            read_header(&stream)?,
+            Payload::Current {
+                flags: 0,
+                payload: &[1, 2, 3],
+                signed: None
+            }
+        );
+        Ok(())
+    }
+
+    /// Tests the info block being written and skipped over when reading the
+    /// header
+    #[test]
+    fn info_round_trip() -> Result<(), error::DatabaseError> {
+        let info = Info {
+            created_at: 1,
+            last_modified: 2,
+            last_dumped: 3,
+            schema: u64::MAX,
+        };
+        let mut stream = Vec::new();
+
+        write_header(&mut stream, FLAG_INFO)?;
+        info.write(&mut stream)?;
+        stream.extend_from_slice(&[1, 2, 3]);
+
+        assert_eq!(payload_offset(FLAG_INFO), HEADER_LEN + INFO_LEN);
+        assert_eq!(Info::read(&stream[HEADER_LEN..]), Some(info));
+        assert_eq!(Info::read(&stream[HEADER_LEN..HEADER_LEN + 8]), None);
+        assert_eq!(
+            read_header(&stream)?,
+            Payload::Current {
+                flags: FLAG_INFO,
+                payload: &[1, 2, 3],
+                signed: None
+            }
+        );
+        Ok(())
+    }
+
+    /// Tests archived dumps having their footer checked
+    #[test]
+    fn archived_footer() -> Result<(), error::DatabaseError> {
+        let mut stream = Vec::new();
+
+        write_header(&mut stream, FLAG_ARCHIVED)?;
+        stream.extend_from_slice(&[1, 2, 3]);
+        write_footer(&mut stream, &[1, 2, 3])?;
+
+        assert_eq!(read_flags(&stream), Some(FLAG_ARCHIVED));
+        assert_eq!(
+            read_header(&stream)?,
+            Payload::Current {
+                flags: FLAG_ARCHIVED,
+                payload: &[1, 2, 3],
+                signed: None
+            }
+        );
+
+        stream[HEADER_LEN] = 9;
+
+        match read_header(&stream) {
+            Err(error::DatabaseError::ChecksumMismatch) => Ok(()),
+            _ => panic!("changed archive wasn't detected"),
+        }
+    }
+
+    /// Tests signatures being split from the end of an archived dump
+    #[test]
+    fn signature_split() -> Result<(), error::DatabaseError> {
+        let mut stream = Vec::new();
+
+        write_header(&mut stream, FLAG_ARCHIVED | FLAG_SIGNED)?;
+        stream.extend_from_slice(&[1, 2, 3]);
+        write_footer(&mut stream, &[1, 2, 3])?;
+
+        let message = stream.clone();
+
+        write_signature(&mut stream, &[9, 9])?;
+
+        match read_header(&stream)? {
+            Payload::Current {
+                payload, signed, ..
+            } => {
+                assert_eq!(payload, &[1, 2, 3]);
+                assert_eq!(
+                    signed,
+                    Some(Signed {
+                        message: &message,
+                        signature: &[9, 9]
+                    })
+                );
+            }
+            _ => panic!("signed dump wasn't read"),
+        }
+
+        Ok(())
+    }
+
+    /// Tests other formats being detected rather than decoded
+    #[test]
+    fn sniff_formats() {
+        assert_eq!(
+            sniff(b"  {\"label\": \"json\"}"),
+            Some(String::from("json"))
+        );
+        assert_eq!(sniff(b"PK\x03\x04rest"), Some(String::from("zip archive")));
+        assert_eq!(sniff(b""), Some(String::from("empty file")));
+        assert_eq!(sniff(&[12, 0, 0, 0, 0, 0, 0, 0]), None);
+
+        match read_header(b"TINYDB\x09\x00\x00") {
+            Err(error::DatabaseError::UnrecognizedFormat { detected }) => {
+                assert_eq!(detected, Some(String::from("tinydb format version 9")))
+            }
+            _ => panic!("newer format version wasn't detected"),
+        }
+    }
+}
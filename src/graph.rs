@@ -0,0 +1,392 @@
+//! Contains [Graph], which keeps nodes in one [Database] and typed edges
+//! between them in another for modelling relationships between items.
+
+use crate::managed::Managed;
+use crate::{error, paths, Database};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::hash;
+
+/// A directed edge between two nodes of a [Graph], with a `kind` telling
+/// different relationships between the same nodes apart.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Edge<K> {
+    from: u64,
+    to: u64,
+    kind: K,
+}
+
+impl<K> Edge<K> {
+    /// Creates an edge, for adding to the database underneath a [Graph]
+    /// directly.
+    pub fn new(from: u64, to: u64, kind: K) -> Self {
+        Edge { from, to, kind }
+    }
+
+    /// Gets the id of the node this edge starts at.
+    pub fn from(&self) -> u64 {
+        self.from
+    }
+
+    /// Gets the id of the node this edge points to.
+    pub fn to(&self) -> u64 {
+        self.to
+    }
+
+    /// Gets the kind of this edge.
+    pub fn kind(&self) -> &K {
+        &self.kind
+    }
+}
+
+/// Nodes of type `N` linked by directed edges of kind `K`. Nodes are stored
+/// as [Managed] items so each gets an id from [Database::next_id], with edges
+/// kept in a second database and both dumped alongside each other.
+///
+/// # Examples
+///
+/// ```rust
+/// use tinydb::graph::Graph;
+///
+/// fn main() {
+///     let mut graph: Graph<String, String> = Graph::new("graph_test");
+///     let main = graph.add_node(String::from("main.rs")).unwrap();
+///     let lib = graph.add_node(String::from("lib.rs")).unwrap();
+///     let util = graph.add_node(String::from("util.rs")).unwrap();
+///
+///     graph.connect(main, lib, String::from("imports")).unwrap();
+///     graph.connect(lib, util, String::from("imports")).unwrap();
+///     graph.dump().unwrap();
+///     drop(graph);
+///
+///     let graph: Graph<String, String> = Graph::from("graph_test").unwrap();
+///
+///     assert_eq!(graph.node(lib), Some(&String::from("lib.rs")));
+///     assert_eq!(graph.path(main, util), Some(vec![main, lib, util]));
+///     # std::fs::remove_file("graph_test.nodes.tinydb").unwrap();
+///     # std::fs::remove_file("graph_test.edges.tinydb").unwrap();
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Graph<N, K: hash::Hash + Eq> {
+    nodes: Database<Managed<N>>,
+    edges: Database<Edge<K>>,
+}
+
+impl<N, K: hash::Hash + Eq> Graph<N, K> {
+    /// Creates an empty graph, dumped to `<label>.nodes.tinydb` and
+    /// `<label>.edges.tinydb` in the working directory.
+    pub fn new(label: impl AsRef<str>) -> Self {
+        let (nodes, edges) = labels(label.as_ref());
+
+        Graph {
+            nodes: Database::new(nodes, None, false),
+            edges: Database::new(edges, None, false),
+        }
+    }
+
+    /// Wraps a database of nodes and one of edges between them, such as ones
+    /// saved to paths other than [Graph::new] uses.
+    pub fn from_dbs(nodes: Database<Managed<N>>, edges: Database<Edge<K>>) -> Self {
+        Graph { nodes, edges }
+    }
+
+    /// Adds a node, returning its id.
+    ///
+    /// # Errors
+    ///
+    /// Fails in the same ways as [Database::add_managed].
+    pub fn add_node(&mut self, node: N) -> Result<u64, error::DatabaseError> {
+        self.nodes.add_managed(node)
+    }
+
+    /// Gets the node with an id.
+    pub fn node(&self, id: u64) -> Option<&N> {
+        self.nodes.get_managed(id).map(Managed::item)
+    }
+
+    /// Removes the node with an id along with every edge to or from it,
+    /// returning the node.
+    ///
+    /// # Errors
+    ///
+    /// Fails in the same ways as [Database::remove_managed], or with
+    /// [error::DatabaseError::Archived] if the edges can't be changed.
+    pub fn remove_node(&mut self, id: u64) -> Result<N, error::DatabaseError> {
+        self.edges.check_mutable()?;
+
+        let node = self.nodes.remove_managed(id)?;
+
+        self.edges.history.begin_step();
+        self.edges
+            .take_where(|edge| edge.from == id || edge.to == id);
+
+        Ok(node)
+    }
+
+    /// Adds an edge of `kind` from one node to another, returning `false` if
+    /// the same edge already existed.
+    ///
+    /// # Errors
+    ///
+    /// Will return [error::DatabaseError::ItemNotFound] if either node
+    /// doesn't exist, otherwise fails in the same ways as
+    /// [Database::add_item].
+    pub fn connect(&mut self, from: u64, to: u64, kind: K) -> Result<bool, error::DatabaseError> {
+        if self.node(from).is_none() || self.node(to).is_none() {
+            return Err(error::DatabaseError::ItemNotFound);
+        }
+
+        self.edges.check_mutable()?;
+        self.edges.history.begin_step();
+        self.edges.insert_by_policy(Edge::new(from, to, kind))
+    }
+
+    /// Removes the edge of `kind` from one node to another.
+    ///
+    /// # Errors
+    ///
+    /// Will return [error::DatabaseError::ItemNotFound] if there's no such
+    /// edge or [error::DatabaseError::Archived] if the edges can't be
+    /// changed.
+    pub fn disconnect(&mut self, from: u64, to: u64, kind: &K) -> Result<(), error::DatabaseError> {
+        self.edges.check_mutable()?;
+        self.edges.history.begin_step();
+
+        let taken = self
+            .edges
+            .take_where(|edge| edge.from == from && edge.to == to && &edge.kind == kind);
+
+        match taken.is_empty() {
+            true => Err(error::DatabaseError::ItemNotFound),
+            false => Ok(()),
+        }
+    }
+
+    /// Iterates over every edge starting at a node, in an arbitrary order.
+    pub fn edges_from(&self, id: u64) -> impl Iterator<Item = &Edge<K>> {
+        self.live_edges().filter(move |edge| edge.from == id)
+    }
+
+    /// Iterates over every edge pointing to a node, in an arbitrary order.
+    pub fn edges_to(&self, id: u64) -> impl Iterator<Item = &Edge<K>> {
+        self.live_edges().filter(move |edge| edge.to == id)
+    }
+
+    /// Gets the ids of every node a node has an edge to, whatever its kind.
+    pub fn neighbors(&self, id: u64) -> HashSet<u64> {
+        self.edges_from(id).map(Edge::to).collect()
+    }
+
+    /// Gets the ids of every node a node has an edge of `kind` to.
+    pub fn neighbors_of_kind(&self, id: u64, kind: &K) -> HashSet<u64> {
+        self.edges_from(id)
+            .filter(|edge| &edge.kind == kind)
+            .map(Edge::to)
+            .collect()
+    }
+
+    /// Gets the ids of every node with an edge to a node, whatever its kind.
+    pub fn incoming(&self, id: u64) -> HashSet<u64> {
+        self.edges_to(id).map(Edge::from).collect()
+    }
+
+    /// Gets the ids of every node which can be reached from a node by
+    /// following edges, not including the node itself unless it's part of a
+    /// cycle.
+    pub fn reachable(&self, from: u64) -> HashSet<u64> {
+        self.search(from, None, |_| true).into_keys().collect()
+    }
+
+    /// Gets the ids of every node which can be reached from a node by only
+    /// following edges of `kind`, like [Graph::reachable].
+    pub fn reachable_via(&self, from: u64, kind: &K) -> HashSet<u64> {
+        self.search(from, None, |edge| &edge.kind == kind)
+            .into_keys()
+            .collect()
+    }
+
+    /// Gets the ids of the nodes along one of the shortest paths between two
+    /// nodes, including both ends, or [None] if `to` can't be reached.
+    pub fn path(&self, from: u64, to: u64) -> Option<Vec<u64>> {
+        if from == to {
+            return self.node(from).map(|_| vec![from]);
+        }
+
+        let parents = self.search(from, Some(to), |_| true);
+        let mut path = vec![to];
+        let mut current = to;
+
+        while current != from {
+            current = *parents.get(&current)?;
+            path.push(current);
+        }
+
+        path.reverse();
+        Some(path)
+    }
+
+    /// Gets the database of nodes underneath, for anything not covered here
+    /// such as [Database::update_managed].
+    pub fn nodes(&self) -> &Database<Managed<N>> {
+        &self.nodes
+    }
+
+    /// Gets the database of nodes underneath mutably.
+    pub fn nodes_mut(&mut self) -> &mut Database<Managed<N>> {
+        &mut self.nodes
+    }
+
+    /// Gets the database of edges underneath.
+    pub fn edges(&self) -> &Database<Edge<K>> {
+        &self.edges
+    }
+
+    /// Gets the database of edges underneath mutably. Edges added to it
+    /// directly aren't checked to point between existing nodes.
+    pub fn edges_mut(&mut self) -> &mut Database<Edge<K>> {
+        &mut self.edges
+    }
+
+    /// Unwraps the databases of nodes and edges underneath.
+    pub fn into_dbs(self) -> (Database<Managed<N>>, Database<Edge<K>>) {
+        (self.nodes, self.edges)
+    }
+
+    /// Iterates over every edge which hasn't expired.
+    fn live_edges(&self) -> impl Iterator<Item = &Edge<K>> {
+        self.edges
+            .items
+            .iter()
+            .filter(move |edge| !self.edges.is_expired(edge))
+    }
+
+    /// Searches breadth-first from a node along edges matching `follow`,
+    /// stopping early once `target` is found. Returns every node found
+    /// alongside the node it was first reached from.
+    fn search(
+        &self,
+        from: u64,
+        target: Option<u64>,
+        mut follow: impl FnMut(&Edge<K>) -> bool,
+    ) -> HashMap<u64, u64> {
+        let mut adjacent: HashMap<u64, Vec<u64>> = HashMap::new();
+
+        for edge in self.live_edges().filter(|edge| follow(edge)) {
+            adjacent.entry(edge.from).or_default().push(edge.to);
+        }
+
+        let mut parents = HashMap::new();
+        let mut queue = VecDeque::from(vec![from]);
+
+        while let Some(current) = queue.pop_front() {
+            for &next in adjacent.get(&current).into_iter().flatten() {
+                if parents.contains_key(&next) || self.node(next).is_none() {
+                    continue;
+                }
+
+                parents.insert(next, current);
+
+                if Some(next) == target {
+                    return parents;
+                }
+
+                queue.push_back(next);
+            }
+        }
+
+        parents
+    }
+}
+
+impl<N: Serialize, K: hash::Hash + Eq + Serialize> Graph<N, K> {
+    /// Dumps both the nodes and edges using [Database::dump_db].
+    pub fn dump(&self) -> Result<(), error::DatabaseError> {
+        self.nodes.dump_db()?;
+        self.edges.dump_db()?;
+
+        Ok(())
+    }
+}
+
+impl<N: DeserializeOwned, K: hash::Hash + Eq + DeserializeOwned> Graph<N, K> {
+    /// Loads a graph dumped by a [Graph::new] with the same `label`, using
+    /// [Database::from] for both the nodes and edges.
+    ///
+    /// # Errors
+    ///
+    /// Fails in the same ways as [Database::from].
+    pub fn from(label: impl AsRef<str>) -> Result<Self, error::DatabaseError> {
+        let (nodes, edges) = labels(label.as_ref());
+
+        Ok(Graph {
+            nodes: Database::from(paths::label_path(&nodes))?,
+            edges: Database::from(paths::label_path(&edges))?,
+        })
+    }
+
+    /// Loads a graph like [Graph::from], creating an empty one in place of
+    /// any dump which doesn't exist yet using [Database::auto_from].
+    ///
+    /// # Errors
+    ///
+    /// Fails in the same ways as [Database::auto_from].
+    pub fn auto_from(label: impl AsRef<str>) -> Result<Self, error::DatabaseError> {
+        let (nodes, edges) = labels(label.as_ref());
+
+        Ok(Graph {
+            nodes: Database::auto_from(paths::label_path(&nodes), false)?,
+            edges: Database::auto_from(paths::label_path(&edges), false)?,
+        })
+    }
+}
+
+/// Gets the labels of the databases of nodes and edges for a graph.
+fn labels(label: &str) -> (String, String) {
+    (format!("{}.nodes", label), format!("{}.edges", label))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests neighbor queries, reachability along kinds of edge and edges
+    /// being removed with their nodes
+    #[test]
+    fn graph_reachability() -> Result<(), error::DatabaseError> {
+        let mut graph: Graph<&str, u8> = Graph::new("Graph test");
+        let a = graph.add_node("a")?;
+        let b = graph.add_node("b")?;
+        let c = graph.add_node("c")?;
+        let d = graph.add_node("d")?;
+
+        assert!(graph.connect(a, b, 0)?);
+        assert!(!graph.connect(a, b, 0)?);
+        graph.connect(b, c, 1)?;
+        graph.connect(c, a, 0)?;
+
+        assert_eq!(
+            graph.connect(a, 99, 0),
+            Err(error::DatabaseError::ItemNotFound)
+        );
+        assert_eq!(graph.neighbors(a), HashSet::from([b]));
+        assert_eq!(graph.incoming(a), HashSet::from([c]));
+        assert_eq!(graph.reachable(a), HashSet::from([a, b, c]));
+        assert_eq!(graph.reachable_via(a, &0), HashSet::from([b]));
+        assert_eq!(graph.path(b, a), Some(vec![b, c, a]));
+        assert_eq!(graph.path(a, d), None);
+
+        graph.remove_node(c)?;
+
+        assert_eq!(graph.edges().items.len(), 1);
+        assert_eq!(graph.neighbors_of_kind(b, &1), HashSet::new());
+        assert_eq!(
+            graph.disconnect(b, c, &1),
+            Err(error::DatabaseError::ItemNotFound)
+        );
+        graph.disconnect(a, b, &0)?;
+
+        assert!(graph.reachable(a).is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,412 @@
+//! Contains [ItemId] handles, which refer to an item inside of a
+//! [crate::Database] without borrowing it so they can be kept across
+//! mutations, see [crate::Database::track_ids].
+
+use crate::compat::HashMap;
+use crate::{error, Database};
+use alloc::sync::Arc;
+use core::{fmt, hash};
+
+/// An opaque handle to an item, assigned when it's added to a database which
+/// is tracking ids with [Database::track_ids].
+///
+/// Handles stay the same when an item is replaced using [Database::update]
+/// and are never reused for another item, even once the item is removed. Each
+/// handle also has a revision which is bumped on every update, see
+/// [Database::update_if_revision].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ItemId(u64);
+
+/// Clones an item, captured when ids start being tracked so that
+/// [Clone] isn't required everywhere.
+type Cloner<T> = Arc<dyn Fn(&T) -> T + Send + Sync>;
+
+/// Ids assigned to each item once [Database::track_ids] has been called,
+/// kept up-to-date on each mutation.
+///
+/// Ids aren't saved when dumping so they never take part in comparisons
+/// between databases.
+pub(crate) struct Handles<T> {
+    cloner: Option<Cloner<T>>,
+    ids: HashMap<T, ItemId>,
+    items: HashMap<ItemId, T>,
+    revisions: HashMap<ItemId, u64>,
+    next_id: u64,
+}
+
+impl<T: hash::Hash + Eq> Handles<T> {
+    /// Checks if ids are being tracked.
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.cloner.is_some()
+    }
+
+    /// Assigns a new id to an item which has just been added to the database,
+    /// if ids are being tracked.
+    pub(crate) fn insert(&mut self, item: &T) {
+        if let Some(cloner) = &self.cloner {
+            let id = ItemId(self.next_id);
+
+            self.next_id += 1;
+            self.ids.insert(cloner(item), id);
+            self.items.insert(id, cloner(item));
+            self.revisions.insert(id, 0);
+        }
+    }
+
+    /// Forgets the id of an item which has just been removed from the
+    /// database.
+    pub(crate) fn remove(&mut self, item: &T) {
+        if let Some(id) = self.ids.remove(item) {
+            self.items.remove(&id);
+            self.revisions.remove(&id);
+        }
+    }
+
+    /// Makes sure every item has exactly one id whilst ids are being tracked,
+    /// see [crate::testing::check_invariants].
+    #[cfg(feature = "testing")]
+    pub(crate) fn check_consistent(
+        &self,
+        len: usize,
+        contains: &dyn Fn(&T) -> bool,
+    ) -> Result<(), String> {
+        if !self.is_enabled() {
+            return Ok(());
+        }
+
+        match self.ids.len() == len && self.items.len() == len && self.ids.keys().all(contains) {
+            true => Ok(()),
+            false => Err(String::from("ids are out of sync with the items")),
+        }
+    }
+
+    /// Gives the most recently added item the id of the item it replaced at
+    /// the given revision, so handles survive updates.
+    fn reassign_last(&mut self, id: ItemId, revision: u64) {
+        let last = ItemId(self.next_id.wrapping_sub(1));
+
+        if let Some(copy) = self.items.remove(&last) {
+            if let Some(assigned) = self.ids.get_mut(&copy) {
+                *assigned = id;
+            }
+
+            self.items.insert(id, copy);
+            self.revisions.remove(&last);
+            self.revisions.insert(id, revision);
+        }
+    }
+}
+
+impl<T> Default for Handles<T> {
+    fn default() -> Self {
+        Handles {
+            cloner: None,
+            ids: HashMap::new(),
+            items: HashMap::new(),
+            revisions: HashMap::new(),
+            next_id: 0,
+        }
+    }
+}
+
+impl<T: hash::Hash + Eq> Clone for Handles<T> {
+    fn clone(&self) -> Self {
+        let cloner = match &self.cloner {
+            Some(cloner) => cloner,
+            None => return Handles::default(),
+        };
+
+        Handles {
+            cloner: Some(Arc::clone(cloner)),
+            ids: self
+                .ids
+                .iter()
+                .map(|(item, id)| (cloner(item), *id))
+                .collect(),
+            items: self
+                .items
+                .iter()
+                .map(|(id, item)| (*id, cloner(item)))
+                .collect(),
+            revisions: self.revisions.clone(),
+            next_id: self.next_id,
+        }
+    }
+}
+
+impl<T> PartialEq for Handles<T> {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl<T> Eq for Handles<T> {}
+
+impl<T> fmt::Debug for Handles<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.items.keys()).finish()
+    }
+}
+
+impl<T: hash::Hash + Eq> Database<T> {
+    /// Starts assigning an [ItemId] to every item, including those already
+    /// inside of the database. Calling this again does nothing.
+    ///
+    /// Handles are runtime-only, so they are not saved when dumping and have
+    /// to be tracked again after loading. Tracking keeps a copy of each item,
+    /// hence [Clone] being needed.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tinydb::Database;
+    ///
+    /// fn main() {
+    ///     let mut db = Database::new("handle_test", None, false);
+    ///
+    ///     db.track_ids();
+    ///     db.add_item(String::from("Lister")).unwrap();
+    ///
+    ///     let id = db.id_of(&String::from("Lister")).unwrap();
+    ///
+    ///     db.add_item(String::from("Rimmer")).unwrap();
+    ///     db.update(id, String::from("Dave Lister")).unwrap();
+    ///
+    ///     assert_eq!(db.get(id).unwrap(), "Dave Lister");
+    ///     assert_eq!(db.remove(id).unwrap(), "Dave Lister");
+    ///     assert!(db.get(id).is_none());
+    /// }
+    /// ```
+    pub fn track_ids(&mut self)
+    where
+        T: Clone + 'static,
+    {
+        if self.handles.cloner.is_some() {
+            return;
+        }
+
+        self.handles.cloner = Some(Arc::new(T::clone));
+
+        for item in self.items.iter() {
+            self.handles.insert(item);
+        }
+    }
+
+    /// Gets the [ItemId] of an item, or [Option::None] if it isn't inside of
+    /// the database or ids aren't being tracked with [Database::track_ids].
+    pub fn id_of(&self, item: &T) -> Option<ItemId> {
+        self.handles
+            .ids
+            .get(item)
+            .copied()
+            .filter(|_| !self.is_expired(item))
+    }
+
+    /// Gets the item an [ItemId] refers to, or [Option::None] if it has been
+    /// removed or has expired.
+    pub fn get(&self, id: ItemId) -> Option<&T> {
+        self.handles
+            .items
+            .get(&id)
+            .and_then(|copy| self.items.get(copy))
+            .filter(|item| !self.is_expired(item))
+            .inspect(|item| {
+                self.access.accessed(item);
+            })
+    }
+
+    /// Gets the revision of the item an [ItemId] refers to, which starts at
+    /// `0` when the item is added and goes up by one on each
+    /// [Database::update]. Returns [Option::None] like [Database::get] does.
+    pub fn revision(&self, id: ItemId) -> Option<u64> {
+        self.get(id)?;
+        self.handles.revisions.get(&id).copied()
+    }
+
+    /// Removes the item an [ItemId] refers to, returning it.
+    ///
+    /// # Errors
+    ///
+    /// Will return [error::DatabaseError::ItemNotFound] if the item has
+    /// already been removed or [error::DatabaseError::Archived] if the
+    /// database has been archived.
+    pub fn remove(&mut self, id: ItemId) -> Result<T, error::DatabaseError> {
+        self.check_mutable()?;
+        self.history.begin_step();
+
+        let copy = self
+            .handles
+            .items
+            .remove(&id)
+            .ok_or(error::DatabaseError::ItemNotFound)?;
+
+        self.take_item(&copy)
+            .ok_or(error::DatabaseError::ItemNotFound)
+    }
+
+    /// Replaces the item an [ItemId] refers to with `new`, which then takes
+    /// over the same id. See [Database::update_item] for how updates are
+    /// otherwise handled.
+    ///
+    /// If [Database::strict_dupes] is disabled and `new` is already another
+    /// item, the two are merged and only the id of the other item is kept.
+    ///
+    /// # Errors
+    ///
+    /// Will return [error::DatabaseError::ItemNotFound] if the item has
+    /// already been removed, [error::DatabaseError::DupeFound] if
+    /// [Database::strict_dupes] is enabled and `new` is already another item
+    /// or [error::DatabaseError::Archived] if the database has been archived.
+    pub fn update(&mut self, id: ItemId, new: T) -> Result<(), error::DatabaseError> {
+        self.check_mutable()?;
+        self.constraints.check(&new)?;
+
+        if let Some(old) = self.handles.items.get(&id) {
+            self.indexes.check_unique(&new, core::slice::from_ref(old), &[])?;
+        }
+
+        self.history.begin_step();
+
+        let old = match self.handles.items.get(&id) {
+            Some(old) if *old == new => return Ok(()),
+            Some(old) => self.items.take(old),
+            None => None,
+        }
+        .ok_or(error::DatabaseError::ItemNotFound)?;
+
+        if self.strict_dupes && self.items.contains(&new) {
+            self.items.insert(old);
+            return Err(error::DatabaseError::DupeFound);
+        }
+
+        let revision = self.handles.revisions.get(&id).map_or(0, |rev| rev + 1);
+
+        self.forget_item(&old);
+
+        if self.insert_item(new) {
+            self.handles.reassign_last(id, revision);
+        }
+
+        Ok(())
+    }
+
+    /// Replaces the item an [ItemId] refers to like [Database::update], but
+    /// only if it's still at the `expected` revision, returning the new
+    /// revision. This allows optimistic concurrency where several parts of
+    /// an app read an item, change it and write it back without overwriting
+    /// each other.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tinydb::Database;
+    ///
+    /// fn main() {
+    ///     let mut db = Database::new("revision_test", None, false);
+    ///
+    ///     db.track_ids();
+    ///     db.add_item(String::from("Lister")).unwrap();
+    ///
+    ///     let id = db.id_of(&String::from("Lister")).unwrap();
+    ///     let seen = db.revision(id).unwrap();
+    ///
+    ///     assert_eq!(db.update_if_revision(id, seen, String::from("Dave")).unwrap(), 1);
+    ///     assert!(db.update_if_revision(id, seen, String::from("Dave Lister")).is_err());
+    ///     assert_eq!(db.get(id).unwrap(), "Dave");
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Will return [error::DatabaseError::RevisionConflict] if the item has
+    /// been updated since `expected` was read, otherwise fails in the same
+    /// ways as [Database::update].
+    pub fn update_if_revision(
+        &mut self,
+        id: ItemId,
+        expected: u64,
+        new: T,
+    ) -> Result<u64, error::DatabaseError> {
+        let found = self
+            .revision(id)
+            .ok_or(error::DatabaseError::ItemNotFound)?;
+
+        if found != expected {
+            return Err(error::DatabaseError::RevisionConflict { expected, found });
+        }
+
+        self.update(id, new)?;
+
+        Ok(self.handles.revisions.get(&id).copied().unwrap_or(found))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests ids surviving other mutations and being assigned to existing items
+    #[test]
+    fn handle_tracking() -> Result<(), error::DatabaseError> {
+        let mut db = Database::new("Handle test", None, true);
+
+        db.add_item(1)?;
+        db.track_ids();
+        db.add_item(2)?;
+
+        let first = db.id_of(&1).unwrap();
+        let second = db.id_of(&2).unwrap();
+
+        assert_ne!(first, second);
+
+        db.add_item(3)?;
+        db.remove_item(&3)?;
+
+        assert_eq!(db.get(first), Some(&1));
+        assert_eq!(db.get(second), Some(&2));
+        assert!(db.update(first, 2).is_err());
+        assert_eq!(db.get(first), Some(&1));
+
+        db.update(first, 10)?;
+
+        assert_eq!(db.get(first), Some(&10));
+        assert_eq!(db.id_of(&10), Some(first));
+        assert_eq!(db.clone().get(second), Some(&2));
+
+        db.remove_item(&2)?;
+
+        assert!(db.get(second).is_none());
+        assert!(db.remove(second).is_err());
+
+        Ok(())
+    }
+
+    /// Tests revisions being bumped on updates and conflicting when stale
+    #[test]
+    fn handle_revisions() -> Result<(), error::DatabaseError> {
+        let mut db = Database::new("Revision test", None, true);
+
+        db.track_ids();
+        db.add_item(1)?;
+
+        let id = db.id_of(&1).unwrap();
+
+        assert_eq!(db.revision(id), Some(0));
+        assert_eq!(db.update_if_revision(id, 0, 2)?, 1);
+        assert_eq!(db.update_if_revision(id, 1, 3)?, 2);
+        assert!(matches!(
+            db.update_if_revision(id, 1, 4),
+            Err(error::DatabaseError::RevisionConflict {
+                expected: 1,
+                found: 2
+            })
+        ));
+        assert_eq!(db.get(id), Some(&3));
+
+        db.remove(id)?;
+
+        assert!(db.revision(id).is_none());
+
+        Ok(())
+    }
+}
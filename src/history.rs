@@ -0,0 +1,301 @@
+//! Contains the undo and redo history of a [crate::Database], which is opt-in
+//! using [crate::Database::enable_history].
+
+use crate::compat::Vec;
+use crate::events::ChangeEvent;
+use crate::snapshot::SharedState;
+use crate::{error, Database};
+use alloc::collections::VecDeque;
+use alloc::sync::Arc;
+use core::{fmt, hash};
+
+/// Clones an item, captured when history is enabled so that [Clone] isn't
+/// required everywhere.
+type Cloner<T> = Arc<dyn Fn(&T) -> T + Send + Sync>;
+
+/// All changes made by a single call to a public method, which are undone and
+/// redone together.
+type Step<T> = Vec<ChangeEvent<T>>;
+
+/// Steps which can be undone or redone, recorded from each mutation.
+///
+/// History is runtime-only so it never takes part in comparisons between
+/// databases.
+pub(crate) struct History<T> {
+    cloner: Option<Cloner<T>>,
+    depth: usize,
+    undo: VecDeque<Step<T>>,
+    redo: Vec<Step<T>>,
+    new_step: bool,
+    replaying: bool,
+}
+
+impl<T> History<T> {
+    /// Checks if mutations are being recorded.
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.cloner.is_some()
+    }
+
+    /// Starts a new step, which happens at the start of every public method
+    /// which mutates the database.
+    pub(crate) fn begin_step(&mut self) {
+        self.new_step = true;
+    }
+
+    /// Records a change as part of the current step, if history is enabled.
+    pub(crate) fn record(&mut self, event: ChangeEvent<&T>) {
+        let cloner = match &self.cloner {
+            Some(cloner) if !self.replaying => cloner,
+            _ => return,
+        };
+
+        if self.new_step || self.undo.is_empty() {
+            self.new_step = false;
+            self.redo.clear();
+            self.undo.push_back(Vec::new());
+
+            if self.undo.len() > self.depth {
+                self.undo.pop_front();
+            }
+        }
+
+        if let Some(step) = self.undo.back_mut() {
+            step.push(event.map(|item| cloner(item)));
+        }
+    }
+
+    /// Forgets all steps whilst staying enabled.
+    #[cfg(feature = "std")]
+    pub(crate) fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+}
+
+impl<T> Default for History<T> {
+    fn default() -> Self {
+        History {
+            cloner: None,
+            depth: 0,
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+            new_step: false,
+            replaying: false,
+        }
+    }
+}
+
+impl<T> Clone for History<T> {
+    fn clone(&self) -> Self {
+        let cloner = match &self.cloner {
+            Some(cloner) => cloner,
+            None => return History::default(),
+        };
+        let copy_step = |step: &Step<T>| {
+            step.iter()
+                .map(|event| event.as_ref().map(|item| cloner(item)))
+                .collect()
+        };
+
+        History {
+            cloner: Some(Arc::clone(cloner)),
+            depth: self.depth,
+            undo: self.undo.iter().map(copy_step).collect(),
+            redo: self.redo.iter().map(copy_step).collect(),
+            new_step: self.new_step,
+            replaying: false,
+        }
+    }
+}
+
+impl<T> PartialEq for History<T> {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl<T> Eq for History<T> {}
+
+impl<T> fmt::Debug for History<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("History")
+            .field("depth", &self.depth)
+            .field("undo", &self.undo.len())
+            .field("redo", &self.redo.len())
+            .finish()
+    }
+}
+
+impl<T: hash::Hash + Eq> Database<T> {
+    /// Starts recording each mutation so it can be reverted with
+    /// [Database::undo] and reapplied with [Database::redo], keeping up to
+    /// `depth` steps. Enabling history again clears any recorded steps.
+    ///
+    /// Each call to a method such as [Database::add_item],
+    /// [Database::update_item] or [Database::modify_where] is a single step.
+    /// Only the items themselves are recorded, so pins and expiries aren't
+    /// brought back when undoing. History is not saved when dumping and is
+    /// cleared by [Database::reload].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tinydb::Database;
+    ///
+    /// fn main() {
+    ///     let mut db = Database::new("history_test", None, false);
+    ///
+    ///     db.enable_history(100);
+    ///     db.add_item(String::from("Lister")).unwrap();
+    ///     db.update_item(&String::from("Lister"), String::from("Rimmer")).unwrap();
+    ///
+    ///     assert!(db.undo().unwrap());
+    ///     assert!(db.contains(&String::from("Lister")));
+    ///
+    ///     assert!(db.redo().unwrap());
+    ///     assert!(db.contains(&String::from("Rimmer")));
+    /// }
+    /// ```
+    pub fn enable_history(&mut self, depth: usize)
+    where
+        T: Clone + 'static,
+    {
+        self.history = SharedState::new(History {
+            cloner: Some(Arc::new(T::clone)),
+            depth,
+            ..History::default()
+        });
+    }
+
+    /// Stops recording mutations and forgets all recorded steps.
+    pub fn disable_history(&mut self) {
+        self.history = SharedState::default();
+    }
+
+    /// Checks if there is a step which can be undone with [Database::undo].
+    pub fn can_undo(&self) -> bool {
+        !self.history.undo.is_empty()
+    }
+
+    /// Checks if there is a step which can be redone with [Database::redo].
+    pub fn can_redo(&self) -> bool {
+        !self.history.redo.is_empty()
+    }
+
+    /// Reverts the most recent step recorded since [Database::enable_history],
+    /// returning `false` if there was nothing to undo.
+    ///
+    /// # Errors
+    ///
+    /// Will return [error::DatabaseError::Archived] if the database has been
+    /// archived.
+    pub fn undo(&mut self) -> Result<bool, error::DatabaseError> {
+        self.check_mutable()?;
+
+        let step = match self.history.undo.pop_back() {
+            Some(step) => step,
+            None => return Ok(false),
+        };
+
+        self.replay(step.iter().rev(), true);
+        self.history.redo.push(step);
+        Ok(true)
+    }
+
+    /// Reapplies the most recent step reverted with [Database::undo],
+    /// returning `false` if there was nothing to redo. Any new mutation after
+    /// undoing stops it from being redone.
+    ///
+    /// # Errors
+    ///
+    /// Will return [error::DatabaseError::Archived] if the database has been
+    /// archived.
+    pub fn redo(&mut self) -> Result<bool, error::DatabaseError> {
+        self.check_mutable()?;
+
+        let step = match self.history.redo.pop() {
+            Some(step) => step,
+            None => return Ok(false),
+        };
+
+        self.replay(step.iter(), false);
+        self.history.undo.push_back(step);
+        Ok(true)
+    }
+
+    /// Applies recorded changes without recording them again, optionally
+    /// inverting them when undoing.
+    fn replay<'a>(&mut self, events: impl Iterator<Item = &'a ChangeEvent<T>>, invert: bool)
+    where
+        T: 'a,
+    {
+        let cloner = match &self.history.cloner {
+            Some(cloner) => Arc::clone(cloner),
+            None => return,
+        };
+
+        self.history.replaying = true;
+
+        for event in events {
+            match (event, invert) {
+                (ChangeEvent::Added(item), false) | (ChangeEvent::Removed(item), true) => {
+                    self.trash.items.remove(item);
+
+                    if self.items.contains(item) {
+                        self.count_up(cloner(item), 1);
+                    } else {
+                        self.insert_item(cloner(item));
+                    }
+                }
+                (ChangeEvent::Removed(item), false) | (ChangeEvent::Added(item), true) => {
+                    if !self.uncount(item) {
+                        self.take_item(item);
+                    }
+                }
+            }
+        }
+
+        self.history.replaying = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests steps being undone and redone as a whole, within the depth
+    #[test]
+    fn history_undo_redo() -> Result<(), error::DatabaseError> {
+        let mut db = Database::new("History test", None, false);
+
+        db.add_item(0)?;
+        db.enable_history(2);
+
+        for value in 1..=3 {
+            db.add_item(value)?;
+        }
+
+        db.modify_where(|value| *value > 1, |value| value * 10)?;
+
+        assert!(db.undo()?);
+        assert!(db.contains(&2) && db.contains(&3));
+        assert!(db.undo()?);
+        assert!(!db.contains(&3));
+        assert!(!db.undo()?);
+        assert!(db.contains(&2));
+
+        assert!(db.redo()?);
+        db.remove_item(&0)?;
+
+        assert!(!db.can_redo());
+        assert!(db.undo()?);
+        assert!(db.contains(&0));
+        assert_eq!(db.items.len(), 4);
+
+        db.disable_history();
+
+        assert!(!db.can_undo());
+
+        Ok(())
+    }
+}
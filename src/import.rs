@@ -0,0 +1,33 @@
+//! Contains the report returned when importing items from other formats, such
+//! as with [crate::Database::import_ndjson].
+
+/// Summary of what happened when importing items from another format.
+///
+/// Imports carry on past records which couldn't be read, collecting them here
+/// rather than failing the whole import, so one bad line in a large dataset
+/// doesn't need the entire file to be fixed first.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct ImportReport {
+    /// Items which were read and added to the database.
+    pub added: usize,
+
+    /// Records which couldn't be imported, in the order they were found.
+    pub errors: Vec<ImportError>,
+}
+
+impl ImportReport {
+    /// Checks if every record was imported.
+    pub fn is_ok(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+/// A single record which couldn't be imported, see [ImportReport].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ImportError {
+    /// Line the record starts on, counting from `1`.
+    pub line: usize,
+
+    /// Description of what was wrong with the record.
+    pub message: String,
+}
@@ -0,0 +1,1006 @@
+//! Contains opt-in secondary indexes which are kept up-to-date on every
+//! mutation, so queries against them don't have to scan every item.
+//!
+//! Indexes are registered by name and, like tracked statistics, aren't saved
+//! when dumping so have to be created again after loading.
+
+use crate::compat::{Box, HashMap, HashSet, String, ToString, Vec};
+use crate::stats::ordered_bits;
+use crate::{compat, error, Database};
+use alloc::collections::BTreeSet;
+use alloc::sync::Arc;
+use core::ops::{Bound, RangeBounds};
+use core::{fmt, hash};
+
+/// How the terms of a query are combined when using
+/// [Database::search_text].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TextMatch {
+    /// Only match items containing every term.
+    All,
+
+    /// Match items containing any of the terms.
+    Any,
+}
+
+/// Extractor for the text of an item which is indexed.
+type TextExtractor<T> = Arc<dyn Fn(&T) -> String + Send + Sync>;
+
+/// Extractor for the numeric value of an item which is indexed.
+type NumberExtractor<T> = Arc<dyn Fn(&T) -> f64 + Send + Sync>;
+
+/// Clones an item, captured when creating an index so indexes can keep their
+/// own copy of items without [Clone] being required everywhere.
+type Cloner<T> = Arc<dyn Fn(&T) -> T + Send + Sync>;
+
+/// Copies of indexed items alongside the ids postings refer to them by, so
+/// each item is only copied once per index rather than once per term.
+struct Documents<T> {
+    cloner: Cloner<T>,
+    ids: HashMap<T, usize>,
+    items: HashMap<usize, T>,
+    next_id: usize,
+}
+
+impl<T: hash::Hash + Eq> Documents<T> {
+    fn new(cloner: Cloner<T>) -> Self {
+        Documents {
+            cloner,
+            ids: HashMap::new(),
+            items: HashMap::new(),
+            next_id: 0,
+        }
+    }
+
+    /// Stores a copy of an item, returning its new id.
+    fn insert(&mut self, item: &T) -> usize {
+        let id = self.next_id;
+
+        self.next_id += 1;
+        self.ids.insert((self.cloner)(item), id);
+        self.items.insert(id, (self.cloner)(item));
+        id
+    }
+
+    /// Forgets an item, returning the id it had.
+    fn remove(&mut self, item: &T) -> Option<usize> {
+        let id = self.ids.remove(item)?;
+
+        self.items.remove(&id);
+        Some(id)
+    }
+
+    /// Gets the copy of an item by its id.
+    fn get(&self, id: usize) -> Option<&T> {
+        self.items.get(&id)
+    }
+}
+
+impl<T: hash::Hash + Eq> Clone for Documents<T> {
+    fn clone(&self) -> Self {
+        Documents {
+            cloner: Arc::clone(&self.cloner),
+            ids: self
+                .ids
+                .iter()
+                .map(|(item, id)| ((self.cloner)(item), *id))
+                .collect(),
+            items: self
+                .items
+                .iter()
+                .map(|(id, item)| (*id, (self.cloner)(item)))
+                .collect(),
+            next_id: self.next_id,
+        }
+    }
+}
+
+/// A full-text inverted index over a string field, mapping each term to the
+/// items containing it and how many times.
+struct TextIndex<T> {
+    extractor: TextExtractor<T>,
+    documents: Documents<T>,
+    postings: HashMap<String, HashMap<usize, u32>>,
+}
+
+impl<T: hash::Hash + Eq> TextIndex<T> {
+    fn insert(&mut self, item: &T) {
+        let id = self.documents.insert(item);
+
+        for term in tokenize(&(self.extractor)(item)) {
+            *self
+                .postings
+                .entry(term)
+                .or_default()
+                .entry(id)
+                .or_insert(0) += 1;
+        }
+    }
+
+    fn remove(&mut self, item: &T) {
+        let id = match self.documents.remove(item) {
+            Some(id) => id,
+            None => return,
+        };
+
+        for term in tokenize(&(self.extractor)(item)) {
+            if let Some(posting) = self.postings.get_mut(&term) {
+                posting.remove(&id);
+
+                if posting.is_empty() {
+                    self.postings.remove(&term);
+                }
+            }
+        }
+    }
+
+    /// Finds the items matching a query, ranked by the sum of the tf-idf
+    /// score of each matched term with the best matches first.
+    fn search(&self, query: &str, mode: TextMatch) -> Vec<&T> {
+        let mut terms = tokenize(query);
+
+        terms.sort();
+        terms.dedup();
+
+        let total = self.documents.items.len() as f64;
+        let mut scores: HashMap<usize, (usize, f64)> = HashMap::new();
+
+        for term in terms.iter() {
+            let posting = match self.postings.get(term) {
+                Some(posting) => posting,
+                None => continue,
+            };
+            let idf = compat::ln(1.0 + total / posting.len() as f64);
+
+            for (id, frequency) in posting {
+                let score = scores.entry(*id).or_insert((0, 0.0));
+
+                score.0 += 1;
+                score.1 += *frequency as f64 * idf;
+            }
+        }
+
+        let mut ranked: Vec<(usize, f64)> = scores
+            .into_iter()
+            .filter(|(_, (matched, _))| mode == TextMatch::Any || *matched == terms.len())
+            .map(|(id, (_, score))| (id, score))
+            .collect();
+
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+        ranked
+            .into_iter()
+            .filter_map(|(id, _)| self.documents.get(id))
+            .collect()
+    }
+}
+
+impl<T: hash::Hash + Eq> Clone for TextIndex<T> {
+    fn clone(&self) -> Self {
+        TextIndex {
+            extractor: Arc::clone(&self.extractor),
+            documents: self.documents.clone(),
+            postings: self.postings.clone(),
+        }
+    }
+}
+
+/// A sorted index over a string field, used to find every item with a field
+/// starting with a prefix without scanning them all.
+struct PrefixIndex<T> {
+    extractor: TextExtractor<T>,
+    documents: Documents<T>,
+    sorted: BTreeSet<(String, usize)>,
+}
+
+impl<T: hash::Hash + Eq> PrefixIndex<T> {
+    fn insert(&mut self, item: &T) {
+        let id = self.documents.insert(item);
+
+        self.sorted.insert(((self.extractor)(item), id));
+    }
+
+    fn remove(&mut self, item: &T) {
+        if let Some(id) = self.documents.remove(item) {
+            self.sorted.remove(&((self.extractor)(item), id));
+        }
+    }
+
+    /// Finds the items starting with `prefix` in sorted order, by seeking to
+    /// where `prefix` would be and stopping once past it.
+    fn search(&self, prefix: &str) -> Vec<&T> {
+        self.sorted
+            .range((prefix.to_string(), 0)..)
+            .take_while(|(value, _)| value.starts_with(prefix))
+            .filter_map(|(_, id)| self.documents.get(*id))
+            .collect()
+    }
+}
+
+impl<T: hash::Hash + Eq> Clone for PrefixIndex<T> {
+    fn clone(&self) -> Self {
+        PrefixIndex {
+            extractor: Arc::clone(&self.extractor),
+            documents: self.documents.clone(),
+            sorted: self.sorted.clone(),
+        }
+    }
+}
+
+/// A sorted index over a numeric field, used to find every item with a field
+/// inside of a range without scanning them all.
+struct RangeIndex<T> {
+    extractor: NumberExtractor<T>,
+    documents: Documents<T>,
+    sorted: BTreeSet<(u64, usize)>,
+}
+
+impl<T: hash::Hash + Eq> RangeIndex<T> {
+    /// Gets the sortable key of an item, treating `-0.0` the same as `0.0`.
+    fn key(&self, item: &T) -> u64 {
+        ordered_bits((self.extractor)(item) + 0.0)
+    }
+
+    fn insert(&mut self, item: &T) {
+        let id = self.documents.insert(item);
+
+        self.sorted.insert((self.key(item), id));
+    }
+
+    fn remove(&mut self, item: &T) {
+        if let Some(id) = self.documents.remove(item) {
+            self.sorted.remove(&(self.key(item), id));
+        }
+    }
+
+    /// Finds the items with a value between `start` and `end` in ascending
+    /// order of that value.
+    fn search(&self, start: Bound<f64>, end: Bound<f64>) -> Vec<&T> {
+        let (low, high) = match (start, end) {
+            (Bound::Included(low), Bound::Included(high)) if low > high => return Vec::new(),
+            (Bound::Included(low), Bound::Excluded(high))
+            | (Bound::Excluded(low), Bound::Included(high))
+            | (Bound::Excluded(low), Bound::Excluded(high))
+                if low >= high =>
+            {
+                return Vec::new()
+            }
+            (start, end) => (start, end),
+        };
+
+        if matches!(low, Bound::Included(value) | Bound::Excluded(value) if value.is_nan())
+            || matches!(high, Bound::Included(value) | Bound::Excluded(value) if value.is_nan())
+        {
+            return Vec::new();
+        }
+
+        let low = match low {
+            Bound::Included(value) => Bound::Included((ordered_bits(value + 0.0), 0)),
+            Bound::Excluded(value) => Bound::Excluded((ordered_bits(value + 0.0), usize::MAX)),
+            Bound::Unbounded => Bound::Unbounded,
+        };
+        let high = match high {
+            Bound::Included(value) => Bound::Included((ordered_bits(value + 0.0), usize::MAX)),
+            Bound::Excluded(value) => Bound::Excluded((ordered_bits(value + 0.0), 0)),
+            Bound::Unbounded => Bound::Unbounded,
+        };
+
+        self.sorted
+            .range((low, high))
+            .filter_map(|(_, id)| self.documents.get(*id))
+            .collect()
+    }
+}
+
+impl<T: hash::Hash + Eq> Clone for RangeIndex<T> {
+    fn clone(&self) -> Self {
+        RangeIndex {
+            extractor: Arc::clone(&self.extractor),
+            documents: self.documents.clone(),
+            sorted: self.sorted.clone(),
+        }
+    }
+}
+
+/// Keys of a unique index, with the type of key erased so indexes over
+/// different fields can be kept together.
+trait UniqueKeys<T>: Send + Sync {
+    fn insert(&mut self, item: &T);
+
+    fn remove(&mut self, item: &T);
+
+    /// Checks if the key of `item` is already used, either by an item in the
+    /// database other than those being `freed` or by an item `pending`
+    /// alongside it.
+    fn conflicts(&self, item: &T, freed: &[T], pending: &[T]) -> bool;
+
+    /// Amount of keys in use, which should be one per item.
+    #[cfg(feature = "testing")]
+    fn len(&self) -> usize;
+
+    fn boxed_clone(&self) -> Box<dyn UniqueKeys<T>>;
+}
+
+/// Index making sure no two items share a key, see
+/// [Database::add_unique_index].
+struct UniqueIndex<T, K> {
+    extractor: Arc<dyn Fn(&T) -> K + Send + Sync>,
+    keys: HashSet<K>,
+}
+
+impl<T: 'static, K: hash::Hash + Eq + Clone + Send + Sync + 'static> UniqueKeys<T>
+    for UniqueIndex<T, K>
+{
+    fn insert(&mut self, item: &T) {
+        self.keys.insert((self.extractor)(item));
+    }
+
+    fn remove(&mut self, item: &T) {
+        self.keys.remove(&(self.extractor)(item));
+    }
+
+    fn conflicts(&self, item: &T, freed: &[T], pending: &[T]) -> bool {
+        let key = (self.extractor)(item);
+
+        if pending.iter().any(|other| (self.extractor)(other) == key) {
+            return true;
+        }
+
+        self.keys.contains(&key) && !freed.iter().any(|other| (self.extractor)(other) == key)
+    }
+
+    #[cfg(feature = "testing")]
+    fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    fn boxed_clone(&self) -> Box<dyn UniqueKeys<T>> {
+        Box::new(UniqueIndex {
+            extractor: Arc::clone(&self.extractor),
+            keys: self.keys.clone(),
+        })
+    }
+}
+
+/// Converts a bound of any number which fits into a float into a float.
+fn float_bound<N: Copy + Into<f64>>(bound: Bound<&N>) -> Bound<f64> {
+    match bound {
+        Bound::Included(value) => Bound::Included((*value).into()),
+        Bound::Excluded(value) => Bound::Excluded((*value).into()),
+        Bound::Unbounded => Bound::Unbounded,
+    }
+}
+
+/// Splits text into lowercase terms, breaking on anything which isn't
+/// alphanumeric.
+fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|term| !term.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// A single registered index.
+enum Index<T> {
+    Text(TextIndex<T>),
+    Prefix(PrefixIndex<T>),
+    Range(RangeIndex<T>),
+}
+
+impl<T: hash::Hash + Eq> Clone for Index<T> {
+    fn clone(&self) -> Self {
+        match self {
+            Index::Text(index) => Index::Text(index.clone()),
+            Index::Prefix(index) => Index::Prefix(index.clone()),
+            Index::Range(index) => Index::Range(index.clone()),
+        }
+    }
+}
+
+impl<T: hash::Hash + Eq> Index<T> {
+    fn insert(&mut self, item: &T) {
+        match self {
+            Index::Text(index) => index.insert(item),
+            Index::Prefix(index) => index.insert(item),
+            Index::Range(index) => index.insert(item),
+        }
+    }
+
+    fn remove(&mut self, item: &T) {
+        match self {
+            Index::Text(index) => index.remove(item),
+            Index::Prefix(index) => index.remove(item),
+            Index::Range(index) => index.remove(item),
+        }
+    }
+
+    /// Gets the copies of every indexed item.
+    #[cfg(feature = "testing")]
+    fn documents(&self) -> &Documents<T> {
+        match self {
+            Index::Text(index) => &index.documents,
+            Index::Prefix(index) => &index.documents,
+            Index::Range(index) => &index.documents,
+        }
+    }
+}
+
+/// All indexes registered for a database, kept up-to-date on each mutation.
+///
+/// Indexes are derived from the items themselves so they never take part in
+/// comparisons between databases.
+pub(crate) struct Indexes<T> {
+    indexes: HashMap<String, Index<T>>,
+    unique: HashMap<String, Box<dyn UniqueKeys<T>>>,
+}
+
+impl<T: hash::Hash + Eq> Indexes<T> {
+    /// Checks if no indexes have been created.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.indexes.is_empty() && self.unique.is_empty()
+    }
+
+    /// Records an item which has just been added to the database.
+    pub(crate) fn insert(&mut self, item: &T) {
+        for index in self.indexes.values_mut() {
+            index.insert(item);
+        }
+
+        for index in self.unique.values_mut() {
+            index.insert(item);
+        }
+    }
+
+    /// Records an item which has just been removed from the database.
+    pub(crate) fn remove(&mut self, item: &T) {
+        for index in self.indexes.values_mut() {
+            index.remove(item);
+        }
+
+        for index in self.unique.values_mut() {
+            index.remove(item);
+        }
+    }
+
+    /// Makes sure adding `item` wouldn't break any unique index, where
+    /// `freed` are items it replaces and `pending` are other items being
+    /// added alongside it.
+    ///
+    /// # Errors
+    ///
+    /// Returns [error::DatabaseError::UniqueViolation] naming the first
+    /// unique index which would be broken.
+    pub(crate) fn check_unique(
+        &self,
+        item: &T,
+        freed: &[T],
+        pending: &[T],
+    ) -> Result<(), error::DatabaseError> {
+        match self
+            .unique
+            .iter()
+            .find(|(_, index)| index.conflicts(item, freed, pending))
+        {
+            Some((name, _)) => Err(error::DatabaseError::UniqueViolation {
+                index: name.clone(),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Makes sure every index holds exactly the `len` items for which
+    /// `contains` is true, see [crate::testing::check_invariants].
+    #[cfg(feature = "testing")]
+    pub(crate) fn check_consistent(
+        &self,
+        len: usize,
+        contains: &dyn Fn(&T) -> bool,
+    ) -> Result<(), String> {
+        for (name, index) in self.indexes.iter() {
+            let documents = index.documents();
+
+            if documents.ids.len() != len || !documents.ids.keys().all(contains) {
+                return Err(format!("index `{}` is out of sync with the items", name));
+            }
+        }
+
+        match self.unique.iter().find(|(_, index)| index.len() != len) {
+            Some((name, _)) => Err(format!(
+                "unique index `{}` is out of sync with the items",
+                name
+            )),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<T> Default for Indexes<T> {
+    fn default() -> Self {
+        Indexes {
+            indexes: HashMap::new(),
+            unique: HashMap::new(),
+        }
+    }
+}
+
+impl<T: hash::Hash + Eq> Clone for Indexes<T> {
+    fn clone(&self) -> Self {
+        Indexes {
+            indexes: self.indexes.clone(),
+            unique: self
+                .unique
+                .iter()
+                .map(|(name, index)| (name.clone(), index.boxed_clone()))
+                .collect(),
+        }
+    }
+}
+
+impl<T> PartialEq for Indexes<T> {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl<T> Eq for Indexes<T> {}
+
+impl<T> fmt::Debug for Indexes<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set()
+            .entries(self.indexes.keys().chain(self.unique.keys()))
+            .finish()
+    }
+}
+
+impl<T: hash::Hash + Eq> Database<T> {
+    /// Creates a full-text index called `name` over the text given by
+    /// `extractor`, indexing all existing items. Creating an index under an
+    /// existing name will replace it.
+    ///
+    /// Text is split into lowercase terms on anything which isn't a letter or
+    /// number, which can then be searched for using [Database::search_text].
+    /// The index keeps its own copy of each item, hence [Clone] being needed.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tinydb::index::TextMatch;
+    /// use tinydb::Database;
+    ///
+    /// fn main() {
+    ///     let mut db = Database::new("index_test", None, false);
+    ///
+    ///     db.create_text_index("notes", |note: &String| note.clone());
+    ///     db.add_item(String::from("Buy milk and eggs")).unwrap();
+    ///     db.add_item(String::from("Milk the cow")).unwrap();
+    ///
+    ///     assert_eq!(db.search_text("notes", "milk eggs", TextMatch::All).unwrap().len(), 1);
+    ///     assert_eq!(db.search_text("notes", "milk eggs", TextMatch::Any).unwrap().len(), 2);
+    /// }
+    /// ```
+    pub fn create_text_index(
+        &mut self,
+        name: impl Into<String>,
+        extractor: impl Fn(&T) -> String + Send + Sync + 'static,
+    ) where
+        T: Clone + 'static,
+    {
+        let mut index = TextIndex {
+            extractor: Arc::new(extractor),
+            documents: Documents::new(Arc::new(T::clone)),
+            postings: HashMap::new(),
+        };
+
+        for item in self.items.iter() {
+            index.insert(item);
+        }
+
+        self.indexes.indexes.insert(name.into(), Index::Text(index));
+    }
+
+    /// Creates a sorted index called `name` over the text given by
+    /// `extractor`, indexing all existing items. Creating an index under an
+    /// existing name will replace it.
+    ///
+    /// This can then be used to find every item starting with a prefix using
+    /// [Database::query_prefix], which is useful for autocompletion. The index
+    /// keeps its own copy of each item, hence [Clone] being needed.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tinydb::Database;
+    ///
+    /// fn main() {
+    ///     let mut db = Database::new("prefix_test", None, false);
+    ///
+    ///     for name in &["Kryten", "Krissie", "Lister"] {
+    ///         db.add_item(name.to_string()).unwrap();
+    ///     }
+    ///
+    ///     db.create_prefix_index("names", |name: &String| name.clone());
+    ///
+    ///     assert_eq!(db.query_prefix("names", "Kr").unwrap(), vec!["Krissie", "Kryten"]);
+    /// }
+    /// ```
+    pub fn create_prefix_index(
+        &mut self,
+        name: impl Into<String>,
+        extractor: impl Fn(&T) -> String + Send + Sync + 'static,
+    ) where
+        T: Clone + 'static,
+    {
+        let mut index = PrefixIndex {
+            extractor: Arc::new(extractor),
+            documents: Documents::new(Arc::new(T::clone)),
+            sorted: BTreeSet::new(),
+        };
+
+        for item in self.items.iter() {
+            index.insert(item);
+        }
+
+        self.indexes
+            .indexes
+            .insert(name.into(), Index::Prefix(index));
+    }
+
+    /// Creates a sorted index called `name` over the number given by
+    /// `extractor`, indexing all existing items. Creating an index under an
+    /// existing name will replace it.
+    ///
+    /// This can then be used to find every item with a number inside of a
+    /// range using [Database::query_range], taking logarithmic rather than
+    /// linear time to find where the range starts. The index keeps its own
+    /// copy of each item, hence [Clone] being needed.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tinydb::Database;
+    ///
+    /// fn main() {
+    ///     let mut db = Database::new("range_test", None, false);
+    ///
+    ///     for age in &[12, 18, 34, 64, 65, 3000000] {
+    ///         db.add_item(*age).unwrap();
+    ///     }
+    ///
+    ///     db.create_range_index("ages", |age: &u32| *age as f64);
+    ///
+    ///     assert_eq!(db.query_range("ages", 18..65).unwrap(), vec![&18, &34, &64]);
+    ///     assert_eq!(db.query_range("ages", 65..).unwrap(), vec![&65, &3000000]);
+    /// }
+    /// ```
+    pub fn create_range_index(
+        &mut self,
+        name: impl Into<String>,
+        extractor: impl Fn(&T) -> f64 + Send + Sync + 'static,
+    ) where
+        T: Clone + 'static,
+    {
+        let mut index = RangeIndex {
+            extractor: Arc::new(extractor),
+            documents: Documents::new(Arc::new(T::clone)),
+            sorted: BTreeSet::new(),
+        };
+
+        for item in self.items.iter() {
+            index.insert(item);
+        }
+
+        self.indexes
+            .indexes
+            .insert(name.into(), Index::Range(index));
+    }
+
+    /// Creates a unique index called `name` over the key given by
+    /// `extractor`, so no two items can share a key even if they differ
+    /// otherwise. Adding or updating an item to a key which is already used
+    /// fails with [error::DatabaseError::UniqueViolation] naming this index.
+    /// Creating an index under an existing name will replace it.
+    ///
+    /// Items added with [Database::add_items_unchecked] or put back by
+    /// undoing, restoring or reloading aren't checked.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tinydb::error::DatabaseError;
+    /// use tinydb::Database;
+    ///
+    /// fn main() {
+    ///     let mut db = Database::new("unique_index_test", None, false);
+    ///
+    ///     db.add_unique_index("ids", |(id, _): &(u32, String)| *id).unwrap();
+    ///     db.add_item((1, String::from("Lister"))).unwrap();
+    ///     db.update_item(&(1, String::from("Lister")), (1, String::from("Dave Lister"))).unwrap();
+    ///
+    ///     assert_eq!(
+    ///         db.add_item((1, String::from("Rimmer"))),
+    ///         Err(DatabaseError::UniqueViolation { index: String::from("ids") })
+    ///     );
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns [error::DatabaseError::UniqueViolation] if items already
+    /// inside of the database share a key, in which case the index isn't
+    /// created.
+    pub fn add_unique_index<K: hash::Hash + Eq + Clone + Send + Sync + 'static>(
+        &mut self,
+        name: impl Into<String>,
+        extractor: impl Fn(&T) -> K + Send + Sync + 'static,
+    ) -> Result<(), error::DatabaseError>
+    where
+        T: 'static,
+    {
+        let name = name.into();
+        let mut keys = HashSet::with_capacity(self.items.len());
+
+        for item in self.items.iter() {
+            if !keys.insert(extractor(item)) {
+                return Err(error::DatabaseError::UniqueViolation { index: name });
+            }
+        }
+
+        let index = UniqueIndex {
+            extractor: Arc::new(extractor),
+            keys,
+        };
+
+        self.indexes.unique.insert(name, Box::new(index));
+        Ok(())
+    }
+
+    /// Removes an index created with [Database::create_text_index],
+    /// [Database::create_prefix_index], [Database::create_range_index] or
+    /// [Database::add_unique_index], returning if it existed.
+    pub fn drop_index(&mut self, name: &str) -> bool {
+        self.indexes.indexes.remove(name).is_some() || self.indexes.unique.remove(name).is_some()
+    }
+
+    /// Searches a full-text index created with [Database::create_text_index]
+    /// for the terms in `query`, ranking items which contain rarer terms or
+    /// contain them more often first.
+    ///
+    /// # Errors
+    ///
+    /// Returns [error::DatabaseError::IndexNotFound] if there is no text index
+    /// called `name`.
+    pub fn search_text(
+        &self,
+        name: &str,
+        query: &str,
+        mode: TextMatch,
+    ) -> Result<Vec<&T>, error::DatabaseError> {
+        match self.indexes.indexes.get(name) {
+            Some(Index::Text(index)) => {
+                Ok(self.indexed("search_text", name, index.search(query, mode)))
+            }
+            _ => Err(error::DatabaseError::IndexNotFound),
+        }
+    }
+
+    /// Finds all items where the text indexed by a prefix index created with
+    /// [Database::create_prefix_index] starts with `prefix`, in sorted order
+    /// of that text. Matching is case-sensitive.
+    ///
+    /// # Errors
+    ///
+    /// Returns [error::DatabaseError::IndexNotFound] if there is no prefix
+    /// index called `name`.
+    pub fn query_prefix(&self, name: &str, prefix: &str) -> Result<Vec<&T>, error::DatabaseError> {
+        match self.indexes.indexes.get(name) {
+            Some(Index::Prefix(index)) => {
+                Ok(self.indexed("query_prefix", name, index.search(prefix)))
+            }
+            _ => Err(error::DatabaseError::IndexNotFound),
+        }
+    }
+
+    /// Finds all items where the number indexed by a range index created with
+    /// [Database::create_range_index] is inside of `range`, in ascending order
+    /// of that number. Any kind of range can be used, such as `18..65`,
+    /// `18..=64` or `65..`, with any number type which converts to a [f64].
+    ///
+    /// # Errors
+    ///
+    /// Returns [error::DatabaseError::IndexNotFound] if there is no range
+    /// index called `name`.
+    pub fn query_range<N: Copy + Into<f64>>(
+        &self,
+        name: &str,
+        range: impl RangeBounds<N>,
+    ) -> Result<Vec<&T>, error::DatabaseError> {
+        match self.indexes.indexes.get(name) {
+            Some(Index::Range(index)) => {
+                let found = index.search(
+                    float_bound(range.start_bound()),
+                    float_bound(range.end_bound()),
+                );
+
+                Ok(self.indexed("query_range", name, found))
+            }
+            _ => Err(error::DatabaseError::IndexNotFound),
+        }
+    }
+
+    /// Gets the items inside of the database which an index called `name`
+    /// found for a query, skipping any which have expired.
+    fn indexed(&self, query: &'static str, name: &str, found: Vec<&T>) -> Vec<&T> {
+        let scanned = found.len();
+        let live: Vec<&T> = found
+            .into_iter()
+            .filter_map(|item| self.items.get(item))
+            .filter(|item| !self.is_expired(item))
+            .collect();
+
+        self.explain_index(query, name, scanned, live.len());
+        live
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Makes a database of notes with a text index over them
+    fn make_db(notes: &[&str]) -> Database<String> {
+        let mut db = Database::new("Index test", None, false);
+
+        db.create_text_index("notes", |note: &String| note.clone());
+
+        for note in notes {
+            db.add_item(note.to_string()).unwrap();
+        }
+
+        db
+    }
+
+    /// Tests AND and OR queries with ranking
+    #[test]
+    fn text_index_search() -> Result<(), error::DatabaseError> {
+        let db = make_db(&[
+            "The cat sat on the mat",
+            "Cat food, cat toys and cat beds",
+            "A dog and a cat",
+            "Only dogs here",
+        ]);
+
+        assert_eq!(
+            db.search_text("notes", "CAT", TextMatch::All)?,
+            vec![
+                "Cat food, cat toys and cat beds",
+                "The cat sat on the mat",
+                "A dog and a cat"
+            ]
+        );
+        assert_eq!(
+            db.search_text("notes", "cat dog", TextMatch::All)?,
+            vec!["A dog and a cat"]
+        );
+        assert_eq!(db.search_text("notes", "dog mat", TextMatch::Any)?.len(), 2);
+        assert!(db.search_text("notes", "", TextMatch::Any)?.is_empty());
+
+        Ok(())
+    }
+
+    /// Tests the index following removals and being created after items
+    #[test]
+    fn text_index_updates() -> Result<(), error::DatabaseError> {
+        let mut db = make_db(&["red apple", "green apple"]);
+
+        db.remove_item(&String::from("red apple"))?;
+        db.create_text_index("late", |note: &String| note.clone());
+        db.add_item(String::from("apple pie"))?;
+
+        for name in &["notes", "late"] {
+            assert_eq!(db.search_text(name, "apple", TextMatch::All)?.len(), 2);
+            assert!(db.search_text(name, "red", TextMatch::All)?.is_empty());
+        }
+
+        assert!(db.drop_index("late"));
+        assert!(db.search_text("late", "apple", TextMatch::All).is_err());
+
+        Ok(())
+    }
+
+    /// Tests prefix queries staying sorted and following mutations
+    #[test]
+    fn prefix_index_query() -> Result<(), error::DatabaseError> {
+        let mut db = Database::new("Index test", None, false);
+
+        db.create_prefix_index("names", |name: &(String, u32)| name.0.clone());
+
+        for (id, name) in ["Kryten", "Kristine", "Kryten", "Krissie", "kr", "Lister"]
+            .iter()
+            .enumerate()
+        {
+            db.add_item((name.to_string(), id as u32))?;
+        }
+
+        let names = |db: &Database<(String, u32)>, prefix| -> Vec<String> {
+            db.query_prefix("names", prefix)
+                .unwrap()
+                .into_iter()
+                .map(|item| item.0.clone())
+                .collect()
+        };
+
+        assert_eq!(
+            names(&db, "Kr"),
+            vec!["Krissie", "Kristine", "Kryten", "Kryten"]
+        );
+        assert_eq!(names(&db, "Krys"), Vec::<String>::new());
+        assert_eq!(names(&db, "").len(), 6);
+
+        db.remove_item(&(String::from("Kryten"), 0))?;
+
+        assert_eq!(names(&db, "Kry"), vec!["Kryten"]);
+        assert!(db.search_text("names", "Kryten", TextMatch::All).is_err());
+
+        Ok(())
+    }
+
+    /// Tests every kind of range bound, including negative and empty ranges
+    #[test]
+    fn range_index_query() -> Result<(), error::DatabaseError> {
+        let mut db = Database::new("Index test", None, false);
+
+        for value in &[-10, -1, 0, 1, 5, 5, 10] {
+            db.add_item(*value)?;
+        }
+
+        db.create_range_index("values", |value: &i32| *value as f64);
+
+        assert_eq!(db.query_range("values", -1..5)?, vec![&-1, &0, &1]);
+        assert_eq!(db.query_range("values", -1..=5)?, vec![&-1, &0, &1, &5]);
+        assert_eq!(db.query_range("values", ..0)?, vec![&-10, &-1]);
+        assert_eq!(db.query_range("values", 0.5..)?, vec![&1, &5, &10]);
+        assert_eq!(db.query_range::<i32>("values", ..)?.len(), 6);
+        assert!(db.query_range("values", 5..5)?.is_empty());
+        assert!(db.query_range("values", f64::NAN..)?.is_empty());
+
+        db.remove_item(&0)?;
+
+        assert_eq!(db.query_range("values", -0.0..=0.0)?, Vec::<&i32>::new());
+        assert!(db.query_prefix("values", "").is_err());
+
+        Ok(())
+    }
+
+    /// Tests unique keys being enforced across batch modifications and freed
+    /// by removals
+    #[test]
+    fn unique_index_modify() -> Result<(), error::DatabaseError> {
+        let mut db = Database::new("Index test", None, false);
+        let violation = || error::DatabaseError::UniqueViolation {
+            index: String::from("ids"),
+        };
+
+        db.add_item((1, 'a'))?;
+        db.add_item((2, 'b'))?;
+        db.add_unique_index("ids", |(id, _): &(u32, char)| *id)?;
+
+        assert_eq!(db.add_item((2, 'c')), Err(violation()));
+        assert_eq!(
+            db.modify_where(|_| true, |(_, c)| (7, *c)),
+            Err(violation())
+        );
+        assert_eq!(db.modify_where(|_| true, |(id, c)| (id + 1, *c)), Ok(2));
+        assert!(db.contains(&(3, 'b')));
+
+        db.remove_item(&(3, 'b'))?;
+        db.add_item((3, 'c'))?;
+
+        assert!(db.drop_index("ids"));
+        db.add_item((3, 'd'))?;
+
+        assert_eq!(
+            db.add_unique_index("ids", |(id, _): &(u32, char)| *id),
+            Err(violation())
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,215 @@
+//! Contains [inspect], which describes a dump without knowing what type of
+//! items it holds.
+
+use crate::{encoding, error, format, lazy, meta};
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+use std::time::SystemTime;
+
+/// Largest label and save path [inspect] will read, so random files which
+/// happen to look like headerless dumps can't cause huge allocations.
+const HEAD_LIMIT: u64 = 1024 * 1024;
+
+/// Description of a dump returned by [inspect].
+///
+/// Timestamps and the schema fingerprint are read from a small block written
+/// before the encoded database. Dumps made before this block was added don't
+/// have one, leaving them as [Option::None].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DumpInfo {
+    /// The [crate::Database::label] of the dumped database.
+    pub label: String,
+
+    /// The [crate::Database::save_path] the dump would have once loaded,
+    /// which is the path it was inspected at.
+    pub save_path: Option<std::path::PathBuf>,
+
+    /// The [crate::Database::strict_dupes] of the dumped database.
+    pub strict_dupes: bool,
+
+    /// Amount of items inside of the dump.
+    pub items: u64,
+
+    /// Version of the dump format, or [Option::None] for dumps made before
+    /// format versioning.
+    pub format_version: Option<u16>,
+
+    /// If the dump was made with [crate::Database::archive].
+    pub archived: bool,
+
+    /// If the dump was made with [crate::Database::dump_signed].
+    pub signed: bool,
+
+    /// How the dump was laid out, see [crate::Database::set_encoding].
+    pub encoding: encoding::Encoding,
+
+    /// Size of the dump in bytes.
+    pub size: u64,
+
+    /// The [crate::Database::created_at] of the dumped database.
+    pub created_at: Option<SystemTime>,
+
+    /// The [crate::Database::last_modified] of the dumped database.
+    pub last_modified: Option<SystemTime>,
+
+    /// When the database was dumped, which is the
+    /// [crate::Database::last_dumped] it has once loaded.
+    pub last_dumped: Option<SystemTime>,
+
+    /// The [crate::Database::schema_fingerprint] of the item type the dump
+    /// was made with, which can be compared against a type before loading.
+    pub schema: Option<u64>,
+}
+
+/// Describes a `.tinydb` file by only reading the start of it, without
+/// knowing or decoding the type of items it holds. This is useful for tools
+/// which list or describe dumps generically.
+///
+/// Nothing is verified, so archived or signed dumps which have been tampered
+/// with are only noticed once loaded.
+///
+/// # Examples
+///
+/// ```rust
+/// use tinydb::Database;
+///
+/// fn main() {
+///     let mut db = Database::new("inspect_test", None, false);
+///
+///     db.add_item(String::from("Cassandra")).unwrap();
+///     db.dump_db().unwrap();
+///
+///     let info = tinydb::inspect("inspect_test.tinydb").unwrap();
+///
+///     assert_eq!(info.label, "inspect_test");
+///     assert_eq!(info.items, 1);
+///     assert_eq!(info.last_dumped, db.last_dumped());
+///     assert_eq!(info.schema, Some(Database::<String>::schema_fingerprint()));
+///     # std::fs::remove_file("inspect_test.tinydb").unwrap();
+/// }
+/// ```
+///
+/// # Errors
+///
+/// Will return [error::DatabaseError::DatabaseNotFound] if there is no dump,
+/// [error::DatabaseError::UnrecognizedFormat] if it isn't a tinydb dump this
+/// version can read or [error::DatabaseError::BincodeError] if the start of it
+/// is damaged.
+pub fn inspect(path: impl AsRef<Path>) -> Result<DumpInfo, error::DatabaseError> {
+    let path = path.as_ref();
+
+    if !path.exists() {
+        return Err(error::DatabaseError::DatabaseNotFound(path.to_path_buf()));
+    }
+
+    let file = File::open(path)?;
+    let metadata = file.metadata()?;
+    let mut reader = BufReader::new(file);
+    let mut start = Vec::with_capacity(format::HEADER_LEN);
+
+    reader
+        .by_ref()
+        .take(format::HEADER_LEN as u64)
+        .read_to_end(&mut start)?;
+
+    let (format_version, flags) = match format::read_flags(&start) {
+        Some(flags) => (Some(format::FORMAT_VERSION), flags),
+        None => match format::read_header(&start)? {
+            format::Payload::Headerless(_) => (None, 0),
+            format::Payload::Current { .. } => unreachable!("header without flags"),
+        },
+    };
+    let encoding = encoding::Encoding::from_flags(flags);
+    let info = match flags & format::FLAG_INFO {
+        0 => None,
+        _ => Some(read_info(&mut reader)?),
+    };
+    let head: lazy::Head = match format_version {
+        Some(_) => decode_head(encoding, &mut reader)?,
+        None => decode_head(encoding, &mut start.chain(reader))?,
+    };
+
+    Ok(DumpInfo {
+        label: head.label,
+        save_path: Some(path.to_path_buf()),
+        strict_dupes: head.strict_dupes,
+        items: head.items_len,
+        format_version,
+        archived: flags & format::FLAG_ARCHIVED != 0,
+        signed: flags & format::FLAG_SIGNED != 0,
+        encoding,
+        size: metadata.len(),
+        created_at: info.map(|info| meta::from_millis(info.created_at)),
+        last_modified: info.map(|info| meta::from_millis(info.last_modified)),
+        last_dumped: info.and_then(|info| match info.last_dumped {
+            0 => None,
+            millis => Some(meta::from_millis(millis)),
+        }),
+        schema: info.map(|info| info.schema),
+    })
+}
+
+/// Reads the [format::Info] block which follows the header of a dump.
+fn read_info(reader: &mut impl Read) -> Result<format::Info, error::DatabaseError> {
+    let mut block = [0; format::INFO_LEN];
+
+    reader.read_exact(&mut block)?;
+
+    Ok(format::Info::read(&block).expect("block is long enough"))
+}
+
+/// Decodes the fields before the items of a dump.
+fn decode_head(
+    encoding: encoding::Encoding,
+    reader: &mut impl Read,
+) -> Result<lazy::Head, error::DatabaseError> {
+    encoding::Encoding {
+        max_size: Some(HEAD_LIMIT),
+        ..encoding
+    }
+    .deserialize_from(reader)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Database;
+    use std::fs;
+
+    /// Tests flags being read and other files being rejected
+    #[test]
+    fn inspect_archived() -> Result<(), error::DatabaseError> {
+        let path = std::path::PathBuf::from("inspect_archived.tinydb");
+        let json_path = "inspect_archived.json";
+        let mut db = Database::new("Inspect test", path.clone(), true);
+
+        db.add_item(1u64)?;
+        db.add_item(2u64)?;
+        db.archive()?;
+
+        let info = inspect(&path)?;
+
+        assert_eq!(info.label, "Inspect test");
+        assert_eq!(info.save_path, Some(path.clone()));
+        assert_eq!(info.items, 2);
+        assert_eq!(info.format_version, Some(format::FORMAT_VERSION));
+        assert!(info.archived && !info.signed);
+        assert_eq!(info.size, fs::metadata(&path)?.len());
+        assert_eq!(info.created_at, Some(db.created_at()));
+        assert_eq!(info.last_dumped, db.last_dumped());
+        assert_eq!(info.schema, Some(Database::<u64>::schema_fingerprint()));
+        assert_ne!(info.schema, Some(Database::<u32>::schema_fingerprint()));
+
+        fs::write(json_path, "{\"label\": 1}")?;
+
+        assert!(matches!(
+            inspect(json_path),
+            Err(error::DatabaseError::UnrecognizedFormat { .. })
+        ));
+
+        fs::remove_file(path)?;
+        fs::remove_file(json_path)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,706 @@
+//! Contains [Items], the storage behind [crate::Database::items] which
+//! clones and snapshots share until one of them changes.
+
+use crate::compat::{HashSet, OnceLock};
+use crate::ItemHasher;
+use core::borrow::Borrow;
+use core::iter::{FromIterator, FusedIterator};
+use core::{fmt, hash};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Copies a single item once it's shared and about to be changed.
+type Cloner<T> = fn(&T) -> T;
+
+/// Every item inside of a database, hashed using [ItemHasher]. This has the
+/// same methods as a [HashSet] for reading and changing items.
+///
+/// Cloning this, such as when cloning a database or taking a
+/// [snapshot](crate::Database::snapshot), shares the same items rather than
+/// copying them. How much is copied once either side changes whilst they're
+/// still shared depends on the `im` feature:
+///
+/// - Without it, the whole set is copied by the first change, so a change
+///   made after every snapshot costs as much as copying every item.
+/// - With it, items are kept in a persistent hash map from
+///   [im](https://docs.rs/im), so a change only copies the few nodes leading
+///   to the item being changed and items which never change are never
+///   copied. Each item is kept behind an [std::sync::Arc], which makes
+///   lookups and unshared changes a little slower.
+pub struct Items<T> {
+    set: backend::Set<T>,
+    unshare: OnceLock<Cloner<T>>,
+}
+
+impl<T> Items<T> {
+    /// Creates an empty set of items hashed using `hasher`.
+    pub fn with_hasher(hasher: ItemHasher) -> Self {
+        Items {
+            set: backend::Set::with_hasher(hasher),
+            unshare: OnceLock::new(),
+        }
+    }
+
+    /// Checks if `other` shares the same set of items, meaning neither has
+    /// changed since one was cloned from the other.
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        self.set.ptr_eq(&other.set)
+    }
+
+    /// Gets how many items there are.
+    pub fn len(&self) -> usize {
+        self.set.len()
+    }
+
+    /// Checks if there are no items at all.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Iterates over every item in an arbitrary order.
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter(self.set.iter())
+    }
+
+    /// Gets how many items can be held before the set has to grow, which is
+    /// only an estimate whilst using the `im` feature.
+    #[cfg(feature = "std")]
+    pub(crate) fn capacity(&self) -> usize {
+        self.set.capacity()
+    }
+}
+
+impl<T: hash::Hash + Eq> Items<T> {
+    /// Checks if an item is inside of the set, which can be looked up by
+    /// anything the items can be borrowed as like with [HashSet::contains].
+    pub fn contains<Q: hash::Hash + Eq + ?Sized>(&self, item: &Q) -> bool
+    where
+        T: Borrow<Q>,
+    {
+        self.set.get(item).is_some()
+    }
+
+    /// Gets the copy of an item which is inside of the set, which may differ
+    /// from `item` in fields not used by its [Eq] implementation.
+    pub fn get<Q: hash::Hash + Eq + ?Sized>(&self, item: &Q) -> Option<&T>
+    where
+        T: Borrow<Q>,
+    {
+        self.set.get(item)
+    }
+
+    /// Adds an item, returning `false` if an equal item was already there in
+    /// which case it's left alone.
+    pub fn insert(&mut self, item: T) -> bool {
+        if self.contains(&item) {
+            return false;
+        }
+
+        self.set.insert(item, self.unshare.get().copied());
+        true
+    }
+
+    /// Removes an item, returning `false` if it wasn't there.
+    pub fn remove<Q: hash::Hash + Eq + ?Sized>(&mut self, item: &Q) -> bool
+    where
+        T: Borrow<Q>,
+    {
+        self.take(item).is_some()
+    }
+
+    /// Removes an item and gives it back, copying it if it's still shared.
+    pub fn take<Q: hash::Hash + Eq + ?Sized>(&mut self, item: &Q) -> Option<T>
+    where
+        T: Borrow<Q>,
+    {
+        self.set.take(item, self.unshare.get().copied())
+    }
+
+    /// Keeps only the items which `keep` returns `true` for.
+    pub fn retain(&mut self, mut keep: impl FnMut(&T) -> bool) {
+        let unshare = self.unshare.get().copied();
+
+        self.set.extract_if(|item| !keep(item), unshare);
+    }
+
+    /// Takes out every item which `pred` returns `true` for, in an arbitrary
+    /// order.
+    pub fn extract_if(&mut self, pred: impl FnMut(&T) -> bool) -> alloc::vec::IntoIter<T> {
+        let unshare = self.unshare.get().copied();
+
+        self.set.extract_if(pred, unshare).into_iter()
+    }
+
+    /// Takes out every item, leaving the set empty.
+    pub fn drain(&mut self) -> IntoIter<T> {
+        let empty = self.set.emptied();
+        let set = core::mem::replace(&mut self.set, empty);
+
+        IntoIter(set.into_iter(self.unshare.get().copied()))
+    }
+
+    /// Removes every item.
+    pub fn clear(&mut self) {
+        self.set = self.set.emptied();
+    }
+
+    /// Makes room for at least `additional` more items, which does nothing
+    /// whilst using the `im` feature.
+    pub fn reserve(&mut self, additional: usize) {
+        let unshare = self.unshare.get().copied();
+
+        self.set.reserve(additional, unshare);
+    }
+
+    /// Unwraps the items into a [HashSet], copying any which are still
+    /// shared.
+    pub fn into_inner(self) -> HashSet<T, ItemHasher> {
+        let unshare = self.unshare.get().copied();
+
+        self.set.into_inner(unshare)
+    }
+}
+
+impl<T: Clone> Clone for Items<T> {
+    fn clone(&self) -> Self {
+        let unshare: Cloner<T> = T::clone;
+
+        self.unshare.get_or_init(|| unshare);
+
+        Items {
+            set: self.set.share(),
+            unshare: OnceLock::from(unshare),
+        }
+    }
+}
+
+impl<T: hash::Hash + Eq> From<HashSet<T, ItemHasher>> for Items<T> {
+    fn from(set: HashSet<T, ItemHasher>) -> Self {
+        Items {
+            set: backend::Set::from(set),
+            unshare: OnceLock::new(),
+        }
+    }
+}
+
+impl<T: hash::Hash + Eq> FromIterator<T> for Items<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut items = Items::default();
+
+        items.extend(iter);
+        items
+    }
+}
+
+impl<T: hash::Hash + Eq> Extend<T> for Items<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for item in iter {
+            self.insert(item);
+        }
+    }
+}
+
+impl<T> Default for Items<T> {
+    fn default() -> Self {
+        Items::with_hasher(ItemHasher::default())
+    }
+}
+
+impl<T: hash::Hash + Eq> PartialEq for Items<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.ptr_eq(other)
+            || (self.len() == other.len() && self.iter().all(|item| other.contains(item)))
+    }
+}
+
+impl<T: hash::Hash + Eq> Eq for Items<T> {}
+
+impl<T: fmt::Debug> fmt::Debug for Items<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+/// Iterator over every item inside of [Items], see [Items::iter].
+pub struct Iter<'a, T>(backend::Iter<'a, T>);
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+impl<T> FusedIterator for Iter<'_, T> {}
+
+impl<T: fmt::Debug> fmt::Debug for Iter<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Iter").finish_non_exhaustive()
+    }
+}
+
+/// Iterator taking every item out of [Items], see [Items::drain].
+pub struct IntoIter<T>(backend::IntoIter<T>);
+
+impl<T: hash::Hash + Eq> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<T: hash::Hash + Eq> ExactSizeIterator for IntoIter<T> {}
+
+impl<T: fmt::Debug> fmt::Debug for IntoIter<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IntoIter").finish_non_exhaustive()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a Items<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T: hash::Hash + Eq> IntoIterator for Items<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        let unshare = self.unshare.get().copied();
+
+        IntoIter(self.set.into_iter(unshare))
+    }
+}
+
+impl<T: Serialize> Serialize for Items<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+impl<'de, T: hash::Hash + Eq + Deserialize<'de>> Deserialize<'de> for Items<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        HashSet::deserialize(deserializer).map(Items::from)
+    }
+}
+
+/// Items kept in a [HashSet] behind an [Arc], which is copied as a whole the
+/// first time it's changed whilst shared.
+#[cfg(not(feature = "im"))]
+// [ItemHasher] is only `Copy` with the `fxhash` feature or without `std`.
+#[allow(clippy::clone_on_copy)]
+mod backend {
+    use super::Cloner;
+    use crate::compat::{hash_set, HashSet, Vec};
+    use crate::ItemHasher;
+    use alloc::sync::Arc;
+    use core::borrow::Borrow;
+    use core::hash;
+
+    pub(super) type Iter<'a, T> = hash_set::Iter<'a, T>;
+    pub(super) type IntoIter<T> = hash_set::IntoIter<T>;
+
+    pub(super) struct Set<T>(Arc<HashSet<T, ItemHasher>>);
+
+    /// Copies every item of a shared set.
+    fn copy<T: hash::Hash + Eq>(
+        set: &HashSet<T, ItemHasher>,
+        unshare: Option<Cloner<T>>,
+    ) -> HashSet<T, ItemHasher> {
+        let unshare = unshare.expect("shared items can always be copied");
+        let mut copy = HashSet::with_capacity_and_hasher(set.len(), set.hasher().clone());
+
+        copy.extend(set.iter().map(unshare));
+        copy
+    }
+
+    impl<T> Set<T> {
+        pub(super) fn with_hasher(hasher: ItemHasher) -> Self {
+            Set(Arc::new(HashSet::with_hasher(hasher)))
+        }
+
+        pub(super) fn share(&self) -> Self {
+            Set(Arc::clone(&self.0))
+        }
+
+        pub(super) fn emptied(&self) -> Self {
+            Set::with_hasher(self.0.hasher().clone())
+        }
+
+        pub(super) fn ptr_eq(&self, other: &Self) -> bool {
+            Arc::ptr_eq(&self.0, &other.0)
+        }
+
+        pub(super) fn len(&self) -> usize {
+            self.0.len()
+        }
+
+        #[cfg(feature = "std")]
+        pub(super) fn capacity(&self) -> usize {
+            self.0.capacity()
+        }
+
+        pub(super) fn iter(&self) -> Iter<'_, T> {
+            self.0.iter()
+        }
+    }
+
+    impl<T: hash::Hash + Eq> Set<T> {
+        /// Gets the set to change, copying it first if it's shared.
+        fn make_mut(&mut self, unshare: Option<Cloner<T>>) -> &mut HashSet<T, ItemHasher> {
+            if Arc::get_mut(&mut self.0).is_none() {
+                self.0 = Arc::new(copy(&self.0, unshare));
+            }
+
+            Arc::get_mut(&mut self.0).unwrap()
+        }
+
+        pub(super) fn get<Q: hash::Hash + Eq + ?Sized>(&self, item: &Q) -> Option<&T>
+        where
+            T: Borrow<Q>,
+        {
+            self.0.get(item)
+        }
+
+        pub(super) fn insert(&mut self, item: T, unshare: Option<Cloner<T>>) {
+            self.make_mut(unshare).insert(item);
+        }
+
+        pub(super) fn take<Q: hash::Hash + Eq + ?Sized>(
+            &mut self,
+            item: &Q,
+            unshare: Option<Cloner<T>>,
+        ) -> Option<T>
+        where
+            T: Borrow<Q>,
+        {
+            match self.0.contains(item) {
+                true => self.make_mut(unshare).take(item),
+                false => None,
+            }
+        }
+
+        pub(super) fn extract_if(
+            &mut self,
+            mut pred: impl FnMut(&T) -> bool,
+            unshare: Option<Cloner<T>>,
+        ) -> Vec<T> {
+            if !self.0.iter().any(&mut pred) {
+                return Vec::new();
+            }
+
+            self.make_mut(unshare).extract_if(pred).collect()
+        }
+
+        pub(super) fn reserve(&mut self, additional: usize, unshare: Option<Cloner<T>>) {
+            self.make_mut(unshare).reserve(additional);
+        }
+
+        pub(super) fn into_inner(self, unshare: Option<Cloner<T>>) -> HashSet<T, ItemHasher> {
+            Arc::try_unwrap(self.0).unwrap_or_else(|set| copy(&set, unshare))
+        }
+
+        pub(super) fn into_iter(self, unshare: Option<Cloner<T>>) -> IntoIter<T> {
+            self.into_inner(unshare).into_iter()
+        }
+    }
+
+    impl<T: hash::Hash + Eq> From<HashSet<T, ItemHasher>> for Set<T> {
+        fn from(set: HashSet<T, ItemHasher>) -> Self {
+            Set(Arc::new(set))
+        }
+    }
+}
+
+/// Items kept in a persistent [im::HashMap] of buckets keyed by the hash of
+/// each item, so lookups work with anything items can be borrowed as. Each
+/// item is behind an [Arc] so buckets can be shared without items being
+/// [Clone].
+#[cfg(feature = "im")]
+// [ItemHasher] is only `Copy` with the `fxhash` feature.
+#[allow(clippy::clone_on_copy)]
+mod backend {
+    use super::Cloner;
+    use crate::compat::HashSet;
+    use crate::ItemHasher;
+    use alloc::sync::Arc;
+    use alloc::vec;
+    use core::borrow::Borrow;
+    use core::hash::{self, BuildHasher};
+    use core::slice;
+
+    /// Items which have the same hash, which almost always only holds one.
+    type Bucket<T> = Vec<Arc<T>>;
+
+    pub(super) struct Set<T> {
+        buckets: im::HashMap<u64, Bucket<T>, ItemHasher>,
+        hasher: ItemHasher,
+        len: usize,
+    }
+
+    /// Takes an item out of its [Arc], copying it if it's still shared.
+    fn unwrap<T>(item: Arc<T>, unshare: Option<Cloner<T>>) -> T {
+        Arc::try_unwrap(item)
+            .unwrap_or_else(|item| unshare.expect("shared items can always be copied")(&item))
+    }
+
+    impl<T> Set<T> {
+        pub(super) fn with_hasher(hasher: ItemHasher) -> Self {
+            Set {
+                buckets: im::HashMap::with_hasher(hasher.clone()),
+                hasher,
+                len: 0,
+            }
+        }
+
+        pub(super) fn share(&self) -> Self {
+            Set {
+                buckets: self.buckets.clone(),
+                hasher: self.hasher.clone(),
+                len: self.len,
+            }
+        }
+
+        pub(super) fn emptied(&self) -> Self {
+            Set::with_hasher(self.hasher.clone())
+        }
+
+        pub(super) fn ptr_eq(&self, other: &Self) -> bool {
+            self.buckets.ptr_eq(&other.buckets)
+        }
+
+        pub(super) fn len(&self) -> usize {
+            self.len
+        }
+
+        pub(super) fn capacity(&self) -> usize {
+            self.len
+        }
+
+        pub(super) fn iter(&self) -> Iter<'_, T> {
+            Iter {
+                buckets: self.buckets.values(),
+                bucket: [].iter(),
+                len: self.len,
+            }
+        }
+    }
+
+    impl<T: hash::Hash + Eq> Set<T> {
+        pub(super) fn get<Q: hash::Hash + Eq + ?Sized>(&self, item: &Q) -> Option<&T>
+        where
+            T: Borrow<Q>,
+        {
+            self.buckets
+                .get(&self.hasher.hash_one(item))?
+                .iter()
+                .map(|found| &**found)
+                .find(|found| (*found).borrow() == item)
+        }
+
+        pub(super) fn insert(&mut self, item: T, _unshare: Option<Cloner<T>>) {
+            let hash = self.hasher.hash_one(&item);
+
+            self.buckets.entry(hash).or_default().push(Arc::new(item));
+            self.len += 1;
+        }
+
+        pub(super) fn take<Q: hash::Hash + Eq + ?Sized>(
+            &mut self,
+            item: &Q,
+            unshare: Option<Cloner<T>>,
+        ) -> Option<T>
+        where
+            T: Borrow<Q>,
+        {
+            let hash = self.hasher.hash_one(item);
+
+            self.get(item)?;
+
+            let bucket = self.buckets.get_mut(&hash)?;
+            let pos = bucket.iter().position(|found| (**found).borrow() == item)?;
+            let taken = bucket.swap_remove(pos);
+
+            if bucket.is_empty() {
+                self.buckets.remove(&hash);
+            }
+
+            self.len -= 1;
+            Some(unwrap(taken, unshare))
+        }
+
+        pub(super) fn extract_if(
+            &mut self,
+            mut pred: impl FnMut(&T) -> bool,
+            unshare: Option<Cloner<T>>,
+        ) -> Vec<T> {
+            let matched: Vec<(u64, Arc<T>)> = self
+                .buckets
+                .iter()
+                .flat_map(|(hash, bucket)| bucket.iter().map(move |item| (*hash, item)))
+                .filter(|(_, item)| pred(item))
+                .map(|(hash, item)| (hash, Arc::clone(item)))
+                .collect();
+
+            for (hash, item) in matched.iter() {
+                if let Some(bucket) = self.buckets.get_mut(hash) {
+                    bucket.retain(|other| !Arc::ptr_eq(other, item));
+
+                    if bucket.is_empty() {
+                        self.buckets.remove(hash);
+                    }
+                }
+            }
+
+            self.len -= matched.len();
+            matched
+                .into_iter()
+                .map(|(_, item)| unwrap(item, unshare))
+                .collect()
+        }
+
+        pub(super) fn reserve(&mut self, _additional: usize, _unshare: Option<Cloner<T>>) {}
+
+        pub(super) fn into_inner(self, unshare: Option<Cloner<T>>) -> HashSet<T, ItemHasher> {
+            let mut set = HashSet::with_capacity_and_hasher(self.len, self.hasher.clone());
+
+            set.extend(self.into_iter(unshare));
+            set
+        }
+
+        pub(super) fn into_iter(self, unshare: Option<Cloner<T>>) -> IntoIter<T> {
+            IntoIter {
+                buckets: self.buckets.into_iter(),
+                bucket: Vec::new().into_iter(),
+                len: self.len,
+                unshare,
+            }
+        }
+    }
+
+    impl<T: hash::Hash + Eq> From<HashSet<T, ItemHasher>> for Set<T> {
+        fn from(set: HashSet<T, ItemHasher>) -> Self {
+            let mut items = Set::with_hasher(set.hasher().clone());
+
+            for item in set {
+                items.insert(item, None);
+            }
+
+            items
+        }
+    }
+
+    /// Borrows every item, one bucket at a time.
+    pub(super) struct Iter<'a, T> {
+        buckets: im::hashmap::Values<'a, u64, Bucket<T>>,
+        bucket: slice::Iter<'a, Arc<T>>,
+        len: usize,
+    }
+
+    impl<'a, T> Iterator for Iter<'a, T> {
+        type Item = &'a T;
+
+        fn next(&mut self) -> Option<Self::Item> {
+            loop {
+                if let Some(item) = self.bucket.next() {
+                    self.len -= 1;
+                    return Some(item);
+                }
+
+                self.bucket = self.buckets.next()?.iter();
+            }
+        }
+
+        fn size_hint(&self) -> (usize, Option<usize>) {
+            (self.len, Some(self.len))
+        }
+    }
+
+    /// Takes every item out, copying any which are still shared.
+    pub(super) struct IntoIter<T> {
+        buckets: im::hashmap::ConsumingIter<(u64, Bucket<T>)>,
+        bucket: vec::IntoIter<Arc<T>>,
+        len: usize,
+        unshare: Option<Cloner<T>>,
+    }
+
+    impl<T: hash::Hash + Eq> Iterator for IntoIter<T> {
+        type Item = T;
+
+        fn next(&mut self) -> Option<Self::Item> {
+            loop {
+                if let Some(item) = self.bucket.next() {
+                    self.len -= 1;
+                    return Some(unwrap(item, self.unshare));
+                }
+
+                self.bucket = self.buckets.next()?.1.into_iter();
+            }
+        }
+
+        fn size_hint(&self) -> (usize, Option<usize>) {
+            (self.len, Some(self.len))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests clones sharing items until either side changes, without the
+    /// other seeing the change
+    #[test]
+    fn items_copy_on_write() {
+        let mut items = Items::default();
+
+        items.insert(1u32);
+
+        let mut copy = items.clone();
+
+        assert!(copy.ptr_eq(&items));
+
+        items.insert(2);
+
+        assert!(!copy.ptr_eq(&items));
+        assert_eq!(copy.len(), 1);
+
+        let shared = copy.clone();
+
+        copy.remove(&1);
+
+        assert!(copy.is_empty());
+        assert_eq!(shared.into_inner().len(), 1);
+        assert_eq!(items.into_iter().count(), 2);
+    }
+
+    /// Tests items being taken out whilst shared, leaving the clone intact
+    #[test]
+    fn items_taken_whilst_shared() {
+        let mut items: Items<String> = (0..10).map(|n| n.to_string()).collect();
+        let shared = items.clone();
+
+        assert_eq!(items.take(&String::from("3")), Some(String::from("3")));
+        assert_eq!(items.extract_if(|item| item.len() == 1).count(), 9);
+        assert!(items.is_empty());
+        assert_eq!(shared.len(), 10);
+        assert_eq!(shared.clone().drain().count(), 10);
+        assert_eq!(shared.len(), 10);
+    }
+}
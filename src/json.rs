@@ -0,0 +1,373 @@
+//! Contains a minimal JSON parser and writer for [Value], used for importing
+//! and exporting items in JSON-based formats.
+
+use crate::value::Value;
+use std::iter::Peekable;
+use std::str::Chars;
+
+/// Parses a single JSON document into a [Value], returning a description of
+/// the problem if it isn't valid JSON.
+pub(crate) fn parse(text: &str) -> Result<Value, String> {
+    let mut parser = Parser {
+        chars: text.chars().peekable(),
+        column: 0,
+    };
+    let value = parser.value()?;
+
+    parser.whitespace();
+
+    match parser.chars.peek() {
+        Some(&c) => Err(parser.error(&format!("unexpected {:?} after value", c))),
+        None => Ok(value),
+    }
+}
+
+/// Writes a [Value] as compact JSON on a single line. Bytes are written as
+/// an array of numbers and non-finite floats as `null`, as JSON has no way of
+/// representing either.
+pub(crate) fn write(value: &Value) -> String {
+    let mut out = String::new();
+
+    write_into(&mut out, value);
+    out
+}
+
+/// Appends a [Value] to `out` as JSON, see [write].
+fn write_into(out: &mut String, value: &Value) {
+    match value {
+        Value::Null => out.push_str("null"),
+        Value::Bool(value) => out.push_str(if *value { "true" } else { "false" }),
+        Value::Int(value) => out.push_str(&value.to_string()),
+        Value::UInt(value) => out.push_str(&value.to_string()),
+        Value::Float(value) if value.is_finite() => out.push_str(&value.to_string()),
+        Value::Float(_) => out.push_str("null"),
+        Value::String(value) => write_string(out, value),
+        Value::Bytes(bytes) => {
+            let items: Vec<Value> = bytes.iter().map(|byte| Value::UInt(*byte as u64)).collect();
+
+            write_into(out, &Value::Seq(items))
+        }
+        Value::Seq(items) => {
+            out.push('[');
+
+            for (index, item) in items.iter().enumerate() {
+                if index != 0 {
+                    out.push(',');
+                }
+
+                write_into(out, item);
+            }
+
+            out.push(']');
+        }
+        Value::Map(entries) => {
+            out.push('{');
+
+            for (index, (key, value)) in entries.iter().enumerate() {
+                if index != 0 {
+                    out.push(',');
+                }
+
+                write_string(out, key);
+                out.push(':');
+                write_into(out, value);
+            }
+
+            out.push('}');
+        }
+    }
+}
+
+/// Appends a quoted and escaped string to `out`.
+fn write_string(out: &mut String, string: &str) {
+    out.push('"');
+
+    for c in string.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+
+    out.push('"');
+}
+
+/// Recursive descent parser over the characters of a document.
+struct Parser<'a> {
+    chars: Peekable<Chars<'a>>,
+    column: usize,
+}
+
+impl<'a> Parser<'a> {
+    /// Makes an error message pointing at the current column.
+    fn error(&self, message: &str) -> String {
+        format!("{} at column {}", message, self.column)
+    }
+
+    /// Takes the next character.
+    fn next(&mut self) -> Option<char> {
+        let c = self.chars.next()?;
+
+        self.column += 1;
+        Some(c)
+    }
+
+    /// Takes the next character, failing if it isn't `expected`.
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        match self.next() {
+            Some(c) if c == expected => Ok(()),
+            Some(c) => Err(self.error(&format!("expected {:?} but found {:?}", expected, c))),
+            None => Err(self.error(&format!("expected {:?} but found the end", expected))),
+        }
+    }
+
+    /// Skips any whitespace.
+    fn whitespace(&mut self) {
+        while matches!(
+            self.chars.peek(),
+            Some(' ') | Some('\t') | Some('\n') | Some('\r')
+        ) {
+            self.next();
+        }
+    }
+
+    /// Parses any value, skipping whitespace before it.
+    fn value(&mut self) -> Result<Value, String> {
+        self.whitespace();
+
+        match self.chars.peek() {
+            Some('{') => self.object(),
+            Some('[') => self.array(),
+            Some('"') => self.string().map(Value::String),
+            Some('t') => self.literal("true", Value::Bool(true)),
+            Some('f') => self.literal("false", Value::Bool(false)),
+            Some('n') => self.literal("null", Value::Null),
+            Some(c) if *c == '-' || c.is_ascii_digit() => self.number(),
+            Some(&c) => Err(self.error(&format!("unexpected {:?}", c))),
+            None => Err(self.error("unexpected end")),
+        }
+    }
+
+    /// Parses a keyword such as `true`.
+    fn literal(&mut self, word: &str, value: Value) -> Result<Value, String> {
+        for expected in word.chars() {
+            self.expect(expected)?;
+        }
+
+        Ok(value)
+    }
+
+    /// Parses a number, keeping integers separate from floats.
+    fn number(&mut self) -> Result<Value, String> {
+        let mut text = String::new();
+
+        while let Some(c) = self.chars.peek() {
+            if c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E') {
+                text.push(*c);
+                self.next();
+            } else {
+                break;
+            }
+        }
+
+        let integer = !text.contains(['.', 'e', 'E']);
+
+        if integer && text.starts_with('-') {
+            if let Ok(number) = text.parse() {
+                return Ok(Value::Int(number));
+            }
+        } else if integer {
+            if let Ok(number) = text.parse() {
+                return Ok(Value::UInt(number));
+            }
+        }
+
+        text.parse()
+            .map(Value::Float)
+            .map_err(|_| self.error(&format!("invalid number {:?}", text)))
+    }
+
+    /// Parses a quoted string, decoding any escapes.
+    fn string(&mut self) -> Result<String, String> {
+        let mut string = String::new();
+
+        self.expect('"')?;
+
+        loop {
+            match self.next() {
+                Some('"') => return Ok(string),
+                Some('\\') => string.push(self.escape()?),
+                Some(c) if (c as u32) < 0x20 => {
+                    return Err(self.error("control character in string"))
+                }
+                Some(c) => string.push(c),
+                None => return Err(self.error("unterminated string")),
+            }
+        }
+    }
+
+    /// Decodes the escape after a backslash.
+    fn escape(&mut self) -> Result<char, String> {
+        let c = match self.next() {
+            Some('"') => '"',
+            Some('\\') => '\\',
+            Some('/') => '/',
+            Some('b') => '\u{8}',
+            Some('f') => '\u{c}',
+            Some('n') => '\n',
+            Some('r') => '\r',
+            Some('t') => '\t',
+            Some('u') => {
+                let high = self.hex()?;
+
+                if !(0xd800..0xdc00).contains(&high) {
+                    return char::from_u32(high).ok_or_else(|| self.error("invalid escape"));
+                }
+
+                self.expect('\\')?;
+                self.expect('u')?;
+
+                let low = self.hex()?;
+
+                if !(0xdc00..0xe000).contains(&low) {
+                    return Err(self.error("invalid surrogate pair"));
+                }
+
+                return char::from_u32(0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00))
+                    .ok_or_else(|| self.error("invalid surrogate pair"));
+            }
+            _ => return Err(self.error("invalid escape")),
+        };
+
+        Ok(c)
+    }
+
+    /// Parses the four hex digits of a `\u` escape.
+    fn hex(&mut self) -> Result<u32, String> {
+        let mut code = 0;
+
+        for _ in 0..4 {
+            let digit = self
+                .next()
+                .and_then(|c| c.to_digit(16))
+                .ok_or_else(|| self.error("invalid unicode escape"))?;
+
+            code = code * 16 + digit;
+        }
+
+        Ok(code)
+    }
+
+    /// Parses an array into a [Value::Seq].
+    fn array(&mut self) -> Result<Value, String> {
+        let mut items = Vec::new();
+
+        self.expect('[')?;
+        self.whitespace();
+
+        if self.chars.peek() == Some(&']') {
+            self.next();
+            return Ok(Value::Seq(items));
+        }
+
+        loop {
+            items.push(self.value()?);
+            self.whitespace();
+
+            match self.next() {
+                Some(',') => (),
+                Some(']') => return Ok(Value::Seq(items)),
+                _ => return Err(self.error("expected ',' or ']' in array")),
+            }
+        }
+    }
+
+    /// Parses an object into a [Value::Map], keeping the order of its keys.
+    fn object(&mut self) -> Result<Value, String> {
+        let mut entries = Vec::new();
+
+        self.expect('{')?;
+        self.whitespace();
+
+        if self.chars.peek() == Some(&'}') {
+            self.next();
+            return Ok(Value::Map(entries));
+        }
+
+        loop {
+            self.whitespace();
+
+            let key = self.string()?;
+
+            self.whitespace();
+            self.expect(':')?;
+            entries.push((key, self.value()?));
+            self.whitespace();
+
+            match self.next() {
+                Some(',') => (),
+                Some('}') => return Ok(Value::Map(entries)),
+                _ => return Err(self.error("expected ',' or '}' in object")),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests parsing every kind of JSON value
+    #[test]
+    fn json_parse() {
+        assert_eq!(
+            parse(r#" {"a": [1, -2, 3.5e1, true, null], "b\n": "é\ud83d\ude00"} "#),
+            Ok(Value::Map(vec![
+                (
+                    String::from("a"),
+                    Value::Seq(vec![
+                        Value::UInt(1),
+                        Value::Int(-2),
+                        Value::Float(35.0),
+                        Value::Bool(true),
+                        Value::Null
+                    ])
+                ),
+                (String::from("b\n"), Value::String(String::from("é😀")))
+            ]))
+        );
+        assert_eq!(parse("[]"), Ok(Value::Seq(Vec::new())));
+    }
+
+    /// Tests written JSON parsing back into the same value
+    #[test]
+    fn json_write() {
+        let value = Value::Map(vec![
+            (
+                String::from("quote\""),
+                Value::String(String::from("a\\b\n\u{1}")),
+            ),
+            (
+                String::from("n"),
+                Value::Seq(vec![Value::Int(-1), Value::Float(0.5)]),
+            ),
+        ]);
+
+        assert_eq!(write(&value), r#"{"quote\"":"a\\b\n\u0001","n":[-1,0.5]}"#);
+        assert_eq!(parse(&write(&value)), Ok(value));
+        assert_eq!(write(&Value::Float(f64::NAN)), "null");
+    }
+
+    /// Tests invalid JSON being refused
+    #[test]
+    fn json_parse_invalid() {
+        for invalid in &["", "{", "[1,]", "{\"a\" 1}", "tru", "\"open", "1 2"] {
+            assert!(parse(invalid).is_err(), "{:?} was parsed", invalid);
+        }
+    }
+}
@@ -0,0 +1,365 @@
+//! Contains [KvDatabase], a persistent key-value map built on top of
+//! [Database] for when whole structs as items aren't needed.
+
+use crate::{error, metrics, record, Database};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::borrow::Borrow;
+use std::hash;
+use std::path::PathBuf;
+
+/// A key alongside its value, which is what a [KvDatabase] stores as items.
+///
+/// Only the key takes part in comparisons and hashing, so values don't need
+/// to implement either and each key can only be stored once. This also
+/// implements [record::Record], keyed by the key.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Pair<K, V> {
+    key: K,
+    value: V,
+}
+
+impl<K, V> Pair<K, V> {
+    /// Creates a pair, for adding to the database underneath a [KvDatabase]
+    /// directly.
+    pub fn new(key: K, value: V) -> Self {
+        Pair { key, value }
+    }
+
+    /// Gets the key.
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    /// Gets the value.
+    pub fn value(&self) -> &V {
+        &self.value
+    }
+
+    /// Unwraps into the key and value.
+    pub fn into_inner(self) -> (K, V) {
+        (self.key, self.value)
+    }
+}
+
+impl<K: PartialEq, V> PartialEq for Pair<K, V> {
+    fn eq(&self, other: &Self) -> bool {
+        self.key == other.key
+    }
+}
+
+impl<K: Eq, V> Eq for Pair<K, V> {}
+
+impl<K: hash::Hash, V> hash::Hash for Pair<K, V> {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.key.hash(state);
+    }
+}
+
+impl<K, V> Borrow<K> for Pair<K, V> {
+    fn borrow(&self) -> &K {
+        &self.key
+    }
+}
+
+impl<K: hash::Hash + Eq, V> record::Record for Pair<K, V> {
+    type Key = K;
+
+    const FIELDS: &'static [&'static str] = &["key", "value"];
+
+    fn key(&self) -> &K {
+        &self.key
+    }
+}
+
+/// A persistent map of keys to values, dumped and loaded in the same way as
+/// a [Database] of [Pair]s which it wraps. Lookups by key take O(1) rather
+/// than scanning every item like [Database::query_item] does.
+///
+/// # Examples
+///
+/// ```rust
+/// use tinydb::kv::KvDatabase;
+///
+/// fn main() {
+///     let mut scores: KvDatabase<String, u32> = KvDatabase::new("kv_test", None);
+///
+///     scores.insert(String::from("Lister"), 10).unwrap();
+///     scores.entry(String::from("Lister")).and_modify(|score| *score += 5).unwrap();
+///     scores.entry(String::from("Rimmer")).or_insert(0).unwrap();
+///     scores.dump().unwrap();
+///     drop(scores);
+///
+///     let scores: KvDatabase<String, u32> = KvDatabase::from("kv_test.tinydb").unwrap();
+///
+///     assert_eq!(scores.get(&String::from("Lister")), Some(&15));
+///     assert_eq!(scores.len(), 2);
+///     # std::fs::remove_file("kv_test.tinydb").unwrap();
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KvDatabase<K: hash::Hash + Eq, V> {
+    db: Database<Pair<K, V>>,
+}
+
+impl<K: hash::Hash + Eq, V> KvDatabase<K, V> {
+    /// Creates an empty map, with the same `label` and `save_path` as
+    /// [Database::new].
+    pub fn new(label: impl Into<String>, save_path: impl Into<Option<PathBuf>>) -> Self {
+        KvDatabase {
+            db: Database::new(label, save_path, false),
+        }
+    }
+
+    /// Wraps a database of pairs.
+    pub fn from_db(db: Database<Pair<K, V>>) -> Self {
+        KvDatabase { db }
+    }
+
+    /// Gets the value stored under a key.
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.get_pair(key).map(|pair| &pair.value)
+    }
+
+    /// Checks if a value is stored under a key.
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.get_pair(key).is_some()
+    }
+
+    /// Stores a value under a key, returning the value it replaced.
+    ///
+    /// # Errors
+    ///
+    /// Fails in the same ways as [Database::update_item].
+    pub fn insert(&mut self, key: K, value: V) -> Result<Option<V>, error::DatabaseError> {
+        self.db.check_mutable()?;
+
+        let new = Pair::new(key, value);
+        let freed = self
+            .db
+            .items
+            .get(&new.key)
+            .map(std::slice::from_ref)
+            .unwrap_or_default();
+
+        self.db.constraints.check(&new)?;
+        self.db.indexes.check_unique(&new, freed, &[])?;
+        self.db.history.begin_step();
+
+        let old = self.take(&new.key);
+
+        self.db.insert_item(new);
+        Ok(old)
+    }
+
+    /// Removes the value stored under a key, returning it.
+    ///
+    /// # Errors
+    ///
+    /// Will return [error::DatabaseError::ItemNotFound] if nothing is stored
+    /// under the key or [error::DatabaseError::Archived] if the database has
+    /// been archived.
+    pub fn remove(&mut self, key: &K) -> Result<V, error::DatabaseError> {
+        self.db.check_mutable()?;
+        self.db.history.begin_step();
+        self.take(key).ok_or(error::DatabaseError::ItemNotFound)
+    }
+
+    /// Gets the entry for a key, for changing its value depending on if it's
+    /// already there.
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, V> {
+        Entry { kv: self, key }
+    }
+
+    /// Gets how many keys are stored.
+    pub fn len(&self) -> usize {
+        self.db.items.len()
+    }
+
+    /// Checks if nothing is stored.
+    pub fn is_empty(&self) -> bool {
+        self.db.items.is_empty()
+    }
+
+    /// Iterates over every key and value, in an arbitrary order.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.db
+            .items
+            .iter()
+            .filter(move |pair| !self.db.is_expired(pair))
+            .map(|pair| (&pair.key, &pair.value))
+    }
+
+    /// Iterates over every key, in an arbitrary order.
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.iter().map(|(key, _)| key)
+    }
+
+    /// Iterates over every value, in an arbitrary order.
+    pub fn values(&self) -> impl Iterator<Item = &V> {
+        self.iter().map(|(_, value)| value)
+    }
+
+    /// Gets the database underneath, for anything not covered here such as
+    /// [Database::add_constraint].
+    pub fn db(&self) -> &Database<Pair<K, V>> {
+        &self.db
+    }
+
+    /// Gets the database underneath mutably.
+    pub fn db_mut(&mut self) -> &mut Database<Pair<K, V>> {
+        &mut self.db
+    }
+
+    /// Unwraps the database underneath.
+    pub fn into_db(self) -> Database<Pair<K, V>> {
+        self.db
+    }
+
+    /// Gets the pair stored under a key without scanning, if it hasn't
+    /// expired.
+    fn get_pair(&self, key: &K) -> Option<&Pair<K, V>> {
+        self.db
+            .items
+            .get(key)
+            .filter(|pair| !self.db.is_expired(pair))
+    }
+
+    /// Takes the pair stored under a key out of the database, returning its
+    /// value.
+    fn take(&mut self, key: &K) -> Option<V> {
+        let taken = self.db.items.take(key)?;
+
+        self.db.forget_item(&taken);
+        Some(taken.value)
+    }
+}
+
+/// A key which may or may not have a value stored under it, see
+/// [KvDatabase::entry].
+#[derive(Debug)]
+pub struct Entry<'a, K: hash::Hash + Eq, V> {
+    kv: &'a mut KvDatabase<K, V>,
+    key: K,
+}
+
+impl<'a, K: hash::Hash + Eq, V> Entry<'a, K, V> {
+    /// Gets the key of this entry.
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    /// Gets the value stored under the key, if there is one.
+    pub fn get(&self) -> Option<&V> {
+        self.kv.get(&self.key)
+    }
+
+    /// Stores `default` under the key if nothing is there yet, returning the
+    /// value now stored.
+    ///
+    /// # Errors
+    ///
+    /// Fails in the same ways as [KvDatabase::insert].
+    pub fn or_insert(self, default: V) -> Result<&'a V, error::DatabaseError>
+    where
+        K: Clone,
+    {
+        self.or_insert_with(|| default)
+    }
+
+    /// Stores the value made by `default` under the key if nothing is there
+    /// yet, returning the value now stored.
+    ///
+    /// # Errors
+    ///
+    /// Fails in the same ways as [KvDatabase::insert].
+    pub fn or_insert_with(self, default: impl FnOnce() -> V) -> Result<&'a V, error::DatabaseError>
+    where
+        K: Clone,
+    {
+        let Entry { kv, key } = self;
+
+        if !kv.contains_key(&key) {
+            kv.insert(key.clone(), default())?;
+        }
+
+        Ok(kv.get(&key).expect("value was just inserted"))
+    }
+
+    /// Changes the value stored under the key using `modify` if there is one.
+    /// The value is changed on a copy, so it's kept as it was if the new
+    /// value is rejected.
+    ///
+    /// # Errors
+    ///
+    /// Fails in the same ways as [KvDatabase::insert].
+    pub fn and_modify(self, modify: impl FnOnce(&mut V)) -> Result<Self, error::DatabaseError>
+    where
+        K: Clone,
+        V: Clone,
+    {
+        if let Some(value) = self.get() {
+            let mut value = value.clone();
+
+            modify(&mut value);
+            self.kv.insert(self.key.clone(), value)?;
+        }
+
+        Ok(self)
+    }
+}
+
+impl<K: hash::Hash + Eq + Serialize, V: Serialize> KvDatabase<K, V> {
+    /// Dumps the map using [Database::dump_db].
+    pub fn dump(&self) -> Result<metrics::DumpReport, error::DatabaseError> {
+        self.db.dump_db()
+    }
+}
+
+impl<K: hash::Hash + Eq + DeserializeOwned, V: DeserializeOwned> KvDatabase<K, V> {
+    /// Loads a map from a `.tinydb` file using [Database::from].
+    ///
+    /// # Errors
+    ///
+    /// Fails in the same ways as [Database::from].
+    pub fn from(path: impl Into<PathBuf>) -> Result<Self, error::DatabaseError> {
+        Ok(KvDatabase::from_db(Database::from(path)?))
+    }
+
+    /// Loads a map from a `.tinydb` file or creates an empty one if there
+    /// isn't a dump yet, using [Database::auto_from].
+    ///
+    /// # Errors
+    ///
+    /// Fails in the same ways as [Database::auto_from].
+    pub fn auto_from(path: impl Into<PathBuf>) -> Result<Self, error::DatabaseError> {
+        Ok(KvDatabase::from_db(Database::auto_from(path, false)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests values being replaced, removed and rejected by constraints
+    #[test]
+    fn kv_insert_remove() -> Result<(), error::DatabaseError> {
+        let mut kv = KvDatabase::new("Kv test", None);
+
+        kv.db_mut()
+            .add_constraint(|pair: &Pair<u8, i32>| match *pair.value() >= 0 {
+                true => Ok(()),
+                false => Err(String::from("negative")),
+            });
+
+        assert_eq!(kv.insert(1, 10)?, None);
+        assert_eq!(kv.insert(1, 20)?, Some(10));
+        assert!(kv.insert(1, -5).is_err());
+        assert!(kv.entry(1).and_modify(|value| *value -= 100).is_err());
+        assert_eq!(kv.get(&1), Some(&20));
+        assert_eq!(kv.entry(2).or_insert(5)?, &5);
+        assert_eq!(kv.remove(&1)?, 20);
+        assert_eq!(kv.remove(&1), Err(error::DatabaseError::ItemNotFound));
+        assert_eq!(kv.keys().collect::<Vec<_>>(), vec![&2]);
+        Ok(())
+    }
+}
@@ -0,0 +1,345 @@
+//! Contains [LazyDatabase], a read-only view of a dump which only decodes
+//! items once they are needed, see [crate::Database::from_lazy].
+
+use crate::{encoding, error, format, Database};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::cell::OnceCell;
+use std::fs;
+use std::hash;
+use std::path::PathBuf;
+
+/// Amount of items decoded at once.
+const CHUNK_LEN: usize = 1024;
+
+/// Fields of a dump which come before its items, in the order they are
+/// encoded in. This is the same for legacy dumps.
+#[derive(Deserialize)]
+pub(crate) struct Head {
+    pub(crate) label: String,
+    /// Ignored, see [Database::save_path].
+    _save_path: Option<PathBuf>,
+    pub(crate) strict_dupes: bool,
+    pub(crate) items_len: u64,
+}
+
+/// Items of a single chunk alongside where the next chunk starts.
+type Chunk<T> = (Vec<T>, usize);
+
+/// A dump which has only had its header read, with items being decoded in
+/// chunks as they are first iterated or queried. This is ideal for huge dumps
+/// when only a fraction of items will be looked at, as opening one takes
+/// roughly as long as reading the file.
+///
+/// Items are visited in the order they were dumped in, so finding an item
+/// near the end still decodes every chunk before it. Expiries, pins and other
+/// tracked state aren't decoded at all until the dump is fully loaded with
+/// [LazyDatabase::into_db], meaning expired items which haven't been purged
+/// are still visible here.
+#[derive(Debug)]
+pub struct LazyDatabase<T> {
+    label: String,
+    save_path: Option<PathBuf>,
+    strict_dupes: bool,
+    stream: Vec<u8>,
+    encoding: encoding::Encoding,
+    payload: std::ops::Range<usize>,
+    items_start: usize,
+    len: usize,
+    chunks: Vec<OnceCell<Chunk<T>>>,
+}
+
+impl<T: DeserializeOwned> LazyDatabase<T> {
+    /// Reads the header of a dump without decoding any of its items.
+    fn new(stream: Vec<u8>) -> Result<Self, error::DatabaseError> {
+        let (payload, encoding) = match format::read_header(&stream)? {
+            format::Payload::Current { flags, payload, .. } => {
+                (payload, encoding::Encoding::from_flags(flags))
+            }
+            format::Payload::Headerless(payload) => (payload, encoding::Encoding::default()),
+        };
+        let start = payload.as_ptr() as usize - stream.as_ptr() as usize;
+        let payload = start..start + payload.len();
+
+        let mut rest = &stream[payload.clone()];
+        let head: Head = encoding.deserialize_from(&mut rest)?;
+        let items_start = payload.end - rest.len() - payload.start;
+        let len = head.items_len as usize;
+
+        Ok(LazyDatabase {
+            label: head.label,
+            save_path: None,
+            strict_dupes: head.strict_dupes,
+            encoding,
+            payload,
+            items_start,
+            len,
+            chunks: (0..len.div_ceil(CHUNK_LEN)).map(|_| OnceCell::new()).collect(),
+            stream,
+        })
+    }
+
+    /// Friendly name of the dumped database, see [Database::label].
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    /// Path the dumped database saves to, which is where it was opened from,
+    /// see [Database::save_path].
+    pub fn save_path(&self) -> Option<&PathBuf> {
+        self.save_path.as_ref()
+    }
+
+    /// If the dumped database disallows duplicates, see
+    /// [Database::strict_dupes].
+    pub fn strict_dupes(&self) -> bool {
+        self.strict_dupes
+    }
+
+    /// Amount of items inside of the dump, which is known without decoding
+    /// any of them.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Checks if the dump has no items.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Amount of items which have been decoded so far.
+    pub fn decoded(&self) -> usize {
+        self.chunks
+            .iter()
+            .filter_map(|chunk| chunk.get())
+            .map(|(items, _)| items.len())
+            .sum()
+    }
+
+    /// Iterates over all items in the order they were dumped in, decoding
+    /// them as it goes.
+    ///
+    /// Iteration stops after the first error, which will be an
+    /// [error::DatabaseError::BincodeError] if the dump is corrupted.
+    pub fn iter(&self) -> impl Iterator<Item = Result<&T, error::DatabaseError>> {
+        let mut failed = false;
+
+        (0..self.chunks.len())
+            .map_while(move |index| {
+                if failed {
+                    return None;
+                }
+
+                let chunk = self.chunk(index);
+
+                failed = chunk.is_err();
+                Some(chunk)
+            })
+            .flat_map(|chunk| -> Box<dyn Iterator<Item = Result<&T, error::DatabaseError>>> {
+                match chunk {
+                    Ok(items) => Box::new(items.iter().map(Ok)),
+                    Err(e) => Box::new(std::iter::once(Err(e))),
+                }
+            })
+    }
+
+    /// Finds the first item whose field matches `value`, like
+    /// [Database::query_item] but only decoding items up until the match.
+    ///
+    /// # Errors
+    ///
+    /// Will return [error::DatabaseError::ItemNotFound] if no item matches.
+    pub fn query_item<Q, F: PartialEq<Q> + ?Sized, V: Fn(&T) -> &F>(
+        &self,
+        value: V,
+        query: Q,
+    ) -> Result<&T, error::DatabaseError> {
+        for item in self.iter() {
+            let item = item?;
+
+            if *value(item) == query {
+                return Ok(item);
+            }
+        }
+
+        Err(error::DatabaseError::ItemNotFound)
+    }
+
+    /// Finds every item whose field matches `value`, which decodes every
+    /// item inside of the dump.
+    pub fn find_by<Q, F: PartialEq<Q> + ?Sized, V: Fn(&T) -> &F>(
+        &self,
+        value: V,
+        query: Q,
+    ) -> Result<Vec<&T>, error::DatabaseError> {
+        let mut found = Vec::new();
+
+        for item in self.iter() {
+            let item = item?;
+
+            if *value(item) == query {
+                found.push(item);
+            }
+        }
+
+        Ok(found)
+    }
+
+    /// Checks if an item is inside of the dump, only decoding items up until
+    /// it's found.
+    pub fn contains(&self, query: &T) -> Result<bool, error::DatabaseError>
+    where
+        T: PartialEq,
+    {
+        for item in self.iter() {
+            if item? == query {
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
+
+    /// Fully loads the dump into a [Database], including everything which
+    /// was skipped over when lazily decoding. Items which have already been
+    /// decoded are decoded again.
+    pub fn into_db(self) -> Result<Database<T>, error::DatabaseError>
+    where
+        T: hash::Hash + Eq + Serialize,
+    {
+        let mut db = Database::from_bytes(&self.stream)?;
+
+        if let Some(path) = self.save_path {
+            db.loaded_from(path);
+        }
+
+        Ok(db)
+    }
+
+    /// Gets the items of a chunk, decoding it and any chunks before it which
+    /// haven't been decoded yet.
+    fn chunk(&self, index: usize) -> Result<&[T], error::DatabaseError> {
+        let first_missing = (0..=index)
+            .rev()
+            .take_while(|ind| self.chunks[*ind].get().is_none())
+            .last()
+            .unwrap_or(index + 1);
+
+        for ind in first_missing..=index {
+            let start = match ind {
+                0 => self.items_start,
+                _ => self.chunks[ind - 1].get().unwrap().1,
+            };
+            let payload = &self.stream[self.payload.clone()];
+            let mut rest = &payload[start..];
+            let amount = CHUNK_LEN.min(self.len - ind * CHUNK_LEN);
+            let mut items = Vec::with_capacity(amount);
+
+            for _ in 0..amount {
+                items.push(self.encoding.deserialize_from(&mut rest)?);
+            }
+
+            let end = payload.len() - rest.len();
+
+            self.chunks[ind].set((items, end)).ok();
+        }
+
+        Ok(&self.chunks[index].get().unwrap().0)
+    }
+}
+
+impl<T: hash::Hash + Eq + DeserializeOwned> Database<T> {
+    /// Opens a `.tinydb` file lazily, only reading its header straight away
+    /// and decoding items in chunks as they are needed. See [LazyDatabase]
+    /// for more infomation.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tinydb::Database;
+    ///
+    /// fn main() {
+    ///     let mut db = Database::new("lazy_test", None, false);
+    ///
+    ///     for id in 0..5000 {
+    ///         db.add_item(id).unwrap();
+    ///     }
+    ///
+    ///     db.dump_db().unwrap();
+    ///
+    ///     let lazy = Database::<i32>::from_lazy("lazy_test.tinydb").unwrap();
+    ///
+    ///     assert_eq!(lazy.len(), 5000);
+    ///     assert_eq!(lazy.decoded(), 0);
+    ///     assert!(lazy.contains(&42).unwrap());
+    ///     # std::fs::remove_file("lazy_test.tinydb").unwrap();
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Fails in the same ways as [Database::from_unlocked], although a
+    /// corrupted item is only noticed once it's decoded.
+    pub fn from_lazy(path: impl Into<PathBuf>) -> Result<LazyDatabase<T>, error::DatabaseError> {
+        let path = path.into();
+
+        if !path.exists() {
+            return Err(error::DatabaseError::DatabaseNotFound(path));
+        }
+
+        let mut lazy = fs::read(&path)
+            .map_err(error::DatabaseError::from)
+            .and_then(LazyDatabase::new)
+            .map_err(|e| e.with_context("load", Some(path.clone()), None))?;
+
+        lazy.save_path = Some(path);
+        Ok(lazy)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests chunks only being decoded once needed and matching a full load
+    #[test]
+    fn lazy_chunks() -> Result<(), error::DatabaseError> {
+        let mut db = Database::new("Lazy test", None, false);
+
+        for id in 0..(CHUNK_LEN * 2 + 10) as u32 {
+            db.add_item(id)?;
+        }
+
+        let lazy: LazyDatabase<u32> = LazyDatabase::new(db.to_bytes()?)?;
+        let first = *lazy.iter().next().unwrap()?;
+
+        assert_eq!(lazy.label(), "Lazy test");
+        assert_eq!(lazy.decoded(), CHUNK_LEN);
+        assert_eq!(lazy.query_item(|id| id, first)?, &first);
+        assert_eq!(lazy.decoded(), CHUNK_LEN);
+        assert!(!lazy.contains(&u32::MAX)?);
+        assert_eq!(lazy.decoded(), lazy.len());
+        assert_eq!(lazy.iter().count(), db.items.len());
+        assert_eq!(lazy.into_db()?, db);
+        Ok(())
+    }
+
+    /// Tests corrupted items only erroring once decoded
+    #[test]
+    fn lazy_corrupted() -> Result<(), error::DatabaseError> {
+        let mut db = Database::new("Lazy test", None, false);
+
+        db.add_item(String::from("ok"))?;
+
+        let mut stream = db.to_bytes()?;
+        let head: LazyDatabase<String> = LazyDatabase::new(stream.clone())?;
+
+        stream.truncate(head.payload.start + head.items_start + 3);
+
+        let lazy: LazyDatabase<String> = LazyDatabase::new(stream)?;
+
+        assert_eq!(lazy.len(), 1);
+        assert!(lazy.iter().next().unwrap().is_err());
+        assert_eq!(lazy.iter().count(), 1);
+        Ok(())
+    }
+}
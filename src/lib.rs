@@ -1,6 +1,6 @@
 //! > NOTE: This project is not affiliated with the Python [TinyDB](https://tinydb.readthedocs.io/en/latest/),
-//! accidental naming error from when this project was started. See
-//! [renaming](https://github.com/scOwez/tinydb/issues/3) for updates
+//! > accidental naming error from when this project was started. See
+//! > [renaming](https://github.com/scOwez/tinydb/issues/3) for updates
 //! 
 //! TinyDB or `tinydb` is a small-footprint, superfast database designed to be
 //! used in-memory and easily dumped/retrieved from a file when it's time to save
@@ -41,16 +41,21 @@
 //! 
 //! ```toml
 //! [dependencies]
-//! tinydb = "1"
+//! tinydb = "2"
 //! ```
+//!
+//! Without the default `std` feature, only the in-memory database is built
+//! using `alloc` and [hashbrown] for `no_std` targets. Dumping, timestamps,
+//! expiries and anything else needing files, a clock or threads is left out.
+//!
 //! # Implementation notes
 //!
 //! - This database does not save 2 duplicated items, either ignoring or raising an
-//! error depending on end-user preference.
+//!   error depending on end-user preference.
 //! - This project is not intended to be used inside of any critical systems due to
-//! the nature of dumping/recovery. If you are using this crate as a temporary and
-//! in-memory only database, it should preform at a reasonable speed (as it uses
-//! [HashSet] underneath).
+//!   the nature of dumping/recovery. If you are using this crate as a temporary and
+//!   in-memory only database, it should preform at a reasonable speed (as it uses
+//!   [HashSet] underneath).
 //!
 //! # Essential operations
 //!
@@ -71,24 +76,202 @@
     html_logo_url = "https://github.com/Owez/tinydb/raw/master/logo.png",
     html_favicon_url = "https://github.com/Owez/tinydb/raw/master/logo.png"
 )]
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+// Lets code generated by tinydb_derive refer to `::tinydb` from inside here.
+extern crate self as tinydb;
 
-use serde::{de::DeserializeOwned, Deserialize, Serialize};
-use std::collections::HashSet;
+#[cfg(feature = "std")]
+use crate::compat::HashSet;
+use crate::compat::{Box, PathBuf, String, Vec};
+use alloc::sync::Arc;
+use core::hash;
+#[cfg(feature = "std")]
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "std")]
 use std::fs::File;
-use std::hash;
+#[cfg(feature = "std")]
 use std::io::prelude::*;
-use std::path::PathBuf;
+#[cfg(feature = "std")]
+use std::sync::mpsc;
+#[cfg(feature = "std")]
+use std::time::{Duration, Instant};
 
+#[cfg(feature = "std")]
+pub use inspect::{inspect, DumpInfo};
+#[cfg(feature = "derive")]
+pub use tinydb_derive::TinyRecord;
+#[cfg(feature = "uuid")]
+pub use uuid::Uuid;
+
+/// Paths used by code generated by tinydb_derive, which work with or without
+/// the `std` feature. This isn't part of the public API.
+#[doc(hidden)]
+pub mod __private {
+    pub use alloc::vec::Vec;
+}
+
+mod access;
+mod aggregate;
+#[cfg(feature = "std")]
+pub mod autosave;
+#[cfg(feature = "std")]
+mod blob;
+mod bulk;
+#[cfg(feature = "std")]
+pub mod cache;
+#[cfg(feature = "std")]
+pub mod cancel;
+pub mod capacity;
+mod compat;
+#[cfg(feature = "std")]
+pub mod config;
+mod constraint;
+#[cfg(feature = "csv")]
+pub mod csv;
+#[cfg(feature = "std")]
+pub mod diff;
+#[cfg(feature = "std")]
+pub mod doc;
+pub mod dupes;
+#[cfg(feature = "std")]
+pub mod encoding;
 pub mod error;
+pub mod events;
+pub mod explain;
+#[cfg(feature = "std")]
+pub mod expr;
+#[cfg(feature = "std")]
+mod format;
+#[cfg(feature = "std")]
+pub mod graph;
+pub mod handle;
+mod history;
+#[cfg(feature = "std")]
+pub mod import;
+pub mod index;
+#[cfg(feature = "std")]
+mod inspect;
+pub mod items;
+#[cfg(feature = "std")]
+mod json;
+#[cfg(feature = "std")]
+pub mod kv;
+#[cfg(feature = "std")]
+pub mod lazy;
+#[cfg(feature = "std")]
+pub mod limits;
+#[cfg(feature = "std")]
+mod lock;
+#[cfg(feature = "std")]
+pub mod managed;
+#[cfg(feature = "std")]
+pub mod merge;
+mod meta;
+pub mod metrics;
+#[cfg(feature = "std")]
+mod mirror;
+#[cfg(feature = "std")]
+mod ndjson;
+#[cfg(feature = "server")]
+pub mod net;
+#[cfg(feature = "std")]
+pub mod options;
+mod order;
+#[cfg(feature = "std")]
+pub mod overwrite;
+#[cfg(feature = "std")]
+pub mod page;
+mod paths;
+#[cfg(feature = "std")]
+pub mod persist;
+#[cfg(feature = "std")]
+mod progress;
+#[cfg(feature = "std")]
+pub mod recover;
+pub mod record;
+#[cfg(feature = "std")]
+pub mod replica;
+#[cfg(feature = "std")]
+mod schema;
+#[cfg(feature = "rand")]
+mod sample;
+mod search;
+#[cfg(feature = "std")]
+mod segment;
+mod sequence;
+mod shared;
+#[cfg(feature = "std")]
+pub mod sign;
+pub mod snapshot;
+mod sort;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+pub mod stats;
+#[cfg(feature = "testing")]
+pub mod testing;
+#[cfg(feature = "std")]
+pub mod timeseries;
+#[cfg(feature = "chrono")]
+pub mod timestamp;
+mod trace;
+mod trash;
+mod ttl;
+pub mod usage;
+#[cfg(feature = "uuid")]
+mod uuids;
+#[cfg(feature = "std")]
+mod value;
+#[cfg(feature = "std")]
+pub mod view;
+#[cfg(feature = "watch")]
+pub mod watch;
+
+/// Hasher used for [Database::items], which is the standard library's
+/// [std::collections::hash_map::RandomState] unless the `fxhash` feature is
+/// enabled.
+///
+/// FxHash is much faster than the default SipHash for small items such as
+/// integers or short structures, but isn't resistant to HashDoS attacks so
+/// shouldn't be used with items an attacker can choose.
+#[cfg(all(feature = "std", not(feature = "fxhash")))]
+pub type ItemHasher = std::collections::hash_map::RandomState;
+
+/// Hasher used for [Database::items], which is [hashbrown]'s default hasher
+/// without the `std` feature unless the `fxhash` feature is enabled.
+///
+/// FxHash is much faster for small items such as integers or short
+/// structures, but isn't resistant to HashDoS attacks so shouldn't be used
+/// with items an attacker can choose.
+#[cfg(all(not(feature = "std"), not(feature = "fxhash")))]
+pub type ItemHasher = hashbrown::DefaultHashBuilder;
+
+/// Hasher used for [Database::items], which is FxHash as the `fxhash` feature
+/// is enabled.
+///
+/// FxHash is much faster than the default SipHash for small items such as
+/// integers or short structures, but isn't resistant to HashDoS attacks so
+/// shouldn't be used with items an attacker can choose.
+#[cfg(feature = "fxhash")]
+pub type ItemHasher = rustc_hash::FxBuildHasher;
 
 /// The primary database structure, allowing storage of a generic type with
 /// dumping/saving options avalible.
 ///
 /// The generic type used should primarily be structures as they resemble a
 /// conventional database model and should implament [hash::Hash] and [Eq] for
-/// basic in-memory storage with [Serialize] and [Deserialize] being implamented
-/// for file operations involving the database (these are also required).
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// basic in-memory storage. [Serialize] and [Deserialize] are only needed for
+/// file operations involving the database, such as [Database::dump_db] and
+/// [Database::from], so purely in-memory databases can hold types which can't
+/// be saved, including references.
+///
+/// Databases are equal if their labels and items are, regardless of where
+/// they're saved or any other state such as pins and timestamps.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound(deserialize = "T: Deserialize<'de>"))]
 pub struct Database<T: hash::Hash + Eq> {
     /// Friendly name for the database, preferibly in `slug-form-like-this` as
     /// this is the fallback path
@@ -101,32 +284,275 @@ pub struct Database<T: hash::Hash + Eq> {
     /// it will end up as `./Hello\ There.tinydb` if [Database::label] is "Hello
     /// There"
     ///
-    /// Primarily used inside of [Database::dump_db].
+    /// Primarily used inside of [Database::dump_db]. This isn't saved when
+    /// dumping, as it would break once the dump is moved to another machine.
+    /// Databases loaded from a file save back to it instead, which can be
+    /// changed with [Database::set_save_path].
+    #[serde(
+        serialize_with = "paths::serialize_save_path",
+        deserialize_with = "paths::deserialize_save_path"
+    )]
     pub save_path: Option<PathBuf>,
 
     /// If the database should return an error if it tries to insert where an
     /// identical item already is. Setting this as `false` doesn't allow
     /// duplicates, it just doesn't flag an error.
+    ///
+    /// See [Database::set_dupe_policy] for other ways of handling duplicates.
     pub strict_dupes: bool,
 
-    /// In-memory [HashSet] of all items
-    pub items: HashSet<T>,
+    /// In-memory [HashSet] of all items, hashed using [ItemHasher]. Clones
+    /// and snapshots share these until they change, see [items::Items].
+    pub items: items::Items<T>,
+
+    /// Items pinned using [Database::move_to_front] or [Database::move_to_back]
+    order: order::PinnedOrder<T>,
+
+    /// Expiry times of items added with [Database::add_item_with_ttl]
+    expiries: ttl::Expiries<T>,
+
+    /// If queries should purge expired items, see
+    /// [Database::set_purge_on_query]. This is not saved when dumping.
+    #[serde(skip)]
+    lazy_purge: ttl::LazyPurge,
+
+    /// Items removed whilst [Database::set_soft_delete] is enabled
+    trash: trash::Trash<T>,
+
+    /// Policy set with [Database::set_dupe_policy] and counts of items added
+    /// more than once
+    dupes: dupes::Duplicates<T>,
+
+    /// Timestamps for [Database::created_at], [Database::last_modified] and
+    /// [Database::last_dumped]
+    meta: meta::Metadata,
+
+    /// Last id given out by [Database::next_id]
+    sequence: sequence::Sequence,
+
+    /// Running statistics registered with [Database::track_stat], these are
+    /// not saved when dumping.
+    #[serde(skip)]
+    stats: stats::StatTracker<T>,
+
+    /// Indexes registered with [Database::create_text_index],
+    /// [Database::create_prefix_index] or [Database::create_range_index],
+    /// these are not saved when dumping.
+    #[serde(skip)]
+    indexes: snapshot::SharedState<index::Indexes<T>>,
+
+    /// Ids assigned to items once [Database::track_ids] is called, these are
+    /// not saved when dumping.
+    #[serde(skip)]
+    handles: handle::Handles<T>,
+
+    /// Items by their UUID once [Database::index_uuids] is called, these are
+    /// not saved when dumping.
+    #[cfg(feature = "uuid")]
+    #[serde(skip)]
+    uuids: uuids::UuidIndex<T>,
+
+    /// Last accesses of items once [Database::track_access] is called, these
+    /// are not saved when dumping.
+    #[serde(skip)]
+    access: access::AccessTracker<T>,
+
+    /// Limit set with [Database::set_capacity], which is not saved when
+    /// dumping.
+    #[serde(skip)]
+    capacity: snapshot::SharedState<capacity::Capacity<T>>,
+
+    /// Listeners registered with [Database::on_change] or
+    /// [Database::subscribe], these are not saved when dumping.
+    #[serde(skip)]
+    listeners: events::Listeners<T>,
+
+    /// Aggregates registered with [Database::register_aggregate], these are
+    /// not saved when dumping.
+    #[serde(skip)]
+    aggregates: aggregate::Aggregates<T>,
+
+    /// Constraints added with [Database::add_constraint], these are not saved
+    /// when dumping.
+    #[serde(skip)]
+    constraints: constraint::Constraints<T>,
+
+    /// Steps recorded for [Database::undo] and [Database::redo] once
+    /// [Database::enable_history] is called, these are not saved when dumping.
+    #[serde(skip)]
+    history: snapshot::SharedState<history::History<T>>,
+
+    /// If this database has been finalized with [Database::archive], which is
+    /// stored in the header of the dump rather than here.
+    #[serde(skip)]
+    archived: bool,
+
+    /// If this database was opened with [Database::open_readonly].
+    #[serde(skip)]
+    read_only: bool,
+
+    /// If dumping should be skipped whilst nothing has changed, see
+    /// [Database::set_skip_clean_dumps].
+    #[cfg(feature = "std")]
+    #[serde(skip)]
+    skip_clean_dumps: bool,
+
+    /// If missing directories should be created when dumping, see
+    /// [Database::set_create_dirs].
+    #[cfg(feature = "std")]
+    #[serde(skip)]
+    create_dirs: bool,
+
+    /// What to do when dumping over an existing file, see
+    /// [Database::set_overwrite_policy].
+    #[cfg(feature = "std")]
+    #[serde(skip)]
+    overwrite: overwrite::Overwrite,
+
+    /// Lock on the dump this database was opened from, see [Database::from].
+    #[cfg(feature = "std")]
+    #[serde(skip)]
+    lock: lock::HeldLock,
+
+    /// Changes made since the last [Database::sync_dump], which are not saved
+    /// when dumping.
+    #[serde(skip)]
+    journal: snapshot::SharedState<shared::Journal<T>>,
+
+    /// Blobs put with [Database::put_blob] which haven't been dumped yet,
+    /// these are saved next to the dump rather than inside of it.
+    #[cfg(feature = "std")]
+    #[serde(skip)]
+    blobs: blob::Blobs,
+
+    /// Counters reported by [Database::stats].
+    #[serde(skip)]
+    counters: metrics::Counters,
+
+    /// Scans recorded for [Database::index_suggestions], if enabled.
+    #[serde(skip)]
+    usage: usage::UsageTracker,
+
+    /// Plan of the last query for [Database::last_plan], if enabled.
+    #[serde(skip)]
+    explainer: explain::Explainer,
+
+    /// How dumps are laid out, see [Database::set_encoding].
+    #[cfg(feature = "std")]
+    #[serde(skip)]
+    encoding: encoding::Encoding,
+
+    /// Extra paths written by [Database::dump_db], added with
+    /// [Database::add_mirror].
+    #[cfg(feature = "std")]
+    #[serde(skip)]
+    mirrors: Vec<PathBuf>,
+
+    /// Counts changes so snapshots can tell if they're still up-to-date, see
+    /// [snapshot::Snapshot::refresh].
+    #[serde(skip)]
+    version: snapshot::Version,
+}
+
+impl<T: hash::Hash + Eq> PartialEq for Database<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.label == other.label && self.items == other.items
+    }
+}
+
+impl<T: hash::Hash + Eq> Eq for Database<T> {}
+
+/// Creates an empty database with no label or [Database::save_path], for
+/// databases which only live in memory.
+impl<T: hash::Hash + Eq> Default for Database<T> {
+    fn default() -> Self {
+        Database::new(String::new(), None, false)
+    }
 }
 
-impl<T: hash::Hash + Eq + Serialize + DeserializeOwned> Database<T> {
+impl<T: hash::Hash + Eq> Database<T> {
     /// Creates a new database instance from given parameters.
     ///
     /// - To add a first item, use [Database::add_item].
     /// - If you'd like to load a dumped database, use [Database::from].
     pub fn new(label: impl Into<String>, save_path: impl Into<Option<PathBuf>>, strict_dupes: bool) -> Self {
+        Database::with_hasher(label, save_path, strict_dupes, ItemHasher::default())
+    }
+
+    /// Creates a new database like [Database::new] but hashing items with a
+    /// given instance of [ItemHasher], for example one with a fixed seed.
+    pub fn with_hasher(
+        label: impl Into<String>,
+        save_path: impl Into<Option<PathBuf>>,
+        strict_dupes: bool,
+        hasher: ItemHasher,
+    ) -> Self {
         Database {
             label: label.into(),
             save_path: save_path.into(),
             strict_dupes,
-            items: HashSet::new(),
+            items: items::Items::with_hasher(hasher),
+            order: order::PinnedOrder::default(),
+            expiries: ttl::Expiries::default(),
+            lazy_purge: ttl::LazyPurge::default(),
+            trash: trash::Trash::default(),
+            dupes: dupes::Duplicates::default(),
+            meta: meta::Metadata::new(),
+            sequence: sequence::Sequence::default(),
+            stats: stats::StatTracker::default(),
+            indexes: snapshot::SharedState::default(),
+            handles: handle::Handles::default(),
+            #[cfg(feature = "uuid")]
+            uuids: uuids::UuidIndex::default(),
+            access: access::AccessTracker::default(),
+            capacity: snapshot::SharedState::default(),
+            listeners: events::Listeners::default(),
+            aggregates: aggregate::Aggregates::default(),
+            constraints: constraint::Constraints::default(),
+            history: snapshot::SharedState::default(),
+            archived: false,
+            read_only: false,
+            #[cfg(feature = "std")]
+            skip_clean_dumps: false,
+            #[cfg(feature = "std")]
+            create_dirs: true,
+            #[cfg(feature = "std")]
+            overwrite: overwrite::Overwrite::default(),
+            #[cfg(feature = "std")]
+            lock: lock::HeldLock::default(),
+            journal: snapshot::SharedState::default(),
+            #[cfg(feature = "std")]
+            blobs: blob::Blobs::default(),
+            counters: metrics::Counters::default(),
+            usage: usage::UsageTracker::default(),
+            explainer: explain::Explainer::default(),
+            #[cfg(feature = "std")]
+            encoding: encoding::Encoding::default(),
+            #[cfg(feature = "std")]
+            mirrors: Vec::new(),
+            version: snapshot::Version::default(),
         }
     }
 
+    /// Checks if this database was opened with [Database::open_readonly].
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// Removes the `.lock` file left next to a dump, returning `true` if
+    /// there was one.
+    ///
+    /// Locks are released automatically, even if the process holding one
+    /// crashes, so this is only needed to tidy up leftover files. Returns
+    /// [error::DatabaseError::Locked] if the lock is still held.
+    #[cfg(feature = "std")]
+    pub fn break_lock(path: impl Into<PathBuf>) -> Result<bool, error::DatabaseError> {
+        lock::force_unlock(&path.into())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: hash::Hash + Eq + DeserializeOwned> Database<T> {
     /// Creates a database from a `.tinydb` file.
     ///
     /// This retrives a dump file (saved database) from the path given and loads
@@ -147,7 +573,7 @@ impl<T: hash::Hash + Eq + Serialize + DeserializeOwned> Database<T> {
     ///
     /// /// Makes a small testing database.
     /// fn make_db() {
-    ///     let mut test_db = Database::new("test", None, false);
+    ///     let mut test_db = Database::new("from_test", None, false);
     ///     test_db.add_item(ExampleStruct { data: 34 });
     ///     test_db.dump_db();
     /// }
@@ -157,22 +583,206 @@ impl<T: hash::Hash + Eq + Serialize + DeserializeOwned> Database<T> {
     ///     make_db();
     ///
     ///     let got_db = Database::from(
-    ///         PathBuf::from("test.tinydb")
+    ///         PathBuf::from("from_test.tinydb")
     ///     ).unwrap();
     ///
     ///     assert_eq!(
     ///         got_db.query_item(|s: &ExampleStruct| &s.data, 34).unwrap(),
     ///         &ExampleStruct { data: 34 }
     ///     ); // Check that the database still has added [ExampleStruct].
+    ///     # drop(got_db);
+    ///     # std::fs::remove_file("from_test.tinydb").unwrap();
     /// }
     /// ```
+    ///
+    /// # Locking
+    ///
+    /// The dump is locked until this database (and all clones of it) are
+    /// dropped, stopping other processes from opening it or dumping over it in
+    /// the meantime. Use [Database::from_unlocked] if you only want to read the
+    /// dump without locking it.
+    ///
+    /// # Errors
+    ///
+    /// Will return [error::DatabaseError::UnrecognizedFormat] if the file
+    /// isn't a tinydb dump this version can read, such as a JSON export, or
+    /// [error::DatabaseError::Locked] if it's already open elsewhere. Io and
+    /// decoding errors are wrapped in [error::DatabaseError::Context] saying
+    /// which path was being loaded.
     pub fn from(path: impl Into<PathBuf>) -> Result<Self, error::DatabaseError> {
-        let stream = get_stream_from_path(path.into())?;
-        let decoded: Database<T> = bincode::deserialize(&stream[..]).unwrap();
+        let path = path.into();
+        let lock = lock::FileLock::acquire(&path)?;
+        let mut decoded = Database::from_unlocked(path)?;
+
+        decoded.lock = lock::HeldLock(Some(Arc::new(lock)));
+        Ok(decoded)
+    }
+
+    /// Creates a database from a `.tinydb` file like [Database::from] does but
+    /// without locking it, for tools which only read dumps.
+    ///
+    /// Databases opened this way can still be dumped, as long as no other
+    /// process currently has the dump locked.
+    pub fn from_unlocked(path: impl Into<PathBuf>) -> Result<Self, error::DatabaseError> {
+        Database::load(path.into(), None)
+    }
+
+    /// Reads and decodes a dump without locking it, optionally reporting
+    /// progress whilst decoding.
+    fn load(path: PathBuf, progress: progress::Progress<'_>) -> Result<Self, error::DatabaseError> {
+        let timer = trace::start("load", &path.to_string_lossy(), 0);
+        let stream = get_stream_from_path(path.clone())
+            .map_err(|e| e.with_context("load", Some(path.clone()), None))?;
+        let mut decoded = format::read_header(&stream)
+            .and_then(|payload| Database::decode(payload, progress))
+            .map_err(|e| e.with_context("load", Some(path.clone()), None))?;
+
+        decoded.loaded_from(path);
+        timer.bytes(stream.len());
+        timer.items(decoded.items.len());
+        Ok(decoded)
+    }
+
+    /// Creates a database from a `.tinydb` file which can only be read, for
+    /// viewer tools which must never rewrite the dump by accident.
+    ///
+    /// The dump isn't locked, so other processes can keep writing to it and
+    /// [Database::reload] still works. Anything which would mutate the
+    /// database or write to its dump returns
+    /// [error::DatabaseError::ReadOnly] instead, although [Database::items]
+    /// can't be guarded as it's a public field.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tinydb::Database;
+    /// use tinydb::error::DatabaseError;
+    ///
+    /// fn main() {
+    ///     let mut db = Database::new("readonly_test", None, false);
+    ///
+    ///     db.add_item(String::from("Talkie Toaster")).unwrap();
+    ///     db.dump_db().unwrap();
+    ///
+    ///     let mut viewer: Database<String> = Database::open_readonly("readonly_test.tinydb").unwrap();
+    ///
+    ///     assert!(viewer.contains(&String::from("Talkie Toaster")));
+    ///     assert!(matches!(viewer.add_item(String::from("Skutter")), Err(DatabaseError::ReadOnly)));
+    ///     assert!(matches!(viewer.dump_db(), Err(DatabaseError::ReadOnly)));
+    ///     # std::fs::remove_file("readonly_test.tinydb").unwrap();
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Fails in the same ways as [Database::from_unlocked].
+    pub fn open_readonly(path: impl Into<PathBuf>) -> Result<Self, error::DatabaseError> {
+        let mut decoded = Database::from_unlocked(path)?;
 
+        decoded.read_only = true;
         Ok(decoded)
     }
 
+    /// Creates a database from a dump read out of any [Read], such as a
+    /// socket or an entry inside of a zip archive, rather than a path.
+    ///
+    /// Everything is read until the end of `reader`, so dumps embedded inside
+    /// of larger files should be limited to their length using [Read::take].
+    ///
+    /// # Errors
+    ///
+    /// Fails in the same ways as [Database::from_unlocked], with
+    /// [error::DatabaseError::IOError] if reading fails.
+    pub fn from_reader(mut reader: impl Read) -> Result<Self, error::DatabaseError> {
+        let mut stream = Vec::new();
+
+        reader.read_to_end(&mut stream)?;
+        Database::from_bytes(&stream)
+    }
+
+    /// Creates a database from a dump held in memory, such as one made with
+    /// [Database::to_bytes] and embedded inside of another save file.
+    ///
+    /// # Errors
+    ///
+    /// Fails in the same ways as [Database::from_unlocked].
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, error::DatabaseError> {
+        Database::decode(format::read_header(bytes)?, None)
+    }
+
+    /// Reloads this database from its dump, replacing all items with those
+    /// currently saved so changes made by another process are picked up.
+    ///
+    /// Items which differ are removed and added as usual, so tracked
+    /// statistics and listeners registered with [Database::on_change] see
+    /// exactly what changed. The label, pins, expiries and soft-deleted items
+    /// are also replaced but [Database::save_path] and
+    /// [Database::strict_dupes] are kept as they are.
+    ///
+    /// # Errors
+    ///
+    /// Will return [error::DatabaseError::DatabaseNotFound] if there is no
+    /// dump to reload from.
+    pub fn reload(&mut self) -> Result<(), error::DatabaseError> {
+        let mut reloaded = Database::from_unlocked(self.smart_path_get())?;
+        let mut items = core::mem::take(&mut reloaded.items);
+
+        self.take_where(|item| !items.contains(item));
+
+        for item in items.drain() {
+            self.insert_item(item);
+        }
+
+        self.version.bump();
+        self.label = reloaded.label;
+        self.order = reloaded.order;
+        self.expiries = reloaded.expiries;
+        self.trash = reloaded.trash;
+        self.dupes = reloaded.dupes;
+        self.meta = reloaded.meta;
+        self.archived = reloaded.archived;
+        self.history.clear();
+        self.journal.clear();
+
+        Ok(())
+    }
+
+    /// Decodes a database from a dump which has had its header read,
+    /// optionally reporting progress.
+    fn decode(
+        payload: format::Payload<'_>,
+        progress: progress::Progress<'_>,
+    ) -> Result<Self, error::DatabaseError> {
+        Database::decode_within(payload, progress, None)
+    }
+
+    /// Decodes a database like [Database::decode], failing if any `limits`
+    /// are gone over.
+    fn decode_within(
+        payload: format::Payload<'_>,
+        progress: progress::Progress<'_>,
+        limits: Option<&limits::LoadLimits>,
+    ) -> Result<Self, error::DatabaseError> {
+        match payload {
+            format::Payload::Current { flags, payload, .. } => {
+                let encoding = encoding::Encoding::from_flags(flags);
+                let mut decoded: Database<T> = encoding.deserialize(payload, progress, limits)?;
+
+                decoded.archived = flags & format::FLAG_ARCHIVED != 0;
+                decoded.encoding = encoding;
+                Ok(decoded)
+            }
+            format::Payload::Headerless(payload) => {
+                let legacy: LegacyDump<T> =
+                    encoding::Encoding::default().deserialize(payload, progress, limits)?;
+                let mut decoded = Database::new(legacy.label, None, legacy.strict_dupes);
+
+                decoded.items = legacy.items.into();
+                Ok(decoded)
+            }
+        }
+    }
+
     /// Loads database from existant path or creates a new one if it doesn't already
     /// exist.
     ///
@@ -216,6 +826,7 @@ impl<T: hash::Hash + Eq + Serialize + DeserializeOwned> Database<T> {
         if path_into.exists() {
             Database::from(path_into)
         } else {
+            let lock = lock::FileLock::acquire(&path_into)?;
             let db_name = match path_into.file_stem() {
                 Some(x) => match x.to_str() {
                     Some(y) => String::from(y),
@@ -224,24 +835,29 @@ impl<T: hash::Hash + Eq + Serialize + DeserializeOwned> Database<T> {
                 None => return Err(error::DatabaseError::BadDbName),
             };
 
-            Ok(Database::new(db_name, Some(path_into), strict_dupes))
+            let mut created = Database::new(db_name, Some(path_into), strict_dupes);
+
+            created.lock = lock::HeldLock(Some(Arc::new(lock)));
+            Ok(created)
         }
     }
+}
 
+impl<T: hash::Hash + Eq> Database<T> {
     /// Adds a new item to the in-memory database.
     ///
     /// If this is the first item added to the database, please ensure it's the
     /// only type you'd like to add. Due to generics, the first item you add
     /// will be set as the type to use (unless removed).
+    ///
+    /// Adding an item which is already inside of the database is handled
+    /// according to [Database::dupe_policy].
     pub fn add_item(&mut self, item: T) -> Result<(), error::DatabaseError> {
-        if self.strict_dupes {
-            if self.items.contains(&item) {
-                return Err(error::DatabaseError::DupeFound);
-            }
-        }
+        self.check_mutable()?;
+        self.history.begin_step();
+        self.insert_by_policy(item)?;
 
-        self.items.insert(item);
-        return Ok(());
+        Ok(())
     }
 
     /// Replaces an item inside of the database with another
@@ -250,149 +866,1134 @@ impl<T: hash::Hash + Eq + Serialize + DeserializeOwned> Database<T> {
     /// [Database::query_item] can be used in conjunction to find and replace
     /// values individually if needed.
     pub fn update_item(&mut self, item: &T, new: T) -> Result<(), error::DatabaseError> {
-        self.remove_item(item)?;
-        self.add_item(new)?;
+        self.check_mutable()?;
+        self.constraints.check(&new)?;
+        self.indexes.check_unique(&new, core::slice::from_ref(item), &[])?;
+        self.history.begin_step();
+        self.take_item(item)
+            .ok_or(error::DatabaseError::ItemNotFound)?;
+        self.insert_by_policy(new)?;
 
         Ok(())
     }
 
+    /// Replaces every item matching `pred` with the item made by `modify`,
+    /// returning how many items were replaced. This is the same as calling
+    /// [Database::update_item] for each match but without needing to collect
+    /// them first.
+    ///
+    /// Expired items are never modified. Like with [Database::update_item],
+    /// any pin or expiry on a replaced item is dropped. Modified items equal
+    /// to another item follow [Database::dupe_policy], and counts kept by
+    /// [dupes::DupePolicy::Count] are carried over to the modified item.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tinydb::Database;
+    ///
+    /// fn main() {
+    ///     let mut db = Database::new("modify_test", None, false);
+    ///
+    ///     db.add_item((String::from("Lister"), 25)).unwrap();
+    ///     db.add_item((String::from("Rimmer"), 30)).unwrap();
+    ///     db.add_item((String::from("Cat"), 3000000)).unwrap();
+    ///
+    ///     let modified = db
+    ///         .modify_where(|(_, age)| *age < 100, |(name, age)| (name.clone(), age + 1))
+    ///         .unwrap();
+    ///
+    ///     assert_eq!(modified, 2);
+    ///     assert!(db.contains(&(String::from("Rimmer"), 31)));
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// If using [dupes::DupePolicy::Error] and a modified item would be a
+    /// duplicate of another item, [error::DatabaseError::DupeFound] is
+    /// returned and nothing is changed. The same goes for modified items
+    /// rejected by [Database::add_constraint], which return
+    /// [error::DatabaseError::ConstraintViolation].
+    pub fn modify_where(
+        &mut self,
+        mut pred: impl FnMut(&T) -> bool,
+        mut modify: impl FnMut(&T) -> T,
+    ) -> Result<usize, error::DatabaseError> {
+        self.check_mutable()?;
+        self.history.begin_step();
+
+        let policy = self.dupe_policy();
+        let now = meta::now_millis();
+        let expiries = &self.expiries;
+        let taken: Vec<T> = self
+            .items
+            .extract_if(|item| pred(item) && !expiries.is_expired(item, now))
+            .collect();
+        let mut modified: Vec<T> = Vec::with_capacity(taken.len());
+
+        for item in taken.iter().map(&mut modify) {
+            if let Err(e) = self
+                .constraints
+                .check(&item)
+                .and_then(|_| self.indexes.check_unique(&item, &taken, &modified))
+            {
+                self.items.extend(taken);
+                return Err(e);
+            }
+
+            if policy == dupes::DupePolicy::Error
+                && (self.items.get(&item).is_some_and(|old| !self.is_expired(old)) || modified.contains(&item))
+            {
+                self.items.extend(taken);
+                return Err(error::DatabaseError::DupeFound);
+            }
+
+            modified.push(item);
+        }
+
+        let spares: Vec<Option<(T, usize)>> = taken
+            .iter()
+            .map(|item| self.dupes.counts.remove_entry(item))
+            .collect();
+
+        for (item, spare) in taken.iter().zip(spares.iter()) {
+            for _ in 1..spare.as_ref().map_or(1, |(_, count)| *count) {
+                self.record_count(events::ChangeEvent::Removed(item));
+            }
+
+            self.forget_item(item);
+        }
+
+        for (item, spare) in modified.into_iter().zip(spares) {
+            self.insert_checked(item);
+
+            if let Some((spare, count)) = spare.filter(|_| policy == dupes::DupePolicy::Count) {
+                let spare = modify(&spare);
+
+                if self.items.contains(&spare) {
+                    self.count_up(spare, count - 1);
+                }
+            }
+        }
+
+        Ok(taken.len())
+    }
+
+    /// Mutates every item in place by taking them all out, passing each to
+    /// `mutate` and putting them back following [Database::dupe_policy], so
+    /// statistics and indexes stay in sync. This avoids cloning every item
+    /// just to change them with [Database::modify_where].
+    ///
+    /// Expired items are left untouched. Any pin or expiry on the other items
+    /// is dropped, as are counts kept by [dupes::DupePolicy::Count]. Items
+    /// which can't be put back once mutated, such as duplicates whilst using
+    /// [dupes::DupePolicy::Error] or those rejected by
+    /// [Database::add_constraint] or a unique index, are returned rather than
+    /// lost.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tinydb::Database;
+    ///
+    /// fn main() {
+    ///     let mut db = Database::new("for_each_mut_test", None, true);
+    ///
+    ///     db.add_item((String::from("Lister"), 25)).unwrap();
+    ///     db.add_item((String::from("Rimmer"), 30)).unwrap();
+    ///
+    ///     let rejected = db.for_each_mut(|(_, age)| *age += 1).unwrap();
+    ///
+    ///     assert!(rejected.is_empty());
+    ///     assert!(db.contains(&(String::from("Rimmer"), 31)));
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Will return [error::DatabaseError::Archived] if the database has been
+    /// archived.
+    pub fn for_each_mut(&mut self, mut mutate: impl FnMut(&mut T)) -> Result<Vec<T>, error::DatabaseError> {
+        self.check_mutable()?;
+        self.history.begin_step();
+
+        let now = meta::now_millis();
+        let expiries = core::mem::take(&mut self.expiries);
+        let taken = self.take_where(|item| !expiries.is_expired(item, now));
+        let mut rejected = Vec::new();
+
+        self.expiries = expiries;
+
+        for item in taken.iter() {
+            self.expiries.remove(item);
+        }
+
+        for mut item in taken {
+            mutate(&mut item);
+
+            match self.check_policy(&item) {
+                Ok(()) => {
+                    self.insert_checked(item);
+                }
+                Err(_) => rejected.push(item),
+            }
+        }
+
+        Ok(rejected)
+    }
+
     /// Removes an item from the database.
     ///
     /// See [Database::update_item] if you'd like to update/replace an item easily,
     /// rather than individually deleting and adding.
     ///
+    /// If soft-deletion has been enabled with [Database::set_soft_delete], the
+    /// item is kept aside so it can be restored later. Items added more than
+    /// once with [dupes::DupePolicy::Count] only have their count lowered.
+    ///
     /// # Errors
     ///
     /// Will return [error::DatabaseError::ItemNotFound] if the item that is attempting
     /// to be deleted was not found.
     pub fn remove_item(&mut self, item: &T) -> Result<(), error::DatabaseError> {
-        if self.items.remove(item) {
-            Ok(())
-        } else {
-            Err(error::DatabaseError::ItemNotFound)
+        self.check_mutable()?;
+        self.history.begin_step();
+
+        if self.uncount(item) {
+            return Ok(());
+        }
+
+        self.discard_item(item)
+    }
+
+    /// Takes an item out regardless of its count, keeping it in the trash if
+    /// soft-deletion is enabled.
+    fn discard_item(&mut self, item: &T) -> Result<(), error::DatabaseError> {
+        match self.take_item(item) {
+            Some(taken) if self.trash.enabled => {
+                self.trash.items.insert(taken);
+                Ok(())
+            }
+            Some(_) => Ok(()),
+            None => Err(error::DatabaseError::ItemNotFound),
         }
     }
+}
 
+#[cfg(feature = "std")]
+impl<T: hash::Hash + Eq + Serialize> Database<T> {
     /// Dumps/saves database to a binary file.
     ///
     /// # Saving path methods
     ///
     /// The database will usually save as `\[label\].tinydb` where `\[label\]`
     /// is the defined [Database::label] (path is reletive to where tinydb was
-    /// executed).
+    /// executed). Path separators and characters which aren't allowed in file
+    /// names on Windows are replaced with `-`, so labels can't point at
+    /// another directory.
     ///
     /// You can also overwrite this behaviour by defining a [Database::save_path]
     /// when generating the database inside of [Database::new].
-    pub fn dump_db(&self) -> Result<(), error::DatabaseError> {
-        let mut dump_file = self.open_db_path()?;
-        bincode::serialize_into(&mut dump_file, self).unwrap();
-
-        Ok(())
-    }
-
-    /// Query the database for a specific item.
     ///
-    /// # Syntax
+    /// If [Database::set_skip_clean_dumps] has been enabled, nothing is
+    /// written whilst the database hasn't changed since it was last dumped.
+    /// The dump is also copied to any paths added with [Database::add_mirror].
+    /// A [metrics::DumpReport] of what was written is returned.
     ///
-    /// ```none
-    /// self.query_item(|[p]| [p].[field], [query]);
-    /// ```
+    /// # Errors
     ///
-    /// - `[p]` The closure (Will be whatever the database currently is saving as a schema).
-    /// - `[field]` The exact field of `p`. If the database doesn't contain structures, don't add the `.[field]`.
-    /// - `[query]` Item to query for. This is a generic and can be of any reasonable type.
+    /// Will return [error::DatabaseError::Archived] if the dump being
+    /// overwritten was finalized using [Database::archive], or
+    /// [error::DatabaseError::MirrorsFailed] if the dump was saved but some
+    /// mirrors couldn't be written. Without a [Database::save_path],
+    /// [error::DatabaseError::InvalidLabel] is returned if the label can't be
+    /// used as a file name. Missing directories the dump goes into are
+    /// created, returning [error::DatabaseError::CreateDirFailed] if they
+    /// couldn't be, or [error::DatabaseError::MissingDirectory] if
+    /// [Database::set_create_dirs] has been disabled. If another database's
+    /// dump is already there, [error::DatabaseError::DumpExists] is returned
+    /// when using [overwrite::OverwritePolicy::Error].
+    pub fn dump_db(&self) -> Result<metrics::DumpReport, error::DatabaseError> {
+        if self.skip_clean_dumps && !self.is_dirty() {
+            return Ok(metrics::DumpReport {
+                bytes: 0,
+                items: self.items.len(),
+                duration: Duration::ZERO,
+                path: self.smart_path_get(),
+            });
+        }
+
+        self.write_dump(None, None)
+    }
+
+    /// Dumps this database to any [Write] rather than its path, such as a
+    /// socket, an in-memory buffer or part of a larger file. The dump is the
+    /// same as [Database::dump_db] would write, so it can be loaded with
+    /// [Database::from_reader].
     ///
     /// # Examples
     ///
     /// ```rust
-    /// use serde::{Serialize, Deserialize};
     /// use tinydb::Database;
     ///
-    /// #[derive(Debug, Eq, PartialEq, Hash, Serialize, Deserialize, Clone)]
-    /// struct ExampleStruct {
-    ///     my_age: i32
-    /// }
-    ///
     /// fn main() {
-    ///     let my_struct = ExampleStruct { my_age: 329 };
-    ///     let mut my_db = Database::new("query_test", None, false);
+    ///     let mut db = Database::new("writer_test", None, false);
+    ///     let mut buffer = Vec::new();
     ///
-    ///     my_db.add_item(my_struct.clone());
+    ///     db.add_item(String::from("Kryten")).unwrap();
+    ///     db.dump_to_writer(&mut buffer).unwrap();
     ///
-    ///     let results = my_db.query_item(|s: &ExampleStruct| &s.my_age, 329);
+    ///     let loaded: Database<String> = Database::from_reader(buffer.as_slice()).unwrap();
     ///
-    ///     assert_eq!(results.unwrap(), &my_struct);
+    ///     assert!(loaded.contains(&String::from("Kryten")));
     /// }
     /// ```
-    pub fn query_item<Q: PartialEq, V: Fn(&T) -> &Q>(
-        &self,
-        value: V,
-        query: Q,
-    ) -> Result<&T, error::DatabaseError> {
-        for item in self.items.iter() {
-            if value(item) == &query {
-                return Ok(item);
-            }
-        }
+    pub fn dump_to_writer(&self, writer: &mut impl Write) -> Result<(), error::DatabaseError> {
+        writer.write_all(&self.to_bytes()?)?;
 
-        Err(error::DatabaseError::ItemNotFound)
+        Ok(())
     }
 
-    /// Searches the database for a specific value. If it does not exist, this
-    /// method will return [error::DatabaseError::ItemNotFound].
+    /// Encodes this database into the same bytes [Database::dump_db] would
+    /// write, without touching the filesystem. Use [Database::from_bytes] to
+    /// load it again.
     ///
-    /// This is a wrapper around [HashSet::contains].
+    /// The bytes record being dumped right now, but as nothing reaches
+    /// [Database::save_path] this database isn't marked as dumped and stays
+    /// [dirty](Database::is_dirty).
     ///
     /// # Examples
     ///
     /// ```rust
     /// use tinydb::Database;
-    /// use serde::{Serialize, Deserialize};
-    ///
-    /// #[derive(Hash, Eq, PartialEq, Serialize, Deserialize, Copy, Clone)]
-    /// struct ExampleStruct {
-    ///     item: i32
-    /// }
     ///
     /// fn main() {
-    ///     let exp_struct = ExampleStruct { item: 4942 };
-    ///     let mut db = Database::new("Contains example", None, false);
+    ///     let mut db = Database::new("bytes_test", None, false);
     ///
-    ///     db.add_item(exp_struct.clone());
+    ///     db.add_item(42u32).unwrap();
     ///
-    ///     assert_eq!(db.contains(&exp_struct), true);
+    ///     let bytes = db.to_bytes().unwrap();
+    ///     let loaded: Database<u32> = Database::from_bytes(&bytes).unwrap();
+    ///
+    ///     assert_eq!(loaded, db);
+    /// }
+    /// ```
+    pub fn to_bytes(&self) -> Result<Vec<u8>, error::DatabaseError> {
+        let previous = self.meta.stamp_dumped();
+        let stream = self.encode_dump(None, None);
+
+        self.meta.restore_dumped(previous);
+        stream
+    }
+
+    /// Encodes and writes this database to its path, optionally signing it
+    /// and reporting progress.
+    fn write_dump(
+        &self,
+        signer: Option<&dyn sign::Signer>,
+        progress: progress::Progress<'_>,
+    ) -> Result<metrics::DumpReport, error::DatabaseError> {
+        if self.read_only {
+            return Err(error::DatabaseError::ReadOnly);
+        }
+
+        let started = Instant::now();
+
+        self.dumping(|| {
+            let timer = trace::start("dump", &self.label, self.items.len());
+            let stream = self
+                .encode_dump(signer, progress)
+                .and_then(|stream| {
+                    self.write_db_path(&stream)?;
+                    Ok(stream)
+                })
+                .map_err(|e| {
+                    e.with_context("dump", Some(self.smart_path_get()), Some(&self.label))
+                })?;
+            let blobs_dir = self.blobs_dir();
+
+            self.blobs
+                .flush(&blobs_dir)
+                .map_err(|e| e.with_context("dump blobs", Some(blobs_dir), Some(&self.label)))?;
+            timer.bytes(stream.len());
+
+            self.write_mirrors(&stream)?;
+
+            Ok(metrics::DumpReport {
+                bytes: stream.len() as u64,
+                items: self.items.len(),
+                duration: started.elapsed(),
+                path: self.smart_path_get(),
+            })
+        })
+    }
+
+    /// Encodes this database into a complete dump, optionally signing it and
+    /// reporting progress.
+    fn encode_dump(
+        &self,
+        signer: Option<&dyn sign::Signer>,
+        progress: progress::Progress<'_>,
+    ) -> Result<Vec<u8>, error::DatabaseError> {
+        let mut flags = self.encoding.flags() | format::FLAG_INFO;
+
+        if self.archived {
+            flags |= format::FLAG_ARCHIVED;
+        }
+
+        if signer.is_some() {
+            flags |= format::FLAG_SIGNED;
+        }
+
+        let payload = self.encoding.serialize(self, progress)?;
+        let mut stream =
+            Vec::with_capacity(format::HEADER_LEN + format::INFO_LEN + payload.len());
+
+        format::write_header(&mut stream, flags)?;
+        self.meta
+            .info(Database::<T>::schema_fingerprint())
+            .write(&mut stream)?;
+        stream.extend_from_slice(&payload);
+
+        if self.archived {
+            let mut footer = Vec::with_capacity(format::FOOTER_LEN);
+
+            format::write_footer(&mut footer, &stream[format::HEADER_LEN..])?;
+            stream.extend_from_slice(&footer);
+        }
+
+        if let Some(signer) = signer {
+            let signature = signer.sign(&stream);
+
+            format::write_signature(&mut stream, &signature)?;
+        }
+
+        Ok(stream)
+    }
+
+    /// Finalizes this database, dumping it as a write-once archive which can
+    /// never be changed again.
+    ///
+    /// Archived dumps are marked as immutable in their header and end with a
+    /// checksum which is verified when loading with [Database::from]. Once
+    /// archived, all mutations return [error::DatabaseError::Archived] and
+    /// nothing can be dumped over the archive, making this ideal for audit or
+    /// export snapshots which must not change.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tinydb::Database;
+    /// use std::path::PathBuf;
+    ///
+    /// fn main() {
+    ///     let path = PathBuf::from("archive_example.tinydb");
+    ///     let mut db: Database<u32> = Database::new("Archive example", path.clone(), false);
+    ///
+    ///     db.add_item(2020).unwrap();
+    ///     db.archive().unwrap();
+    ///
+    ///     let mut loaded: Database<u32> = Database::from(path.clone()).unwrap();
+    ///
+    ///     assert!(loaded.is_archived());
+    ///     assert!(loaded.add_item(2021).is_err());
+    ///     assert!(loaded.dump_db().is_err());
+    ///     # std::fs::remove_file(path).unwrap();
+    /// }
+    /// ```
+    pub fn archive(&mut self) -> Result<(), error::DatabaseError> {
+        self.check_mutable()?;
+
+        self.archived = true;
+
+        if let Err(err) = self.write_dump(None, None) {
+            self.archived = false;
+            return Err(err);
+        }
+
+        Ok(())
+    }
+}
+
+impl<T: hash::Hash + Eq> Database<T> {
+    /// Checks if this database has been finalized with [Database::archive].
+    pub fn is_archived(&self) -> bool {
+        self.archived
+    }
+
+    /// Query the database for a specific item.
+    ///
+    /// # Syntax
+    ///
+    /// ```none
+    /// self.query_item(|[p]| [p].[field], [query]);
+    /// ```
+    ///
+    /// - `[p]` The closure (Will be whatever the database currently is saving as a schema).
+    /// - `[field]` The exact field of `p`. If the database doesn't contain structures, don't add the `.[field]`.
+    /// - `[query]` Item to query for. This is a generic and can be of any type the field can be
+    ///   compared against, so a [String] field can be queried with a `&str` without allocating.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use serde::{Serialize, Deserialize};
+    /// use tinydb::Database;
+    ///
+    /// #[derive(Debug, Eq, PartialEq, Hash, Serialize, Deserialize, Clone)]
+    /// struct ExampleStruct {
+    ///     my_age: i32
+    /// }
+    ///
+    /// fn main() {
+    ///     let my_struct = ExampleStruct { my_age: 329 };
+    ///     let mut my_db = Database::new("query_test", None, false);
+    ///
+    ///     my_db.add_item(my_struct.clone());
+    ///
+    ///     let results = my_db.query_item(|s: &ExampleStruct| &s.my_age, 329);
+    ///
+    ///     assert_eq!(results.unwrap(), &my_struct);
+    ///
+    ///     let mut names = Database::new("query_borrowed_test", None, false);
+    ///
+    ///     names.add_item(String::from("Rimmer")).unwrap();
+    ///
+    ///     assert!(names.query_item(|name: &String| name, "Rimmer").is_ok());
+    /// }
+    /// ```
+    pub fn query_item<Q, F: PartialEq<Q> + ?Sized, V: Fn(&T) -> &F>(
+        &self,
+        value: V,
+        query: Q,
+    ) -> Result<&T, error::DatabaseError> {
+        let _timer = trace::start("query_item", &self.label, self.items.len());
+
+        self.counters.queried();
+
+        for (scanned, item) in self.items.iter().enumerate() {
+            if *value(item) == query && !self.is_expired(item) {
+                self.usage.record::<V>(scanned + 1);
+                self.explain_scan("query_item", scanned + 1, 1);
+                self.access.accessed(item);
+                return Ok(item);
+            }
+        }
+
+        self.usage.record::<V>(self.items.len());
+        self.explain_scan("query_item", self.items.len(), 0);
+        Err(error::DatabaseError::ItemNotFound)
+    }
+
+    /// Queries the database like [Database::query_item], inserting the item
+    /// made by `make` if nothing matches so a reference is returned either
+    /// way. This avoids having to query, add and query again by hand.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tinydb::Database;
+    ///
+    /// fn key((key, _): &(String, String)) -> &String {
+    ///     key
+    /// }
+    ///
+    /// fn main() {
+    ///     let mut db = Database::new("get_or_insert_test", None, false);
+    ///
+    ///     db.add_item((String::from("theme"), String::from("dark"))).unwrap();
+    ///
+    ///     let theme = db.get_or_insert_with(key, String::from("theme"), || {
+    ///         (String::from("theme"), String::from("light"))
+    ///     });
+    ///
+    ///     assert_eq!(theme.unwrap().1, "dark");
+    ///
+    ///     let font = db.get_or_insert_with(key, String::from("font"), || {
+    ///         (String::from("font"), String::from("mono"))
+    ///     });
+    ///
+    ///     assert_eq!(font.unwrap().1, "mono");
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns [error::DatabaseError::ItemNotFound] if the made item doesn't
+    /// match `query` itself, in which case it isn't inserted. Adding the item
+    /// can also fail in the same ways as [Database::add_item].
+    pub fn get_or_insert_with<Q: PartialEq, V: Fn(&T) -> &Q>(
+        &mut self,
+        value: V,
+        query: Q,
+        make: impl FnOnce() -> T,
+    ) -> Result<&T, error::DatabaseError> {
+        let found = self
+            .items
+            .iter()
+            .any(|item| value(item) == &query && !self.is_expired(item));
+
+        if !found {
+            self.check_mutable()?;
+
+            let item = make();
+
+            if value(&item) != &query {
+                return Err(error::DatabaseError::ItemNotFound);
+            }
+
+            self.history.begin_step();
+            self.insert_by_policy(item)?;
+        }
+
+        self.query_item(value, query)
+    }
+
+    /// Finds all items where the field given by `value` equals `query`, unlike
+    /// [Database::query_item] which only returns the first match.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tinydb::Database;
+    ///
+    /// fn main() {
+    ///     let mut db = Database::new("find_test", None, false);
+    ///
+    ///     db.add_item((String::from("Cat"), 3000000));
+    ///     db.add_item((String::from("Kryten"), 3000000));
+    ///     db.add_item((String::from("Lister"), 25));
+    ///
+    ///     assert_eq!(db.find_by(|(_, age): &(String, u32)| age, 3000000).len(), 2);
+    /// }
+    /// ```
+    pub fn find_by<Q, F: PartialEq<Q> + ?Sized, V: Fn(&T) -> &F>(
+        &self,
+        value: V,
+        query: Q,
+    ) -> Vec<&T> {
+        let timer = trace::start("find_by", &self.label, self.items.len());
+
+        self.counters.queried();
+        self.usage.record::<V>(self.items.len());
+
+        let found: Vec<&T> = self
+            .items
+            .iter()
+            .filter(|item| value(item) == &query && !self.is_expired(item))
+            .collect();
+
+        self.explain_scan("find_by", self.items.len(), found.len());
+        timer.items(found.len());
+        found
+    }
+
+    /// Searches the database for a specific value. If it does not exist, this
+    /// method will return [error::DatabaseError::ItemNotFound].
+    ///
+    /// This is a wrapper around [HashSet::contains].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tinydb::Database;
+    /// use serde::{Serialize, Deserialize};
+    ///
+    /// #[derive(Hash, Eq, PartialEq, Serialize, Deserialize, Copy, Clone)]
+    /// struct ExampleStruct {
+    ///     item: i32
+    /// }
+    ///
+    /// fn main() {
+    ///     let exp_struct = ExampleStruct { item: 4942 };
+    ///     let mut db = Database::new("Contains example", None, false);
+    ///
+    ///     db.add_item(exp_struct.clone());
+    ///
+    ///     assert_eq!(db.contains(&exp_struct), true);
+    /// }
+    /// ```
+    pub fn contains(&self, query: &T) -> bool {
+        self.counters.queried();
+
+        let found = self.items.contains(query) && !self.is_expired(query);
+
+        if found {
+            self.access.accessed(query);
+        }
+
+        self.explain_lookup("contains", found);
+        found
+    }
+
+    /// Checks if any item has a field equal to `query`, without having to
+    /// build an entire item like [Database::contains] needs.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use serde::{Serialize, Deserialize};
+    /// use tinydb::Database;
+    ///
+    /// #[derive(Hash, Eq, PartialEq, Serialize, Deserialize)]
+    /// struct ExampleStruct {
+    ///     name: String,
+    ///     age: u32,
+    /// }
+    ///
+    /// fn main() {
+    ///     let mut db = Database::new("contains_by_test", None, false);
+    ///
+    ///     db.add_item(ExampleStruct { name: String::from("Cat"), age: 3000000 });
+    ///
+    ///     assert!(db.contains_by(|s: &ExampleStruct| &s.name, "Cat"));
+    ///     assert!(!db.contains_by(|s: &ExampleStruct| &s.age, 25));
+    /// }
+    /// ```
+    pub fn contains_by<Q, F: PartialEq<Q> + ?Sized, V: Fn(&T) -> &F>(
+        &self,
+        value: V,
+        query: Q,
+    ) -> bool {
+        self.counters.queried();
+
+        for (scanned, item) in self.items.iter().enumerate() {
+            if *value(item) == query && !self.is_expired(item) {
+                self.usage.record::<V>(scanned + 1);
+                self.explain_scan("contains_by", scanned + 1, 1);
+                return true;
+            }
+        }
+
+        self.usage.record::<V>(self.items.len());
+        self.explain_scan("contains_by", self.items.len(), 0);
+        false
+    }
+
+    /// Counts the items which have a field equal to `query`, see
+    /// [Database::contains_by].
+    pub fn count_by<Q, F: PartialEq<Q> + ?Sized, V: Fn(&T) -> &F>(
+        &self,
+        value: V,
+        query: Q,
+    ) -> usize {
+        self.counters.queried();
+        self.usage.record::<V>(self.items.len());
+
+        let count = self
+            .items
+            .iter()
+            .filter(|item| value(item) == &query && !self.is_expired(item))
+            .count();
+
+        self.explain_scan("count_by", self.items.len(), count);
+        count
+    }
+
+    /// Gets any item, which is the first one from [Database::ordered] so
+    /// items moved to the front are always picked first.
+    ///
+    /// # Errors
+    ///
+    /// Will return [error::DatabaseError::ItemNotFound] if the database is
+    /// empty.
+    pub fn first(&self) -> Result<&T, error::DatabaseError> {
+        self.counters.queried();
+
+        let found = self
+            .ordered()
+            .find(|item| !self.is_expired(item))
+            .ok_or(error::DatabaseError::ItemNotFound)?;
+
+        self.access.accessed(found);
+        Ok(found)
+    }
+
+    /// Gets the only item inside of this database, for databases such as
+    /// settings which should always hold exactly one item.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tinydb::Database;
+    /// use tinydb::error::DatabaseError;
+    ///
+    /// fn main() {
+    ///     let mut db = Database::new("single_test", None, false);
+    ///
+    ///     assert_eq!(db.single(), Err(DatabaseError::ItemNotFound));
+    ///
+    ///     db.add_item(String::from("dark mode")).unwrap();
+    ///
+    ///     assert_eq!(db.single().unwrap(), "dark mode");
+    ///
+    ///     db.add_item(String::from("light mode")).unwrap();
+    ///
+    ///     assert_eq!(db.single(), Err(DatabaseError::MultipleFound(2)));
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Will return [error::DatabaseError::ItemNotFound] if the database is
+    /// empty or [error::DatabaseError::MultipleFound] if there is more than
+    /// one item.
+    pub fn single(&self) -> Result<&T, error::DatabaseError> {
+        self.counters.queried();
+
+        let mut live = self.items.iter().filter(|item| !self.is_expired(item));
+        let found = live.next().ok_or(error::DatabaseError::ItemNotFound)?;
+
+        match live.count() {
+            0 => {
+                self.access.accessed(found);
+                Ok(found)
+            }
+            others => Err(error::DatabaseError::MultipleFound(others + 1)),
+        }
+    }
+
+    /// Checks if any item matches `predicate`, stopping at the first one
+    /// which does.
+    pub fn any(&self, predicate: impl Fn(&T) -> bool) -> bool {
+        self.counters.queried();
+
+        let found = self
+            .items
+            .iter()
+            .position(|item| predicate(item) && !self.is_expired(item));
+
+        match found {
+            Some(position) => self.explain_scan("any", position + 1, 1),
+            None => self.explain_scan("any", self.items.len(), 0),
+        }
+
+        found.is_some()
+    }
+
+    /// Starts tracking a running statistic for a numeric value of each item,
+    /// which is kept up-to-date on every mutation so it can be fetched in O(1)
+    /// using [Database::stat].
+    ///
+    /// Registering a statistic under an existing name will replace it. Tracked
+    /// statistics are not saved when dumping and have to be registered again
+    /// after loading.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tinydb::Database;
+    /// use tinydb::stats::Stat;
+    /// use serde::{Serialize, Deserialize};
+    ///
+    /// #[derive(Hash, Eq, PartialEq, Serialize, Deserialize)]
+    /// struct ExampleStruct {
+    ///     age: i32
+    /// }
+    ///
+    /// fn main() {
+    ///     let mut db = Database::new("Stats example", None, false);
+    ///
+    ///     db.add_item(ExampleStruct { age: 20 }).unwrap();
+    ///     db.add_item(ExampleStruct { age: 40 }).unwrap();
+    ///
+    ///     db.track_stat("age", Stat::MinMaxMean, |s: &ExampleStruct| s.age as f64);
+    ///
+    ///     let age = db.stat("age").unwrap();
+    ///
+    ///     assert_eq!(age.min, Some(20.0));
+    ///     assert_eq!(age.mean(), Some(30.0));
+    /// }
+    /// ```
+    pub fn track_stat(
+        &mut self,
+        name: impl Into<String>,
+        stat: stats::Stat,
+        extractor: impl Fn(&T) -> f64 + Send + Sync + 'static,
+    ) {
+        self.stats
+            .track(name.into(), stat, Arc::new(extractor), self.items.iter());
+    }
+
+    /// Stops tracking a statistic previously registered with
+    /// [Database::track_stat], returning `true` if it existed.
+    pub fn untrack_stat(&mut self, name: &str) -> bool {
+        self.stats.untrack(name)
+    }
+
+    /// Gets the current value of a statistic registered with
+    /// [Database::track_stat] or [Option::None] if it isn't being tracked.
+    pub fn stat(&self, name: &str) -> Option<stats::StatSummary> {
+        self.stats.get(name)
+    }
+
+    /// Registers a callback which is ran with a [events::ChangeEvent] every
+    /// time the database is mutated.
+    ///
+    /// Callbacks are not saved when dumping and are not carried over when
+    /// cloning the database.
+    pub fn on_change(
+        &mut self,
+        mut callback: impl FnMut(events::ChangeEvent<&T>) + Send + Sync + 'static,
+    ) {
+        self.listeners.push(Box::new(move |event| {
+            callback(event);
+            true
+        }));
+    }
+
+    /// Subscribes to all future mutations of the database, returning a
+    /// [mpsc::Receiver] which gets an owned [events::ChangeEvent] for each one.
+    ///
+    /// Unlike [Database::on_change], this doesn't hold a borrow of anything
+    /// so the receiver can be moved to another thread. Once the receiver is
+    /// dropped, the subscription is automatically removed on the next mutation.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tinydb::Database;
+    /// use tinydb::events::ChangeEvent;
+    ///
+    /// fn main() {
+    ///     let mut db: Database<String> = Database::new("Subscribe example", None, false);
+    ///     let receiver = db.subscribe();
+    ///
+    ///     db.add_item(String::from("hello")).unwrap();
+    ///
+    ///     assert_eq!(receiver.recv().unwrap(), ChangeEvent::Added(String::from("hello")));
     /// }
     /// ```
-    pub fn contains(&self, query: &T) -> bool {
-        self.items.contains(query)
+    #[cfg(feature = "std")]
+    pub fn subscribe(&mut self) -> mpsc::Receiver<events::ChangeEvent<T>>
+    where
+        T: Clone + Send + 'static,
+    {
+        let (sender, receiver) = mpsc::channel();
+
+        self.listeners
+            .push(Box::new(move |event: events::ChangeEvent<&T>| {
+                sender.send(event.map(T::clone)).is_ok()
+            }));
+
+        receiver
+    }
+
+    /// Inserts an item into [Database::items] whilst keeping any tracked state
+    /// in sync, returning `false` if an identical item already existed.
+    fn insert_item(&mut self, item: T) -> bool {
+        if self.items.contains(&item) || self.capacity.rejects(self.items.len()) {
+            return false;
+        }
+
+        self.meta.modified();
+        self.version.bump();
+        self.stats.insert(&item);
+        self.indexes.insert(&item);
+        self.handles.insert(&item);
+        #[cfg(feature = "uuid")]
+        self.uuids.insert(&item);
+        self.access.insert(&item);
+        self.counters.inserted(1);
+        self.capacity.insert(&item);
+        self.journal.insert(&item);
+        self.history.record(events::ChangeEvent::Added(&item));
+        self.listeners.emit(events::ChangeEvent::Added(&item));
+        self.aggregates.emit(events::ChangeEvent::Added(&item));
+
+        let inserted = self.items.insert(item);
+
+        trace::mutation("insert", self.items.len());
+
+        self.evict_overflow();
+        inserted
+    }
+
+    /// Takes an item out of [Database::items] whilst keeping any tracked state
+    /// in sync.
+    fn take_item(&mut self, item: &T) -> Option<T> {
+        let taken = self.items.take(item)?;
+
+        self.forget_item(&taken);
+        Some(taken)
     }
 
-    /// Opens the path given in [Database::save_path] (or auto-generates a path).
-    fn open_db_path(&self) -> Result<File, error::DatabaseError> {
-        let definate_path = self.smart_path_get();
+    /// Takes every item matching `pred` out of [Database::items] whilst keeping
+    /// any tracked state in sync.
+    fn take_where(&mut self, pred: impl FnMut(&T) -> bool) -> Vec<T> {
+        let taken: Vec<T> = self.items.extract_if(pred).collect();
+
+        for item in taken.iter() {
+            self.forget_item(item);
+        }
+
+        taken
+    }
+
+    /// Updates any tracked state for an item which has just been taken out of
+    /// [Database::items].
+    fn forget_item(&mut self, item: &T) {
+        if let Some(count) = self.dupes.counts.remove(item) {
+            for _ in 1..count {
+                self.record_count(events::ChangeEvent::Removed(item));
+            }
+        }
+
+        self.order.unpin(item);
+        self.expiries.remove(item);
+        self.meta.modified();
+        self.version.bump();
+        self.stats.remove(item);
+        self.indexes.remove(item);
+        self.handles.remove(item);
+        #[cfg(feature = "uuid")]
+        self.uuids.remove(item);
+        self.access.remove(item);
+        self.counters.removed();
+        trace::mutation("remove", self.items.len());
+        self.capacity.remove(item);
+        self.journal.remove(item);
+        self.history.record(events::ChangeEvent::Removed(item));
+        self.listeners.emit(events::ChangeEvent::Removed(item));
+        self.aggregates.emit(events::ChangeEvent::Removed(item));
+    }
+
+    /// Makes sure this database can be mutated, used at the start of any
+    /// public method which changes it. This is also where lazy purges from
+    /// [Database::set_purge_on_query] happen.
+    fn check_mutable(&mut self) -> Result<(), error::DatabaseError> {
+        if self.read_only {
+            Err(error::DatabaseError::ReadOnly)
+        } else if self.archived {
+            Err(error::DatabaseError::Archived)
+        } else {
+            self.version.bump();
+            self.purge_if_due();
+            Ok(())
+        }
+    }
+
+    /// Checks if an item has expired, see [Database::add_item_with_ttl].
+    fn is_expired(&self, item: &T) -> bool {
+        let expired = !self.expiries.is_empty() && self.expiries.is_expired(item, meta::now_millis());
+
+        if expired {
+            self.lazy_purge.expired_seen();
+        }
+
+        expired
+    }
+
+    /// Writes a dump to the path given in [Database::save_path] (or an
+    /// auto-generated path), replacing any existing dump in one step so it's
+    /// never left half-written.
+    #[cfg(feature = "std")]
+    fn write_db_path(&self, stream: &[u8]) -> Result<(), error::DatabaseError> {
+        let definate_path = self.dump_path()?;
+
+        lock::check(&definate_path, self.lock.get())?;
 
         if definate_path.exists() {
-            std::fs::remove_file(&definate_path)?;
+            let mut header = Vec::with_capacity(format::HEADER_LEN);
+
+            File::open(&definate_path)?
+                .take(format::HEADER_LEN as u64)
+                .read_to_end(&mut header)?;
+
+            if let Some(flags) = format::read_flags(&header) {
+                if flags & format::FLAG_ARCHIVED != 0 {
+                    return Err(error::DatabaseError::Archived);
+                }
+            }
+
+            self.replace_existing(&definate_path)?;
         }
 
-        Ok(File::create(&definate_path)?)
+        paths::write_atomic(&definate_path, stream)?;
+        self.overwrite.own(&definate_path);
+        Ok(())
     }
 
     /// Automatically allocates a path for the database if [Database::save_path]
-    /// is not provided. If it is, this function will simply return it.
+    /// is not provided, with the label made safe to use as a file name. If it
+    /// is, this function will simply return it.
+    #[cfg(feature = "std")]
     fn smart_path_get(&self) -> PathBuf {
         if self.save_path.is_none() {
-            return PathBuf::from(format!("{}.tinydb", self.label));
+            return paths::label_path(&self.label);
         }
 
         PathBuf::from(self.save_path.as_ref().unwrap())
     }
 }
 
+#[cfg(feature = "std")]
+impl<'de, T: hash::Hash + Eq + Deserialize<'de>> Database<T> {
+    /// Creates a database which borrows from a dump held in memory rather
+    /// than copying out of it, for items with `#[serde(borrow)]` fields such
+    /// as `&'de str`. This avoids allocating every string when loading huge
+    /// dumps which are mostly read from.
+    ///
+    /// The buffer, which could also be a memory-mapped file, has to outlive
+    /// the database.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tinydb::Database;
+    ///
+    /// fn main() {
+    ///     let mut owned: Database<String> = Database::new("borrowed_test", None, false);
+    ///
+    ///     owned.add_item(String::from("Ace Rimmer")).unwrap();
+    ///
+    ///     let dump = owned.to_bytes().unwrap();
+    ///     let borrowed: Database<&str> = Database::from_borrowed(&dump).unwrap();
+    ///
+    ///     assert!(borrowed.contains(&"Ace Rimmer"));
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Fails in the same ways as [Database::from_bytes], apart from dumps made
+    /// before format versioning which can't be borrowed from and return
+    /// [error::DatabaseError::UnrecognizedFormat].
+    pub fn from_borrowed(bytes: &'de [u8]) -> Result<Self, error::DatabaseError> {
+        match format::read_header(bytes)? {
+            format::Payload::Current { flags, payload, .. } => {
+                let encoding = encoding::Encoding::from_flags(flags);
+                let mut decoded: Database<T> = encoding.deserialize_borrowed(payload)?;
+
+                decoded.archived = flags & format::FLAG_ARCHIVED != 0;
+                decoded.encoding = encoding;
+                Ok(decoded)
+            }
+            format::Payload::Headerless(_) => Err(error::DatabaseError::UnrecognizedFormat {
+                detected: Some(String::from("headerless tinydb dump")),
+            }),
+        }
+    }
+}
+
+/// Layout of dumps made before format versioning, which only contained the
+/// core fields of [Database] without a header.
+#[cfg(feature = "std")]
+#[derive(Deserialize)]
+struct LegacyDump<T: hash::Hash + Eq> {
+    label: String,
+    /// Ignored, see [Database::save_path].
+    _save_path: Option<PathBuf>,
+    strict_dupes: bool,
+    items: HashSet<T, ItemHasher>,
+}
+
 /// Reads a given path and converts it into a [Vec]<[u8]> stream.
+#[cfg(feature = "std")]
 fn get_stream_from_path(path: PathBuf) -> Result<Vec<u8>, error::DatabaseError> {
     if !path.exists() {
-        return Err(error::DatabaseError::DatabaseNotFound);
+        return Err(error::DatabaseError::DatabaseNotFound(path));
     }
 
     let mut file = File::open(path)?;
@@ -427,6 +2028,111 @@ mod tests {
         Ok(())
     }
 
+    /// Tests only inserting with [Database::get_or_insert_with] when missing
+    #[test]
+    fn item_get_or_insert() -> Result<(), error::DatabaseError> {
+        let mut my_db = Database::new("Get or insert test", None, true);
+        let mut made = 0;
+
+        for _ in 0..2 {
+            let item = my_db.get_or_insert_with(
+                |s: &DemoStruct| &s.name,
+                String::from("Lister"),
+                || {
+                    made += 1;
+
+                    DemoStruct {
+                        name: String::from("Lister"),
+                        age: 25,
+                    }
+                },
+            )?;
+
+            assert_eq!(item.age, 25);
+        }
+
+        assert_eq!(made, 1);
+        assert_eq!(my_db.items.len(), 1);
+        assert!(my_db
+            .get_or_insert_with(
+                |s: &DemoStruct| &s.age,
+                30,
+                || DemoStruct {
+                    name: String::from("Rimmer"),
+                    age: 31,
+                }
+            )
+            .is_err());
+        assert_eq!(my_db.items.len(), 1);
+
+        my_db.set_capacity(1, capacity::EvictionPolicy::Reject);
+
+        assert_eq!(
+            my_db.get_or_insert_with(
+                |s: &DemoStruct| &s.name,
+                String::from("Rimmer"),
+                || DemoStruct {
+                    name: String::from("Rimmer"),
+                    age: 31,
+                }
+            ),
+            Err(error::DatabaseError::CapacityReached)
+        );
+        assert_eq!(my_db.items.len(), 1);
+
+        Ok(())
+    }
+
+    /// Tests [Database::modify_where] and its duplicate handling
+    #[test]
+    fn item_modify_where() -> Result<(), error::DatabaseError> {
+        let mut my_db = Database::new("Modify test", None, true);
+
+        for (name, age) in &[("Lister", 25), ("Rimmer", 30), ("Kryten", 26)] {
+            my_db.add_item(DemoStruct {
+                name: String::from(*name),
+                age: *age,
+            })?;
+        }
+
+        let older = |s: &DemoStruct| DemoStruct {
+            name: s.name.clone(),
+            age: s.age + 1,
+        };
+
+        assert_eq!(my_db.modify_where(|s| s.age < 30, older)?, 2);
+        assert_eq!(my_db.count_where(|s| s.age == 26 || s.age == 27), 2);
+        assert_eq!(my_db.modify_where(|s| s.age > 100, older)?, 0);
+
+        let renamed = |_: &DemoStruct| DemoStruct {
+            name: String::from("Lister"),
+            age: 26,
+        };
+
+        assert!(my_db.modify_where(|s| s.name == "Kryten", renamed).is_err());
+        assert_eq!(my_db.count_where(|s| s.name == "Kryten"), 1);
+        assert_eq!(my_db.items.len(), 3);
+
+        Ok(())
+    }
+
+    /// Tests a database made with [Database::with_hasher] surviving a dump
+    #[test]
+    fn item_with_hasher() -> Result<(), error::DatabaseError> {
+        let path = PathBuf::from("with_hasher_test.tinydb");
+        let mut my_db = Database::with_hasher("Hasher test", path.clone(), true, ItemHasher::default());
+
+        my_db.add_item(5)?;
+        my_db.dump_db()?;
+
+        let loaded: Database<i32> = Database::from(path.clone())?;
+
+        assert!(loaded.contains(&5));
+
+        std::fs::remove_file(path)?;
+        Ok(())
+    }
+
     /// Tests removal from in-memory db
     #[test]
     fn item_remove() -> Result<(), error::DatabaseError> {
@@ -443,13 +2149,15 @@ mod tests {
         Ok(())
     }
 
-    #[test]
-    fn db_dump() -> Result<(), error::DatabaseError> {
-        let mut my_db = Database::new(
-            String::from("Dumping test"),
-            Some(PathBuf::from("test.tinydb")),
-            true,
-        );
+    /// Gets a path in the temporary directory which only the test calling
+    /// this uses
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("tinydb_{}_{}.tinydb", name, std::process::id()))
+    }
+
+    /// Dumps a small database to `path` for tests which load it back
+    fn dump_demo(path: PathBuf) -> Result<(), error::DatabaseError> {
+        let mut my_db = Database::new(String::from("Dumping test"), Some(path), true);
 
         my_db.add_item(DemoStruct {
             name: String::from("Xander"),
@@ -464,14 +2172,23 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn db_dump() -> Result<(), error::DatabaseError> {
+        let path = temp_path("db_dump");
+
+        dump_demo(path.clone())?;
+
+        assert!(path.exists());
+
+        std::fs::remove_file(path)?;
+        Ok(())
+    }
+
     /// Tests [Database::query_item]
     #[test]
     fn query_item_db() {
-        let mut my_db = Database::new(
-            String::from("Query test"),
-            Some(PathBuf::from("test.tinydb")),
-            true,
-        );
+        let mut my_db = Database::new(String::from("Query test"), None, true);
 
         my_db
             .add_item(DemoStruct {
@@ -512,17 +2229,194 @@ mod tests {
                 age: 10,
             }
         ); // Finds "Cat" by searching [DemoStruct::name]
+        assert_eq!(my_db.query_item(|f| &f.name, "Kryten").unwrap().age, 3000); // Borrowed query
     }
 
     /// Tests a [Database::from] method call
     #[test]
     fn db_from() -> Result<(), error::DatabaseError> {
-        db_dump()?; // ensure database was dumped
+        let path = temp_path("db_from");
 
-        let my_db: Database<DemoStruct> = Database::from(PathBuf::from("test.tinydb"))?;
+        dump_demo(path.clone())?; // ensure database was dumped
+
+        let my_db: Database<DemoStruct> = Database::from(path.clone())?;
 
         assert_eq!(my_db.label, String::from("Dumping test"));
 
+        drop(my_db);
+        std::fs::remove_file(path)?;
+        Ok(())
+    }
+
+    /// Tests a dump embedded between other data being read back with
+    /// [Database::from_reader]
+    #[test]
+    fn db_from_reader_embedded() -> Result<(), error::DatabaseError> {
+        let mut my_db = Database::new("Embedded test", None, false);
+        let mut file = b"HEADER".to_vec();
+
+        my_db.add_item(7)?;
+        my_db.dump_to_writer(&mut file)?;
+
+        let dump_len = file.len() - 6;
+
+        file.extend_from_slice(b"TRAILER");
+
+        let loaded: Database<i32> = Database::from_reader((&file[6..]).take(dump_len as u64))?;
+
+        assert_eq!(loaded.label, "Embedded test");
+        assert!(loaded.contains(&7));
+
+        Ok(())
+    }
+
+    /// Tests borrowed items pointing into the dump they were loaded from
+    #[test]
+    fn db_from_borrowed() -> Result<(), error::DatabaseError> {
+        let mut my_db = Database::new("Borrowed test", None, false);
+
+        my_db.add_item(String::from("borrowed"))?;
+
+        let dump = my_db.to_bytes()?;
+        let loaded: Database<&str> = Database::from_borrowed(&dump)?;
+        let item = loaded.items.iter().next().unwrap();
+
+        assert_eq!(*item, "borrowed");
+        assert!(dump.as_ptr_range().contains(&item.as_ptr()));
+
+        Ok(())
+    }
+
+    /// Tests equality only looking at labels and items
+    #[test]
+    fn db_equality() -> Result<(), error::DatabaseError> {
+        let mut first: Database<u8> = Database::default();
+        let mut second = Database::new("", PathBuf::from("equality.tinydb"), true);
+
+        first.add_item(1)?;
+        second.add_item(1)?;
+        second.move_to_front(&1)?;
+
+        assert_eq!(first, second);
+        assert_eq!(first.clone(), first);
+
+        second.label = String::from("Equality test");
+
+        assert_ne!(first, second);
+        Ok(())
+    }
+
+    /// Tests a memory-only database of items which can't be serialized
+    #[test]
+    fn db_memory_only() -> Result<(), error::DatabaseError> {
+        #[derive(Hash, PartialEq, Eq)]
+        struct Unsaved<'a>(&'a str, u8);
+
+        let names = [String::from("Holly"), String::from("Talkie")];
+        let mut my_db = Database::new("Memory test", None, true);
+
+        for name in &names {
+            my_db.add_item(Unsaved(name, 0))?;
+        }
+
+        assert_eq!(my_db.query_item(|item| &item.0, "Holly")?.1, 0);
+        assert!(my_db.add_item(Unsaved("Holly", 0)).is_err());
+
+        Ok(())
+    }
+
+    /// Tests [Database::from] on files which aren't the current format,
+    /// loading headerless dumps and rejecting JSON.
+    #[test]
+    fn db_from_other_formats() -> Result<(), error::DatabaseError> {
+        let legacy_path = PathBuf::from("legacy_format.tinydb");
+        let json_path = PathBuf::from("json_format.tinydb");
+        let mut legacy = bincode::serialize(&String::from("Legacy test")).unwrap();
+
+        legacy.extend(bincode::serialize(&Option::<PathBuf>::None).unwrap());
+        legacy.extend(bincode::serialize(&true).unwrap());
+        legacy.extend(bincode::serialize(&vec![5i32]).unwrap());
+        std::fs::write(&legacy_path, legacy)?;
+        std::fs::write(&json_path, "{\"label\": \"Json test\"}")?;
+
+        let loaded: Database<i32> = Database::from(legacy_path.clone())?;
+
+        assert_eq!(loaded.label, String::from("Legacy test"));
+        assert!(loaded.contains(&5));
+
+        match Database::<i32>::from(json_path.clone()) {
+            Err(error::DatabaseError::UnrecognizedFormat { detected }) => {
+                assert_eq!(detected, Some(String::from("json")))
+            }
+            _ => panic!("json file wasn't detected"),
+        }
+
+        std::fs::remove_file(legacy_path)?;
+        std::fs::remove_file(json_path)?;
+        Ok(())
+    }
+
+    /// Tests that a dump opened with [Database::from] can't be opened again
+    /// until the first database is dropped.
+    #[test]
+    fn db_from_locked() -> Result<(), error::DatabaseError> {
+        let path = PathBuf::from("locked_test.tinydb");
+
+        Database::<i32>::new("Locking test", path.clone(), false).dump_db()?;
+
+        let first: Database<i32> = Database::from(path.clone())?;
+
+        assert!(matches!(
+            Database::<i32>::from(path.clone()),
+            Err(error::DatabaseError::Locked)
+        ));
+        assert!(Database::<i32>::from_unlocked(path.clone()).is_ok());
+        assert!(matches!(
+            Database::<i32>::new("Other test", PathBuf::from("./locked_test.tinydb"), false)
+                .dump_db(),
+            Err(error::DatabaseError::Locked)
+        ));
+
+        first.dump_db()?;
+        drop(first);
+
+        let _second: Database<i32> = Database::from(path.clone())?;
+
+        std::fs::remove_file(path)?;
+        Ok(())
+    }
+
+    /// Tests [Database::reload] picking up changes made by another copy of
+    /// the same database.
+    #[test]
+    fn db_reload() -> Result<(), error::DatabaseError> {
+        let path = PathBuf::from("reload_test.tinydb");
+        let mut db = Database::new("Reload test", path.clone(), false);
+
+        db.add_item(1)?;
+        db.add_item(2)?;
+        db.dump_db()?;
+
+        let mut other: Database<i32> = Database::from_unlocked(path.clone())?;
+
+        other.remove_item(&1)?;
+        other.add_item(3)?;
+        other.dump_db()?;
+
+        let receiver = db.subscribe();
+
+        db.reload()?;
+
+        assert_eq!(db.items, vec![2, 3].into_iter().collect());
+        assert_eq!(
+            receiver.try_iter().collect::<Vec<_>>(),
+            vec![
+                events::ChangeEvent::Removed(1),
+                events::ChangeEvent::Added(3)
+            ]
+        );
+
+        std::fs::remove_file(path)?;
         Ok(())
     }
 
@@ -537,7 +2431,151 @@ mod tests {
 
         let mut db = Database::new(String::from("Contains example"), None, false);
         db.add_item(exp_struct.clone()).unwrap();
-        assert_eq!(db.contains(&exp_struct), true);
+        assert!(db.contains(&exp_struct));
+    }
+
+    /// Tests [Database::contains_by] and [Database::count_by] matching on a
+    /// single field.
+    #[test]
+    fn db_contains_by() -> Result<(), error::DatabaseError> {
+        let mut db = Database::new("Contains by test", None, false);
+
+        for (name, age) in &[("Xander", 33), ("Bob", 33), ("Alice", 20)] {
+            db.add_item(DemoStruct {
+                name: name.to_string(),
+                age: *age,
+            })?;
+        }
+
+        assert!(db.contains_by(|s: &DemoStruct| &s.name, "Bob"));
+        assert!(!db.contains_by(|s: &DemoStruct| &s.name, "Carol"));
+        assert_eq!(db.count_by(|s: &DemoStruct| &s.age, 33), 2);
+        assert_eq!(db.count_by(|s: &DemoStruct| &s.age, 99), 0);
+
+        Ok(())
+    }
+
+    /// Tests [Database::for_each_mut] keeping statistics in sync and handing
+    /// back items which became duplicates.
+    #[test]
+    fn db_for_each_mut() -> Result<(), error::DatabaseError> {
+        let mut db = Database::new("For each mut test", None, true);
+
+        db.track_stat("age", stats::Stat::Sum, |s: &DemoStruct| s.age as f64);
+
+        for (name, age) in &[("Xander", 33), ("Bob", 20), ("Alice", 21)] {
+            db.add_item(DemoStruct {
+                name: name.to_string(),
+                age: *age,
+            })?;
+        }
+
+        let rejected = db.for_each_mut(|s| {
+            s.name.clear();
+            s.age = s.age.max(30);
+        })?;
+
+        assert_eq!(rejected.len(), 1);
+        assert_eq!(db.items.len(), 2);
+        assert_eq!(db.stat("age").unwrap().count, 2);
+        assert!(db.contains(&DemoStruct {
+            name: String::new(),
+            age: 33
+        }));
+
+        Ok(())
+    }
+
+    /// Tests [Database::first] preferring pinned items and [Database::any]
+    /// skipping expired ones.
+    #[test]
+    fn db_first_any() -> Result<(), error::DatabaseError> {
+        let mut db = Database::new("First test", None, false);
+
+        assert_eq!(db.first(), Err(error::DatabaseError::ItemNotFound));
+
+        for item in 0..10u8 {
+            db.add_item(item)?;
+        }
+
+        db.add_item_with_ttl(20, std::time::Duration::from_secs(0))?;
+        db.move_to_front(&7)?;
+
+        assert_eq!(db.first(), Ok(&7));
+        assert!(db.any(|item| *item > 8));
+        assert!(!db.any(|item| *item > 10));
+        assert_eq!(db.single(), Err(error::DatabaseError::MultipleFound(10)));
+
+        Ok(())
+    }
+
+    /// Tests [Database::track_stat] staying up-to-date through additions,
+    /// updates and removals.
+    #[test]
+    fn db_track_stat() -> Result<(), error::DatabaseError> {
+        let mut db = Database::new(String::from("Stat test"), None, false);
+        let kryten = DemoStruct {
+            name: String::from("Kryten"),
+            age: 3000,
+        };
+
+        db.add_item(kryten.clone())?;
+        db.track_stat("age", stats::Stat::MinMaxMean, |s: &DemoStruct| {
+            s.age as f64
+        });
+        db.add_item(DemoStruct {
+            name: String::from("Cat"),
+            age: 10,
+        })?;
+        db.update_item(
+            &kryten,
+            DemoStruct {
+                name: String::from("Kryten"),
+                age: 30,
+            },
+        )?;
+
+        let age = db.stat("age").unwrap();
+
+        assert_eq!(age.count, 2);
+        assert_eq!(age.sum, 40.0);
+        assert_eq!(age.max, Some(30.0));
+        assert!(db.untrack_stat("age"));
+        assert!(db.stat("age").is_none());
+
+        Ok(())
+    }
+
+    /// Tests [Database::subscribe] receiving events from another thread and
+    /// being dropped once the receiver hangs up.
+    #[test]
+    fn db_subscribe() -> Result<(), error::DatabaseError> {
+        let mut db = Database::new(String::from("Subscribe test"), None, false);
+        let receiver = db.subscribe();
+        let lister = DemoStruct {
+            name: String::from("Lister"),
+            age: 62,
+        };
+
+        db.add_item(lister.clone())?;
+        db.remove_item(&lister)?;
+
+        let events: Vec<events::ChangeEvent<DemoStruct>> =
+            std::thread::spawn(move || receiver.iter().take(2).collect())
+                .join()
+                .unwrap();
+
+        assert_eq!(
+            events,
+            vec![
+                events::ChangeEvent::Added(lister.clone()),
+                events::ChangeEvent::Removed(lister.clone())
+            ]
+        );
+
+        db.add_item(lister)?; // receiver is gone, shouldn't error
+
+        Ok(())
     }
 
     /// Tests [Database::auto_from]'s ability to create new databases and fetch
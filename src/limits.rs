@@ -0,0 +1,611 @@
+//! Contains [LoadLimits], which stop dumps from untrusted sources using up
+//! unbounded memory whilst loading, see [crate::Database::from_with_limits].
+//!
+//! Dumps are always decoded without trusting lengths inside of them when
+//! reserving space, so a single corrupted length can't cause a huge
+//! allocation on its own. The limits here go further by capping sizes which
+//! are perfectly valid but larger than an application expects.
+
+use crate::{error, format, lazy, lock, Database};
+use bincode::Options;
+use serde::de::{
+    self, DeserializeOwned, DeserializeSeed, Deserializer, EnumAccess, MapAccess, SeqAccess,
+    VariantAccess, Visitor,
+};
+use std::cell::Cell;
+use std::fmt;
+use std::fs::File;
+use std::hash;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Limits on the size of a dump being loaded with
+/// [Database::from_with_limits] or [Database::from_bytes_with_limits],
+/// returning [error::DatabaseError::LimitExceeded] if any are gone over.
+///
+/// Every limit is disabled by default, so only the ones which are needed
+/// have to be set:
+///
+/// ```rust
+/// use tinydb::limits::LoadLimits;
+///
+/// let limits = LoadLimits {
+///     max_items: Some(10_000),
+///     ..LoadLimits::default()
+/// };
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct LoadLimits {
+    /// Largest dump which can be loaded in bytes, checked before anything is
+    /// decoded.
+    pub max_file_size: Option<u64>,
+
+    /// Most items a dump can contain, checked before any items are decoded.
+    pub max_items: Option<u64>,
+
+    /// Longest string, byte array, sequence or map anywhere inside of a dump.
+    /// This includes the set of items itself, so it should be at least as
+    /// high as [LoadLimits::max_items].
+    pub max_element_len: Option<usize>,
+}
+
+impl LoadLimits {
+    /// Makes sure `found` is within a limit, describing `what` went over it
+    /// otherwise.
+    fn check(what: &str, found: u64, limit: Option<u64>) -> Result<(), error::DatabaseError> {
+        match limit {
+            Some(limit) if found > limit => Err(error::DatabaseError::LimitExceeded(format!(
+                "{} of {} is over the limit of {}",
+                what, found, limit
+            ))),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Decodes a dump like [crate::progress::deserialize] but within `limits`,
+/// using `options` picked by [crate::encoding::Encoding]. The dump should
+/// start with a [lazy::Head], as both current and legacy dumps do.
+pub(crate) fn deserialize<V: DeserializeOwned>(
+    bytes: &[u8],
+    options: impl Options + Copy,
+    limits: &LoadLimits,
+) -> Result<V, error::DatabaseError> {
+    if limits.max_items.is_some() {
+        let head: lazy::Head = options.deserialize(bytes)?;
+
+        LoadLimits::check("item count", head.items_len, limits.max_items)?;
+    }
+
+    let guard = Guard {
+        max_len: limits.max_element_len.unwrap_or(usize::MAX),
+        exceeded: Cell::new(None),
+    };
+    let mut deserializer = bincode::Deserializer::from_slice(bytes, options);
+
+    V::deserialize(Limited {
+        inner: &mut deserializer,
+        guard: &guard,
+    })
+    .map_err(|e| match guard.exceeded.take() {
+        Some(what) => error::DatabaseError::LimitExceeded(what),
+        None => e.into(),
+    })
+}
+
+/// Reads a dump from a path, reading no more than one byte past
+/// [LoadLimits::max_file_size] in case it's far larger.
+fn read_limited(path: &Path, limits: &LoadLimits) -> Result<Vec<u8>, error::DatabaseError> {
+    if !path.exists() {
+        return Err(error::DatabaseError::DatabaseNotFound(path.to_path_buf()));
+    }
+
+    let max = limits
+        .max_file_size
+        .map_or(u64::MAX, |max| max.saturating_add(1));
+    let mut stream = Vec::new();
+
+    File::open(path)?.take(max).read_to_end(&mut stream)?;
+    Ok(stream)
+}
+
+/// Element length limit shared by everything taking part in a limited
+/// decode, recording which limit was gone over so it can be told apart from
+/// other decoding errors.
+struct Guard {
+    max_len: usize,
+    exceeded: Cell<Option<String>>,
+}
+
+impl Guard {
+    fn check<E: de::Error>(&self, len: usize) -> Result<(), E> {
+        if len <= self.max_len {
+            return Ok(());
+        }
+
+        let what = format!(
+            "element length of {} is over the limit of {}",
+            len, self.max_len
+        );
+        let e = E::custom(&what);
+
+        self.exceeded.set(Some(what));
+        Err(e)
+    }
+}
+
+/// Wraps a deserializer, visitor or one of their accessors so every length
+/// found whilst decoding is checked by a [Guard].
+///
+/// Bincode always knows the length of sequences and maps upfront, so these
+/// are checked before any of their elements are decoded or space is
+/// reserved for them.
+struct Limited<'g, I> {
+    inner: I,
+    guard: &'g Guard,
+}
+
+impl<'g, I> Limited<'g, I> {
+    /// Wraps something else using the same guard.
+    fn wrap<O>(&self, inner: O) -> Limited<'g, O> {
+        Limited {
+            inner,
+            guard: self.guard,
+        }
+    }
+}
+
+/// Forwards methods of [Deserializer] which only take a visitor.
+macro_rules! forward_deserialize {
+    ($($method:ident),*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+                let visitor = self.wrap(visitor);
+
+                self.inner.$method(visitor)
+            }
+        )*
+    };
+}
+
+impl<'de, D: Deserializer<'de>> Deserializer<'de> for Limited<'_, D> {
+    type Error = D::Error;
+
+    forward_deserialize!(
+        deserialize_any,
+        deserialize_bool,
+        deserialize_i8,
+        deserialize_i16,
+        deserialize_i32,
+        deserialize_i64,
+        deserialize_i128,
+        deserialize_u8,
+        deserialize_u16,
+        deserialize_u32,
+        deserialize_u64,
+        deserialize_u128,
+        deserialize_f32,
+        deserialize_f64,
+        deserialize_char,
+        deserialize_str,
+        deserialize_string,
+        deserialize_bytes,
+        deserialize_byte_buf,
+        deserialize_option,
+        deserialize_unit,
+        deserialize_seq,
+        deserialize_map,
+        deserialize_identifier,
+        deserialize_ignored_any
+    );
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        let visitor = self.wrap(visitor);
+
+        self.inner.deserialize_unit_struct(name, visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        let visitor = self.wrap(visitor);
+
+        self.inner.deserialize_newtype_struct(name, visitor)
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        let visitor = self.wrap(visitor);
+
+        self.inner.deserialize_tuple(len, visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        let visitor = self.wrap(visitor);
+
+        self.inner.deserialize_tuple_struct(name, len, visitor)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        let visitor = self.wrap(visitor);
+
+        self.inner.deserialize_struct(name, fields, visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        let visitor = self.wrap(visitor);
+
+        self.inner.deserialize_enum(name, variants, visitor)
+    }
+
+    fn is_human_readable(&self) -> bool {
+        self.inner.is_human_readable()
+    }
+}
+
+/// Forwards methods of [Visitor] which are given a value as is.
+macro_rules! forward_visit {
+    ($($method:ident($ty:ty)),*) => {
+        $(
+            fn $method<E: de::Error>(self, value: $ty) -> Result<Self::Value, E> {
+                self.inner.$method(value)
+            }
+        )*
+    };
+}
+
+/// Forwards methods of [Visitor] which are given a value with a length,
+/// checking it first.
+macro_rules! forward_visit_len {
+    ($($method:ident($ty:ty)),*) => {
+        $(
+            fn $method<E: de::Error>(self, value: $ty) -> Result<Self::Value, E> {
+                self.guard.check(value.len())?;
+                self.inner.$method(value)
+            }
+        )*
+    };
+}
+
+impl<'de, V: Visitor<'de>> Visitor<'de> for Limited<'_, V> {
+    type Value = V::Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.inner.expecting(f)
+    }
+
+    forward_visit!(
+        visit_bool(bool),
+        visit_i8(i8),
+        visit_i16(i16),
+        visit_i32(i32),
+        visit_i64(i64),
+        visit_i128(i128),
+        visit_u8(u8),
+        visit_u16(u16),
+        visit_u32(u32),
+        visit_u64(u64),
+        visit_u128(u128),
+        visit_f32(f32),
+        visit_f64(f64),
+        visit_char(char)
+    );
+
+    forward_visit_len!(
+        visit_str(&str),
+        visit_borrowed_str(&'de str),
+        visit_string(String),
+        visit_bytes(&[u8]),
+        visit_borrowed_bytes(&'de [u8]),
+        visit_byte_buf(Vec<u8>)
+    );
+
+    fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+        self.inner.visit_none()
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        let deserializer = self.wrap(deserializer);
+
+        self.inner.visit_some(deserializer)
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+        self.inner.visit_unit()
+    }
+
+    fn visit_newtype_struct<D: Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<Self::Value, D::Error> {
+        let deserializer = self.wrap(deserializer);
+
+        self.inner.visit_newtype_struct(deserializer)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<Self::Value, A::Error> {
+        self.guard.check(seq.size_hint().unwrap_or(0))?;
+
+        let seq = self.wrap(seq);
+
+        self.inner.visit_seq(seq)
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Self::Value, A::Error> {
+        self.guard.check(map.size_hint().unwrap_or(0))?;
+
+        let map = self.wrap(map);
+
+        self.inner.visit_map(map)
+    }
+
+    fn visit_enum<A: EnumAccess<'de>>(self, data: A) -> Result<Self::Value, A::Error> {
+        let data = self.wrap(data);
+
+        self.inner.visit_enum(data)
+    }
+}
+
+impl<'de, S: DeserializeSeed<'de>> DeserializeSeed<'de> for Limited<'_, S> {
+    type Value = S::Value;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        let deserializer = self.wrap(deserializer);
+
+        self.inner.deserialize(deserializer)
+    }
+}
+
+impl<'de, A: SeqAccess<'de>> SeqAccess<'de> for Limited<'_, A> {
+    type Error = A::Error;
+
+    fn next_element_seed<S: DeserializeSeed<'de>>(
+        &mut self,
+        seed: S,
+    ) -> Result<Option<S::Value>, Self::Error> {
+        let seed = self.wrap(seed);
+
+        self.inner.next_element_seed(seed)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.inner.size_hint()
+    }
+}
+
+impl<'de, A: MapAccess<'de>> MapAccess<'de> for Limited<'_, A> {
+    type Error = A::Error;
+
+    fn next_key_seed<S: DeserializeSeed<'de>>(
+        &mut self,
+        seed: S,
+    ) -> Result<Option<S::Value>, Self::Error> {
+        let seed = self.wrap(seed);
+
+        self.inner.next_key_seed(seed)
+    }
+
+    fn next_value_seed<S: DeserializeSeed<'de>>(
+        &mut self,
+        seed: S,
+    ) -> Result<S::Value, Self::Error> {
+        let seed = self.wrap(seed);
+
+        self.inner.next_value_seed(seed)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.inner.size_hint()
+    }
+}
+
+impl<'de, 'g, A: EnumAccess<'de>> EnumAccess<'de> for Limited<'g, A> {
+    type Error = A::Error;
+    type Variant = Limited<'g, A::Variant>;
+
+    fn variant_seed<S: DeserializeSeed<'de>>(
+        self,
+        seed: S,
+    ) -> Result<(S::Value, Self::Variant), Self::Error> {
+        let guard = self.guard;
+        let (value, variant) = self.inner.variant_seed(Limited { inner: seed, guard })?;
+
+        Ok((
+            value,
+            Limited {
+                inner: variant,
+                guard,
+            },
+        ))
+    }
+}
+
+impl<'de, A: VariantAccess<'de>> VariantAccess<'de> for Limited<'_, A> {
+    type Error = A::Error;
+
+    fn unit_variant(self) -> Result<(), Self::Error> {
+        self.inner.unit_variant()
+    }
+
+    fn newtype_variant_seed<S: DeserializeSeed<'de>>(
+        self,
+        seed: S,
+    ) -> Result<S::Value, Self::Error> {
+        let seed = self.wrap(seed);
+
+        self.inner.newtype_variant_seed(seed)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(
+        self,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        let visitor = self.wrap(visitor);
+
+        self.inner.tuple_variant(len, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        let visitor = self.wrap(visitor);
+
+        self.inner.struct_variant(fields, visitor)
+    }
+}
+
+impl<T: hash::Hash + Eq + DeserializeOwned> Database<T> {
+    /// Creates a database from a `.tinydb` file like [Database::from] does,
+    /// but failing rather than using up unbounded memory if the dump goes
+    /// over any of `limits`. Use this when loading dumps which could have
+    /// been tampered with, such as those uploaded by users.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tinydb::error::DatabaseError;
+    /// use tinydb::limits::LoadLimits;
+    /// use tinydb::Database;
+    ///
+    /// fn main() {
+    ///     let mut db = Database::new("limits_test", None, false);
+    ///
+    ///     db.add_item(String::from("Everybody's dead, Dave")).unwrap();
+    ///     db.dump_db().unwrap();
+    ///     drop(db);
+    ///
+    ///     let limits = LoadLimits {
+    ///         max_element_len: Some(16),
+    ///         ..LoadLimits::default()
+    ///     };
+    ///     let loaded = Database::<String>::from_with_limits("limits_test.tinydb", limits);
+    ///
+    ///     assert!(matches!(loaded.unwrap_err().root(), DatabaseError::LimitExceeded(_)));
+    ///     # std::fs::remove_file("limits_test.tinydb").unwrap();
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns [error::DatabaseError::LimitExceeded] wrapped in
+    /// [error::DatabaseError::Context] if a limit was gone over, otherwise
+    /// fails in the same ways as [Database::from].
+    pub fn from_with_limits(
+        path: impl Into<PathBuf>,
+        limits: LoadLimits,
+    ) -> Result<Self, error::DatabaseError> {
+        let path = path.into();
+        let lock = lock::FileLock::acquire(&path)?;
+        let mut decoded = read_limited(&path, &limits)
+            .and_then(|stream| Database::from_bytes_with_limits(&stream, limits))
+            .map_err(|e| e.with_context("load", Some(path.clone()), None))?;
+
+        decoded.loaded_from(path);
+        decoded.lock = lock::HeldLock(Some(Arc::new(lock)));
+        Ok(decoded)
+    }
+
+    /// Creates a database from a dump held in memory like
+    /// [Database::from_bytes] does, but within `limits` like
+    /// [Database::from_with_limits].
+    ///
+    /// # Errors
+    ///
+    /// Returns [error::DatabaseError::LimitExceeded] if a limit was gone
+    /// over, otherwise fails in the same ways as [Database::from_bytes].
+    pub fn from_bytes_with_limits(
+        bytes: &[u8],
+        limits: LoadLimits,
+    ) -> Result<Self, error::DatabaseError> {
+        LoadLimits::check("dump size", bytes.len() as u64, limits.max_file_size)?;
+
+        Database::decode_within(format::read_header(bytes)?, None, Some(&limits))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests each limit and loading a dump with a corrupted length
+    #[test]
+    fn limits_enforced() -> Result<(), error::DatabaseError> {
+        let mut db = Database::new("Limits test", None, false);
+
+        for item in 0..10u32 {
+            db.add_item(vec![item; 4])?;
+        }
+
+        let bytes = db.to_bytes()?;
+        let exceeded = |limits| {
+            matches!(
+                Database::<Vec<u32>>::from_bytes_with_limits(&bytes, limits),
+                Err(error::DatabaseError::LimitExceeded(_))
+            )
+        };
+
+        assert!(exceeded(LoadLimits {
+            max_file_size: Some(64),
+            ..LoadLimits::default()
+        }));
+        assert!(exceeded(LoadLimits {
+            max_items: Some(9),
+            ..LoadLimits::default()
+        }));
+        assert!(exceeded(LoadLimits {
+            max_element_len: Some(3),
+            ..LoadLimits::default()
+        }));
+
+        let loaded = Database::from_bytes_with_limits(
+            &bytes,
+            LoadLimits {
+                max_file_size: Some(bytes.len() as u64),
+                max_items: Some(10),
+                max_element_len: Some(11),
+            },
+        )?;
+
+        assert_eq!(loaded, db);
+
+        let mut corrupted = bytes.clone();
+
+        let start = format::payload_offset(format::FLAG_INFO);
+
+        corrupted[start..start + 8].copy_from_slice(&[0xff; 8]);
+
+        assert!(matches!(
+            Database::<Vec<u32>>::from_bytes_with_limits(&corrupted, LoadLimits::default()),
+            Err(error::DatabaseError::BincodeError(_))
+        ));
+        Ok(())
+    }
+}
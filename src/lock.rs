@@ -0,0 +1,299 @@
+//! Contains advisory file locking, preventing two processes which opened the
+//! same dump from silently overwriting each other's changes.
+//!
+//! Locks are taken on `.lock` files placed next to the dump when opening a
+//! database with [crate::Database::from] or [crate::Database::auto_from], and
+//! are held by the operating system on the open file. This means they're
+//! released once the database is dropped or the process holding them exits,
+//! even if it crashed. The id of the process holding a lock is written inside
+//! of the file, but only so people can tell who has it.
+//!
+//! Paths are canonicalised before locking, so the same dump is locked once no
+//! matter how its path is written. Databases in the same process are kept
+//! apart as well, so only the database holding a lock (or its clones) can
+//! dump over the locked file.
+
+use crate::error;
+use std::collections::BTreeSet;
+use std::fmt;
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io::prelude::*;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Paths of the `.lock` files held by this process, as locks held by the
+/// operating system can't tell databases in the same process apart.
+static HELD: Mutex<BTreeSet<PathBuf>> = Mutex::new(BTreeSet::new());
+
+/// The lock held by a database, if any, which is shared between clones of it
+/// so it's only released once they have all been dropped.
+///
+/// Whether a lock is held says nothing about the contents of a database so
+/// this never takes part in comparisons between databases.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct HeldLock(pub(crate) Option<Arc<FileLock>>);
+
+impl HeldLock {
+    /// Gets the lock being held, if any.
+    pub(crate) fn get(&self) -> Option<&FileLock> {
+        self.0.as_deref()
+    }
+}
+
+impl PartialEq for HeldLock {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for HeldLock {}
+
+/// A held lock on a dump, which is released when dropped.
+pub(crate) struct FileLock {
+    /// Path of the dump this lock is for.
+    dump_path: PathBuf,
+
+    /// Path of the `.lock` file itself.
+    lock_path: PathBuf,
+
+    /// The open `.lock` file, which the lock is held on.
+    _file: File,
+}
+
+impl FileLock {
+    /// Acquires a lock for the dump at the given path.
+    ///
+    /// # Errors
+    ///
+    /// Returns [error::DatabaseError::Locked] if the dump is already locked.
+    pub(crate) fn acquire(dump_path: &Path) -> Result<Self, error::DatabaseError> {
+        let dump_path = canonical(dump_path);
+        let lock_path = lock_path(&dump_path);
+
+        if is_held_here(&lock_path) {
+            return Err(error::DatabaseError::Locked);
+        }
+
+        loop {
+            let mut file = OpenOptions::new()
+                .read(true)
+                .write(true)
+                .create(true)
+                .truncate(false)
+                .open(&lock_path)?;
+
+            match file.try_lock() {
+                Ok(()) => (),
+                Err(TryLockError::WouldBlock) => return Err(error::DatabaseError::Locked),
+                Err(TryLockError::Error(err)) => return Err(err.into()),
+            }
+
+            // the file could have been removed by the last holder between
+            // opening and locking it, leaving a lock nobody else can see
+            if !is_same_file(&file, &lock_path) {
+                continue;
+            }
+
+            file.set_len(0)?;
+            file.write_all(std::process::id().to_string().as_bytes())?;
+            HELD.lock().unwrap().insert(lock_path.clone());
+
+            return Ok(FileLock {
+                dump_path,
+                lock_path,
+                _file: file,
+            });
+        }
+    }
+
+    /// Checks if this lock is for the dump at the given path.
+    pub(crate) fn is_for(&self, dump_path: &Path) -> bool {
+        self.dump_path == canonical(dump_path)
+    }
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        fs::remove_file(&self.lock_path).ok();
+        HELD.lock().unwrap().remove(&self.lock_path);
+    }
+}
+
+impl fmt::Debug for FileLock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("FileLock").field(&self.lock_path).finish()
+    }
+}
+
+/// Checks that the dump at the given path can be written to, meaning it's
+/// either not locked or locked by the given lock.
+///
+/// # Errors
+///
+/// Returns [error::DatabaseError::Locked] if another database holds the lock,
+/// whether it's in this process or another one.
+pub(crate) fn check(dump_path: &Path, held: Option<&FileLock>) -> Result<(), error::DatabaseError> {
+    if let Some(lock) = held {
+        if lock.is_for(dump_path) {
+            return Ok(());
+        }
+    }
+
+    let lock_path = lock_path(&canonical(dump_path));
+
+    match is_held_here(&lock_path) || is_held_elsewhere(&lock_path)? {
+        true => Err(error::DatabaseError::Locked),
+        false => Ok(()),
+    }
+}
+
+/// Removes a `.lock` file left next to a dump, returning if there was one to
+/// remove.
+///
+/// # Errors
+///
+/// Returns [error::DatabaseError::Locked] if the lock is still held.
+pub(crate) fn force_unlock(dump_path: &Path) -> Result<bool, error::DatabaseError> {
+    let lock_path = lock_path(&canonical(dump_path));
+
+    if is_held_here(&lock_path) || is_held_elsewhere(&lock_path)? {
+        return Err(error::DatabaseError::Locked);
+    }
+
+    match fs::remove_file(lock_path) {
+        Ok(()) => Ok(true),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(false),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Checks if a database in this process holds the lock at the given path.
+fn is_held_here(lock_path: &Path) -> bool {
+    HELD.lock().unwrap().contains(lock_path)
+}
+
+/// Checks if another process holds the lock at the given path by briefly
+/// taking a shared lock, which doesn't get in the way of other checks.
+fn is_held_elsewhere(lock_path: &Path) -> Result<bool, error::DatabaseError> {
+    let file = match File::open(lock_path) {
+        Ok(file) => file,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+        Err(err) => return Err(err.into()),
+    };
+
+    match file.try_lock_shared() {
+        Ok(()) => Ok(false),
+        Err(TryLockError::WouldBlock) => Ok(true),
+        Err(TryLockError::Error(err)) => Err(err.into()),
+    }
+}
+
+/// Checks if an open file is still the one at the given path.
+#[cfg(unix)]
+fn is_same_file(file: &File, path: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+
+    match (file.metadata(), fs::metadata(path)) {
+        (Ok(open), Ok(current)) => open.dev() == current.dev() && open.ino() == current.ino(),
+        _ => false,
+    }
+}
+
+/// Checks if an open file is still the one at the given path, which on
+/// platforms without inodes is only as good as checking it's there.
+#[cfg(not(unix))]
+fn is_same_file(_file: &File, path: &Path) -> bool {
+    path.exists()
+}
+
+/// Canonicalises the path of a dump which may not exist yet by canonicalising
+/// the directory it's in, falling back to the path as written if the
+/// directory doesn't exist either.
+fn canonical(dump_path: &Path) -> PathBuf {
+    let dir = match dump_path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+
+    match (dir.canonicalize(), dump_path.file_name()) {
+        (Ok(dir), Some(name)) => dir.join(name),
+        _ => dump_path.to_path_buf(),
+    }
+}
+
+/// Gets the path of the `.lock` file for a dump.
+fn lock_path(dump_path: &Path) -> PathBuf {
+    let mut lock_path = dump_path.as_os_str().to_os_string();
+
+    lock_path.push(".lock");
+    PathBuf::from(lock_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests a lock being exclusive until dropped
+    #[test]
+    fn lock_exclusive() -> Result<(), error::DatabaseError> {
+        let path = PathBuf::from("lock_exclusive.tinydb");
+        let lock = FileLock::acquire(&path)?;
+
+        assert!(FileLock::acquire(&path).is_err());
+        assert!(FileLock::acquire(Path::new("./lock_exclusive.tinydb")).is_err());
+        assert!(check(&path, Some(&lock)).is_ok());
+        assert!(check(Path::new("./lock_exclusive.tinydb"), Some(&lock)).is_ok());
+        assert!(check(&path, None).is_err());
+
+        drop(lock);
+
+        assert!(FileLock::acquire(&path).is_ok());
+        assert!(!lock_path(&path).exists());
+
+        Ok(())
+    }
+
+    /// Tests locks held by another process stopping writes until released,
+    /// which is simulated by locking the `.lock` file directly
+    #[test]
+    fn lock_other_process() -> Result<(), error::DatabaseError> {
+        let path = PathBuf::from("lock_other_process.tinydb");
+        let other = File::create(lock_path(&path))?;
+
+        other.lock()?;
+
+        assert!(check(&path, None).is_err());
+        assert!(FileLock::acquire(&path).is_err());
+        assert!(force_unlock(&path).is_err());
+
+        drop(other);
+
+        assert!(check(&path, None).is_ok());
+        assert!(force_unlock(&path)?);
+        assert!(!force_unlock(&path)?);
+
+        Ok(())
+    }
+
+    /// Tests a `.lock` file left behind by a crashed process not stopping the
+    /// dump from being locked again
+    #[test]
+    fn lock_stale() -> Result<(), error::DatabaseError> {
+        let path = PathBuf::from("lock_stale.tinydb");
+
+        fs::write(lock_path(&path), "4294967295")?;
+
+        let lock = FileLock::acquire(&path)?;
+
+        assert_eq!(
+            fs::read_to_string(lock_path(&path))?,
+            std::process::id().to_string()
+        );
+
+        drop(lock);
+
+        assert!(!lock_path(&path).exists());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,269 @@
+//! Contains [Managed], a wrapper giving items an id, timestamps and a
+//! revision which the database keeps up-to-date, see
+//! [crate::Database::add_managed].
+
+use crate::{error, meta, record, Database};
+use serde::{Deserialize, Serialize};
+use std::borrow::Borrow;
+use std::hash;
+use std::time::SystemTime;
+
+/// An item along with an id from [Database::next_id], when it was created
+/// and last updated and how many times it has been updated.
+///
+/// Only the id takes part in comparisons and hashing, so items can have any
+/// [PartialEq] implementation of their own (or none at all) whilst still
+/// being keyed sanely. Unlike handles from [Database::track_ids], all of this
+/// is saved when dumping. This also implements [record::Record], keyed by id.
+///
+/// Timestamps are stored as milliseconds since the unix epoch.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Managed<T> {
+    id: u64,
+    created_at: u64,
+    updated_at: u64,
+    revision: u64,
+    item: T,
+}
+
+impl<T> Managed<T> {
+    /// Gets the id given to the item when it was added.
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Gets the inner item.
+    pub fn item(&self) -> &T {
+        &self.item
+    }
+
+    /// Unwraps the inner item, dropping its metadata.
+    pub fn into_inner(self) -> T {
+        self.item
+    }
+
+    /// Gets when the item was first added.
+    pub fn created_at(&self) -> SystemTime {
+        meta::from_millis(self.created_at)
+    }
+
+    /// Gets when the item was last updated, which is when it was added if it
+    /// never has been.
+    pub fn updated_at(&self) -> SystemTime {
+        meta::from_millis(self.updated_at)
+    }
+
+    /// Gets how many times the item has been updated, starting at `0`.
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+}
+
+impl<T> PartialEq for Managed<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl<T> Eq for Managed<T> {}
+
+impl<T> hash::Hash for Managed<T> {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+    }
+}
+
+impl<T> Borrow<u64> for Managed<T> {
+    fn borrow(&self) -> &u64 {
+        &self.id
+    }
+}
+
+impl<T> record::Record for Managed<T> {
+    type Key = u64;
+
+    const FIELDS: &'static [&'static str] = &["id", "created_at", "updated_at", "revision", "item"];
+
+    fn key(&self) -> &u64 {
+        &self.id
+    }
+}
+
+impl<T> Database<Managed<T>> {
+    /// Adds an item under a fresh id from [Database::next_id], returning the
+    /// id.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tinydb::managed::Managed;
+    /// use tinydb::Database;
+    ///
+    /// fn main() {
+    ///     let mut db: Database<Managed<f32>> = Database::new("managed_test", None, false);
+    ///     let id = db.add_managed(0.5).unwrap();
+    ///
+    ///     db.update_managed(id, 1.5).unwrap();
+    ///
+    ///     let managed = db.get_managed(id).unwrap();
+    ///
+    ///     assert_eq!(*managed.item(), 1.5);
+    ///     assert_eq!(managed.revision(), 1);
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Fails in the same ways as [Database::add_item].
+    pub fn add_managed(&mut self, item: T) -> Result<u64, error::DatabaseError> {
+        let id = self.next_id();
+        let now = meta::now_millis();
+
+        self.add_item(Managed {
+            id,
+            created_at: now,
+            updated_at: now,
+            revision: 0,
+            item,
+        })?;
+
+        Ok(id)
+    }
+
+    /// Gets the item with an id, without scanning every item.
+    pub fn get_managed(&self, id: u64) -> Option<&Managed<T>> {
+        self.items.get(&id).filter(|item| !self.is_expired(item))
+    }
+
+    /// Replaces the item with an id, keeping when it was added whilst
+    /// marking it as updated right now and bumping its revision. See
+    /// [Database::update_item] for how updates are otherwise handled.
+    ///
+    /// # Errors
+    ///
+    /// Will return [error::DatabaseError::ItemNotFound] if there's no item
+    /// with the id, otherwise fails in the same ways as
+    /// [Database::update_item].
+    pub fn update_managed(&mut self, id: u64, new: T) -> Result<(), error::DatabaseError> {
+        self.check_mutable()?;
+
+        let old = self
+            .items
+            .get(&id)
+            .ok_or(error::DatabaseError::ItemNotFound)?;
+        let new = Managed {
+            id,
+            created_at: old.created_at,
+            updated_at: meta::now_millis(),
+            revision: old.revision + 1,
+            item: new,
+        };
+
+        self.constraints.check(&new)?;
+        self.indexes
+            .check_unique(&new, std::slice::from_ref(old), &[])?;
+        self.history.begin_step();
+
+        if let Some(old) = self.items.take(&id) {
+            self.forget_item(&old);
+        }
+
+        self.insert_item(new);
+        Ok(())
+    }
+
+    /// Replaces the item with an id like [Database::update_managed], but only
+    /// if it's still at the `expected` revision, returning the new revision.
+    ///
+    /// # Errors
+    ///
+    /// Will return [error::DatabaseError::RevisionConflict] if the item has
+    /// been updated since `expected` was read, otherwise fails in the same
+    /// ways as [Database::update_managed].
+    pub fn update_managed_if_revision(
+        &mut self,
+        id: u64,
+        expected: u64,
+        new: T,
+    ) -> Result<u64, error::DatabaseError> {
+        let found = self
+            .items
+            .get(&id)
+            .ok_or(error::DatabaseError::ItemNotFound)?
+            .revision;
+
+        if found != expected {
+            return Err(error::DatabaseError::RevisionConflict { expected, found });
+        }
+
+        self.update_managed(id, new)?;
+
+        Ok(found + 1)
+    }
+
+    /// Removes the item with an id, returning it.
+    ///
+    /// # Errors
+    ///
+    /// Will return [error::DatabaseError::ItemNotFound] if there's no item
+    /// with the id or [error::DatabaseError::Archived] if the database has
+    /// been archived.
+    pub fn remove_managed(&mut self, id: u64) -> Result<T, error::DatabaseError> {
+        self.check_mutable()?;
+        self.history.begin_step();
+
+        let taken = self
+            .items
+            .take(&id)
+            .ok_or(error::DatabaseError::ItemNotFound)?;
+
+        self.forget_item(&taken);
+        Ok(taken.item)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// Tests metadata being kept across updates and saved when dumping
+    #[test]
+    fn managed_updates() -> Result<(), error::DatabaseError> {
+        let path = PathBuf::from("managed_updates.tinydb");
+        let mut db = Database::new("Managed test", path.clone(), true);
+        let first = db.add_managed(String::from("Lister"))?;
+        let second = db.add_managed(String::from("Lister"))?;
+
+        assert_eq!(
+            db.update_managed_if_revision(first, 0, String::from("Dave"))?,
+            1
+        );
+        assert_eq!(
+            db.update_managed_if_revision(first, 0, String::from("Dave Lister")),
+            Err(error::DatabaseError::RevisionConflict {
+                expected: 0,
+                found: 1
+            })
+        );
+        assert_eq!(db.remove_managed(second)?, "Lister");
+        assert_eq!(
+            db.update_managed(second, String::new()),
+            Err(error::DatabaseError::ItemNotFound)
+        );
+        db.dump_db()?;
+
+        let loaded: Database<Managed<String>> = Database::from(path.clone())?;
+        let managed = loaded.get_managed(first).unwrap();
+
+        assert_eq!(managed.item(), "Dave");
+        assert_eq!(managed.revision(), 1);
+        assert!(managed.updated_at() >= managed.created_at());
+        assert_eq!(loaded.get_by_key(&first).unwrap().id(), first);
+        assert!(loaded.get_managed(second).is_none());
+
+        drop(loaded);
+        std::fs::remove_file(path)?;
+        Ok(())
+    }
+}
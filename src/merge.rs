@@ -0,0 +1,195 @@
+//! Contains merging of two databases, for example when syncing dumps between
+//! machines.
+
+use crate::{error, Database};
+use serde::de::DeserializeOwned;
+use std::hash;
+use std::path::PathBuf;
+
+/// How to handle items which exist in both databases when using
+/// [Database::merge_from].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MergePolicy {
+    /// Return [error::DatabaseError::DupeFound] without merging anything.
+    Error,
+
+    /// Keep the existing item, skipping the one being merged in.
+    Skip,
+
+    /// Replace the existing item with the one being merged in, which is useful
+    /// if your [Eq] implementation only compares some fields of an item.
+    Replace,
+}
+
+/// Summary of what happened during [Database::merge_from].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct MergeReport {
+    /// Items which didn't exist yet and were added.
+    pub added: usize,
+
+    /// Items which already existed and were skipped.
+    pub skipped: usize,
+
+    /// Items which already existed and were replaced.
+    pub replaced: usize,
+
+    /// Items which were removed because another process removed them, only
+    /// counted by [Database::sync_dump].
+    pub removed: usize,
+}
+
+impl<T: hash::Hash + Eq> Database<T> {
+    /// Merges all items from another database into this one, with `policy`
+    /// deciding what happens to items which exist in both.
+    ///
+    /// Only items are merged, the label, pins and expiries of `other` are
+    /// discarded.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tinydb::Database;
+    /// use tinydb::merge::MergePolicy;
+    ///
+    /// fn main() {
+    ///     let mut laptop: Database<u32> = Database::new("laptop", None, false);
+    ///     let mut desktop: Database<u32> = Database::new("desktop", None, false);
+    ///
+    ///     laptop.add_item(1).unwrap();
+    ///     desktop.add_item(1).unwrap();
+    ///     desktop.add_item(2).unwrap();
+    ///
+    ///     let report = laptop.merge_from(desktop, MergePolicy::Skip).unwrap();
+    ///
+    ///     assert_eq!(report.added, 1);
+    ///     assert_eq!(report.skipped, 1);
+    ///     assert_eq!(laptop.items.len(), 2);
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// With [MergePolicy::Error], this will return
+    /// [error::DatabaseError::DupeFound] if any item exists in both databases,
+    /// in which case nothing is merged at all.
+    pub fn merge_from(
+        &mut self,
+        other: Database<T>,
+        policy: MergePolicy,
+    ) -> Result<MergeReport, error::DatabaseError> {
+        self.check_mutable()?;
+        self.history.begin_step();
+
+        if policy == MergePolicy::Error && other.items.iter().any(|item| self.items.contains(item))
+        {
+            return Err(error::DatabaseError::DupeFound);
+        }
+
+        let mut report = MergeReport::default();
+
+        for item in other.items {
+            if !self.items.contains(&item) {
+                report.added += 1;
+            } else if policy == MergePolicy::Replace {
+                self.take_item(&item);
+                report.replaced += 1;
+            } else {
+                report.skipped += 1;
+                continue;
+            }
+
+            self.insert_item(item);
+        }
+
+        Ok(report)
+    }
+
+    /// Merges all items from a dump into this one, see [Database::merge_from].
+    ///
+    /// The dump is read without being locked.
+    pub fn merge_from_file(
+        &mut self,
+        path: impl Into<PathBuf>,
+        policy: MergePolicy,
+    ) -> Result<MergeReport, error::DatabaseError>
+    where
+        T: DeserializeOwned,
+    {
+        self.merge_from(Database::from_unlocked(path)?, policy)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::{Deserialize, Serialize};
+
+    /// Item which is only compared by its id
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    struct Keyed {
+        id: u32,
+        value: String,
+    }
+
+    impl PartialEq for Keyed {
+        fn eq(&self, other: &Self) -> bool {
+            self.id == other.id
+        }
+    }
+
+    impl Eq for Keyed {}
+
+    impl hash::Hash for Keyed {
+        fn hash<H: hash::Hasher>(&self, state: &mut H) {
+            self.id.hash(state);
+        }
+    }
+
+    /// Makes a database with the given items
+    fn make_db(items: &[(u32, &str)]) -> Database<Keyed> {
+        let mut db = Database::new("Merge test", None, false);
+
+        for (id, value) in items {
+            db.add_item(Keyed {
+                id: *id,
+                value: value.to_string(),
+            })
+            .unwrap();
+        }
+
+        db
+    }
+
+    /// Tests [MergePolicy::Error] leaving the database untouched
+    #[test]
+    fn merge_error() {
+        let mut db = make_db(&[(1, "a")]);
+
+        assert!(db
+            .merge_from(make_db(&[(1, "a"), (2, "b")]), MergePolicy::Error)
+            .is_err());
+        assert_eq!(db.items.len(), 1);
+    }
+
+    /// Tests [MergePolicy::Replace] swapping in items which are only equal by
+    /// their key
+    #[test]
+    fn merge_replace() -> Result<(), error::DatabaseError> {
+        let mut db = make_db(&[(1, "old"), (2, "kept")]);
+        let report = db.merge_from(make_db(&[(1, "new"), (3, "added")]), MergePolicy::Replace)?;
+
+        assert_eq!(
+            report,
+            MergeReport {
+                added: 1,
+                skipped: 0,
+                replaced: 1,
+                removed: 0
+            }
+        );
+        assert_eq!(db.query_item(|item| &item.id, 1)?.value, "new");
+        assert_eq!(db.items.len(), 3);
+
+        Ok(())
+    }
+}
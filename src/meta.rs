@@ -0,0 +1,376 @@
+//! Contains metadata timestamps which are saved alongside a [crate::Database],
+//! useful for showing when it was last saved or detecting changes made by
+//! another process.
+
+use crate::Database;
+#[cfg(feature = "std")]
+use crate::{error, format};
+use alloc::sync::Arc;
+use core::hash;
+use core::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+#[cfg(feature = "std")]
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Timestamps of a database, stored as milliseconds since the unix epoch.
+///
+/// These describe the history of a database rather than its contents so they
+/// never take part in comparisons between databases.
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct Metadata {
+    /// When the database was first created with [Database::new].
+    pub(crate) created_at: u64,
+
+    /// When the database was last mutated.
+    pub(crate) last_modified: u64,
+
+    /// When the database was last dumped, `0` if it never has been. This is
+    /// atomic so it can be set from [Database::dump_db] which only borrows,
+    /// and shared with [DumpMarks] so background dumps can set it too.
+    #[serde(
+        serialize_with = "serialize_shared",
+        deserialize_with = "deserialize_shared"
+    )]
+    pub(crate) last_dumped: Arc<AtomicU64>,
+
+    /// How many items have been added or removed since the database was last
+    /// dumped or loaded, which is runtime-only.
+    #[serde(skip)]
+    pub(crate) unsaved: Arc<AtomicUsize>,
+}
+
+/// Handle on whether a database has been dumped, for marking it as dumped
+/// once a [Snapshot](crate::snapshot::Snapshot) of it has been dumped from
+/// another thread.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone)]
+pub(crate) struct DumpMarks {
+    last_dumped: Arc<AtomicU64>,
+    unsaved: Arc<AtomicUsize>,
+}
+
+#[cfg(feature = "std")]
+impl DumpMarks {
+    /// Marks the database as dumped at `dumped_at` if it still has exactly
+    /// `unsaved` changes, which are the ones the dump included.
+    pub(crate) fn dumped(&self, dumped_at: u64, unsaved: usize) -> bool {
+        let marked = self
+            .unsaved
+            .compare_exchange(unsaved, 0, Ordering::SeqCst, Ordering::SeqCst)
+            .is_ok();
+
+        if marked {
+            self.last_dumped.fetch_max(dumped_at, Ordering::SeqCst);
+        }
+
+        marked
+    }
+}
+
+/// Writes a timestamp shared with [DumpMarks] the same way as a plain one.
+fn serialize_shared<S: Serializer>(
+    value: &Arc<AtomicU64>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    value.load(Ordering::SeqCst).serialize(serializer)
+}
+
+/// Reads a timestamp written by [serialize_shared].
+fn deserialize_shared<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Arc<AtomicU64>, D::Error> {
+    u64::deserialize(deserializer).map(|value| Arc::new(AtomicU64::new(value)))
+}
+
+impl Metadata {
+    /// Gets the details written before the encoded database when dumping,
+    /// alongside the `schema` fingerprint of its items.
+    #[cfg(feature = "std")]
+    pub(crate) fn info(&self, schema: u64) -> format::Info {
+        format::Info {
+            created_at: self.created_at,
+            last_modified: self.last_modified,
+            last_dumped: self.last_dumped.load(Ordering::SeqCst),
+            schema,
+        }
+    }
+
+    /// Creates metadata for a brand new database.
+    pub(crate) fn new() -> Self {
+        let now = now_millis();
+
+        Metadata {
+            created_at: now,
+            last_modified: now,
+            last_dumped: Arc::new(AtomicU64::new(0)),
+            unsaved: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// Gets a handle for marking this database as dumped from elsewhere.
+    #[cfg(feature = "std")]
+    pub(crate) fn marks(&self) -> DumpMarks {
+        DumpMarks {
+            last_dumped: Arc::clone(&self.last_dumped),
+            unsaved: Arc::clone(&self.unsaved),
+        }
+    }
+
+    /// Marks the database as modified right now.
+    pub(crate) fn modified(&mut self) {
+        self.modified_by(1);
+    }
+
+    /// Marks the database as modified right now by several changes at once.
+    pub(crate) fn modified_by(&mut self, changes: usize) {
+        self.last_modified = now_millis();
+        self.unsaved.fetch_add(changes, Ordering::SeqCst);
+    }
+
+    /// Sets when the database was last dumped to right now ahead of encoding
+    /// it so the time is saved inside of the dump, returning the time it
+    /// replaced for [Metadata::restore_dumped].
+    #[cfg(feature = "std")]
+    pub(crate) fn stamp_dumped(&self) -> u64 {
+        self.last_dumped.swap(now_millis(), Ordering::SeqCst)
+    }
+
+    /// Puts back when the database was last dumped after a dump which was
+    /// stamped using [Metadata::stamp_dumped] wasn't saved.
+    #[cfg(feature = "std")]
+    pub(crate) fn restore_dumped(&self, previous: u64) {
+        self.last_dumped.store(previous, Ordering::SeqCst);
+    }
+
+    /// Marks the database as saved once a stamped dump has been written.
+    #[cfg(feature = "std")]
+    pub(crate) fn dumped(&self) {
+        self.unsaved.store(0, Ordering::SeqCst);
+    }
+}
+
+impl Default for Metadata {
+    fn default() -> Self {
+        Metadata::new()
+    }
+}
+
+impl Clone for Metadata {
+    fn clone(&self) -> Self {
+        Metadata {
+            created_at: self.created_at,
+            last_modified: self.last_modified,
+            last_dumped: Arc::new(AtomicU64::new(self.last_dumped.load(Ordering::SeqCst))),
+            unsaved: Arc::new(AtomicUsize::new(self.unsaved.load(Ordering::SeqCst))),
+        }
+    }
+}
+
+impl PartialEq for Metadata {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for Metadata {}
+
+impl<T: hash::Hash + Eq> Database<T> {
+    /// Gets when this database was first created, which is kept when dumping
+    /// and loading.
+    #[cfg(feature = "std")]
+    pub fn created_at(&self) -> SystemTime {
+        from_millis(self.meta.created_at)
+    }
+
+    /// Gets when an item was last added or removed from this database.
+    ///
+    /// Comparing this to the result of [Database::last_modified] on a freshly
+    /// loaded copy of the same dump can be used to detect if another process
+    /// has modified it in the meantime.
+    #[cfg(feature = "std")]
+    pub fn last_modified(&self) -> SystemTime {
+        from_millis(self.meta.last_modified)
+    }
+
+    /// Gets when this database was last dumped using [Database::dump_db] or
+    /// [Option::None] if it never has been.
+    #[cfg(feature = "std")]
+    pub fn last_dumped(&self) -> Option<SystemTime> {
+        match self.meta.last_dumped.load(Ordering::SeqCst) {
+            0 => None,
+            millis => Some(from_millis(millis)),
+        }
+    }
+
+    /// Checks if this database has changed since it was last dumped or
+    /// loaded, or if it has never been dumped at all.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tinydb::Database;
+    ///
+    /// fn main() {
+    ///     let mut db = Database::new("dirty_test", None, false);
+    ///
+    ///     db.add_item(String::from("Lister")).unwrap();
+    ///     db.add_item(String::from("Rimmer")).unwrap();
+    ///
+    ///     assert!(db.is_dirty());
+    ///     assert_eq!(db.unsaved_changes(), 2);
+    ///
+    ///     db.dump_db().unwrap();
+    ///
+    ///     assert!(!db.is_dirty());
+    ///     # std::fs::remove_file("dirty_test.tinydb").unwrap();
+    /// }
+    /// ```
+    pub fn is_dirty(&self) -> bool {
+        self.unsaved_changes() != 0 || self.meta.last_dumped.load(Ordering::SeqCst) == 0
+    }
+
+    /// Counts how many items have been added or removed since this database
+    /// was last dumped or loaded, where an update counts as both.
+    pub fn unsaved_changes(&self) -> usize {
+        self.meta.unsaved.load(Ordering::SeqCst)
+    }
+
+    /// Runs `dump`, which saves this database somewhere, with
+    /// [Database::last_dumped] set to right now so the time is saved inside
+    /// of the dump. This database is only marked as dumped if `dump`
+    /// succeeds, so a failed dump leaves it [dirty](Database::is_dirty).
+    #[cfg(feature = "std")]
+    pub(crate) fn dumping<R>(
+        &self,
+        dump: impl FnOnce() -> Result<R, error::DatabaseError>,
+    ) -> Result<R, error::DatabaseError> {
+        let previous = self.meta.stamp_dumped();
+        let result = dump();
+
+        match result {
+            Ok(_) => self.meta.dumped(),
+            Err(_) => self.meta.restore_dumped(previous),
+        }
+
+        result
+    }
+
+    /// Sets if [Database::dump_db] should do nothing whilst the database
+    /// isn't [dirty](Database::is_dirty), which is off by default. This
+    /// setting is not saved when dumping.
+    #[cfg(feature = "std")]
+    pub fn set_skip_clean_dumps(&mut self, skip: bool) {
+        self.skip_clean_dumps = skip;
+    }
+}
+
+/// Converts a [SystemTime] into milliseconds since the unix epoch.
+#[cfg(feature = "std")]
+pub(crate) fn unix_millis(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Gets the current time in milliseconds since the unix epoch.
+#[cfg(feature = "std")]
+pub(crate) fn now_millis() -> u64 {
+    unix_millis(SystemTime::now())
+}
+
+/// Gets the current time, which is always `0` without the `std` feature as
+/// there's no clock to read.
+#[cfg(not(feature = "std"))]
+pub(crate) fn now_millis() -> u64 {
+    0
+}
+
+/// Converts milliseconds since the unix epoch back into a [SystemTime].
+#[cfg(feature = "std")]
+pub(crate) fn from_millis(millis: u64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_millis(millis)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error;
+    use std::path::PathBuf;
+
+    /// Tests timestamps being kept through a dump
+    #[test]
+    fn timestamps_persist() -> Result<(), error::DatabaseError> {
+        let path = PathBuf::from("timestamps_persist.tinydb");
+        let mut db = Database::new("Timestamp test", path.clone(), false);
+
+        assert!(db.last_dumped().is_none());
+
+        std::thread::sleep(Duration::from_millis(5));
+        db.add_item(1)?;
+        db.dump_db()?;
+
+        let loaded: Database<i32> = Database::from(path.clone())?;
+
+        assert!(loaded.last_modified() > loaded.created_at());
+        assert_eq!(loaded.created_at(), db.created_at());
+        assert_eq!(loaded.last_dumped(), db.last_dumped());
+        assert!(loaded.last_dumped().unwrap() >= loaded.last_modified());
+
+        std::fs::remove_file(path)?;
+        Ok(())
+    }
+
+    /// Tests dirty tracking and clean dumps being skipped once enabled
+    #[test]
+    fn dirty_tracking() -> Result<(), error::DatabaseError> {
+        let path = PathBuf::from("dirty_tracking.tinydb");
+        let mut db = Database::new("Dirty test", path.clone(), false);
+
+        db.set_skip_clean_dumps(true);
+        db.add_item(1)?;
+        db.update_item(&1, 2)?;
+
+        assert_eq!(db.unsaved_changes(), 3);
+
+        db.dump_db()?;
+
+        let dumped = db.last_dumped();
+
+        assert!(!db.is_dirty());
+
+        std::thread::sleep(Duration::from_millis(5));
+        db.dump_db()?;
+
+        assert_eq!(db.last_dumped(), dumped);
+
+        let loaded: Database<i32> = Database::from(path.clone())?;
+
+        assert!(!loaded.is_dirty());
+
+        std::fs::remove_file(path)?;
+        Ok(())
+    }
+
+    /// Tests failed dumps and encoding to bytes leaving the database dirty,
+    /// so skipping clean dumps can't lose changes
+    #[test]
+    fn failed_dump_stays_dirty() -> Result<(), error::DatabaseError> {
+        let path = PathBuf::from("failed_dump_stays_dirty/missing/db.tinydb");
+        let mut db = Database::new("Failed dump test", path, false);
+
+        db.set_skip_clean_dumps(true);
+        db.set_create_dirs(false);
+        db.add_item(1)?;
+
+        assert!(db.dump_db().is_err());
+        assert!(db.is_dirty());
+        assert!(db.last_dumped().is_none());
+
+        db.to_bytes()?;
+
+        assert!(db.is_dirty());
+        assert!(db.last_dumped().is_none());
+        assert!(db.dump_db().is_err());
+        Ok(())
+    }
+}
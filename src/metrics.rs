@@ -0,0 +1,208 @@
+//! Contains health information about a [crate::Database] as a whole, see
+//! [crate::Database::stats].
+
+#[cfg(feature = "std")]
+use crate::Database;
+#[cfg(feature = "std")]
+use core::hash;
+use core::sync::atomic::{AtomicU64, Ordering};
+#[cfg(feature = "std")]
+use serde::Serialize;
+#[cfg(feature = "std")]
+use std::path::PathBuf;
+#[cfg(feature = "std")]
+use std::time::{Duration, SystemTime};
+
+/// A snapshot of how a database is doing, returned from
+/// [Database::stats].
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DbStats {
+    /// Amount of items currently inside of the database, including any which
+    /// have expired but haven't been purged yet.
+    pub items: usize,
+
+    /// Rough estimate of the memory used by items in bytes, based on the size
+    /// of the item table plus how large the items are once encoded.
+    pub approx_memory: u64,
+
+    /// When the database was last dumped, see [Database::last_dumped].
+    pub last_dumped: Option<SystemTime>,
+
+    /// Size of the dump file in bytes, or [Option::None] if it doesn't exist.
+    pub dump_size: Option<u64>,
+
+    /// Amount of queries made since the database was created or loaded.
+    pub queries: u64,
+
+    /// Amount of items added since the database was created or loaded.
+    pub inserts: u64,
+
+    /// Amount of items removed since the database was created or loaded.
+    pub removals: u64,
+}
+
+/// What was saved by a dump, returned from [Database::dump_db] and the other
+/// ways of dumping to a path, for logging or showing to users.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DumpReport {
+    /// Size of the dump written in bytes, which is `0` if nothing was written
+    /// as [Database::set_skip_clean_dumps] is enabled and nothing changed.
+    pub bytes: u64,
+
+    /// Amount of items saved, including any which have expired but haven't
+    /// been purged yet.
+    pub items: usize,
+
+    /// How long encoding and writing the dump took.
+    pub duration: Duration,
+
+    /// Where the dump was written to, not including any mirrors.
+    pub path: PathBuf,
+}
+
+/// Counters of operations made on a database.
+///
+/// These are atomic as queries only borrow the database. Counters aren't
+/// saved when dumping, start again from zero when cloning and never take
+/// part in comparisons between databases.
+#[derive(Debug, Default)]
+pub(crate) struct Counters {
+    queries: AtomicU64,
+    inserts: AtomicU64,
+    removals: AtomicU64,
+}
+
+impl Counters {
+    /// Counts a query.
+    pub(crate) fn queried(&self) {
+        self.queries.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Counts items being added.
+    pub(crate) fn inserted(&mut self, amount: u64) {
+        *self.inserts.get_mut() += amount;
+    }
+
+    /// Counts an item being removed.
+    pub(crate) fn removed(&mut self) {
+        *self.removals.get_mut() += 1;
+    }
+}
+
+impl Clone for Counters {
+    fn clone(&self) -> Self {
+        Counters::default()
+    }
+}
+
+impl PartialEq for Counters {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for Counters {}
+
+#[cfg(feature = "std")]
+impl<T: hash::Hash + Eq> Database<T> {
+    /// Gets health information about this database, such as how many items it
+    /// has and how often it's been queried, which is ideal for debug panels.
+    ///
+    /// This encodes every item to estimate memory usage so shouldn't be
+    /// called in a hot loop.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tinydb::Database;
+    ///
+    /// fn main() {
+    ///     let mut db = Database::new("stats_test", None, false);
+    ///
+    ///     db.add_item(String::from("Lister")).unwrap();
+    ///     db.contains(&String::from("Rimmer"));
+    ///
+    ///     let stats = db.stats();
+    ///
+    ///     assert_eq!(stats.items, 1);
+    ///     assert_eq!(stats.queries, 1);
+    ///     assert!(stats.dump_size.is_none());
+    /// }
+    /// ```
+    pub fn stats(&self) -> DbStats
+    where
+        T: Serialize,
+    {
+        let table = (self.items.capacity() * core::mem::size_of::<T>()) as u64;
+        let encoded = bincode::serialized_size(&self.items).unwrap_or(0);
+
+        DbStats {
+            items: self.items.len(),
+            approx_memory: table + encoded,
+            last_dumped: self.last_dumped(),
+            dump_size: std::fs::metadata(self.smart_path_get())
+                .ok()
+                .map(|metadata| metadata.len()),
+            queries: self.counters.queries.load(Ordering::Relaxed),
+            inserts: self.counters.inserts.load(Ordering::Relaxed),
+            removals: self.counters.removals.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error;
+    use std::path::PathBuf;
+
+    /// Tests counters and the dump size being reported
+    #[test]
+    fn db_stats() -> Result<(), error::DatabaseError> {
+        let path = PathBuf::from("db_stats.tinydb");
+        let mut db = Database::new("Stats test", path.clone(), false);
+
+        db.add_item(1)?;
+        db.add_item(2)?;
+        db.update_item(&2, 3)?;
+        db.query_item(|n| n, 1)?;
+        db.find_by(|n| n, 3);
+        db.dump_db()?;
+
+        let stats = db.stats();
+
+        assert_eq!(stats.items, 2);
+        assert_eq!(stats.inserts, 3);
+        assert_eq!(stats.removals, 1);
+        assert_eq!(stats.queries, 2);
+        assert!(stats.approx_memory > 0);
+        assert!(stats.dump_size.unwrap() > 0);
+        assert_eq!(db.clone().stats().queries, 0);
+
+        std::fs::remove_file(path)?;
+        Ok(())
+    }
+
+    /// Tests dump reports matching what was written, including skipped dumps
+    #[test]
+    fn dump_report() -> Result<(), error::DatabaseError> {
+        let path = PathBuf::from("dump_report.tinydb");
+        let mut db = Database::new("Report test", path.clone(), false);
+
+        db.add_item(1)?;
+        db.add_item(2)?;
+        db.set_skip_clean_dumps(true);
+
+        let report = db.dump_db()?;
+
+        assert_eq!(report.items, 2);
+        assert_eq!(report.path, path);
+        assert_eq!(report.bytes, std::fs::metadata(&path)?.len());
+        assert_eq!(db.dump_db()?.bytes, 0);
+
+        std::fs::remove_file(path)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,113 @@
+//! Contains mirror paths which every dump is also written to, for cheap
+//! redundancy such as keeping a copy on a synced drive.
+
+use crate::{error, Database};
+use std::fs;
+use std::hash;
+use std::path::PathBuf;
+
+impl<T: hash::Hash + Eq> Database<T> {
+    /// Adds a path which [Database::dump_db] also writes the dump to, after
+    /// saving it to [Database::save_path] as usual. Mirrors aren't saved when
+    /// dumping, so they have to be added again after loading.
+    ///
+    /// Only the dump itself is mirrored, blobs added with
+    /// [Database::put_blob] are only saved next to the main dump.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tinydb::Database;
+    ///
+    /// fn main() {
+    ///     let mut db = Database::new("mirror_test", None, false);
+    ///
+    ///     db.add_mirror("mirror_test_backup.tinydb");
+    ///     db.add_item(String::from("Starbug")).unwrap();
+    ///     db.dump_db().unwrap();
+    ///
+    ///     let backup: Database<String> = Database::from("mirror_test_backup.tinydb").unwrap();
+    ///
+    ///     assert!(backup.contains(&String::from("Starbug")));
+    ///     # std::fs::remove_file("mirror_test.tinydb").unwrap();
+    ///     # std::fs::remove_file("mirror_test_backup.tinydb").unwrap();
+    /// }
+    /// ```
+    pub fn add_mirror(&mut self, path: impl Into<PathBuf>) {
+        let path = path.into();
+
+        if !self.mirrors.contains(&path) {
+            self.mirrors.push(path);
+        }
+    }
+
+    /// Stops mirroring dumps to a path, returning `false` if it wasn't a
+    /// mirror. The existing copy there isn't deleted.
+    pub fn remove_mirror(&mut self, path: impl Into<PathBuf>) -> bool {
+        let path = path.into();
+        let before = self.mirrors.len();
+
+        self.mirrors.retain(|mirror| *mirror != path);
+        self.mirrors.len() != before
+    }
+
+    /// Paths dumps are mirrored to, in the order they were added.
+    pub fn mirrors(&self) -> &[PathBuf] {
+        &self.mirrors
+    }
+
+    /// Writes an encoded dump to every mirror, carrying on past failures so
+    /// one unavailable drive doesn't stop the others being written.
+    pub(crate) fn write_mirrors(&self, stream: &[u8]) -> Result<(), error::DatabaseError> {
+        let failed: Vec<_> = self
+            .mirrors
+            .iter()
+            .filter_map(|path| {
+                fs::write(path, stream)
+                    .err()
+                    .map(|e| (path.clone(), e.into()))
+            })
+            .collect();
+
+        match failed.is_empty() {
+            true => Ok(()),
+            false => Err(error::DatabaseError::MirrorsFailed(failed)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests one failing mirror not stopping the rest from being written
+    #[test]
+    fn mirrors_partial_failure() -> Result<(), error::DatabaseError> {
+        let path = PathBuf::from("mirrors_partial.tinydb");
+        let working = PathBuf::from("mirrors_partial_copy.tinydb");
+        let broken = PathBuf::from("missing_dir/mirrors_partial.tinydb");
+        let mut db = Database::new("Mirror test", path.clone(), false);
+
+        db.add_mirror(broken.clone());
+        db.add_mirror(working.clone());
+        db.add_mirror(working.clone());
+        db.add_item(1u8)?;
+
+        match db.dump_db() {
+            Err(error::DatabaseError::MirrorsFailed(failed)) => {
+                assert_eq!(failed.len(), 1);
+                assert_eq!(failed[0].0, broken);
+            }
+            other => panic!("expected a mirror to fail, got {:?}", other),
+        }
+
+        assert_eq!(fs::read(&path)?, fs::read(&working)?);
+        assert!(db.remove_mirror(broken));
+        assert_eq!(db.mirrors().len(), 1);
+
+        db.dump_db()?;
+        fs::remove_file(path)?;
+        fs::remove_file(working)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,203 @@
+//! Contains importing and exporting items as newline-delimited JSON, also
+//! known as JSON lines, which is the easiest way of migrating an existing
+//! dataset into or out of tinydb.
+
+use crate::import::{ImportError, ImportReport};
+use crate::value::Value;
+use crate::{error, json, Database};
+use serde::{de::DeserializeOwned, Serialize};
+use std::fs::File;
+use std::hash;
+use std::io::{prelude::*, BufReader, BufWriter};
+use std::path::PathBuf;
+
+impl<T: hash::Hash + Eq + DeserializeOwned> Database<T> {
+    /// Imports items from a newline-delimited JSON file at `path`, where each
+    /// non-blank line is a JSON value deserialized into one item. The file is
+    /// streamed line by line so it doesn't have to fit into memory twice.
+    ///
+    /// Lines which aren't valid JSON or don't match the shape of `T` are
+    /// skipped and reported alongside their line number, as are duplicates if
+    /// [Database::strict_dupes] is enabled. Other duplicates are handled using
+    /// [Database::dupe_policy].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use serde::{Deserialize, Serialize};
+    /// use tinydb::Database;
+    ///
+    /// #[derive(Hash, Eq, PartialEq, Serialize, Deserialize)]
+    /// struct Ship {
+    ///     name: String,
+    ///     crew: u32,
+    /// }
+    ///
+    /// fn main() {
+    ///     std::fs::write(
+    ///         "ships.ndjson",
+    ///         "{\"name\": \"Red Dwarf\", \"crew\": 169}\n{\"name\": \"Starbug\"}\n",
+    ///     )
+    ///     .unwrap();
+    ///
+    ///     let mut db: Database<Ship> = Database::new("ships", None, false);
+    ///     let report = db.import_ndjson("ships.ndjson").unwrap();
+    ///
+    ///     assert_eq!(report.added, 1);
+    ///     assert_eq!(report.errors[0].line, 2);
+    ///     # std::fs::remove_file("ships.ndjson").unwrap();
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns [error::DatabaseError::Archived] if this database is archived
+    /// or [error::DatabaseError::IOError] if the file can't be read.
+    pub fn import_ndjson(
+        &mut self,
+        path: impl Into<PathBuf>,
+    ) -> Result<ImportReport, error::DatabaseError> {
+        self.check_mutable()?;
+        self.history.begin_step();
+
+        let reader = BufReader::new(File::open(path.into())?);
+        let mut report = ImportReport::default();
+
+        for (index, line) in reader.lines().enumerate() {
+            let line_number = index + 1;
+            let line = line?;
+
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let result = json::parse(&line)
+                .and_then(|value| value.into_item::<T>(false).map_err(|err| err.to_string()));
+
+            match result {
+                Ok(item) => match self.insert_by_policy(item) {
+                    Ok(true) => report.added += 1,
+                    Ok(false) => (),
+                    Err(_) => report.errors.push(ImportError {
+                        line: line_number,
+                        message: String::from("duplicate item"),
+                    }),
+                },
+                Err(message) => report.errors.push(ImportError {
+                    line: line_number,
+                    message,
+                }),
+            }
+        }
+
+        Ok(report)
+    }
+}
+
+impl<T: hash::Hash + Eq + Serialize> Database<T> {
+    /// Exports all items to a newline-delimited JSON file at `path`, one item
+    /// per line in the same order as [Database::ordered]. Any existing file is
+    /// overwritten.
+    ///
+    /// # Errors
+    ///
+    /// Returns [error::DatabaseError::ConversionError] if an item can't be
+    /// represented as JSON, such as a map with non-string keys.
+    pub fn export_ndjson(&self, path: impl Into<PathBuf>) -> Result<(), error::DatabaseError> {
+        let mut lines = Vec::with_capacity(self.items.len());
+
+        for item in self.ordered().filter(|item| !self.is_expired(item)) {
+            let value = Value::from_item(item)
+                .map_err(|err| error::DatabaseError::ConversionError(err.0))?;
+
+            lines.push(json::write(&value));
+        }
+
+        let mut writer = BufWriter::new(File::create(path.into())?);
+
+        for line in lines {
+            writer.write_all(line.as_bytes())?;
+            writer.write_all(b"\n")?;
+        }
+
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+    use std::fs;
+
+    /// Nested item to import
+    #[derive(Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
+    struct Crew {
+        name: String,
+        ranks: Vec<u8>,
+        nickname: Option<String>,
+    }
+
+    /// Tests importing lines, skipping and reporting bad ones
+    #[test]
+    fn ndjson_import() -> Result<(), error::DatabaseError> {
+        let path = PathBuf::from("ndjson_import.ndjson");
+
+        fs::write(
+            &path,
+            concat!(
+                "{\"name\": \"Lister\", \"ranks\": [1, 2]}\n",
+                "\n",
+                "{\"name\": \"Rimmer\", \"ranks\": [], \"nickname\": \"Ace\"}\n",
+                "{\"name\": \"Cat\", \"ranks\": [-1]}\n",
+                "{\"name\": \"Kryten\"\n",
+                "{\"name\": \"Lister\", \"ranks\": [1, 2]}\n",
+            ),
+        )?;
+
+        let mut db: Database<Crew> = Database::new("NDJSON test", None, true);
+        let report = db.import_ndjson(path.clone())?;
+
+        assert_eq!(report.added, 2);
+        assert_eq!(
+            report.errors.iter().map(|err| err.line).collect::<Vec<_>>(),
+            vec![4, 5, 6]
+        );
+        assert!(db.contains(&Crew {
+            name: String::from("Rimmer"),
+            ranks: Vec::new(),
+            nickname: Some(String::from("Ace"))
+        }));
+
+        fs::remove_file(path)?;
+        Ok(())
+    }
+
+    /// Tests items surviving a round-trip through NDJSON
+    #[test]
+    fn ndjson_round_trip() -> Result<(), error::DatabaseError> {
+        let path = PathBuf::from("ndjson_round_trip.ndjson");
+        let mut db = Database::new("NDJSON test", None, false);
+
+        db.add_item(Crew {
+            name: String::from("Holly \"IQ 6000\""),
+            ranks: vec![0, 255],
+            nickname: None,
+        })?;
+        db.add_item(Crew {
+            name: String::from("Talkie Toaster"),
+            ranks: Vec::new(),
+            nickname: Some(String::from("Toaster")),
+        })?;
+        db.export_ndjson(path.clone())?;
+
+        let mut loaded = Database::new("NDJSON test", None, false);
+
+        assert!(loaded.import_ndjson(path.clone())?.is_ok());
+        assert_eq!(loaded.items, db.items);
+
+        fs::remove_file(path)?;
+        Ok(())
+    }
+}
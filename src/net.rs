@@ -0,0 +1,576 @@
+//! Contains a tiny TCP server sharing one [crate::Database] between several
+//! processes and the [Client] used to talk to it, enabled with the `server`
+//! feature.
+//!
+//! Requests and responses are bincode-encoded and prefixed by their length as
+//! a little-endian [u32]. Closures can't be sent over the network, so instead
+//! of querying by field the client fetches every item with [Client::items]
+//! and filters them itself.
+
+use crate::{error, Database};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::hash;
+use std::io::{self, prelude::*};
+use std::marker::PhantomData;
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// Largest response a [Client] accepts, which is far larger than requests
+/// as it may contain every item.
+const MAX_RESPONSE: usize = 1 << 28;
+
+/// How long the server waits between checking for new connections.
+const ACCEPT_INTERVAL: Duration = Duration::from_millis(10);
+
+/// A single operation sent from a [Client] to the server.
+#[derive(Serialize, Deserialize)]
+enum Request<T> {
+    Contains(T),
+    Insert(T),
+    Update(T, T),
+    Remove(T),
+    Items,
+    Dump,
+}
+
+/// Result of a [Request], where `I` is an owned item when decoding and a
+/// borrowed one when encoding on the server.
+#[derive(Serialize, Deserialize)]
+enum Response<I> {
+    Done,
+    Bool(bool),
+    Items(Vec<I>),
+    Failed(RemoteError),
+}
+
+/// Errors which are sent back to the client, keeping the variants a client
+/// is likely to match on.
+#[derive(Serialize, Deserialize)]
+enum RemoteError {
+    ItemNotFound,
+    DupeFound,
+    CapacityReached,
+    Archived,
+    SavePathRequired,
+    Other(String),
+}
+
+impl From<error::DatabaseError> for RemoteError {
+    fn from(error: error::DatabaseError) -> Self {
+        match error {
+            error::DatabaseError::ItemNotFound => RemoteError::ItemNotFound,
+            error::DatabaseError::DupeFound => RemoteError::DupeFound,
+            error::DatabaseError::CapacityReached => RemoteError::CapacityReached,
+            error::DatabaseError::Archived => RemoteError::Archived,
+            error::DatabaseError::SavePathRequired => RemoteError::SavePathRequired,
+            other => RemoteError::Other(other.to_string()),
+        }
+    }
+}
+
+impl From<RemoteError> for error::DatabaseError {
+    fn from(error: RemoteError) -> Self {
+        match error {
+            RemoteError::ItemNotFound => error::DatabaseError::ItemNotFound,
+            RemoteError::DupeFound => error::DatabaseError::DupeFound,
+            RemoteError::CapacityReached => error::DatabaseError::CapacityReached,
+            RemoteError::Archived => error::DatabaseError::Archived,
+            RemoteError::SavePathRequired => error::DatabaseError::SavePathRequired,
+            RemoteError::Other(message) => error::DatabaseError::Remote(message),
+        }
+    }
+}
+
+/// Encodes a single length-prefixed frame.
+fn frame(value: &impl Serialize) -> Result<Vec<u8>, error::DatabaseError> {
+    let len = bincode::serialized_size(value)? as usize;
+    let mut bytes = Vec::with_capacity(4 + len);
+
+    bytes.extend_from_slice(&(len as u32).to_le_bytes());
+    bincode::serialize_into(&mut bytes, value)?;
+    Ok(bytes)
+}
+
+/// Writes a single length-prefixed frame.
+fn write_frame(stream: &mut TcpStream, value: &impl Serialize) -> Result<(), error::DatabaseError> {
+    stream.write_all(&frame(value)?)?;
+    Ok(())
+}
+
+/// Reads a single length-prefixed frame of at most `max_len` bytes, returning
+/// [Option::None] if the other side hung up cleanly.
+///
+/// The frame is read into a buffer which only grows as bytes arrive, so a
+/// length prefix can't reserve memory for bytes which are never sent.
+fn read_frame<V: DeserializeOwned>(
+    stream: &mut TcpStream,
+    max_len: usize,
+) -> Result<Option<V>, error::DatabaseError> {
+    let mut len = [0; 4];
+
+    match stream.read_exact(&mut len) {
+        Ok(()) => (),
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e.into()),
+    }
+
+    let len = u32::from_le_bytes(len) as usize;
+
+    if len > max_len {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "frame is too large").into());
+    }
+
+    let mut bytes = Vec::new();
+
+    if stream.take(len as u64).read_to_end(&mut bytes)? < len {
+        return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+    }
+
+    Ok(Some(bincode::deserialize(&bytes)?))
+}
+
+/// Options for a server started with [serve_with], where [serve] uses the
+/// defaults.
+///
+/// ```rust
+/// use tinydb::net::ServerOptions;
+///
+/// let options = ServerOptions {
+///     max_connections: 8,
+///     ..ServerOptions::default()
+/// };
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ServerOptions {
+    /// Largest request accepted from a client in bytes, which is disconnected
+    /// if it sends anything larger. Defaults to 4 MiB.
+    pub max_request: usize,
+
+    /// Most clients served at once, each on their own thread. Any more
+    /// connections wait to be accepted until another client hangs up.
+    /// Defaults to 64.
+    pub max_connections: usize,
+
+    /// How long a client can go without sending a request, or without
+    /// reading a response, before it's disconnected so it stops counting
+    /// towards [ServerOptions::max_connections]. Idle clients are never
+    /// disconnected if this is zero. Defaults to 30 seconds.
+    pub idle_timeout: Duration,
+}
+
+impl Default for ServerOptions {
+    fn default() -> Self {
+        ServerOptions {
+            max_request: 4 * 1024 * 1024,
+            max_connections: 64,
+            idle_timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Counts a client towards [ServerOptions::max_connections] until it's
+/// dropped, even if handling the client panics.
+struct Connection(Arc<AtomicUsize>);
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// A server sharing a database over TCP, started with [serve]. Connections
+/// are each handled on their own thread, up to
+/// [ServerOptions::max_connections] at once, and the database is locked
+/// whilst answering every request but not whilst sending the response.
+///
+/// The server stops accepting connections once this is dropped, although
+/// clients which are already connected are still served until they hang up.
+#[derive(Debug)]
+pub struct Server {
+    addr: SocketAddr,
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Server {
+    /// Gets the address this server is listening on, which is useful when
+    /// binding to port `0`.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Stops accepting connections, waiting for the background thread to
+    /// finish.
+    pub fn stop(self) {}
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+
+        if let Some(handle) = self.handle.take() {
+            handle.thread().unpark();
+            handle.join().ok();
+        }
+    }
+}
+
+/// Serves a shared database on `addr` from a background thread so that
+/// [Client]s in other processes can use it.
+///
+/// There is no authentication or encryption, so this should only be bound to
+/// addresses which untrusted machines can't reach. Use [serve_with] to change
+/// the limits on requests and connections.
+///
+/// # Examples
+///
+/// ```rust
+/// use tinydb::Database;
+/// use tinydb::net::{self, Client};
+/// use std::sync::{Arc, Mutex};
+///
+/// fn main() {
+///     let db: Database<String> = Database::new("served", None, false);
+///     let shared = Arc::new(Mutex::new(db));
+///     let server = net::serve(&shared, "127.0.0.1:0").unwrap();
+///
+///     let mut client = Client::connect(server.local_addr()).unwrap();
+///
+///     client.add_item(String::from("Kryten")).unwrap();
+///
+///     assert!(client.contains(&String::from("Kryten")).unwrap());
+///     assert!(shared.lock().unwrap().contains(&String::from("Kryten")));
+/// }
+/// ```
+pub fn serve<T>(
+    shared: &Arc<Mutex<Database<T>>>,
+    addr: impl ToSocketAddrs,
+) -> Result<Server, error::DatabaseError>
+where
+    T: hash::Hash + Eq + Serialize + DeserializeOwned + Send + Sync + 'static,
+{
+    serve_with(shared, addr, ServerOptions::default())
+}
+
+/// Serves a shared database like [serve] but with the given `options`.
+pub fn serve_with<T>(
+    shared: &Arc<Mutex<Database<T>>>,
+    addr: impl ToSocketAddrs,
+    options: ServerOptions,
+) -> Result<Server, error::DatabaseError>
+where
+    T: hash::Hash + Eq + Serialize + DeserializeOwned + Send + Sync + 'static,
+{
+    let listener = TcpListener::bind(addr)?;
+    let addr = listener.local_addr()?;
+
+    listener.set_nonblocking(true)?;
+
+    let shared = Arc::clone(shared);
+    let stop = Arc::new(AtomicBool::new(false));
+    let thread_stop = Arc::clone(&stop);
+    let active = Arc::new(AtomicUsize::new(0));
+    let handle = thread::spawn(move || {
+        while !thread_stop.load(Ordering::SeqCst) {
+            if active.load(Ordering::SeqCst) >= options.max_connections {
+                thread::park_timeout(ACCEPT_INTERVAL);
+                continue;
+            }
+
+            match listener.accept() {
+                Ok((stream, _)) => {
+                    let shared = Arc::clone(&shared);
+
+                    active.fetch_add(1, Ordering::SeqCst);
+
+                    let connection = Connection(Arc::clone(&active));
+
+                    thread::spawn(move || {
+                        handle_client(&shared, stream, options);
+                        drop(connection);
+                    });
+                }
+                Err(_) => thread::park_timeout(ACCEPT_INTERVAL),
+            }
+        }
+    });
+
+    Ok(Server {
+        addr,
+        stop,
+        handle: Some(handle),
+    })
+}
+
+/// Answers requests from a single client until it hangs up, goes idle or
+/// breaks the limits given in `options`.
+fn handle_client<T>(shared: &Mutex<Database<T>>, mut stream: TcpStream, options: ServerOptions)
+where
+    T: hash::Hash + Eq + Serialize + DeserializeOwned,
+{
+    let timeout = Some(options.idle_timeout).filter(|timeout| !timeout.is_zero());
+
+    if stream.set_nonblocking(false).is_err()
+        || stream.set_read_timeout(timeout).is_err()
+        || stream.set_write_timeout(timeout).is_err()
+    {
+        return;
+    }
+
+    while let Ok(Some(request)) = read_frame::<Request<T>>(&mut stream, options.max_request) {
+        let response = match shared.lock() {
+            Ok(mut db) => respond(&mut db, request),
+            Err(_) => frame(&Response::<T>::Failed(RemoteError::Other(String::from(
+                "the shared database was poisoned by a panic",
+            )))),
+        };
+
+        if response.and_then(|bytes| Ok(stream.write_all(&bytes)?)).is_err() {
+            break;
+        }
+    }
+}
+
+/// Answers a single request, encoding the response straight away so the
+/// database can be unlocked before it's sent.
+fn respond<T>(db: &mut Database<T>, request: Request<T>) -> Result<Vec<u8>, error::DatabaseError>
+where
+    T: hash::Hash + Eq + Serialize,
+{
+    let response: Response<&T> = match request {
+        Request::Contains(item) => Response::Bool(db.contains(&item)),
+        Request::Insert(item) => done(db.add_item(item)),
+        Request::Update(item, new) => done(db.update_item(&item, new)),
+        Request::Remove(item) => done(db.remove_item(&item)),
+        Request::Items => {
+            Response::Items(db.items.iter().filter(|item| !db.is_expired(item)).collect())
+        }
+        Request::Dump => done(db.dump_db().map(|_| ())),
+    };
+
+    frame(&response)
+}
+
+/// Turns the result of a mutation into a [Response].
+fn done<I>(result: Result<(), error::DatabaseError>) -> Response<I> {
+    match result {
+        Ok(()) => Response::Done,
+        Err(error) => Response::Failed(error.into()),
+    }
+}
+
+/// A connection to a database shared with [serve], mirroring the methods of
+/// [Database] which can work over the network.
+#[derive(Debug)]
+pub struct Client<T> {
+    stream: TcpStream,
+    item: PhantomData<T>,
+}
+
+impl<T: hash::Hash + Eq + Serialize + DeserializeOwned> Client<T> {
+    /// Connects to a server started with [serve].
+    pub fn connect(addr: impl ToSocketAddrs) -> Result<Self, error::DatabaseError> {
+        Ok(Client {
+            stream: TcpStream::connect(addr)?,
+            item: PhantomData,
+        })
+    }
+
+    /// Checks if the shared database contains an item, see
+    /// [Database::contains].
+    pub fn contains(&mut self, item: &T) -> Result<bool, error::DatabaseError> {
+        match self.send(&Request::Contains(item))? {
+            Response::Bool(found) => Ok(found),
+            _ => Err(unexpected()),
+        }
+    }
+
+    /// Adds an item to the shared database, see [Database::add_item].
+    pub fn add_item(&mut self, item: T) -> Result<(), error::DatabaseError> {
+        self.mutate(&Request::Insert(item))
+    }
+
+    /// Replaces an item inside of the shared database, see
+    /// [Database::update_item].
+    pub fn update_item(&mut self, item: &T, new: T) -> Result<(), error::DatabaseError> {
+        self.mutate(&Request::Update(item, &new))
+    }
+
+    /// Removes an item from the shared database, see [Database::remove_item].
+    pub fn remove_item(&mut self, item: &T) -> Result<(), error::DatabaseError> {
+        self.mutate(&Request::Remove(item))
+    }
+
+    /// Fetches a copy of every item inside of the shared database, which can
+    /// then be queried locally.
+    pub fn items(&mut self) -> Result<Vec<T>, error::DatabaseError> {
+        match self.send(&Request::<T>::Items)? {
+            Response::Items(items) => Ok(items),
+            _ => Err(unexpected()),
+        }
+    }
+
+    /// Dumps the shared database on the server, see [Database::dump_db].
+    pub fn dump_db(&mut self) -> Result<(), error::DatabaseError> {
+        self.mutate(&Request::<T>::Dump)
+    }
+
+    /// Sends a request which only reports if it succeeded.
+    fn mutate(&mut self, request: &Request<impl Serialize>) -> Result<(), error::DatabaseError> {
+        match self.send(request)? {
+            Response::Done => Ok(()),
+            _ => Err(unexpected()),
+        }
+    }
+
+    /// Sends a request and waits for its response, turning failures back
+    /// into errors.
+    fn send(&mut self, request: &Request<impl Serialize>) -> Result<Response<T>, error::DatabaseError> {
+        write_frame(&mut self.stream, request)?;
+
+        match read_frame(&mut self.stream, MAX_RESPONSE)? {
+            Some(Response::Failed(error)) => Err(error.into()),
+            Some(response) => Ok(response),
+            None => Err(io::Error::from(io::ErrorKind::UnexpectedEof).into()),
+        }
+    }
+}
+
+/// Error for a response which doesn't match the request it was sent for.
+fn unexpected() -> error::DatabaseError {
+    io::Error::new(io::ErrorKind::InvalidData, "unexpected response from server").into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// Tests several clients sharing one database and errors being sent back
+    #[test]
+    fn clients_share_db() -> Result<(), error::DatabaseError> {
+        let path = PathBuf::from("clients_share_db.tinydb");
+        let db: Database<i32> = Database::new("Server test", path.clone(), true);
+        let shared = Arc::new(Mutex::new(db));
+        let server = serve(&shared, "127.0.0.1:0")?;
+        let mut first = Client::connect(server.local_addr())?;
+        let mut second = Client::connect(server.local_addr())?;
+
+        first.add_item(1)?;
+        second.add_item(2)?;
+        second.update_item(&2, 3)?;
+
+        assert!(first.contains(&3)?);
+        assert!(matches!(first.add_item(1), Err(error::DatabaseError::DupeFound)));
+        assert!(matches!(
+            second.remove_item(&2),
+            Err(error::DatabaseError::ItemNotFound)
+        ));
+
+        let mut items = first.items()?;
+
+        items.sort_unstable();
+
+        assert_eq!(items, vec![1, 3]);
+        assert_eq!(shared.lock().unwrap().items.len(), 2);
+
+        second.dump_db()?;
+        server.stop();
+
+        let loaded: Database<i32> = Database::from_unlocked(path.clone())?;
+
+        assert_eq!(loaded.items.len(), 2);
+
+        std::fs::remove_file(path)?;
+        Ok(())
+    }
+
+    /// Tests clients being disconnected for large requests and waiting to be
+    /// accepted once too many are connected
+    #[test]
+    fn server_limits() -> Result<(), error::DatabaseError> {
+        let db: Database<String> = Database::new("Server limits test", None, false);
+        let shared = Arc::new(Mutex::new(db));
+        let options = ServerOptions {
+            max_request: 64,
+            max_connections: 1,
+            ..ServerOptions::default()
+        };
+        let server = serve_with(&shared, "127.0.0.1:0", options)?;
+        let mut first = Client::connect(server.local_addr())?;
+
+        first.add_item(String::from("Talkie"))?;
+
+        let mut second = Client::<String>::connect(server.local_addr())?;
+
+        second.stream.set_read_timeout(Some(Duration::from_millis(200)))?;
+        write_frame(&mut second.stream, &Request::Contains("Talkie"))?;
+
+        assert!(read_frame::<Response<String>>(&mut second.stream, MAX_RESPONSE).is_err());
+        assert!(first.add_item("Toaster".repeat(10)).is_err());
+
+        second.stream.set_read_timeout(Some(Duration::from_secs(10)))?;
+
+        assert!(matches!(
+            read_frame::<Response<String>>(&mut second.stream, MAX_RESPONSE)?,
+            Some(Response::Bool(true))
+        ));
+        assert_eq!(shared.lock().unwrap().items.len(), 1);
+        Ok(())
+    }
+
+    /// Tests idle clients being disconnected so they free up their slot
+    #[test]
+    fn idle_disconnected() -> Result<(), error::DatabaseError> {
+        let db: Database<u8> = Database::new("Idle test", None, false);
+        let shared = Arc::new(Mutex::new(db));
+        let options = ServerOptions {
+            max_connections: 1,
+            idle_timeout: Duration::from_millis(100),
+            ..ServerOptions::default()
+        };
+        let server = serve_with(&shared, "127.0.0.1:0", options)?;
+        let mut idle = Client::<u8>::connect(server.local_addr())?;
+
+        idle.add_item(1)?;
+
+        let mut second = Client::<u8>::connect(server.local_addr())?;
+
+        second.stream.set_read_timeout(Some(Duration::from_secs(10)))?;
+
+        assert!(second.contains(&1)?);
+        assert!(idle.contains(&1).is_err());
+        Ok(())
+    }
+
+    /// Tests a poisoned database being reported rather than dropping clients
+    #[test]
+    fn poisoned_reported() -> Result<(), error::DatabaseError> {
+        let db: Database<u8> = Database::new("Poisoned test", None, false);
+        let shared = Arc::new(Mutex::new(db));
+        let server = serve(&shared, "127.0.0.1:0")?;
+        let mut client = Client::<u8>::connect(server.local_addr())?;
+        let poisoner = Arc::clone(&shared);
+
+        thread::spawn(move || {
+            let _db = poisoner.lock().unwrap();
+
+            panic!("poisoning the database");
+        })
+        .join()
+        .ok();
+
+        assert!(matches!(
+            client.add_item(1),
+            Err(error::DatabaseError::Remote(_))
+        ));
+        assert!(matches!(
+            client.contains(&1),
+            Err(error::DatabaseError::Remote(_))
+        ));
+        Ok(())
+    }
+}
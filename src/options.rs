@@ -0,0 +1,131 @@
+//! Contains [LoadOptions], which let the program loading a dump decide how
+//! the database behaves rather than the one which dumped it, see
+//! [crate::Database::from_with_options].
+
+use crate::limits::LoadLimits;
+use crate::{error, Database};
+use serde::de::DeserializeOwned;
+use std::hash;
+use std::path::PathBuf;
+
+/// Settings used in place of those saved in a dump when loading it with
+/// [Database::from_with_options]. Anything left as the default is kept as it
+/// was dumped, or as [Database::from] would set it.
+///
+/// # Examples
+///
+/// ```rust
+/// use tinydb::options::LoadOptions;
+///
+/// let options = LoadOptions {
+///     strict_dupes: Some(true),
+///     ..LoadOptions::default()
+/// };
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LoadOptions {
+    /// Overrides the [Database::strict_dupes] the dump was made with.
+    pub strict_dupes: Option<bool>,
+
+    /// Where to save to instead of the path being loaded from, see
+    /// [Database::set_save_path].
+    pub save_path: Option<PathBuf>,
+
+    /// Enables [Database::set_skip_clean_dumps] straight away.
+    pub skip_clean_dumps: bool,
+
+    /// Limits checked whilst loading, see [Database::from_with_limits].
+    pub limits: Option<LoadLimits>,
+}
+
+impl<T: hash::Hash + Eq + DeserializeOwned> Database<T> {
+    /// Creates a database from a `.tinydb` file like [Database::from] does,
+    /// with settings from `options` used in place of those the dump was made
+    /// with. This is useful when the program loading a dump isn't the one
+    /// which made it, so shouldn't have to inherit its choices.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tinydb::options::LoadOptions;
+    /// use tinydb::Database;
+    ///
+    /// fn main() {
+    ///     let mut db = Database::new("options_test", None, true);
+    ///
+    ///     db.add_item(String::from("Cat")).unwrap();
+    ///     db.dump_db().unwrap();
+    ///     drop(db);
+    ///
+    ///     let options = LoadOptions {
+    ///         strict_dupes: Some(false),
+    ///         ..LoadOptions::default()
+    ///     };
+    ///     let mut loaded: Database<String> =
+    ///         Database::from_with_options("options_test.tinydb", options).unwrap();
+    ///
+    ///     assert!(loaded.add_item(String::from("Cat")).is_ok());
+    ///     # std::fs::remove_file("options_test.tinydb").unwrap();
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Fails in the same ways as [Database::from], or
+    /// [Database::from_with_limits] if [LoadOptions::limits] are given.
+    pub fn from_with_options(
+        path: impl Into<PathBuf>,
+        options: LoadOptions,
+    ) -> Result<Self, error::DatabaseError> {
+        let mut decoded = match options.limits {
+            Some(limits) => Database::from_with_limits(path, limits)?,
+            None => Database::from(path)?,
+        };
+
+        if let Some(strict_dupes) = options.strict_dupes {
+            decoded.strict_dupes = strict_dupes;
+        }
+
+        if options.save_path.is_some() {
+            decoded.set_save_path(options.save_path);
+        }
+
+        decoded.skip_clean_dumps = options.skip_clean_dumps;
+        Ok(decoded)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests dumped settings being replaced and the old dump being unlocked
+    /// once saving elsewhere
+    #[test]
+    fn options_override() -> Result<(), error::DatabaseError> {
+        let path = PathBuf::from("options_override.tinydb");
+        let elsewhere = PathBuf::from("options_override_copy.tinydb");
+        let mut db = Database::new("Options test", path.clone(), true);
+
+        db.add_item(1u8)?;
+        db.dump_db()?;
+        drop(db);
+
+        let loaded: Database<u8> = Database::from_with_options(
+            path.clone(),
+            LoadOptions {
+                strict_dupes: Some(false),
+                save_path: Some(elsewhere.clone()),
+                limits: Some(LoadLimits::default()),
+                ..LoadOptions::default()
+            },
+        )?;
+
+        assert!(!loaded.strict_dupes);
+        assert_eq!(loaded.save_path, Some(elsewhere));
+        assert!(Database::<u8>::from(path.clone())?.strict_dupes);
+
+        std::fs::remove_file(path)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,210 @@
+//! Contains the pinned ordering of items, allowing UI lists such as
+//! most-recently-used files to be kept directly inside of a [crate::Database].
+
+use crate::compat::{HashSet, Vec};
+use crate::{error, Database};
+use core::hash;
+use serde::{Deserialize, Serialize};
+
+/// Items which have been pinned to the front or back of a database with
+/// [Database::move_to_front] or [Database::move_to_back].
+///
+/// This is saved alongside the database so the order persists between dumps.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct PinnedOrder<T> {
+    /// Items pinned to the front, with the most recently moved first.
+    front: Vec<T>,
+
+    /// Items pinned to the back, with the most recently moved last.
+    back: Vec<T>,
+}
+
+impl<T: PartialEq> PinnedOrder<T> {
+    /// Removes an item from both ends, returning if it was pinned at all.
+    pub(crate) fn unpin(&mut self, item: &T) -> bool {
+        let before = self.front.len() + self.back.len();
+
+        self.front.retain(|pinned| pinned != item);
+        self.back.retain(|pinned| pinned != item);
+
+        before != self.front.len() + self.back.len()
+    }
+
+    /// Makes sure every pinned item is still inside of the database and only
+    /// pinned once, see [crate::testing::check_invariants].
+    #[cfg(feature = "testing")]
+    pub(crate) fn check_consistent(&self, contains: &dyn Fn(&T) -> bool) -> Result<(), String> {
+        let pinned: Vec<&T> = self.front.iter().chain(self.back.iter()).collect();
+
+        if !pinned.iter().all(|item| contains(item)) {
+            return Err(String::from("a pinned item isn't inside of the database"));
+        }
+
+        match (1..pinned.len()).any(|at| pinned[at..].contains(&pinned[at - 1])) {
+            true => Err(String::from("an item is pinned more than once")),
+            false => Ok(()),
+        }
+    }
+}
+
+impl<T> PinnedOrder<T> {
+    /// Converts every pinned item into another type, keeping their order.
+    #[cfg(feature = "std")]
+    pub(crate) fn map<U>(self, convert: &mut impl FnMut(T) -> U) -> PinnedOrder<U> {
+        PinnedOrder {
+            front: self.front.into_iter().map(&mut *convert).collect(),
+            back: self.back.into_iter().map(convert).collect(),
+        }
+    }
+}
+
+impl<T> Default for PinnedOrder<T> {
+    fn default() -> Self {
+        PinnedOrder {
+            front: Vec::new(),
+            back: Vec::new(),
+        }
+    }
+}
+
+impl<T: hash::Hash + Eq> Database<T> {
+    /// Pins an item to the front of [Database::ordered], ahead of any items
+    /// which were previously moved to the front.
+    ///
+    /// This is ideal for most-recently-used lists, where opening something
+    /// should move it to the top. Pins are saved when dumping but are lost if
+    /// the item is removed or replaced using [Database::update_item].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tinydb::Database;
+    ///
+    /// fn main() {
+    ///     let mut recent: Database<String> = Database::new("recent", None, false);
+    ///
+    ///     recent.add_item(String::from("main.rs")).unwrap();
+    ///     recent.add_item(String::from("lib.rs")).unwrap();
+    ///
+    ///     recent.move_to_front(&String::from("lib.rs")).unwrap();
+    ///     recent.move_to_front(&String::from("main.rs")).unwrap();
+    ///
+    ///     let order: Vec<&String> = recent.ordered().collect();
+    ///     assert_eq!(order, vec!["main.rs", "lib.rs"]);
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Will return [error::DatabaseError::ItemNotFound] if the item isn't
+    /// inside of the database or [error::DatabaseError::Archived] if the
+    /// database has been archived.
+    pub fn move_to_front(&mut self, item: &T) -> Result<(), error::DatabaseError>
+    where
+        T: Clone,
+    {
+        let pinned = self.pinnable(item)?;
+
+        self.order.front.insert(0, pinned);
+        Ok(())
+    }
+
+    /// Pins an item to the back of [Database::ordered], behind any items which
+    /// were previously moved to the back.
+    ///
+    /// See [Database::move_to_front] for more infomation on pinning.
+    ///
+    /// # Errors
+    ///
+    /// Will return [error::DatabaseError::ItemNotFound] if the item isn't
+    /// inside of the database or [error::DatabaseError::Archived] if the
+    /// database has been archived.
+    pub fn move_to_back(&mut self, item: &T) -> Result<(), error::DatabaseError>
+    where
+        T: Clone,
+    {
+        let pinned = self.pinnable(item)?;
+
+        self.order.back.push(pinned);
+        Ok(())
+    }
+
+    /// Unpins an item from the front or back of [Database::ordered], returning
+    /// `true` if it was pinned. Archived databases can't be unpinned.
+    pub fn unpin(&mut self, item: &T) -> bool {
+        self.check_mutable().is_ok() && self.order.unpin(item)
+    }
+
+    /// Iterates over all items with those moved to the front first, then all
+    /// unpinned items in an arbitrary order and finally those moved to the
+    /// back.
+    pub fn ordered(&self) -> impl Iterator<Item = &T> {
+        let pinned: HashSet<&T> = self
+            .order
+            .front
+            .iter()
+            .chain(self.order.back.iter())
+            .collect();
+
+        self.order
+            .front
+            .iter()
+            .chain(self.items.iter().filter(move |item| !pinned.contains(item)))
+            .chain(self.order.back.iter())
+    }
+
+    /// Unpins an item and clones it so it can be pinned again, making sure
+    /// it's actually inside of the database.
+    fn pinnable(&mut self, item: &T) -> Result<T, error::DatabaseError>
+    where
+        T: Clone,
+    {
+        self.check_mutable()?;
+
+        match self.items.get(item) {
+            Some(found) => {
+                let found = found.clone();
+
+                self.order.unpin(&found);
+                Ok(found)
+            }
+            None => Err(error::DatabaseError::ItemNotFound),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// Tests pins surviving a dump and being dropped on removal
+    #[test]
+    fn pins_persist() -> Result<(), error::DatabaseError> {
+        let path = PathBuf::from("pins_persist.tinydb");
+        let mut db = Database::new("Pin test", path.clone(), false);
+
+        for num in 0..10 {
+            db.add_item(num)?;
+        }
+
+        db.move_to_back(&0)?;
+        db.move_to_front(&5)?;
+        db.move_to_front(&7)?;
+        db.dump_db()?;
+
+        let mut loaded: Database<i32> = Database::from(path.clone())?;
+        let order: Vec<i32> = loaded.ordered().copied().collect();
+
+        assert_eq!(order.len(), 10);
+        assert_eq!(&order[..2], &[7, 5]);
+        assert_eq!(order[9], 0);
+
+        loaded.remove_item(&7)?;
+        assert_eq!(loaded.ordered().next(), Some(&5));
+        assert!(loaded.move_to_front(&7).is_err());
+
+        std::fs::remove_file(path)?;
+        Ok(())
+    }
+}
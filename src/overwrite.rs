@@ -0,0 +1,178 @@
+//! Contains what happens when dumping over a file which already exists, see
+//! [crate::Database::set_overwrite_policy].
+
+use crate::{error, Database};
+use std::fmt;
+use std::fs;
+use std::hash;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// What [Database::dump_db] does when a file already exists where the dump
+/// is being written.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum OverwritePolicy {
+    /// Replaces the file, which is the default.
+    #[default]
+    Overwrite,
+
+    /// Fails with [error::DatabaseError::DumpExists] unless the file is a dump
+    /// of this database, meaning it was loaded from or dumped to it before.
+    /// This stops two databases with the same label from silently replacing
+    /// each other's dumps.
+    Error,
+
+    /// Copies the file to the same path with `.bak` added on the end before
+    /// replacing it, replacing any older backup.
+    Backup,
+}
+
+/// Overwrite policy of a database alongside the path it last loaded from or
+/// dumped to, which it's always allowed to write over.
+#[derive(Default)]
+pub(crate) struct Overwrite {
+    pub(crate) policy: OverwritePolicy,
+    owned: Mutex<Option<PathBuf>>,
+}
+
+impl Overwrite {
+    /// Remembers a path as holding a dump of this database.
+    pub(crate) fn own(&self, path: &Path) {
+        *self.owned.lock().unwrap() = Some(path.to_path_buf());
+    }
+
+    /// Checks if a path was last loaded from or dumped to.
+    fn owns(&self, path: &Path) -> bool {
+        self.owned.lock().unwrap().as_deref() == Some(path)
+    }
+}
+
+impl Clone for Overwrite {
+    fn clone(&self) -> Self {
+        Overwrite {
+            policy: self.policy,
+            owned: Mutex::new(self.owned.lock().unwrap().clone()),
+        }
+    }
+}
+
+impl fmt::Debug for Overwrite {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Overwrite")
+            .field("policy", &self.policy)
+            .field("owned", &self.owned.lock().unwrap())
+            .finish()
+    }
+}
+
+/// Gets the path a dump is moved to by [OverwritePolicy::Backup].
+pub(crate) fn backup_path(path: &Path) -> PathBuf {
+    let mut backup = path.as_os_str().to_owned();
+
+    backup.push(".bak");
+    PathBuf::from(backup)
+}
+
+impl<T: hash::Hash + Eq> Database<T> {
+    /// Sets what [Database::dump_db] does when a file already exists where
+    /// the dump is being written, which is to overwrite it by default. This
+    /// setting is not saved when dumping.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tinydb::error::DatabaseError;
+    /// use tinydb::overwrite::OverwritePolicy;
+    /// use tinydb::Database;
+    ///
+    /// fn main() {
+    ///     let first: Database<u8> = Database::new("overwrite_test", None, false);
+    ///     let mut second: Database<u8> = Database::new("overwrite_test", None, false);
+    ///
+    ///     first.dump_db().unwrap();
+    ///     second.set_overwrite_policy(OverwritePolicy::Error);
+    ///
+    ///     assert!(matches!(second.dump_db().unwrap_err().root(), DatabaseError::DumpExists(_)));
+    ///
+    ///     first.dump_db().unwrap();
+    ///     # std::fs::remove_file("overwrite_test.tinydb").unwrap();
+    /// }
+    /// ```
+    pub fn set_overwrite_policy(&mut self, policy: OverwritePolicy) {
+        self.overwrite.policy = policy;
+    }
+
+    /// Gets what [Database::dump_db] does when a file already exists where the
+    /// dump is being written, see [Database::set_overwrite_policy].
+    pub fn overwrite_policy(&self) -> OverwritePolicy {
+        self.overwrite.policy
+    }
+
+    /// Sets the path this database was just loaded from as where it saves to,
+    /// which it's always allowed to dump over.
+    pub(crate) fn loaded_from(&mut self, path: PathBuf) {
+        self.overwrite.own(&path);
+        self.save_path = Some(path);
+    }
+
+    /// Clears the way for a dump to replace `path` which already exists, by
+    /// checking it's allowed to or backing up the existing file. The existing
+    /// file is left where it is until the new dump is renamed over it.
+    pub(crate) fn replace_existing(&self, path: &Path) -> Result<(), error::DatabaseError> {
+        match self.overwrite.policy {
+            OverwritePolicy::Error if !self.overwrite.owns(path) => {
+                Err(error::DatabaseError::DumpExists(path.to_path_buf()))
+            }
+            OverwritePolicy::Backup => {
+                let backup = backup_path(path);
+
+                if backup.exists() {
+                    fs::remove_file(&backup)?;
+                }
+
+                if fs::hard_link(path, &backup).is_err() {
+                    fs::copy(path, backup)?;
+                }
+
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests each policy when dumping over another database's dump, and
+    /// databases always being able to dump over their own
+    #[test]
+    fn overwrite_policies() -> Result<(), error::DatabaseError> {
+        let path = PathBuf::from("overwrite_policies.tinydb");
+        let mut ours = Database::new("Overwrite test", path.clone(), false);
+        let mut theirs = Database::new("Overwrite test", path.clone(), false);
+
+        ours.add_item(1u8)?;
+        theirs.add_item(2u8)?;
+        ours.set_overwrite_policy(OverwritePolicy::Error);
+        theirs.set_overwrite_policy(OverwritePolicy::Error);
+        ours.dump_db()?;
+        ours.dump_db()?;
+
+        assert!(matches!(
+            theirs.dump_db().unwrap_err().root(),
+            error::DatabaseError::DumpExists(_)
+        ));
+
+        theirs.set_overwrite_policy(OverwritePolicy::Backup);
+        theirs.dump_db()?;
+
+        assert!(Database::<u8>::from(backup_path(&path))?.contains(&1));
+        assert!(Database::<u8>::from(path.clone())?.contains(&2));
+
+        fs::remove_file(backup_path(&path))?;
+        fs::remove_file(path)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,137 @@
+//! Contains pagination over the items of a [Database], for listing large
+//! databases a screen at a time without collecting every item first.
+//!
+//! Pages follow the same order as [Database::ordered], which is stable for as
+//! long as the database isn't mutated. As reading pages only borrows the
+//! database immutably, the borrow checker makes sure pages can't shift
+//! underneath you whilst paginating.
+
+use crate::Database;
+use std::fmt;
+use std::hash;
+
+impl<T: hash::Hash + Eq> Database<T> {
+    /// Gets up to `limit` items starting from the `offset`th item, in the same
+    /// order as [Database::ordered]. Expired items are skipped.
+    ///
+    /// This has to walk past the first `offset` items on every call, so use
+    /// [Database::pages] when reading every page in turn.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tinydb::Database;
+    ///
+    /// fn main() {
+    ///     let mut db = Database::new("page_test", None, false);
+    ///
+    ///     for i in 0..25 {
+    ///         db.add_item(i).unwrap();
+    ///     }
+    ///
+    ///     assert_eq!(db.read_page(0, 10).len(), 10);
+    ///     assert_eq!(db.read_page(20, 10).len(), 5);
+    ///     assert!(db.read_page(30, 10).is_empty());
+    /// }
+    /// ```
+    pub fn read_page(&self, offset: usize, limit: usize) -> Vec<&T> {
+        self.ordered()
+            .filter(|item| !self.is_expired(item))
+            .skip(offset)
+            .take(limit)
+            .collect()
+    }
+
+    /// Iterates over pages of up to `page_size` items each, see
+    /// [Database::read_page]. The last page may be shorter and there are no
+    /// empty pages.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `page_size` is `0`.
+    pub fn pages(&self, page_size: usize) -> Pages<'_, T> {
+        assert!(page_size != 0, "page size must be greater than 0");
+
+        Pages {
+            items: Box::new(self.ordered().filter(move |item| !self.is_expired(item))),
+            page_size,
+            page: 0,
+        }
+    }
+}
+
+/// Iterator over pages of a database made using [Database::pages], which
+/// carries on from where the last page ended rather than starting over.
+pub struct Pages<'a, T> {
+    items: Box<dyn Iterator<Item = &'a T> + 'a>,
+    page_size: usize,
+    page: usize,
+}
+
+impl<'a, T> Pages<'a, T> {
+    /// Gets the number of the next page which will be returned, counting from
+    /// `0`, which is useful as a cursor to show alongside the page.
+    pub fn page(&self) -> usize {
+        self.page
+    }
+}
+
+impl<'a, T> Iterator for Pages<'a, T> {
+    type Item = Vec<&'a T>;
+
+    fn next(&mut self) -> Option<Vec<&'a T>> {
+        let page: Vec<&'a T> = self.items.by_ref().take(self.page_size).collect();
+
+        if page.is_empty() {
+            return None;
+        }
+
+        self.page += 1;
+        Some(page)
+    }
+}
+
+impl<'a, T> fmt::Debug for Pages<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Pages")
+            .field("page_size", &self.page_size)
+            .field("page", &self.page)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    /// Tests pages covering every item exactly once and matching
+    /// [Database::read_page]
+    #[test]
+    fn pages_cover_items() {
+        let mut db = Database::new("Page test", None, false);
+
+        for i in 0..23 {
+            db.add_item(i).unwrap();
+        }
+
+        db.move_to_front(&7).unwrap();
+
+        let mut pages = db.pages(5);
+        let mut seen: HashSet<&i32> = HashSet::new();
+
+        assert_eq!(pages.page(), 0);
+
+        for number in 0..5 {
+            let page = pages.next().unwrap();
+
+            assert_eq!(page, db.read_page(number * 5, 5));
+            seen.extend(page);
+        }
+
+        assert!(pages.next().is_none());
+        assert_eq!(pages.page(), 5);
+        assert_eq!(seen.len(), 23);
+        assert_eq!(db.read_page(0, 1), vec![&7]);
+    }
+}
@@ -0,0 +1,317 @@
+//! Contains how the path of a dump is worked out from [crate::Database::label]
+//! when no [crate::Database::save_path] is given, making sure labels can't
+//! point outside of the working directory or at names which are reserved on
+//! some platforms.
+
+use crate::compat::PathBuf;
+#[cfg(feature = "std")]
+use crate::{error, lock, Database};
+#[cfg(feature = "std")]
+use core::hash;
+#[cfg(feature = "std")]
+use core::sync::atomic::{AtomicU64, Ordering};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+#[cfg(feature = "std")]
+use std::fs::{self, File};
+#[cfg(feature = "std")]
+use std::io::{self, Write};
+#[cfg(feature = "std")]
+use std::path::Path;
+#[cfg(feature = "std")]
+use std::process;
+
+/// Names of devices on Windows, which can't be used as the stem of a file
+/// regardless of their extension or case.
+#[cfg(feature = "std")]
+const RESERVED_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Makes a label safe to use as a file name, replacing path separators and
+/// characters which aren't allowed on Windows with `-`.
+#[cfg(feature = "std")]
+pub(crate) fn slugify(label: &str) -> String {
+    label
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '-',
+            c if c.is_control() => '-',
+            c => c,
+        })
+        .collect()
+}
+
+/// Gets the path of a dump saved without a [Database::save_path], which is
+/// the [slugified](slugify) label in the working directory.
+#[cfg(feature = "std")]
+pub(crate) fn label_path(label: &str) -> PathBuf {
+    PathBuf::from(format!("{}.tinydb", slugify(label)))
+}
+
+/// Makes sure a label can be used for [label_path], failing if it's empty or
+/// would make a file name reserved on Windows.
+#[cfg(feature = "std")]
+pub(crate) fn check_label(label: &str) -> Result<(), error::DatabaseError> {
+    let slug = slugify(label);
+    let stem = slug.split('.').next().unwrap_or("").trim_end();
+
+    if slug.trim().is_empty()
+        || RESERVED_NAMES
+            .iter()
+            .any(|reserved| reserved.eq_ignore_ascii_case(stem))
+    {
+        return Err(error::DatabaseError::InvalidLabel(label.to_string()));
+    }
+
+    Ok(())
+}
+
+/// Counter making each temporary file written by [write_atomic] unique, so
+/// dumps of the same path from several threads never share one.
+#[cfg(feature = "std")]
+static TEMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Writes `bytes` to a temporary file next to `path` before renaming it into
+/// place, so readers and other writers only ever see a whole file. If several
+/// dumps of the same path happen at once, the last one to finish wins.
+#[cfg(feature = "std")]
+pub(crate) fn write_atomic(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let temp_path = path.with_file_name(format!(
+        ".{}.{}-{}.tmp",
+        name,
+        process::id(),
+        TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    let written = File::create(&temp_path)
+        .and_then(|mut file| {
+            file.write_all(bytes)?;
+            file.sync_all()
+        })
+        .and_then(|_| fs::rename(&temp_path, path));
+
+    if written.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+
+    written
+}
+
+/// Writes [Database::save_path] into a dump as always being empty, as paths
+/// are rarely valid once a dump is moved to another directory or machine.
+/// The slot is kept so dumps still have the same layout.
+pub(crate) fn serialize_save_path<S: Serializer>(
+    _save_path: &Option<PathBuf>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    None::<PathBuf>.serialize(serializer)
+}
+
+/// Reads the [Database::save_path] slot of a dump and ignores it, as dumps
+/// made before paths stopped being saved may hold one from another machine.
+/// Loading from a path sets it to that path instead.
+pub(crate) fn deserialize_save_path<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<PathBuf>, D::Error> {
+    Option::<PathBuf>::deserialize(deserializer).map(|_| None)
+}
+
+#[cfg(feature = "std")]
+impl<T: hash::Hash + Eq> Database<T> {
+    /// Sets where [Database::dump_db] saves to, such as after loading a dump
+    /// with [Database::from_bytes] which doesn't know where it came from.
+    /// Setting this to [None] saves to a path made from [Database::label].
+    ///
+    /// Unlike setting [Database::save_path] directly, this releases the lock
+    /// on the dump this database was opened from if the path changes.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tinydb::Database;
+    /// use std::path::PathBuf;
+    ///
+    /// fn main() {
+    ///     let mut db = Database::new("set_save_path_test", None, false);
+    ///
+    ///     db.add_item(String::from("Holly")).unwrap();
+    ///
+    ///     let mut loaded: Database<String> = Database::from_bytes(&db.to_bytes().unwrap()).unwrap();
+    ///
+    ///     assert_eq!(loaded.save_path, None);
+    ///
+    ///     loaded.set_save_path(PathBuf::from("set_save_path_test.tinydb"));
+    ///
+    ///     assert_eq!(loaded.save_path, Some(PathBuf::from("set_save_path_test.tinydb")));
+    /// }
+    /// ```
+    pub fn set_save_path(&mut self, save_path: impl Into<Option<PathBuf>>) {
+        let save_path = save_path.into();
+
+        if save_path != self.save_path {
+            self.lock = lock::HeldLock::default();
+        }
+
+        self.save_path = save_path;
+    }
+
+    /// Sets if [Database::dump_db] should create the directories
+    /// [Database::save_path] is inside of when they don't exist yet, which is
+    /// on by default. This setting is not saved when dumping.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tinydb::Database;
+    /// use std::path::PathBuf;
+    ///
+    /// fn main() {
+    ///     let path = PathBuf::from("create_dirs_test/nested.tinydb");
+    ///     let mut db: Database<u8> = Database::new("nested", path, false);
+    ///
+    ///     db.dump_db().unwrap();
+    ///     std::fs::remove_dir_all("create_dirs_test").unwrap();
+    ///
+    ///     db.set_create_dirs(false);
+    ///
+    ///     assert!(db.dump_db().is_err());
+    /// }
+    /// ```
+    pub fn set_create_dirs(&mut self, create: bool) {
+        self.create_dirs = create;
+    }
+
+    /// Gets the path to dump to, checking the label is usable if the path is
+    /// made from it and that the directory it's inside of exists.
+    pub(crate) fn dump_path(&self) -> Result<PathBuf, error::DatabaseError> {
+        if self.save_path.is_none() {
+            check_label(&self.label)?;
+        }
+
+        let path = self.smart_path_get();
+
+        match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() && !parent.is_dir() => {
+                self.create_parent(parent)?
+            }
+            _ => (),
+        }
+
+        Ok(path)
+    }
+
+    /// Creates the directory a dump goes into if [Database::set_create_dirs]
+    /// is enabled, otherwise failing as it doesn't exist.
+    fn create_parent(&self, parent: &Path) -> Result<(), error::DatabaseError> {
+        if !self.create_dirs {
+            return Err(error::DatabaseError::MissingDirectory(parent.to_path_buf()));
+        }
+
+        std::fs::create_dir_all(parent).map_err(|source| error::DatabaseError::CreateDirFailed {
+            path: parent.to_path_buf(),
+            source,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests labels being turned into safe file names
+    #[test]
+    fn labels_sandboxed() {
+        assert_eq!(
+            label_path("../etc/passwd"),
+            PathBuf::from("..-etc-passwd.tinydb")
+        );
+        assert_eq!(label_path("C:\\Users"), PathBuf::from("C--Users.tinydb"));
+        assert_eq!(
+            label_path("Hello There"),
+            PathBuf::from("Hello There.tinydb")
+        );
+
+        assert!(check_label("Hello There").is_ok());
+        assert!(check_label("..").is_ok());
+        assert!(check_label("").is_err());
+        assert!(check_label("con").is_err());
+        assert!(check_label("Lpt1.backup").is_err());
+        assert!(check_label("console").is_ok());
+    }
+
+    /// Tests save paths not being saved, with loaded databases saving back to
+    /// where they came from even if old dumps hold another path
+    #[test]
+    fn save_path_portable() -> Result<(), error::DatabaseError> {
+        let path = PathBuf::from("save_path_portable.tinydb");
+        let moved = PathBuf::from("save_path_moved.tinydb");
+        let db = Database::<u8>::new("Portable test", path.clone(), false);
+
+        db.dump_db()?;
+        std::fs::rename(&path, &moved)?;
+
+        assert_eq!(Database::<u8>::from_bytes(&db.to_bytes()?)?.save_path, None);
+        assert_eq!(
+            Database::<u8>::from(moved.clone())?.save_path,
+            Some(moved.clone())
+        );
+
+        let mut legacy = bincode::serialize("Legacy test").unwrap();
+
+        legacy.extend(bincode::serialize(&Some(PathBuf::from("/home/kryten/old.tinydb"))).unwrap());
+        legacy.extend(bincode::serialize(&false).unwrap());
+        legacy.extend(bincode::serialize(&vec![1u8]).unwrap());
+        std::fs::write(&moved, legacy)?;
+
+        assert_eq!(
+            Database::<u8>::from(moved.clone())?.save_path,
+            Some(moved.clone())
+        );
+
+        std::fs::remove_file(moved)?;
+        Ok(())
+    }
+
+    /// Tests missing directories being created unless disabled, and failing
+    /// to create them
+    #[test]
+    fn missing_dirs() -> Result<(), error::DatabaseError> {
+        let mut db = Database::new("Dirs test", PathBuf::from("missing_dirs/a/b.tinydb"), false);
+
+        db.add_item(1u8)?;
+        db.set_create_dirs(false);
+
+        assert!(matches!(
+            db.dump_db().unwrap_err().root(),
+            error::DatabaseError::MissingDirectory(_)
+        ));
+
+        db.set_create_dirs(true);
+        db.dump_db()?;
+
+        assert!(Path::new("missing_dirs/a/b.tinydb").exists());
+
+        let blocked = Database::<u8>::new(
+            "",
+            PathBuf::from("missing_dirs/a/b.tinydb/c/d.tinydb"),
+            false,
+        );
+
+        assert!(matches!(
+            blocked.dump_db().unwrap_err().root(),
+            error::DatabaseError::CreateDirFailed { .. }
+        ));
+        assert!(matches!(
+            Database::<u8>::new("", None, false)
+                .dump_db()
+                .unwrap_err()
+                .root(),
+            error::DatabaseError::InvalidLabel(_)
+        ));
+
+        drop(db);
+        std::fs::remove_dir_all("missing_dirs")?;
+        Ok(())
+    }
+}
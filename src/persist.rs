@@ -0,0 +1,232 @@
+//! Contains [PersistTarget], which lets dumps be saved somewhere other than
+//! the filesystem, such as browser storage when compiled to WebAssembly.
+//!
+//! Dumps saved to a target are byte-for-byte the same as those written by
+//! [crate::Database::dump_db], so they can be moved between targets and files
+//! freely.
+
+use crate::{error, Database};
+use serde::{de::DeserializeOwned, Serialize};
+use std::collections::HashMap;
+use std::hash;
+use std::path::PathBuf;
+
+/// Somewhere dumps can be saved to and loaded from by key, used with
+/// [Database::dump_to_target] and [Database::from_target].
+pub trait PersistTarget {
+    /// Saves a complete dump under `key`, replacing anything already saved
+    /// there.
+    fn save(&mut self, key: &str, dump: &[u8]) -> Result<(), error::DatabaseError>;
+
+    /// Loads the dump saved under `key`, or [Option::None] if nothing has
+    /// been saved there.
+    fn load(&mut self, key: &str) -> Result<Option<Vec<u8>>, error::DatabaseError>;
+}
+
+/// A [PersistTarget] which keeps dumps in memory, useful for tests or for
+/// handing dumps to another part of an app.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MemoryTarget {
+    /// Dumps which have been saved, by key.
+    pub dumps: HashMap<String, Vec<u8>>,
+}
+
+impl PersistTarget for MemoryTarget {
+    fn save(&mut self, key: &str, dump: &[u8]) -> Result<(), error::DatabaseError> {
+        self.dumps.insert(key.to_string(), dump.to_vec());
+        Ok(())
+    }
+
+    fn load(&mut self, key: &str) -> Result<Option<Vec<u8>>, error::DatabaseError> {
+        Ok(self.dumps.get(key).cloned())
+    }
+}
+
+/// A [PersistTarget] saving dumps into the browser's `localStorage`, enabled
+/// with the `wasm` feature.
+///
+/// As `localStorage` can only hold text, dumps are base64-encoded and saved
+/// under their key prefixed with `tinydb:`. Browsers typically limit storage
+/// to around 5MB per site. This only works when compiled to WebAssembly and
+/// ran inside of a browser, otherwise every call returns
+/// [error::DatabaseError::IOError].
+#[cfg(feature = "wasm")]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LocalStorage;
+
+#[cfg(feature = "wasm")]
+impl LocalStorage {
+    /// Gets the `localStorage` of the current window.
+    fn storage() -> Result<web_sys::Storage, error::DatabaseError> {
+        web_sys::window()
+            .and_then(|window| window.local_storage().ok().flatten())
+            .ok_or_else(|| storage_error("localStorage isn't available"))
+    }
+}
+
+#[cfg(feature = "wasm")]
+impl PersistTarget for LocalStorage {
+    fn save(&mut self, key: &str, dump: &[u8]) -> Result<(), error::DatabaseError> {
+        LocalStorage::storage()?
+            .set_item(&format!("tinydb:{}", key), &base64::encode(dump))
+            .map_err(|_| storage_error("couldn't write to localStorage, it may be full"))
+    }
+
+    fn load(&mut self, key: &str) -> Result<Option<Vec<u8>>, error::DatabaseError> {
+        let encoded = LocalStorage::storage()?
+            .get_item(&format!("tinydb:{}", key))
+            .map_err(|_| storage_error("couldn't read from localStorage"))?;
+
+        match encoded {
+            Some(encoded) => base64::decode(&encoded)
+                .map(Some)
+                .ok_or_else(|| storage_error("saved dump isn't valid base64")),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Makes an [error::DatabaseError::IOError] for browser storage failures.
+#[cfg(feature = "wasm")]
+fn storage_error(message: &str) -> error::DatabaseError {
+    error::DatabaseError::IOError(std::io::Error::other(message))
+}
+
+/// Minimal standard base64, as browser storage only holds text.
+#[cfg(feature = "wasm")]
+mod base64 {
+    /// Characters used for each 6-bit group.
+    const ALPHABET: &[u8; 64] =
+        b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    /// Encodes bytes into padded base64.
+    pub(super) fn encode(bytes: &[u8]) -> String {
+        let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+
+        for chunk in bytes.chunks(3) {
+            let group = chunk
+                .iter()
+                .enumerate()
+                .fold(0u32, |group, (ind, byte)| group | (*byte as u32) << (16 - ind * 8));
+
+            for ind in 0..4 {
+                if ind <= chunk.len() {
+                    encoded.push(ALPHABET[(group >> (18 - ind * 6)) as usize & 63] as char);
+                } else {
+                    encoded.push('=');
+                }
+            }
+        }
+
+        encoded
+    }
+
+    /// Decodes padded base64, returning [Option::None] if it's invalid.
+    pub(super) fn decode(encoded: &str) -> Option<Vec<u8>> {
+        let encoded = encoded.trim_end_matches('=').as_bytes();
+        let mut bytes = Vec::with_capacity(encoded.len() * 3 / 4);
+
+        for chunk in encoded.chunks(4) {
+            if chunk.len() == 1 {
+                return None;
+            }
+
+            let mut group = 0u32;
+
+            for (ind, char) in chunk.iter().enumerate() {
+                let value = ALPHABET.iter().position(|other| other == char)? as u32;
+
+                group |= value << (18 - ind * 6);
+            }
+
+            for ind in 0..chunk.len() - 1 {
+                bytes.push((group >> (16 - ind * 8)) as u8);
+            }
+        }
+
+        Some(bytes)
+    }
+}
+
+impl<T: hash::Hash + Eq + Serialize> Database<T> {
+    /// Dumps this database to a [PersistTarget] rather than a file, saving it
+    /// under [Database::label].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tinydb::Database;
+    /// use tinydb::persist::MemoryTarget;
+    ///
+    /// fn main() {
+    ///     let mut target = MemoryTarget::default();
+    ///     let mut db = Database::new("settings", None, false);
+    ///
+    ///     db.add_item(String::from("dark mode")).unwrap();
+    ///     db.dump_to_target(&mut target).unwrap();
+    ///
+    ///     let loaded: Database<String> = Database::from_target(&mut target, "settings").unwrap();
+    ///
+    ///     assert!(loaded.contains(&String::from("dark mode")));
+    /// }
+    /// ```
+    pub fn dump_to_target(&self, target: &mut impl PersistTarget) -> Result<(), error::DatabaseError> {
+        target.save(&self.label, &self.to_bytes()?)
+    }
+}
+
+impl<T: hash::Hash + Eq + DeserializeOwned> Database<T> {
+    /// Loads a database saved to a [PersistTarget] under `key`.
+    ///
+    /// # Errors
+    ///
+    /// Will return [error::DatabaseError::DatabaseNotFound] if nothing has
+    /// been saved under `key`, otherwise fails in the same ways as
+    /// [Database::from].
+    pub fn from_target(
+        target: &mut impl PersistTarget,
+        key: &str,
+    ) -> Result<Self, error::DatabaseError> {
+        let stream = target
+            .load(key)?
+            .ok_or_else(|| error::DatabaseError::DatabaseNotFound(PathBuf::from(key)))?;
+
+        Database::from_bytes(&stream)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests targets being keyed by label and missing keys erroring
+    #[test]
+    fn memory_target() -> Result<(), error::DatabaseError> {
+        let mut target = MemoryTarget::default();
+        let mut db = Database::new("first", None, false);
+
+        db.add_item(1)?;
+        db.dump_to_target(&mut target)?;
+
+        assert!(target.dumps.contains_key("first"));
+        assert!(Database::<i32>::from_target(&mut target, "second").is_err());
+
+        let loaded: Database<i32> = Database::from_target(&mut target, "first")?;
+
+        assert!(loaded.contains(&1));
+        Ok(())
+    }
+
+    /// Tests base64 round trips for every padding length
+    #[cfg(feature = "wasm")]
+    #[test]
+    fn base64_round_trip() {
+        assert_eq!(base64::encode(b"tiny"), "dGlueQ==");
+
+        for len in 0..8 {
+            let bytes: Vec<u8> = (0..len).map(|n: u32| (n * 37 + 200) as u8).collect();
+
+            assert_eq!(base64::decode(&base64::encode(&bytes)), Some(bytes));
+        }
+    }
+}
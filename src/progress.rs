@@ -0,0 +1,289 @@
+//! Contains progress reporting whilst dumping or loading large databases, see
+//! [crate::Database::dump_db_with_progress].
+
+use crate::{error, lock, metrics, Database};
+use bincode::Options;
+use serde::{de::DeserializeOwned, Serialize};
+use std::hash;
+use std::io::{self, Read, Write};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Amount of bytes between each report, so callbacks aren't called for every
+/// single field.
+const REPORT_EVERY: u64 = 64 * 1024;
+
+/// Optional callback given how many bytes have been processed out of a total,
+/// returning `false` to cancel.
+pub(crate) type Progress<'a> = Option<&'a mut dyn FnMut(u64, u64) -> bool>;
+
+/// Counts bytes passing through a reader or writer, reporting them every
+/// [REPORT_EVERY] bytes.
+struct Counter<'a, I> {
+    inner: I,
+    done: u64,
+    total: u64,
+    progress: &'a mut dyn FnMut(u64, u64) -> bool,
+    cancelled: bool,
+}
+
+impl<'a, I> Counter<'a, I> {
+    fn new(inner: I, total: u64, progress: &'a mut dyn FnMut(u64, u64) -> bool) -> Self {
+        let cancelled = !progress(0, total);
+
+        Counter {
+            inner,
+            done: 0,
+            total,
+            progress,
+            cancelled,
+        }
+    }
+
+    /// Counts newly processed bytes, reporting if another step was crossed
+    /// and failing once cancelled.
+    fn advance(&mut self, amount: usize) -> io::Result<()> {
+        let before = self.done / REPORT_EVERY;
+
+        self.done += amount as u64;
+
+        if !self.cancelled && self.done / REPORT_EVERY != before {
+            self.cancelled = !(self.progress)(self.done.min(self.total), self.total);
+        }
+
+        match self.cancelled {
+            true => Err(io::Error::other("cancelled")),
+            false => Ok(()),
+        }
+    }
+
+    /// Turns an error from encoding or decoding into
+    /// [error::DatabaseError::Cancelled] if it was caused by cancelling.
+    fn check(&self, e: bincode::Error) -> error::DatabaseError {
+        match self.cancelled {
+            true => error::DatabaseError::Cancelled,
+            false => e.into(),
+        }
+    }
+
+    /// Reports the final total, as the last step is usually only partly
+    /// filled.
+    fn finish(self) {
+        (self.progress)(self.total, self.total);
+    }
+}
+
+impl<I: Read> Read for Counter<'_, I> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let amount = self.inner.read(buf)?;
+
+        self.advance(amount)?;
+        Ok(amount)
+    }
+}
+
+impl<I: Write> Write for Counter<'_, I> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let amount = self.inner.write(buf)?;
+
+        self.advance(amount)?;
+        Ok(amount)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Encodes a value with the given bincode `options`, reporting progress if
+/// asked to which takes an extra pass to work out the total size.
+pub(crate) fn serialize<V: Serialize>(
+    value: &V,
+    options: impl Options + Copy,
+    progress: Progress<'_>,
+) -> Result<Vec<u8>, error::DatabaseError> {
+    let progress = match progress {
+        Some(progress) => progress,
+        None => return Ok(options.serialize(value)?),
+    };
+
+    let total = options.serialized_size(value)?;
+    let mut counter = Counter::new(Vec::with_capacity(total as usize), total, progress);
+
+    options
+        .serialize_into(&mut counter, value)
+        .map_err(|e| counter.check(e))?;
+
+    let encoded = std::mem::take(&mut counter.inner);
+
+    counter.finish();
+    Ok(encoded)
+}
+
+/// Decodes a value with the given bincode `options`, reporting progress if
+/// asked to.
+pub(crate) fn deserialize<V: DeserializeOwned>(
+    bytes: &[u8],
+    options: impl Options + Copy,
+    progress: Progress<'_>,
+) -> Result<V, error::DatabaseError> {
+    let progress = match progress {
+        Some(progress) => progress,
+        None => return Ok(options.deserialize(bytes)?),
+    };
+
+    let mut counter = Counter::new(bytes, bytes.len() as u64, progress);
+    let decoded = options
+        .deserialize_from(&mut counter)
+        .map_err(|e| counter.check(e))?;
+
+    counter.finish();
+    Ok(decoded)
+}
+
+impl<T: hash::Hash + Eq + Serialize> Database<T> {
+    /// Dumps this database like [Database::dump_db], calling `progress` with
+    /// how many bytes have been encoded out of the total as it goes. This is
+    /// useful for showing a progress bar whilst saving a large database
+    /// rather than freezing silently.
+    ///
+    /// Progress is reported at the start, roughly every 64KiB and once
+    /// everything has been encoded. Working out the total takes an extra pass
+    /// over all items, so this is a little slower than [Database::dump_db].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tinydb::Database;
+    ///
+    /// fn main() {
+    ///     let mut db = Database::new("progress_test", None, false);
+    ///
+    ///     for id in 0..100_000u32 {
+    ///         db.add_item(id).unwrap();
+    ///     }
+    ///
+    ///     db.dump_db_with_progress(|done, total| {
+    ///         println!("Saving.. {}%", done * 100 / total);
+    ///     })
+    ///     .unwrap();
+    ///
+    ///     let loaded: Database<u32> =
+    ///         Database::from_with_progress("progress_test.tinydb", |done, total| {
+    ///             println!("Loading.. {}%", done * 100 / total);
+    ///         })
+    ///         .unwrap();
+    ///
+    ///     assert_eq!(loaded.items.len(), 100_000);
+    ///     # std::fs::remove_file("progress_test.tinydb").unwrap();
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Fails in the same ways as [Database::dump_db].
+    pub fn dump_db_with_progress(
+        &self,
+        mut progress: impl FnMut(u64, u64),
+    ) -> Result<metrics::DumpReport, error::DatabaseError> {
+        self.write_dump(
+            None,
+            Some(&mut |done, total| {
+                progress(done, total);
+                true
+            }),
+        )
+    }
+}
+
+impl<T: hash::Hash + Eq + DeserializeOwned> Database<T> {
+    /// Creates a database from a `.tinydb` file like [Database::from], calling
+    /// `progress` with how many bytes have been decoded out of the total as
+    /// it goes. See [Database::dump_db_with_progress] for an example.
+    ///
+    /// # Errors
+    ///
+    /// Fails in the same ways as [Database::from].
+    pub fn from_with_progress(
+        path: impl Into<PathBuf>,
+        mut progress: impl FnMut(u64, u64),
+    ) -> Result<Self, error::DatabaseError> {
+        let path = path.into();
+        let lock = lock::FileLock::acquire(&path)?;
+        let mut decoded = Database::load(
+            path,
+            Some(&mut |done, total| {
+                progress(done, total);
+                true
+            }),
+        )?;
+
+        decoded.lock = lock::HeldLock(Some(Arc::new(lock)));
+        Ok(decoded)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Options matching [bincode::serialize], which dumps use by default.
+    fn legacy() -> impl Options + Copy {
+        bincode::DefaultOptions::new()
+            .with_fixint_encoding()
+            .allow_trailing_bytes()
+    }
+
+    /// Tests progress climbing to the total whilst encoding and decoding
+    #[test]
+    fn progress_reported() -> Result<(), error::DatabaseError> {
+        let items: Vec<u64> = (0..50_000).collect();
+        let mut reports = Vec::new();
+        let encoded = serialize(
+            &items,
+            legacy(),
+            Some(&mut |done, total| {
+                reports.push((done, total));
+                true
+            }),
+        )?;
+        let total = encoded.len() as u64;
+
+        assert_eq!(reports.first(), Some(&(0, total)));
+        assert_eq!(reports.last(), Some(&(total, total)));
+        assert!(reports.len() > 3);
+        assert!(reports.windows(2).all(|pair| pair[0].0 <= pair[1].0));
+
+        let mut last = (0, 0);
+        let decoded: Vec<u64> = deserialize(
+            &encoded,
+            legacy(),
+            Some(&mut |done, total| {
+                last = (done, total);
+                true
+            }),
+        )?;
+
+        assert_eq!(decoded, items);
+        assert_eq!(last, (total, total));
+        Ok(())
+    }
+
+    /// Tests returning `false` from a callback cancelling partway through
+    #[test]
+    fn progress_cancelled() {
+        let items: Vec<u64> = (0..50_000).collect();
+        let mut reports = 0;
+        let result = serialize(
+            &items,
+            legacy(),
+            Some(&mut |_, _| {
+                reports += 1;
+                reports < 3
+            }),
+        );
+
+        assert_eq!(result, Err(error::DatabaseError::Cancelled));
+        assert_eq!(reports, 3);
+    }
+}
@@ -0,0 +1,160 @@
+//! Contains the [Record] trait for items identified by a single key field,
+//! which can be derived using `#[derive(TinyRecord)]` with the `derive`
+//! feature enabled.
+//!
+//! Fields marked with `#[tinydb(query)]` also get typed `find_by_<field>`
+//! methods on the database, which are checked by field name at compile time
+//! rather than relying on closures.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # #[cfg(feature = "derive")]
+//! # mod example {
+//! use serde::{Deserialize, Serialize};
+//! use tinydb::{record::Record, Database, TinyRecord};
+//!
+//! #[derive(Debug, Clone, Serialize, Deserialize, TinyRecord)]
+//! struct Crew {
+//!     #[tinydb(key)]
+//!     id: u32,
+//!     #[tinydb(query)]
+//!     name: String,
+//!     #[tinydb(query)]
+//!     age: u32,
+//! }
+//!
+//! # pub fn main() {
+//! let mut db = Database::new("crew", None, false);
+//!
+//! db.add_item(Crew { id: 1, name: String::from("Lister"), age: 25 }).unwrap();
+//! db.add_item(Crew { id: 2, name: String::from("Cat"), age: 25 }).unwrap();
+//!
+//! assert_eq!(Crew::FIELDS, &["id", "name", "age"]);
+//! assert_eq!(db.query_item(Crew::by_name, String::from("Lister")).unwrap().id, 1);
+//! assert_eq!(db.get_by_key(&1).unwrap().name, "Lister");
+//!
+//! // these come from the generated `CrewQueries` trait, which has to be in
+//! // scope like any other trait to be used
+//! assert_eq!(db.find_by_name("Cat")[0].id, 2);
+//! assert_eq!(db.find_by_age(25).len(), 2);
+//! # }
+//! # }
+//! # fn main() {
+//! #     #[cfg(feature = "derive")]
+//! #     example::main();
+//! # }
+//! ```
+
+use crate::Database;
+use core::hash;
+use serde::{de::DeserializeOwned, Serialize};
+
+/// An item which is identified by a single key field rather than all of its
+/// fields, so two items with the same key are considered duplicates.
+///
+/// Implementations should make sure [PartialEq] and [hash::Hash] only look at
+/// [Record::key], which the derive does automatically.
+pub trait Record {
+    /// Type of the key field.
+    type Key: hash::Hash + Eq;
+
+    /// Names of all fields in the order they were declared.
+    const FIELDS: &'static [&'static str];
+
+    /// Gets the key which identifies this item.
+    fn key(&self) -> &Self::Key;
+}
+
+impl<T: hash::Hash + Eq + Serialize + DeserializeOwned + Record> Database<T> {
+    /// Gets the item with the given key, without needing to build an entire
+    /// item to pass to [Database::contains].
+    pub fn get_by_key(&self, key: &T::Key) -> Option<&T> {
+        self.items
+            .iter()
+            .find(|item| item.key() == key && !self.is_expired(item))
+    }
+}
+
+#[cfg(all(test, feature = "derive"))]
+mod tests {
+    use super::*;
+    use crate::TinyRecord;
+    use serde::Deserialize;
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
+    /// Record with a generic field
+    #[derive(Debug, Clone, Serialize, Deserialize, TinyRecord)]
+    struct Tagged<V> {
+        #[tinydb(key, query)]
+        name: String,
+        #[tinydb(query)]
+        value: V,
+    }
+
+    /// Hashes an item using the default hasher
+    fn hash_of(item: &impl Hash) -> u64 {
+        let mut hasher = DefaultHasher::new();
+
+        item.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Tests derived equality and hashing only using the key
+    #[test]
+    fn record_derive_key() {
+        let first = Tagged {
+            name: String::from("a"),
+            value: 1,
+        };
+        let second = Tagged {
+            name: String::from("a"),
+            value: 2,
+        };
+
+        assert_eq!(first, second);
+        assert_eq!(hash_of(&first), hash_of(&second));
+        assert_eq!(first.key(), "a");
+        assert_eq!(Tagged::<i32>::FIELDS, &["name", "value"]);
+    }
+
+    /// Tests generated extractors being usable in queries
+    #[test]
+    fn record_derive_extractors() {
+        let mut db = Database::new("Record test", None, true);
+
+        db.add_item(Tagged {
+            name: String::from("a"),
+            value: 5u8,
+        })
+        .unwrap();
+
+        assert!(db
+            .add_item(Tagged {
+                name: String::from("a"),
+                value: 6,
+            })
+            .is_err());
+        assert_eq!(db.query_item(Tagged::by_value, 5).unwrap().name, "a");
+        assert_eq!(db.get_by_key(&String::from("a")).unwrap().value, 5);
+    }
+
+    /// Tests generated typed queries
+    #[test]
+    fn record_derive_queries() {
+        let mut db = Database::new("Record test", None, false);
+
+        for (name, value) in &[("a", 1), ("b", 1), ("c", 2)] {
+            db.add_item(Tagged {
+                name: name.to_string(),
+                value: *value,
+            })
+            .unwrap();
+        }
+
+        assert_eq!(db.find_by_value(1).len(), 2);
+        assert_eq!(db.find_by_name("c")[0].value, 2);
+        assert!(db.find_by_name("d").is_empty());
+    }
+}
@@ -0,0 +1,175 @@
+//! Contains salvaging items out of damaged dumps, see
+//! [crate::Database::recover].
+
+use crate::{encoding, error, format, lazy, Database};
+use serde::de::DeserializeOwned;
+use std::fs;
+use std::hash;
+use std::path::PathBuf;
+
+/// Result of [Database::recover], holding every item which could still be
+/// decoded alongside how many couldn't.
+#[derive(Debug)]
+pub struct RecoveryReport<T: hash::Hash + Eq> {
+    /// Database holding all salvaged items. If anything was lost, only its
+    /// label, save path and [Database::strict_dupes] are kept, meaning pins,
+    /// expiries and other tracked state are reset.
+    pub db: Database<T>,
+
+    /// Amount of items which were salvaged.
+    pub salvaged: usize,
+
+    /// Amount of items the dump said it held which couldn't be decoded.
+    pub lost: usize,
+}
+
+impl<T: hash::Hash + Eq> RecoveryReport<T> {
+    /// Checks if nothing was lost, meaning the dump wasn't damaged at all.
+    pub fn is_complete(&self) -> bool {
+        self.lost == 0
+    }
+}
+
+impl<T: hash::Hash + Eq + DeserializeOwned> Database<T> {
+    /// Salvages as much as possible out of a damaged `.tinydb` file, rather
+    /// than failing outright like [Database::from] does.
+    ///
+    /// Items are encoded one after another without any framing, so every item
+    /// before the first corrupted one is salvaged and everything after it is
+    /// lost. Checksums and signatures are ignored, and intact dumps are
+    /// loaded in full. Segmented dumps can be recovered far more completely
+    /// with [Database::recover_segmented], as only damaged segments are lost.
+    ///
+    /// The dump isn't locked or changed, so the recovered database should be
+    /// dumped somewhere else before the damaged dump is replaced.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tinydb::Database;
+    ///
+    /// fn main() {
+    ///     let mut db = Database::new("recover_test", None, false);
+    ///
+    ///     db.add_item(String::from("Queeg")).unwrap();
+    ///     db.dump_db().unwrap();
+    ///
+    ///     let report = Database::<String>::recover("recover_test.tinydb").unwrap();
+    ///
+    ///     assert!(report.is_complete());
+    ///     assert_eq!(report.salvaged, 1);
+    ///     # std::fs::remove_file("recover_test.tinydb").unwrap();
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Will return [error::DatabaseError::DatabaseNotFound] if there is no
+    /// dump, [error::DatabaseError::UnrecognizedFormat] if it isn't a tinydb
+    /// dump at all or an [error::DatabaseError::BincodeError] if even the
+    /// label at the start of it is damaged.
+    pub fn recover(path: impl Into<PathBuf>) -> Result<RecoveryReport<T>, error::DatabaseError> {
+        let path = path.into();
+
+        if !path.exists() {
+            return Err(error::DatabaseError::DatabaseNotFound(path));
+        }
+
+        let stream = fs::read(&path)?;
+
+        match format::read_header(&stream) {
+            Err(e @ error::DatabaseError::UnrecognizedFormat { .. }) => return Err(e),
+            Err(_) => (),
+            Ok(payload) => {
+                if let Ok(mut db) = Database::decode(payload, None) {
+                    db.loaded_from(path);
+
+                    return Ok(RecoveryReport {
+                        salvaged: db.items.len(),
+                        lost: 0,
+                        db,
+                    });
+                }
+            }
+        }
+
+        let mut report =
+            salvage(&stream).map_err(|e| e.with_context("recover", Some(path.clone()), None))?;
+
+        report.db.loaded_from(path);
+        Ok(report)
+    }
+}
+
+/// Decodes items one by one from the start of a dump until one fails.
+fn salvage<T: hash::Hash + Eq + DeserializeOwned>(
+    stream: &[u8],
+) -> Result<RecoveryReport<T>, error::DatabaseError> {
+    let (mut rest, encoding) = match stream.starts_with(format::MAGIC) {
+        true => {
+            let flags = format::read_flags(stream).unwrap_or(0);
+
+            (
+                stream
+                    .get(format::payload_offset(flags)..)
+                    .unwrap_or_default(),
+                encoding::Encoding::from_flags(flags),
+            )
+        }
+        false => (stream, encoding::Encoding::default()),
+    };
+    let head: lazy::Head = encoding.deserialize_from(&mut rest)?;
+    let mut db = Database::new(head.label, None, head.strict_dupes);
+    let mut salvaged = 0;
+
+    while (salvaged as u64) < head.items_len {
+        match encoding.deserialize_from(&mut rest) {
+            Ok(item) => {
+                db.items.insert(item);
+                salvaged += 1;
+            }
+            Err(_) => break,
+        }
+    }
+
+    Ok(RecoveryReport {
+        db,
+        salvaged,
+        lost: (head.items_len - salvaged as u64) as usize,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests items before a truncation being salvaged
+    #[test]
+    fn recover_truncated() -> Result<(), error::DatabaseError> {
+        let path = PathBuf::from("recover_truncated.tinydb");
+        let mut db = Database::new("Recover test", path.clone(), false);
+
+        for id in 0..100u64 {
+            db.add_item(id)?;
+        }
+
+        let stream = db.to_bytes()?;
+
+        fs::write(
+            &path,
+            &stream[..format::HEADER_LEN + format::INFO_LEN + 60 + 8 * 40],
+        )?;
+
+        let report: RecoveryReport<u64> = Database::recover(path.clone())?;
+
+        assert!(Database::<u64>::from_unlocked(path.clone()).is_err());
+        assert!(!report.is_complete());
+        assert_eq!(report.db.label, "Recover test");
+        assert_eq!(report.salvaged + report.lost, 100);
+        assert_eq!(report.db.items.len(), report.salvaged);
+        assert!(report.salvaged > 30);
+
+        fs::remove_file(path)?;
+        Ok(())
+    }
+}
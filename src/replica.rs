@@ -0,0 +1,188 @@
+//! Contains [Replica], a follower copy of a [crate::Database] which is kept
+//! eventually consistent by applying the [ChangeEvent]s of its primary, see
+//! [crate::Database::replicate].
+
+use crate::events::ChangeEvent;
+use crate::lock::HeldLock;
+use crate::Database;
+use std::hash;
+use std::sync::mpsc;
+
+/// A read-only follower of another database, which is ideal for read-heavy
+/// multi-threaded apps as each thread can own a replica and read from it
+/// without locking the primary.
+///
+/// Replicas only change when [Replica::sync] is called, so they can lag
+/// behind their primary until then. Once the primary is dropped, syncing
+/// applies whatever is left and then reports the replica as
+/// [disconnected](Replica::is_disconnected).
+#[derive(Debug)]
+pub struct Replica<T: hash::Hash + Eq> {
+    db: Database<T>,
+    events: mpsc::Receiver<ChangeEvent<T>>,
+    disconnected: bool,
+}
+
+impl<T: hash::Hash + Eq> Replica<T> {
+    /// Creates a replica from a `snapshot` of the primary alongside every
+    /// change made to it after the snapshot was taken.
+    ///
+    /// [Database::replicate] does this for replicas in the same process,
+    /// whereas this allows events to arrive from anywhere else, such as a
+    /// socket, by forwarding them into a channel.
+    pub fn new(snapshot: Database<T>, events: mpsc::Receiver<ChangeEvent<T>>) -> Self {
+        Replica {
+            db: snapshot,
+            events,
+            disconnected: false,
+        }
+    }
+
+    /// Applies every change the primary has made since the last sync without
+    /// blocking, returning how many were applied.
+    pub fn sync(&mut self) -> usize {
+        let mut applied = 0;
+
+        loop {
+            match self.events.try_recv() {
+                Ok(event) => {
+                    self.apply(event);
+                    applied += 1;
+                }
+                Err(mpsc::TryRecvError::Empty) => break,
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    self.disconnected = true;
+                    break;
+                }
+            }
+        }
+
+        applied
+    }
+
+    /// Blocks until the primary makes another change, applying it alongside
+    /// any others which are waiting. Returns `false` without blocking if the
+    /// primary has been dropped.
+    pub fn wait(&mut self) -> bool {
+        if self.disconnected {
+            return false;
+        }
+
+        match self.events.recv() {
+            Ok(event) => {
+                self.apply(event);
+                self.sync();
+                true
+            }
+            Err(_) => {
+                self.disconnected = true;
+                false
+            }
+        }
+    }
+
+    /// Checks if the primary has been dropped, meaning this replica won't
+    /// change anymore.
+    pub fn is_disconnected(&self) -> bool {
+        self.disconnected
+    }
+
+    /// Gets the replicated database to read from, as of the last sync.
+    pub fn db(&self) -> &Database<T> {
+        &self.db
+    }
+
+    /// Stops following the primary, keeping the replicated database.
+    pub fn into_inner(self) -> Database<T> {
+        self.db
+    }
+
+    /// Applies a single change to the replicated database.
+    fn apply(&mut self, event: ChangeEvent<T>) {
+        match event {
+            ChangeEvent::Added(item) => {
+                self.db.insert_item(item);
+            }
+            ChangeEvent::Removed(item) => {
+                self.db.take_item(&item);
+            }
+        }
+    }
+}
+
+impl<T: hash::Hash + Eq> Database<T> {
+    /// Creates a [Replica] of this database which starts as a copy of it and
+    /// follows every mutation made afterwards, each time it's synced.
+    ///
+    /// Replicas are meant to be read from, so they should not be dumped over
+    /// the path of their primary.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tinydb::Database;
+    /// use std::thread;
+    ///
+    /// fn main() {
+    ///     let mut primary = Database::new("replicate_test", None, false);
+    ///
+    ///     primary.add_item(String::from("Holly")).unwrap();
+    ///
+    ///     let mut replica = primary.replicate();
+    ///
+    ///     primary.add_item(String::from("Cat")).unwrap();
+    ///
+    ///     let reader = thread::spawn(move || {
+    ///         replica.sync();
+    ///         replica.db().items.len()
+    ///     });
+    ///
+    ///     assert_eq!(reader.join().unwrap(), 2);
+    /// }
+    /// ```
+    pub fn replicate(&mut self) -> Replica<T>
+    where
+        T: Clone + Send + 'static,
+    {
+        let events = self.subscribe();
+        let mut snapshot = self.clone();
+
+        // The primary's lock on its dump shouldn't outlive the primary
+        snapshot.lock = HeldLock::default();
+
+        Replica::new(snapshot, events)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error;
+
+    /// Tests replicas catching up and noticing when their primary is dropped
+    #[test]
+    fn replica_follows_primary() -> Result<(), error::DatabaseError> {
+        let mut primary = Database::new("Replica test", None, false);
+
+        primary.add_item(1)?;
+
+        let mut replica = primary.replicate();
+        let mut other = primary.replicate();
+
+        primary.add_item(2)?;
+        primary.update_item(&1, 3)?;
+
+        assert_eq!(replica.db().items.len(), 1);
+        assert_eq!(replica.sync(), 3);
+        assert_eq!(replica.db().items, primary.items);
+
+        primary.remove_item(&2)?;
+        drop(primary);
+
+        assert!(other.wait());
+        assert!(other.is_disconnected());
+        assert_eq!(other.db().items.len(), 1);
+        assert!(!other.wait());
+        Ok(())
+    }
+}
@@ -0,0 +1,81 @@
+//! Contains picking random items without collecting every item first,
+//! enabled with the `rand` feature.
+
+use crate::compat::Vec;
+use crate::Database;
+use core::hash;
+use rand::seq::IteratorRandom;
+use rand::Rng;
+
+impl<T: hash::Hash + Eq> Database<T> {
+    /// Picks a random item using `rng`, or [Option::None] if the database is
+    /// empty. Expired items are never picked.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use rand::rngs::SmallRng;
+    /// use rand::SeedableRng;
+    /// use tinydb::Database;
+    ///
+    /// fn main() {
+    ///     let mut db = Database::new("random_item_test", None, false);
+    ///     let mut rng = SmallRng::seed_from_u64(0);
+    ///
+    ///     db.add_item(String::from("Buy more curry")).unwrap();
+    ///     db.add_item(String::from("Feed the goldfish")).unwrap();
+    ///
+    ///     assert!(db.random_item(&mut rng).is_some());
+    /// }
+    /// ```
+    pub fn random_item<R: Rng + ?Sized>(&self, rng: &mut R) -> Option<&T> {
+        self.items
+            .iter()
+            .filter(|item| !self.is_expired(item))
+            .choose(rng)
+    }
+
+    /// Picks `amount` distinct random items using `rng`, or every item if
+    /// there aren't that many, in no particular order. Expired items are
+    /// never picked.
+    pub fn sample<R: Rng + ?Sized>(&self, amount: usize, rng: &mut R) -> Vec<&T> {
+        self.items
+            .iter()
+            .filter(|item| !self.is_expired(item))
+            .choose_multiple(rng, amount)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error;
+    use rand::rngs::SmallRng;
+    use rand::SeedableRng;
+    use std::collections::HashSet;
+    use std::time::Duration;
+
+    /// Tests samples being distinct and never including expired items
+    #[test]
+    fn sample_distinct() -> Result<(), error::DatabaseError> {
+        let mut db = Database::new("Sample test", None, false);
+        let mut rng = SmallRng::seed_from_u64(7);
+
+        for item in 0..10 {
+            db.add_item(item)?;
+        }
+
+        db.add_item_with_ttl(10, Duration::from_secs(0))?;
+
+        let sampled: HashSet<_> = db.sample(5, &mut rng).into_iter().collect();
+
+        assert_eq!(sampled.len(), 5);
+        assert_eq!(db.sample(20, &mut rng).len(), 10);
+        assert!((0..50).all(|_| db.random_item(&mut rng) != Some(&10)));
+        assert_eq!(
+            Database::<u8>::new("Empty", None, false).random_item(&mut rng),
+            None
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,196 @@
+//! Contains loading of dumps written with an older item type, converting
+//! each item to the current one, see [crate::Database::from_with] and
+//! [crate::Database::map_into].
+
+use crate::{error, format, Database, ItemHasher};
+use serde::de::DeserializeOwned;
+use std::hash;
+use std::path::PathBuf;
+
+impl<T: hash::Hash + Eq> Database<T> {
+    /// Gets a fingerprint of the item type `T`, which is written into every
+    /// dump so tools using [crate::inspect] can tell which type a dump holds
+    /// without decoding it.
+    ///
+    /// This hashes the name and size of `T`, so it changes when `T` is
+    /// renamed, moved to another module or changes size, such as by gaining a
+    /// field. Changes which keep both the same, like reordering fields, aren't
+    /// noticed, so matching fingerprints don't guarantee a dump will load. It
+    /// can also change between compiler versions.
+    pub fn schema_fingerprint() -> u64 {
+        let schema = format!(
+            "{}:{}",
+            std::any::type_name::<T>(),
+            std::mem::size_of::<T>()
+        );
+
+        format::checksum(schema.as_bytes())
+    }
+
+    /// Creates a database from a `.tinydb` file like [Database::from] does,
+    /// but for dumps made with an older item type `Old` which each item is
+    /// converted from using `convert`.
+    ///
+    /// Dumps are encoded with [bincode](https://docs.rs/bincode), which
+    /// doesn't store field names, so adding, removing or reordering fields of
+    /// an item means old dumps can no longer be read with the new type and
+    /// `#[serde(default)]` won't help. Instead, keep a copy of the old
+    /// definition around and convert from it, dumping afterwards so the dump
+    /// uses the new type from then on.
+    ///
+    /// Pinned, expiring and soft-deleted items are converted too, so
+    /// `convert` may be called more than once for the same item and should
+    /// always give the same result for it.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use serde::{Serialize, Deserialize};
+    /// use tinydb::Database;
+    ///
+    /// #[derive(Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+    /// struct UserV1 {
+    ///     name: String,
+    /// }
+    ///
+    /// #[derive(Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+    /// struct User {
+    ///     name: String,
+    ///     admin: bool,
+    /// }
+    ///
+    /// impl From<UserV1> for User {
+    ///     fn from(old: UserV1) -> Self {
+    ///         User { name: old.name, admin: false }
+    ///     }
+    /// }
+    ///
+    /// fn main() {
+    ///     let mut old = Database::new("schema_test", None, false);
+    ///
+    ///     old.add_item(UserV1 { name: String::from("Lister") }).unwrap();
+    ///     old.dump_db().unwrap();
+    ///     drop(old);
+    ///
+    ///     let db: Database<User> =
+    ///         Database::from_with("schema_test.tinydb", |old: UserV1| User::from(old)).unwrap();
+    ///
+    ///     assert!(db.contains(&User { name: String::from("Lister"), admin: false }));
+    ///
+    ///     db.dump_db().unwrap();
+    ///     # std::fs::remove_file("schema_test.tinydb").unwrap();
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Fails in the same ways as [Database::from], typically with an
+    /// [error::DatabaseError::BincodeError] wrapped in
+    /// [error::DatabaseError::Context] if the dump wasn't made with `Old`
+    /// either.
+    pub fn from_with<Old>(
+        path: impl Into<PathBuf>,
+        mut convert: impl FnMut(Old) -> T,
+    ) -> Result<Self, error::DatabaseError>
+    where
+        Old: hash::Hash + Eq + DeserializeOwned,
+    {
+        Ok(Database::<Old>::from(path)?.convert_into(&mut convert))
+    }
+
+    /// Converts every item of this database into another type, for one-off
+    /// migrations done in memory rather than when loading like
+    /// [Database::from_with] does.
+    ///
+    /// The label, save path and everything else which is saved when dumping
+    /// is carried over, as is the lock on the dump this database was opened
+    /// from. Runtime-only state such as indexes, listeners and constraints
+    /// isn't, so it has to be set up again for the new type. Items which
+    /// convert into the same item are merged.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tinydb::Database;
+    ///
+    /// fn main() {
+    ///     let mut db = Database::new("map_into_test", None, false);
+    ///
+    ///     db.add_item((String::from("Lister"), 25u8)).unwrap();
+    ///
+    ///     let names: Database<String> = db.map_into(|(name, _)| name);
+    ///
+    ///     assert_eq!(names.label, "map_into_test");
+    ///     assert!(names.contains(&String::from("Lister")));
+    /// }
+    /// ```
+    pub fn map_into<U>(self, mut convert: impl FnMut(T) -> U) -> Database<U>
+    where
+        U: hash::Hash + Eq,
+    {
+        self.convert_into(&mut convert)
+    }
+
+    /// Converts every item of this database into another type, keeping
+    /// everything which is saved when dumping as well as the lock on its
+    /// dump. Runtime-only state such as indexes isn't carried over.
+    pub(crate) fn convert_into<U>(self, convert: &mut impl FnMut(T) -> U) -> Database<U>
+    where
+        U: hash::Hash + Eq,
+    {
+        let mut converted = Database::with_hasher(
+            self.label,
+            self.save_path,
+            self.strict_dupes,
+            ItemHasher::default(),
+        );
+
+        converted
+            .items
+            .extend(self.items.into_iter().map(&mut *convert));
+        converted.order = self.order.map(convert);
+        converted.expiries = self.expiries.map(convert);
+        converted.trash = self.trash.map(convert);
+        converted.dupes = self.dupes.map(convert);
+        converted.meta = self.meta;
+        converted.sequence = self.sequence;
+        converted.archived = self.archived;
+        converted.read_only = self.read_only;
+        converted.skip_clean_dumps = self.skip_clean_dumps;
+        converted.create_dirs = self.create_dirs;
+        converted.overwrite = self.overwrite;
+        converted.mirrors = self.mirrors;
+        converted.lock = self.lock;
+        converted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    /// Tests pins and expiries following their items to the new type
+    #[test]
+    fn convert_tracked_state() -> Result<(), error::DatabaseError> {
+        let path = PathBuf::from("convert_tracked_state.tinydb");
+        let mut old = Database::new("Schema test", path.clone(), false);
+
+        old.add_item(1u8)?;
+        old.add_item_with_ttl(2u8, Duration::from_secs(0))?;
+        old.move_to_front(&1)?;
+        old.dump_db()?;
+        drop(old);
+
+        let db: Database<u32> = Database::from_with(path.clone(), |old: u8| old as u32 * 100)?;
+
+        assert!(db.contains(&100));
+        assert!(!db.contains(&200));
+        assert_eq!(db.ordered().next(), Some(&100));
+        assert!(Database::<u32>::from(path.clone()).is_err());
+
+        drop(db);
+        std::fs::remove_file(path)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,202 @@
+//! Contains quick text search over a string field of each item, for when a
+//! full search index would be overkill. With the `fuzzy` feature enabled, a
+//! fuzzy search which tolerates missing characters is available too.
+
+use crate::compat::Vec;
+use crate::Database;
+use core::hash;
+
+impl<T: hash::Hash + Eq> Database<T> {
+    /// Finds all items where the text given by `field` contains `needle`,
+    /// ignoring case. Results are ranked with exact matches first, followed by
+    /// those starting with `needle`, those with a word starting with `needle`
+    /// and finally any other matches, with earlier and shorter matches ranked
+    /// higher within each of these.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tinydb::Database;
+    ///
+    /// fn main() {
+    ///     let mut db = Database::new("search_test", None, false);
+    ///
+    ///     for note in &["Shopping list", "Buy a new list", "List of films", "Lists"] {
+    ///         db.add_item(note.to_string()).unwrap();
+    ///     }
+    ///
+    ///     let results = db.search(|note: &String| note, "list");
+    ///
+    ///     assert_eq!(results, vec!["Lists", "List of films", "Shopping list", "Buy a new list"]);
+    /// }
+    /// ```
+    pub fn search<S: AsRef<str> + ?Sized, V: Fn(&T) -> &S>(
+        &self,
+        field: V,
+        needle: &str,
+    ) -> Vec<&T> {
+        let needle = needle.to_lowercase();
+        let mut results: Vec<((u8, usize, usize), &T)> = self
+            .items
+            .iter()
+            .filter(|item| !self.is_expired(item))
+            .filter_map(|item| {
+                let text = field(item).as_ref().to_lowercase();
+
+                rank(&text, &needle).map(|rank| (rank, item))
+            })
+            .collect();
+
+        results.sort_by_key(|(rank, _)| *rank);
+        results.into_iter().map(|(_, item)| item).collect()
+    }
+
+    /// Finds all items where the text given by `field` contains every
+    /// character of `pattern` in order, ignoring case, such as `"sl"` matching
+    /// `"Shopping list"`. Results are ranked by how closely they match, with
+    /// consecutive characters and those at the start of words scoring higher.
+    ///
+    /// This is only available with the `fuzzy` feature enabled.
+    #[cfg(feature = "fuzzy")]
+    pub fn search_fuzzy<S: AsRef<str> + ?Sized, V: Fn(&T) -> &S>(
+        &self,
+        field: V,
+        pattern: &str,
+    ) -> Vec<&T> {
+        let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+        let mut results: Vec<(i64, &T)> = self
+            .items
+            .iter()
+            .filter(|item| !self.is_expired(item))
+            .filter_map(|item| {
+                fuzzy_score(field(item).as_ref(), &pattern).map(|score| (score, item))
+            })
+            .collect();
+
+        results.sort_by_key(|(score, _)| core::cmp::Reverse(*score));
+        results.into_iter().map(|(_, item)| item).collect()
+    }
+}
+
+/// Ranks a substring match so lower ranks sort first, or returns
+/// [Option::None] if `text` doesn't contain `needle`. Both should already be
+/// lowercase.
+fn rank(text: &str, needle: &str) -> Option<(u8, usize, usize)> {
+    let position = text.find(needle)?;
+    let tier = if text == needle {
+        0
+    } else if position == 0 {
+        1
+    } else if text
+        .match_indices(needle)
+        .any(|(index, _)| is_word_start(text, index))
+    {
+        2
+    } else {
+        3
+    };
+
+    Some((tier, position, text.len()))
+}
+
+/// Checks if the character at `index` starts a word.
+fn is_word_start(text: &str, index: usize) -> bool {
+    text[..index]
+        .chars()
+        .next_back()
+        .is_none_or(|before| !before.is_alphanumeric())
+}
+
+/// Scores how well `pattern` fuzzily matches `text`, returning [Option::None]
+/// if not every character of the lowercase `pattern` is in `text` in order.
+#[cfg(feature = "fuzzy")]
+fn fuzzy_score(text: &str, pattern: &[char]) -> Option<i64> {
+    let mut score = 0;
+    let mut remaining = pattern.iter().peekable();
+    let mut last_match: Option<usize> = None;
+    let mut previous: Option<char> = None;
+
+    for (index, c) in text.chars().enumerate() {
+        let lower = c.to_lowercase().next().unwrap_or(c);
+
+        if remaining.peek() == Some(&&lower) {
+            remaining.next();
+            score += 10;
+
+            if last_match.is_some_and(|last| last + 1 == index) {
+                score += 15;
+            }
+
+            if previous.is_none_or(|before| !before.is_alphanumeric()) {
+                score += 10;
+            }
+
+            if let Some(last) = last_match {
+                score -= (index - last - 1).min(10) as i64;
+            } else {
+                score -= index.min(10) as i64;
+            }
+
+            last_match = Some(index);
+        }
+
+        previous = Some(c);
+    }
+
+    if remaining.peek().is_some() {
+        None
+    } else {
+        Some(score)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Makes a database of the given notes
+    fn make_db(notes: &[&str]) -> Database<String> {
+        let mut db = Database::new("Search test", None, false);
+
+        for note in notes {
+            db.add_item(note.to_string()).unwrap();
+        }
+
+        db
+    }
+
+    /// Tests substring matching being case-insensitive and ranked
+    #[test]
+    fn search_ranked() {
+        let db = make_db(&["Rust", "trusty", "RUST notes", "rusting away", "Go"]);
+
+        assert_eq!(
+            db.search(|note: &String| note, "rust"),
+            vec!["Rust", "RUST notes", "rusting away", "trusty"]
+        );
+        assert!(db.search(|note: &String| note, "python").is_empty());
+    }
+
+    /// Tests words inside the text ranking above other matches
+    #[test]
+    fn search_word_start() {
+        let db = make_db(&["unlisted", "a list"]);
+
+        assert_eq!(
+            db.search(|note: &String| note, "LIST"),
+            vec!["a list", "unlisted"]
+        );
+    }
+
+    /// Tests fuzzy matching preferring closer matches
+    #[cfg(feature = "fuzzy")]
+    #[test]
+    fn search_fuzzy_ranked() {
+        let db = make_db(&["Shopping list", "sl", "spells", "nothing"]);
+
+        assert_eq!(
+            db.search_fuzzy(|note: &String| note, "SL"),
+            vec!["sl", "Shopping list", "spells"]
+        );
+    }
+}
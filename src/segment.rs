@@ -0,0 +1,443 @@
+//! Contains segmented dumps, which split items across several files so
+//! that dumping again only rewrites the segments which changed, see
+//! [crate::Database::dump_segmented].
+//!
+//! A segmented dump is a directory holding a `manifest.tinydb` file and a
+//! `segment-N.bin` file per segment. The manifest is a normal header followed
+//! by the encoded database without its items, alongside how many items and
+//! what checksum each segment has. Segments are just encoded items one after
+//! another.
+
+use crate::{dupes, error, format, meta, order, recover, sequence, trash, ttl, Database};
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{self, Hasher};
+use std::path::{Path, PathBuf};
+use std::thread;
+
+/// Roughly how large each segment should be, in bytes.
+const SEGMENT_SIZE: u64 = 4 * 1024 * 1024;
+
+/// Name of the manifest inside of a segmented dump.
+const MANIFEST_NAME: &str = "manifest.tinydb";
+
+/// Amount of items and checksum of a single segment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+struct SegmentInfo {
+    items: u64,
+    checksum: u64,
+}
+
+/// Everything inside of a manifest after its header.
+#[derive(Debug, Serialize, Deserialize)]
+struct Manifest {
+    segments: Vec<SegmentInfo>,
+    rest: Vec<u8>,
+}
+
+/// Gets the path of a segment inside of a segmented dump.
+fn segment_path(dir: &Path, index: usize) -> PathBuf {
+    dir.join(format!("segment-{}.bin", index))
+}
+
+/// Reads the manifest of a segmented dump.
+fn read_manifest(dir: &Path) -> Result<Manifest, error::DatabaseError> {
+    let path = dir.join(MANIFEST_NAME);
+
+    if !path.exists() {
+        return Err(error::DatabaseError::DatabaseNotFound(dir.to_path_buf()));
+    }
+
+    let context = |e: error::DatabaseError| e.with_context("load", Some(path.clone()), None);
+    let stream = fs::read(&path).map_err(|e| context(e.into()))?;
+
+    match format::read_header(&stream).map_err(context)? {
+        format::Payload::Current { payload, .. } => {
+            bincode::deserialize(payload).map_err(|e| context(e.into()))
+        }
+        format::Payload::Headerless(_) => Err(error::DatabaseError::UnrecognizedFormat {
+            detected: Some(String::from("headerless segment manifest")),
+        }),
+    }
+}
+
+/// Picks which segment an item belongs in. This uses a fixed hasher rather
+/// than [crate::ItemHasher] so items stay in the same segment between runs.
+fn bucket<T: hash::Hash>(item: &T, segments: usize) -> usize {
+    let mut hasher = DefaultHasher::new();
+
+    item.hash(&mut hasher);
+    (hasher.finish() % segments as u64) as usize
+}
+
+/// Decodes every item inside of a segment, checking it against the manifest.
+fn read_segment<T: DeserializeOwned>(
+    dir: &Path,
+    index: usize,
+    info: SegmentInfo,
+) -> Result<Vec<T>, error::DatabaseError> {
+    let path = segment_path(dir, index);
+    let context = |e: error::DatabaseError| e.with_context("load", Some(path.clone()), None);
+    let stream = fs::read(&path).map_err(|e| context(e.into()))?;
+
+    if format::checksum(&stream) != info.checksum {
+        return Err(error::DatabaseError::ChecksumMismatch);
+    }
+
+    let mut rest = stream.as_slice();
+
+    (0..info.items)
+        .map(|_| bincode::deserialize_from(&mut rest).map_err(|e| context(e.into())))
+        .collect()
+}
+
+/// Everything a dump holds apart from its items, borrowed from a database.
+///
+/// This has to list every field of [Database] which is saved when dumping,
+/// in the same order, with items always being empty.
+#[derive(Serialize)]
+struct Skeleton<'a, T: hash::Hash + Eq> {
+    label: &'a String,
+    save_path: &'a Option<PathBuf>,
+    strict_dupes: bool,
+    items: &'a [T],
+    order: &'a order::PinnedOrder<T>,
+    expiries: &'a ttl::Expiries<T>,
+    trash: &'a trash::Trash<T>,
+    dupes: &'a dupes::Duplicates<T>,
+    meta: &'a meta::Metadata,
+    sequence: sequence::Sequence,
+}
+
+/// Encodes the items of a single segment one after another.
+fn encode_segment<T: Serialize>(items: &[&T]) -> Result<Vec<u8>, error::DatabaseError> {
+    let mut stream = Vec::new();
+
+    for item in items {
+        bincode::serialize_into(&mut stream, item)?;
+    }
+
+    Ok(stream)
+}
+
+impl<T: hash::Hash + Eq + Serialize> Database<T> {
+    /// Dumps this database as a directory of segments rather than a single
+    /// file, returning how many segments were rewritten.
+    ///
+    /// Items are spread over segments of roughly 4MB by their hash, so
+    /// dumping again after changing a handful of items only rewrites the few
+    /// segments they're in. Everything is still encoded on every dump to find
+    /// what changed, so this saves on disk writes rather than time spent
+    /// encoding. Archiving and signing only apply to single-file dumps.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tinydb::Database;
+    ///
+    /// fn main() {
+    ///     let mut db = Database::new("segmented", None, false);
+    ///
+    ///     db.add_item(String::from("Queeg")).unwrap();
+    ///
+    ///     assert_eq!(db.dump_segmented("segmented_example").unwrap(), 1);
+    ///     assert_eq!(db.dump_segmented("segmented_example").unwrap(), 0);
+    ///
+    ///     let loaded: Database<String> = Database::from_segmented("segmented_example").unwrap();
+    ///
+    ///     assert_eq!(loaded, db);
+    ///     # std::fs::remove_dir_all("segmented_example").unwrap();
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Will return [error::DatabaseError::ReadOnly] if this database was
+    /// opened with [Database::open_readonly].
+    pub fn dump_segmented(&self, dir: impl Into<PathBuf>) -> Result<usize, error::DatabaseError> {
+        let items_len = self
+            .items
+            .iter()
+            .map(bincode::serialized_size)
+            .sum::<Result<u64, _>>()?;
+
+        self.write_segments(dir.into(), items_len, |groups| {
+            groups.iter().map(|group| encode_segment(group)).collect()
+        })
+    }
+
+    /// Dumps this database as a directory of segments like
+    /// [Database::dump_segmented] does, but encoding segments in parallel
+    /// using [rayon](https://docs.rs/rayon). This is enabled with the `rayon`
+    /// feature.
+    #[cfg(feature = "rayon")]
+    pub fn par_dump_segmented(&self, dir: impl Into<PathBuf>) -> Result<usize, error::DatabaseError>
+    where
+        T: Sync,
+    {
+        let items: Vec<&T> = self.items.iter().collect();
+        let items_len = items
+            .par_iter()
+            .map(bincode::serialized_size)
+            .sum::<Result<u64, _>>()?;
+
+        self.write_segments(dir.into(), items_len, |groups| {
+            groups.par_iter().map(|group| encode_segment(group)).collect()
+        })
+    }
+
+    /// Writes a segmented dump, with `encode` encoding the items of each
+    /// segment given how large all of the items are once encoded.
+    fn write_segments(
+        &self,
+        dir: PathBuf,
+        items_len: u64,
+        encode: impl FnOnce(Vec<Vec<&T>>) -> Result<Vec<Vec<u8>>, error::DatabaseError>,
+    ) -> Result<usize, error::DatabaseError> {
+        if self.read_only {
+            return Err(error::DatabaseError::ReadOnly);
+        }
+
+        self.dumping(|| {
+            let previous = read_manifest(&dir).ok();
+            let segments = match &previous {
+                Some(manifest)
+                    if items_len <= manifest.segments.len() as u64 * SEGMENT_SIZE * 2 =>
+                {
+                    manifest.segments.len()
+                }
+                _ => items_len.div_ceil(SEGMENT_SIZE).max(1) as usize,
+            };
+
+            let mut groups = vec![Vec::new(); segments];
+
+            for item in self.items.iter() {
+                groups[bucket(item, segments)].push(item);
+            }
+
+            let counts: Vec<u64> = groups.iter().map(|group| group.len() as u64).collect();
+            let encoded = encode(groups)?;
+
+            let rest = bincode::serialize(&Skeleton {
+                label: &self.label,
+                save_path: &None,
+                strict_dupes: self.strict_dupes,
+                items: &[],
+                order: &self.order,
+                expiries: &self.expiries,
+                trash: &self.trash,
+                dupes: &self.dupes,
+                meta: &self.meta,
+                sequence: self.sequence,
+            })?;
+
+            fs::create_dir_all(&dir)?;
+
+            let mut infos = Vec::with_capacity(segments);
+            let mut written = 0;
+
+            for (index, (stream, items)) in encoded.iter().zip(counts).enumerate() {
+                let path = segment_path(&dir, index);
+                let info = SegmentInfo {
+                    items,
+                    checksum: format::checksum(stream),
+                };
+                let unchanged = previous
+                    .as_ref()
+                    .and_then(|manifest| manifest.segments.get(index))
+                    .is_some_and(|old| *old == info && path.exists());
+
+                if !unchanged {
+                    fs::write(&path, stream).map_err(|e| {
+                        error::DatabaseError::from(e).with_context(
+                            "dump",
+                            Some(path),
+                            Some(&self.label),
+                        )
+                    })?;
+                    written += 1;
+                }
+
+                infos.push(info);
+            }
+
+            let mut manifest = Vec::new();
+
+            format::write_header(&mut manifest, 0)?;
+            bincode::serialize_into(
+                &mut manifest,
+                &Manifest {
+                    segments: infos,
+                    rest,
+                },
+            )?;
+
+            let manifest_path = dir.join(MANIFEST_NAME);
+            let temp_path = dir.join(format!("{}.tmp", MANIFEST_NAME));
+
+            fs::write(&temp_path, manifest)
+                .and_then(|_| fs::rename(temp_path, &manifest_path))
+                .map_err(|e| {
+                    error::DatabaseError::from(e).with_context(
+                        "dump",
+                        Some(manifest_path),
+                        Some(&self.label),
+                    )
+                })?;
+
+            if let Some(previous) = previous {
+                for index in segments..previous.segments.len() {
+                    fs::remove_file(segment_path(&dir, index)).ok();
+                }
+            }
+
+            Ok(written)
+        })
+    }
+}
+
+impl<T: hash::Hash + Eq + DeserializeOwned> Database<T> {
+    /// Loads a database dumped with [Database::dump_segmented], decoding
+    /// each segment on its own thread.
+    ///
+    /// # Errors
+    ///
+    /// Will return [error::DatabaseError::DatabaseNotFound] if there's no
+    /// manifest inside of `dir`, or [error::DatabaseError::ChecksumMismatch]
+    /// if a segment has been changed or corrupted since it was dumped, which
+    /// includes a crash partway through [Database::dump_segmented].
+    pub fn from_segmented(dir: impl Into<PathBuf>) -> Result<Self, error::DatabaseError>
+    where
+        T: Send,
+    {
+        Database::read_segments(dir.into(), |dir, segments| {
+            thread::scope(|scope| {
+                let handles: Vec<_> = segments
+                    .iter()
+                    .enumerate()
+                    .map(|(index, info)| scope.spawn(move || read_segment(dir, index, *info)))
+                    .collect();
+
+                handles
+                    .into_iter()
+                    .map(|handle| handle.join().expect("segment decoding panicked"))
+                    .collect()
+            })
+        })
+    }
+
+    /// Loads a database dumped with [Database::dump_segmented] like
+    /// [Database::from_segmented] does, but decoding segments on
+    /// [rayon](https://docs.rs/rayon)'s thread pool rather than a thread per
+    /// segment. This is enabled with the `rayon` feature.
+    #[cfg(feature = "rayon")]
+    pub fn par_from_segmented(dir: impl Into<PathBuf>) -> Result<Self, error::DatabaseError>
+    where
+        T: Send,
+    {
+        Database::read_segments(dir.into(), |dir, segments| {
+            segments
+                .par_iter()
+                .enumerate()
+                .map(|(index, info)| read_segment(dir, index, *info))
+                .collect()
+        })
+    }
+
+    /// Salvages as much as possible out of a damaged segmented dump like
+    /// [Database::recover] does, only losing the items of segments which are
+    /// missing or corrupted. Unlike [Database::recover], pins, expiries and
+    /// other tracked state are kept.
+    ///
+    /// # Errors
+    ///
+    /// Will return [error::DatabaseError::DatabaseNotFound] if there's no
+    /// manifest inside of `dir`. Nothing can be salvaged without the
+    /// manifest, so a damaged one fails like [Database::from_segmented].
+    pub fn recover_segmented(
+        dir: impl Into<PathBuf>,
+    ) -> Result<recover::RecoveryReport<T>, error::DatabaseError> {
+        let dir = dir.into();
+        let manifest = read_manifest(&dir)?;
+        let mut db: Database<T> = bincode::deserialize(&manifest.rest)?;
+        let mut lost = 0;
+
+        for (index, info) in manifest.segments.iter().enumerate() {
+            match read_segment(&dir, index, *info) {
+                Ok(items) => db.items.extend(items),
+                Err(_) => lost += info.items as usize,
+            }
+        }
+
+        Ok(recover::RecoveryReport {
+            salvaged: db.items.len(),
+            lost,
+            db,
+        })
+    }
+
+    /// Reads a segmented dump, with `decode` decoding each of its segments.
+    fn read_segments(
+        dir: PathBuf,
+        decode: impl FnOnce(&Path, &[SegmentInfo]) -> Vec<Result<Vec<T>, error::DatabaseError>>,
+    ) -> Result<Self, error::DatabaseError> {
+        let manifest = read_manifest(&dir)?;
+        let mut decoded: Database<T> = bincode::deserialize(&manifest.rest)?;
+
+        for segment in decode(&dir, &manifest.segments) {
+            decoded.items.extend(segment?);
+        }
+
+        Ok(decoded)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests only changed segments being rewritten and leftovers removed
+    #[test]
+    fn segments_incremental() -> Result<(), error::DatabaseError> {
+        let dir = PathBuf::from("segments_incremental");
+        let mut db = Database::new("Segment test", None, false);
+
+        for id in 0..10_000u32 {
+            db.add_item(format!("{:0>500}", id))?;
+        }
+
+        let written = db.dump_segmented(&dir)?;
+
+        assert!(written > 1);
+        assert_eq!(Database::from_segmented(&dir)?, db);
+
+        db.add_item(String::from("new"))?;
+
+        assert_eq!(db.dump_segmented(&dir)?, 1);
+        assert_eq!(Database::from_segmented(&dir)?, db);
+
+        #[cfg(feature = "rayon")]
+        {
+            assert_eq!(db.par_dump_segmented(&dir)?, 0);
+            assert_eq!(Database::par_from_segmented(&dir)?, db);
+        }
+
+        fs::write(segment_path(&dir, 0), b"corrupted")?;
+
+        assert!(matches!(
+            Database::<String>::from_segmented(&dir),
+            Err(error::DatabaseError::ChecksumMismatch)
+        ));
+
+        let report = Database::<String>::recover_segmented(&dir)?;
+
+        assert!(report.lost > 0);
+        assert_eq!(report.salvaged + report.lost, db.items.len());
+
+        fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+}
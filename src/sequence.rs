@@ -0,0 +1,90 @@
+//! Contains allocating increasing ids which are saved alongside a
+//! [crate::Database], see [crate::Database::next_id].
+
+use crate::{error, Database};
+use core::hash;
+use serde::{Deserialize, Serialize};
+
+/// The last id given out by [Database::next_id], `0` if none have been.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct Sequence {
+    last: u64,
+}
+
+impl<T: hash::Hash + Eq> Database<T> {
+    /// Allocates a new id, which is always larger than any given out before
+    /// by this database, starting at `1`. The last id is saved when dumping,
+    /// so ids are never reused after loading even if the items using them
+    /// were removed.
+    ///
+    /// Allocating ids from the database rather than working out the largest
+    /// id in use means separate parts of an app can't race each other into
+    /// using the same one.
+    pub fn next_id(&mut self) -> u64 {
+        self.sequence.last += 1;
+        self.meta.modified();
+        self.sequence.last
+    }
+
+    /// Adds the item made by `make` from a freshly allocated id, returning
+    /// the id. This is useful for items which hold their own id as a field.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tinydb::Database;
+    ///
+    /// fn main() {
+    ///     let mut db = Database::new("add_with_id_test", None, false);
+    ///
+    ///     let lister = db.add_with_id(|id| (id, String::from("Lister"))).unwrap();
+    ///     let rimmer = db.add_with_id(|id| (id, String::from("Rimmer"))).unwrap();
+    ///
+    ///     assert_eq!((lister, rimmer), (1, 2));
+    ///     assert!(db.contains(&(2, String::from("Rimmer"))));
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Fails in the same ways as [Database::add_item], in which case the id
+    /// is still used up.
+    pub fn add_with_id(
+        &mut self,
+        make: impl FnOnce(u64) -> T,
+    ) -> Result<u64, error::DatabaseError> {
+        self.check_mutable()?;
+
+        let id = self.next_id();
+
+        self.add_item(make(id))?;
+        Ok(id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// Tests ids carrying on from where they were after loading
+    #[test]
+    fn sequence_saved() -> Result<(), error::DatabaseError> {
+        let path = PathBuf::from("sequence_saved.tinydb");
+        let mut db = Database::new("Sequence test", path.clone(), false);
+
+        db.add_with_id(|id| id)?;
+        db.remove_item(&1)?;
+        db.next_id();
+        db.dump_db()?;
+
+        let mut loaded: Database<u64> = Database::from(path.clone())?;
+
+        assert_eq!(loaded.add_with_id(|id| id)?, 3);
+        assert_eq!(loaded.next_id(), 4);
+
+        drop(loaded);
+        std::fs::remove_file(path)?;
+        Ok(())
+    }
+}
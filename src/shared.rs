@@ -0,0 +1,369 @@
+//! Contains a cooperative mode for several processes writing to the same
+//! dump, see [crate::Database::sync_dump].
+
+use crate::compat::HashSet;
+#[cfg(feature = "std")]
+use crate::compat::PathBuf;
+#[cfg(feature = "std")]
+use crate::lock::{FileLock, HeldLock};
+#[cfg(feature = "std")]
+use crate::merge::{MergePolicy, MergeReport};
+#[cfg(feature = "std")]
+use crate::{error, Database};
+use alloc::sync::Arc;
+use core::{fmt, hash};
+#[cfg(feature = "std")]
+use serde::{de::DeserializeOwned, Serialize};
+#[cfg(feature = "std")]
+use std::path::Path;
+#[cfg(feature = "std")]
+use std::thread;
+#[cfg(feature = "std")]
+use std::time::{Duration, Instant};
+
+/// How long [Database::sync_dump] waits for another process to finish
+/// syncing before giving up.
+#[cfg(feature = "std")]
+const LOCK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long to wait between attempts at taking the lock.
+#[cfg(feature = "std")]
+const LOCK_RETRY: Duration = Duration::from_millis(5);
+
+/// Clones an item, captured when changes start being journaled so that
+/// [Clone] isn't required everywhere.
+type Cloner<T> = Arc<dyn Fn(&T) -> T + Send + Sync>;
+
+/// Items added and removed since this database was last synced with its
+/// dump, so [Database::sync_dump] knows which differences are its own.
+///
+/// The journal isn't saved when dumping and never takes part in comparisons
+/// between databases.
+pub(crate) struct Journal<T> {
+    cloner: Option<Cloner<T>>,
+    added: HashSet<T>,
+    removed: HashSet<T>,
+    counted: HashSet<T>,
+}
+
+impl<T: hash::Hash + Eq> Journal<T> {
+    /// Records an item which has just been added, cancelling out an earlier
+    /// removal of it.
+    pub(crate) fn insert(&mut self, item: &T) {
+        if let Some(cloner) = &self.cloner {
+            if !self.removed.remove(item) {
+                self.added.insert(cloner(item));
+            }
+        }
+    }
+
+    /// Records an item which has just been removed, cancelling out an
+    /// earlier addition of it.
+    pub(crate) fn remove(&mut self, item: &T) {
+        if let Some(cloner) = &self.cloner {
+            self.counted.remove(item);
+
+            if !self.added.remove(item) {
+                self.removed.insert(cloner(item));
+            }
+        }
+    }
+
+    /// Records an item which had its count changed by
+    /// [crate::dupes::DupePolicy::Count], so it's kept even if another
+    /// process removed it.
+    pub(crate) fn recount(&mut self, item: &T) {
+        if let Some(cloner) = &self.cloner {
+            if !self.added.contains(item) && !self.counted.contains(item) {
+                self.counted.insert(cloner(item));
+            }
+        }
+    }
+
+    /// Forgets all recorded changes, for once they've been synced or thrown
+    /// away.
+    #[cfg(feature = "std")]
+    pub(crate) fn clear(&mut self) {
+        self.added.clear();
+        self.removed.clear();
+        self.counted.clear();
+    }
+}
+
+impl<T> Default for Journal<T> {
+    fn default() -> Self {
+        Journal {
+            cloner: None,
+            added: HashSet::new(),
+            removed: HashSet::new(),
+            counted: HashSet::new(),
+        }
+    }
+}
+
+impl<T: hash::Hash + Eq> Clone for Journal<T> {
+    fn clone(&self) -> Self {
+        let cloner = match &self.cloner {
+            Some(cloner) => cloner,
+            None => return Journal::default(),
+        };
+
+        Journal {
+            cloner: Some(Arc::clone(cloner)),
+            added: self.added.iter().map(|item| cloner(item)).collect(),
+            removed: self.removed.iter().map(|item| cloner(item)).collect(),
+            counted: self.counted.iter().map(|item| cloner(item)).collect(),
+        }
+    }
+}
+
+impl<T> PartialEq for Journal<T> {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl<T> Eq for Journal<T> {}
+
+impl<T> fmt::Debug for Journal<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Journal")
+            .field("added", &self.added.len())
+            .field("removed", &self.removed.len())
+            .field("counted", &self.counted.len())
+            .finish()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: hash::Hash + Eq + Serialize + DeserializeOwned> Database<T> {
+    /// Opens a dump which is shared between several processes, without
+    /// locking it like [Database::from] would. Changes should be saved using
+    /// [Database::sync_dump] rather than [Database::dump_db].
+    ///
+    /// # Errors
+    ///
+    /// Fails in the same ways as [Database::from_unlocked].
+    pub fn open_shared(path: impl Into<PathBuf>) -> Result<Self, error::DatabaseError>
+    where
+        T: Clone + 'static,
+    {
+        let mut db = Database::from_unlocked(path)?;
+
+        db.journal.cloner = Some(Arc::new(T::clone));
+        Ok(db)
+    }
+
+    /// Dumps this database without losing changes other processes have
+    /// dumped in the meantime, for dumps shared between several processes.
+    ///
+    /// The dump is locked for as long as this takes, waiting a few seconds
+    /// for other processes to finish syncing if needed. Whilst locked, the
+    /// dump is read again and the changes made by other processes are merged
+    /// into this database, with `policy` deciding what happens to items both
+    /// processes added. This database is then written over the dump.
+    ///
+    /// Changes are recorded from when the database was opened using
+    /// [Database::open_shared] or was first synced, so any items already
+    /// inside of a database on its first sync count as having been added.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tinydb::Database;
+    /// use tinydb::merge::MergePolicy;
+    ///
+    /// fn main() {
+    ///     let mut first = Database::new("shared_dump", None, false);
+    ///
+    ///     first.add_item(1).unwrap();
+    ///     first.sync_dump(MergePolicy::Skip).unwrap();
+    ///
+    ///     let mut second: Database<u32> = Database::open_shared("shared_dump.tinydb").unwrap();
+    ///
+    ///     first.add_item(2).unwrap();
+    ///     second.remove_item(&1).unwrap();
+    ///
+    ///     second.sync_dump(MergePolicy::Skip).unwrap();
+    ///     first.sync_dump(MergePolicy::Skip).unwrap();
+    ///
+    ///     assert_eq!(first.items.len(), 1);
+    ///     assert!(first.contains(&2));
+    ///     # std::fs::remove_file("shared_dump.tinydb").unwrap();
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Will return [error::DatabaseError::Locked] if the dump stayed locked
+    /// for too long, which also happens if another process opened it using
+    /// [Database::from]. With [MergePolicy::Error], this will return
+    /// [error::DatabaseError::DupeFound] without changing anything if both
+    /// processes added the same item.
+    pub fn sync_dump(&mut self, policy: MergePolicy) -> Result<MergeReport, error::DatabaseError>
+    where
+        T: Clone + 'static,
+    {
+        self.check_mutable()?;
+
+        if self.journal.cloner.is_none() {
+            self.journal.cloner = Some(Arc::new(T::clone));
+
+            for item in self.items.iter() {
+                self.journal.added.insert(item.clone());
+            }
+        }
+
+        let path = self.smart_path_get();
+
+        // hold the lock as this database's own while syncing, so dumping
+        // over the file isn't refused for being locked by someone else
+        let held = match self.wait_for_lock(&path)? {
+            Some(lock) => Some(core::mem::replace(
+                &mut self.lock,
+                HeldLock(Some(Arc::new(lock))),
+            )),
+            None => None,
+        };
+        let result = self.sync_locked(path, policy);
+
+        if let Some(held) = held {
+            self.lock = held;
+        }
+
+        result
+    }
+
+    /// Merges with the dump at `path` and writes the result back, whilst this
+    /// database is holding the lock on it.
+    fn sync_locked(
+        &mut self,
+        path: PathBuf,
+        policy: MergePolicy,
+    ) -> Result<MergeReport, error::DatabaseError>
+    where
+        T: Clone,
+    {
+        let mut report = MergeReport::default();
+
+        let mut theirs = match Database::<T>::from_unlocked(path) {
+            Ok(mut disk) => core::mem::take(&mut disk.items),
+            Err(error::DatabaseError::DatabaseNotFound(_)) => {
+                self.journal.clear();
+                self.write_dump(None, None)?;
+
+                return Ok(report);
+            }
+            Err(e) => return Err(e),
+        };
+
+        let mut journal = core::mem::take(&mut self.journal);
+
+        if policy == MergePolicy::Error && journal.added.iter().any(|item| theirs.contains(item)) {
+            self.journal = journal;
+            return Err(error::DatabaseError::DupeFound);
+        }
+
+        self.history.begin_step();
+
+        report.removed = self
+            .take_where(|item| {
+                !theirs.contains(item) && !journal.added.contains(item) && !journal.counted.contains(item)
+            })
+            .len();
+
+        for item in theirs.drain() {
+            if journal.removed.contains(&item) {
+                continue;
+            } else if !self.items.contains(&item) {
+                report.added += 1;
+            } else if !journal.added.contains(&item) {
+                continue;
+            } else if policy == MergePolicy::Replace {
+                self.take_item(&item);
+                report.replaced += 1;
+            } else {
+                report.skipped += 1;
+                continue;
+            }
+
+            self.insert_item(item);
+        }
+
+        journal.clear();
+        self.journal = journal;
+        self.write_dump(None, None)?;
+
+        Ok(report)
+    }
+
+    /// Takes the lock on a dump for the duration of a sync, unless this
+    /// database is already holding it.
+    fn wait_for_lock(&self, path: &Path) -> Result<Option<FileLock>, error::DatabaseError> {
+        if self.lock.get().is_some_and(|lock| lock.is_for(path)) {
+            return Ok(None);
+        }
+
+        let started = Instant::now();
+
+        loop {
+            match FileLock::acquire(path) {
+                Ok(lock) => return Ok(Some(lock)),
+                Err(error::DatabaseError::Locked) if started.elapsed() < LOCK_TIMEOUT => {
+                    thread::sleep(LOCK_RETRY)
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests two processes adding and removing items without losing each
+    /// other's changes
+    #[test]
+    fn sync_two_writers() -> Result<(), error::DatabaseError> {
+        let path = PathBuf::from("sync_two_writers.tinydb");
+        let mut first = Database::new("Sync test", path.clone(), false);
+
+        first.add_item(1)?;
+        first.add_item(2)?;
+        first.sync_dump(MergePolicy::Error)?;
+
+        let mut second: Database<i32> = Database::open_shared(path.clone())?;
+
+        first.remove_item(&1)?;
+        first.add_item(3)?;
+        second.remove_item(&2)?;
+        second.add_item(3)?;
+        second.add_item(4)?;
+
+        let report = first.sync_dump(MergePolicy::Error)?;
+
+        assert_eq!(report.added, 0);
+        assert!(matches!(
+            second.sync_dump(MergePolicy::Error),
+            Err(error::DatabaseError::DupeFound)
+        ));
+
+        let report = second.sync_dump(MergePolicy::Skip)?;
+
+        assert_eq!(report.removed, 1);
+        assert_eq!(report.skipped, 1);
+
+        first.sync_dump(MergePolicy::Error)?;
+
+        let mut items: Vec<i32> = first.items.iter().copied().collect();
+
+        items.sort_unstable();
+
+        assert_eq!(items, vec![3, 4]);
+        assert_eq!(first.items, second.items);
+
+        std::fs::remove_file(path)?;
+        Ok(())
+    }
+}
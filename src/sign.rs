@@ -0,0 +1,266 @@
+//! Contains signing of dumps for tamper evidence, allowing applications which
+//! distribute data files to verify their integrity and authenticity when
+//! loading them.
+//!
+//! Ed25519 signatures are built in as [Ed25519Signer] and [Ed25519Verifier]
+//! with the `ed25519` feature. Other schemes can be used by implementing the
+//! [Signer] and [Verifier] traits using any crate.
+
+use crate::{error, format, get_stream_from_path, metrics, Database};
+use serde::{de::DeserializeOwned, Serialize};
+use std::hash;
+use std::path::PathBuf;
+
+/// Signs dumps made using [Database::dump_signed], typically with a private key.
+pub trait Signer {
+    /// Signs the given message, returning the signature to append to the dump.
+    fn sign(&self, message: &[u8]) -> Vec<u8>;
+}
+
+/// Verifies dumps loaded using [Database::from_verified], typically with a
+/// public key.
+pub trait Verifier {
+    /// Checks if the signature is valid for the given message.
+    fn verify(&self, message: &[u8], signature: &[u8]) -> bool;
+}
+
+/// Signs dumps with an ed25519 private key, which needs the `ed25519`
+/// feature.
+///
+/// # Examples
+///
+/// ```rust
+/// use tinydb::sign::Ed25519Signer;
+/// use tinydb::Database;
+///
+/// fn main() {
+///     // use 32 securely generated random bytes in practice
+///     let signer = Ed25519Signer::from_bytes(&[7; 32]);
+///     let verifier = signer.verifier();
+///     let mut db = Database::new("ed25519_test", None, false);
+///
+///     db.add_item(String::from("Holly")).unwrap();
+///     db.dump_signed(&signer).unwrap();
+///
+///     let loaded: Database<String> =
+///         Database::from_verified("ed25519_test.tinydb", &verifier).unwrap();
+///
+///     assert!(loaded.contains(&String::from("Holly")));
+///     # std::fs::remove_file("ed25519_test.tinydb").unwrap();
+/// }
+/// ```
+#[cfg(feature = "ed25519")]
+#[derive(Debug, Clone)]
+pub struct Ed25519Signer(ed25519_dalek::SigningKey);
+
+#[cfg(feature = "ed25519")]
+impl Ed25519Signer {
+    /// Creates a signer from the 32 bytes of a secret key, which should be
+    /// securely generated and kept private.
+    pub fn from_bytes(secret: &[u8; 32]) -> Self {
+        Ed25519Signer(ed25519_dalek::SigningKey::from_bytes(secret))
+    }
+
+    /// Gets the public half of this key, for verifying dumps it signed.
+    pub fn verifier(&self) -> Ed25519Verifier {
+        Ed25519Verifier(self.0.verifying_key())
+    }
+}
+
+#[cfg(feature = "ed25519")]
+impl From<ed25519_dalek::SigningKey> for Ed25519Signer {
+    fn from(key: ed25519_dalek::SigningKey) -> Self {
+        Ed25519Signer(key)
+    }
+}
+
+#[cfg(feature = "ed25519")]
+impl Signer for Ed25519Signer {
+    fn sign(&self, message: &[u8]) -> Vec<u8> {
+        ed25519_dalek::Signer::sign(&self.0, message)
+            .to_bytes()
+            .to_vec()
+    }
+}
+
+/// Verifies dumps signed by an [Ed25519Signer] using its public key, which
+/// needs the `ed25519` feature.
+#[cfg(feature = "ed25519")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ed25519Verifier(ed25519_dalek::VerifyingKey);
+
+#[cfg(feature = "ed25519")]
+impl Ed25519Verifier {
+    /// Creates a verifier from the 32 bytes of a public key, or [None] if
+    /// they aren't a valid key.
+    pub fn from_bytes(public: &[u8; 32]) -> Option<Self> {
+        ed25519_dalek::VerifyingKey::from_bytes(public)
+            .ok()
+            .map(Ed25519Verifier)
+    }
+
+    /// Gets the 32 bytes of this public key, for distributing alongside
+    /// signed dumps.
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.0.to_bytes()
+    }
+}
+
+#[cfg(feature = "ed25519")]
+impl From<ed25519_dalek::VerifyingKey> for Ed25519Verifier {
+    fn from(key: ed25519_dalek::VerifyingKey) -> Self {
+        Ed25519Verifier(key)
+    }
+}
+
+#[cfg(feature = "ed25519")]
+impl Verifier for Ed25519Verifier {
+    fn verify(&self, message: &[u8], signature: &[u8]) -> bool {
+        match ed25519_dalek::Signature::from_slice(signature) {
+            Ok(signature) => self.0.verify_strict(message, &signature).is_ok(),
+            Err(_) => false,
+        }
+    }
+}
+
+impl<T: hash::Hash + Eq + Serialize> Database<T> {
+    /// Dumps/saves database to a binary file like [Database::dump_db] does,
+    /// appending a signature from the given [Signer] which can later be
+    /// checked using [Database::from_verified].
+    ///
+    /// The signature covers the entire dump, including its header. Signed
+    /// dumps can still be loaded without verification using [Database::from].
+    pub fn dump_signed(
+        &self,
+        signer: &impl Signer,
+    ) -> Result<metrics::DumpReport, error::DatabaseError> {
+        self.write_dump(Some(signer), None)
+    }
+}
+
+impl<T: hash::Hash + Eq + DeserializeOwned> Database<T> {
+    /// Creates a database from a `.tinydb` file like [Database::from] does,
+    /// only succeeding if it has been signed using [Database::dump_signed]
+    /// and the signature is accepted by the given [Verifier].
+    ///
+    /// # Errors
+    ///
+    /// Will return [error::DatabaseError::SignatureMissing] if the dump isn't
+    /// signed or [error::DatabaseError::SignatureInvalid] if the signature
+    /// doesn't match.
+    pub fn from_verified(
+        path: impl Into<PathBuf>,
+        verifier: &impl Verifier,
+    ) -> Result<Self, error::DatabaseError> {
+        let path = path.into();
+        let stream = get_stream_from_path(path.clone())?;
+        let payload = format::read_header(&stream)?;
+
+        match &payload {
+            format::Payload::Current {
+                signed: Some(signed),
+                ..
+            } => {
+                if !verifier.verify(signed.message, signed.signature) {
+                    return Err(error::DatabaseError::SignatureInvalid);
+                }
+            }
+            _ => return Err(error::DatabaseError::SignatureMissing),
+        }
+
+        let mut decoded = Database::decode(payload, None)?;
+
+        decoded.loaded_from(path);
+        Ok(decoded)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Toy keyed signature, only good enough for testing
+    struct TestKey(u64);
+
+    impl Signer for TestKey {
+        fn sign(&self, message: &[u8]) -> Vec<u8> {
+            (format::checksum(message) ^ self.0).to_le_bytes().to_vec()
+        }
+    }
+
+    impl Verifier for TestKey {
+        fn verify(&self, message: &[u8], signature: &[u8]) -> bool {
+            self.sign(message) == signature
+        }
+    }
+
+    /// Tests signed dumps only loading with the right key
+    #[test]
+    fn signed_round_trip() -> Result<(), error::DatabaseError> {
+        let path = PathBuf::from("signed_round_trip.tinydb");
+        let unsigned_path = PathBuf::from("signed_round_trip_unsigned.tinydb");
+        let mut db = Database::new("Signing test", path.clone(), false);
+
+        db.add_item(String::from("dictionary"))?;
+        db.dump_signed(&TestKey(42))?;
+
+        let loaded: Database<String> = Database::from_verified(path.clone(), &TestKey(42))?;
+
+        assert!(loaded.contains(&String::from("dictionary")));
+        assert!(Database::<String>::from(path.clone()).is_ok());
+
+        match Database::<String>::from_verified(path.clone(), &TestKey(7)) {
+            Err(error::DatabaseError::SignatureInvalid) => (),
+            _ => panic!("wrong key was accepted"),
+        }
+
+        db.save_path = Some(unsigned_path.clone());
+        db.dump_db()?;
+
+        match Database::<String>::from_verified(unsigned_path.clone(), &TestKey(42)) {
+            Err(error::DatabaseError::SignatureMissing) => (),
+            _ => panic!("unsigned dump was accepted"),
+        }
+
+        std::fs::remove_file(path)?;
+        std::fs::remove_file(unsigned_path)?;
+        Ok(())
+    }
+
+    /// Tests ed25519 signed dumps failing to verify once tampered with or
+    /// with another key
+    #[cfg(feature = "ed25519")]
+    #[test]
+    fn ed25519_tampered() -> Result<(), error::DatabaseError> {
+        let path = PathBuf::from("ed25519_tampered.tinydb");
+        let signer = Ed25519Signer::from_bytes(&[42; 32]);
+        let verifier = Ed25519Verifier::from_bytes(&signer.verifier().to_bytes()).unwrap();
+        let mut db = Database::new("Ed25519 test", path.clone(), false);
+
+        db.add_item(String::from("dictionary"))?;
+        db.dump_signed(&signer)?;
+
+        assert!(Database::<String>::from_verified(path.clone(), &verifier).is_ok());
+
+        let other = Ed25519Signer::from_bytes(&[7; 32]).verifier();
+
+        match Database::<String>::from_verified(path.clone(), &other) {
+            Err(error::DatabaseError::SignatureInvalid) => (),
+            _ => panic!("wrong key was accepted"),
+        }
+
+        let mut stream = std::fs::read(&path)?;
+        let last_char = stream.iter().rposition(|byte| *byte == b'y').unwrap();
+
+        stream[last_char] = b'Y';
+        std::fs::write(&path, stream)?;
+
+        match Database::<String>::from_verified(path.clone(), &verifier) {
+            Err(error::DatabaseError::SignatureInvalid) => (),
+            _ => panic!("tampered dump was accepted"),
+        }
+
+        std::fs::remove_file(path)?;
+        Ok(())
+    }
+}
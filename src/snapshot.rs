@@ -0,0 +1,476 @@
+//! Contains [Snapshot], a frozen copy of a [crate::Database] which stays the
+//! same whilst the database carries on changing, see
+//! [crate::Database::snapshot].
+
+#[cfg(feature = "std")]
+use crate::{error, metrics};
+use crate::{meta, Database};
+use alloc::sync::Arc;
+use core::hash;
+use core::ops::{Deref, DerefMut};
+use core::sync::atomic::{AtomicU64, Ordering};
+#[cfg(feature = "std")]
+use serde::Serialize;
+#[cfg(feature = "std")]
+use std::thread::{self, JoinHandle};
+#[cfg(feature = "std")]
+use std::time::SystemTime;
+
+/// An immutable copy of a database as it was when the snapshot was taken,
+/// for long running reads such as generating reports or dumping which
+/// shouldn't see changes made partway through.
+///
+/// Snapshots are cheap to clone as each clone shares the same copy, and can
+/// be sent to other threads if the items can be.
+#[derive(Debug)]
+pub struct Snapshot<T: hash::Hash + Eq> {
+    db: Arc<Database<T>>,
+    taken_at: u64,
+    source: Arc<AtomicU64>,
+    version: u64,
+    #[cfg(feature = "std")]
+    marks: meta::DumpMarks,
+    #[cfg(feature = "std")]
+    unsaved: usize,
+}
+
+impl<T: hash::Hash + Eq> Snapshot<T> {
+    /// Gets the database as it was when this snapshot was taken, which has
+    /// every read-only method of a live database including
+    /// [Database::dump_db].
+    pub fn db(&self) -> &Database<T> {
+        &self.db
+    }
+
+    /// Gets when this snapshot was taken.
+    #[cfg(feature = "std")]
+    pub fn taken_at(&self) -> SystemTime {
+        meta::from_millis(self.taken_at)
+    }
+
+    /// Iterates over every item which hadn't expired when the snapshot was
+    /// taken, in the same order as [Database::ordered].
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.db
+            .ordered()
+            .filter(move |item| !self.db.expiries.is_expired(item, self.taken_at))
+    }
+
+    /// Gets how many items there were when the snapshot was taken, including
+    /// any which had expired but hadn't been purged yet.
+    pub fn len(&self) -> usize {
+        self.db.items.len()
+    }
+
+    /// Checks if there were no items when the snapshot was taken.
+    pub fn is_empty(&self) -> bool {
+        self.db.items.is_empty()
+    }
+
+    /// Checks if `db` is the database this snapshot was taken from and it
+    /// hasn't changed since.
+    pub fn is_current(&self, db: &Database<T>) -> bool {
+        Arc::ptr_eq(&self.source, &db.version.0)
+            && db.version.get() == self.version
+            && db.label == self.db.label
+            && db.save_path == self.db.save_path
+    }
+
+    /// Brings this snapshot up-to-date with `db`, only copying it again if it
+    /// has changed since the snapshot was taken, returning if it did. This
+    /// makes regularly snapshotting a database which rarely changes, such as
+    /// before every autosave, almost free.
+    ///
+    /// Changes made directly to [Database::items] aren't noticed, so should
+    /// be avoided whilst refreshing snapshots.
+    pub fn refresh(&mut self, db: &Database<T>) -> bool
+    where
+        T: Clone,
+    {
+        if self.is_current(db) {
+            self.taken_at = meta::now_millis();
+            return false;
+        }
+
+        *self = db.snapshot();
+        true
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: hash::Hash + Eq + Serialize + Send + Sync + 'static> Snapshot<T> {
+    /// Dumps this snapshot using [Database::dump_db] on a new thread,
+    /// returning a handle to wait for the result with.
+    ///
+    /// Once the dump has been written, the database the snapshot was taken
+    /// from is marked as dumped if it hasn't changed since, so it's no longer
+    /// [dirty](Database::is_dirty). If it has changed, it stays dirty as the
+    /// dump is missing those changes.
+    pub fn dump_in_background(
+        &self,
+    ) -> JoinHandle<Result<metrics::DumpReport, error::DatabaseError>> {
+        let snapshot = self.clone();
+
+        thread::spawn(move || {
+            let report = snapshot.db.dump_db()?;
+
+            if snapshot.source.load(Ordering::SeqCst) == snapshot.version {
+                let dumped_at = snapshot.db.meta.last_dumped.load(Ordering::SeqCst);
+
+                snapshot.marks.dumped(dumped_at, snapshot.unsaved);
+            }
+
+            Ok(report)
+        })
+    }
+}
+
+impl<T: hash::Hash + Eq> Clone for Snapshot<T> {
+    fn clone(&self) -> Self {
+        Snapshot {
+            db: Arc::clone(&self.db),
+            taken_at: self.taken_at,
+            source: Arc::clone(&self.source),
+            version: self.version,
+            #[cfg(feature = "std")]
+            marks: self.marks.clone(),
+            #[cfg(feature = "std")]
+            unsaved: self.unsaved,
+        }
+    }
+}
+
+/// Counter of changes made to a database, which is bumped at the start of
+/// any method which mutates it so snapshots can tell if they're still
+/// current.
+///
+/// Each database has its own counter, so a cloned database starts a new one.
+#[derive(Debug, Default)]
+pub(crate) struct Version(Arc<AtomicU64>);
+
+impl Version {
+    /// Marks the database as having changed.
+    pub(crate) fn bump(&self) {
+        self.0.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Gets how many times the database has changed.
+    fn get(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+impl Clone for Version {
+    fn clone(&self) -> Self {
+        Version::default()
+    }
+}
+
+/// State tracked alongside the items, such as indexes, which clones and
+/// snapshots share until either of them changes it. The first change made
+/// whilst shared copies it, which is skipped entirely for state that hasn't
+/// been enabled as its copy is empty.
+#[derive(Debug, Default)]
+pub(crate) struct SharedState<S>(Arc<S>);
+
+impl<S> SharedState<S> {
+    /// Starts sharing `state`.
+    pub(crate) fn new(state: S) -> Self {
+        SharedState(Arc::new(state))
+    }
+}
+
+impl<S> Clone for SharedState<S> {
+    fn clone(&self) -> Self {
+        SharedState(Arc::clone(&self.0))
+    }
+}
+
+impl<S> Deref for SharedState<S> {
+    type Target = S;
+
+    fn deref(&self) -> &S {
+        &self.0
+    }
+}
+
+impl<S: Clone> DerefMut for SharedState<S> {
+    fn deref_mut(&mut self) -> &mut S {
+        Arc::make_mut(&mut self.0)
+    }
+}
+
+impl<T: hash::Hash + Eq + Clone> Database<T> {
+    /// Takes a [Snapshot] of this database as it is right now, which won't
+    /// see any changes made afterwards. Settings such as
+    /// [Database::save_path] are kept so the snapshot can be dumped in place
+    /// of the live database.
+    ///
+    /// Taking a snapshot doesn't copy any items, as the snapshot shares
+    /// [Database::items] with this database until either of them changes.
+    /// Only the first change made afterwards copies the items, so snapshots
+    /// of a database which rarely changes are almost free. Indexes, history
+    /// and other tracked state are shared in the same way. With the `im`
+    /// feature enabled, that first change only copies the few items around
+    /// it rather than all of them, see [crate::items::Items].
+    ///
+    /// The snapshot itself can be cloned and shared between threads for
+    /// free, and [Snapshot::refresh] only takes a new one once something
+    /// changes.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tinydb::Database;
+    ///
+    /// fn main() {
+    ///     let mut db = Database::new("snapshot_test", None, false);
+    ///
+    ///     db.add_item(String::from("Lister")).unwrap();
+    ///
+    ///     let mut snapshot = db.snapshot();
+    ///
+    ///     db.add_item(String::from("Rimmer")).unwrap();
+    ///     db.remove_item(&String::from("Lister")).unwrap();
+    ///
+    ///     assert!(snapshot.db().contains(&String::from("Lister")));
+    ///     assert_eq!(snapshot.len(), 1);
+    ///
+    ///     assert!(snapshot.refresh(&db));
+    ///     assert!(!snapshot.refresh(&db));
+    ///     assert!(snapshot.db().contains(&String::from("Rimmer")));
+    /// }
+    /// ```
+    pub fn snapshot(&self) -> Snapshot<T> {
+        let version = self.version.get();
+
+        Snapshot {
+            db: Arc::new(self.clone()),
+            taken_at: meta::now_millis(),
+            source: Arc::clone(&self.version.0),
+            version,
+            #[cfg(feature = "std")]
+            marks: self.meta.marks(),
+            #[cfg(feature = "std")]
+            unsaved: self.unsaved_changes(),
+        }
+    }
+
+    /// Dumps this database on a new thread without blocking, returning a
+    /// handle to wait for the result with. A [Snapshot] is taken first so
+    /// the dump is consistent whilst this database carries on changing,
+    /// sharing its items rather than copying them on the calling thread.
+    ///
+    /// Once the dump has been written, this database is marked as dumped if
+    /// nothing has changed since, otherwise it stays
+    /// [dirty](Database::is_dirty). The dump is written to a temporary file
+    /// which is renamed into place, so it never clashes with a
+    /// [Database::dump_db] of the live database. Use
+    /// [Snapshot::dump_in_background] to reuse a snapshot which has already
+    /// been taken.
+    ///
+    /// Changing this database whilst the dump is running copies whatever is
+    /// changed, as the snapshot still holds onto it. With the `im` feature
+    /// that's only a few items rather than all of them, see
+    /// [crate::items::Items].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tinydb::Database;
+    ///
+    /// fn main() {
+    ///     let mut db = Database::new("dump_in_background_test", None, false);
+    ///
+    ///     db.add_item(String::from("Kryten")).unwrap();
+    ///
+    ///     let dumping = db.dump_in_background();
+    ///
+    ///     db.add_item(String::from("Talkie Toaster")).unwrap();
+    ///     dumping.join().unwrap().unwrap();
+    ///
+    ///     assert!(db.is_dirty());
+    ///
+    ///     let dumped: Database<String> = Database::from("dump_in_background_test.tinydb").unwrap();
+    ///
+    ///     assert_eq!(dumped.items.len(), 1);
+    ///     # std::fs::remove_file("dump_in_background_test.tinydb").unwrap();
+    /// }
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn dump_in_background(
+        &self,
+    ) -> JoinHandle<Result<metrics::DumpReport, error::DatabaseError>>
+    where
+        T: Serialize + Send + Sync + 'static,
+    {
+        self.snapshot().dump_in_background()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// Tests snapshots staying the same whilst being dumped from another
+    /// thread as the live database changes
+    #[test]
+    fn snapshot_isolated() -> Result<(), error::DatabaseError> {
+        let path = PathBuf::from("snapshot_isolated.tinydb");
+        let mut db = Database::new("Snapshot test", path.clone(), false);
+
+        for item in 0..100u32 {
+            db.add_item(item)?;
+        }
+
+        let snapshot = db.snapshot();
+        let dumper = snapshot.dump_in_background();
+
+        db.take_where(|item| item % 2 == 0);
+        dumper.join().unwrap()?;
+
+        assert_eq!(snapshot.iter().count(), 100);
+        assert_eq!(db.items.len(), 50);
+        assert_eq!(Database::<u32>::from(path.clone())?.items.len(), 100);
+
+        std::fs::remove_file(path)?;
+        Ok(())
+    }
+
+    /// Tests background dumps marking the live database as dumped only if it
+    /// hasn't changed since the snapshot was taken
+    #[test]
+    fn background_dump_marks() -> Result<(), error::DatabaseError> {
+        let path = PathBuf::from("background_dump_marks.tinydb");
+        let mut db = Database::new("Marks test", path.clone(), false);
+
+        db.add_item(1u32)?;
+        db.dump_in_background().join().unwrap()?;
+
+        assert!(!db.is_dirty());
+        assert!(db.last_dumped().is_some());
+
+        db.add_item(2)?;
+
+        let snapshot = db.snapshot();
+
+        db.add_item(3)?;
+        snapshot.dump_in_background().join().unwrap()?;
+
+        assert!(db.is_dirty());
+        assert_eq!(db.unsaved_changes(), 2);
+
+        std::fs::remove_file(path)?;
+        Ok(())
+    }
+
+    /// Tests background dumps and live dumps of the same path never leaving
+    /// a half-written file or temporary files behind
+    #[test]
+    fn background_dump_atomic() -> Result<(), error::DatabaseError> {
+        let dir = PathBuf::from("background_dump_atomic");
+        let path = dir.join("db.tinydb");
+
+        std::fs::create_dir_all(&dir)?;
+
+        let mut db = Database::new("Atomic test", path.clone(), false);
+
+        for item in 0..10_000u32 {
+            db.add_item(item)?;
+        }
+
+        for _ in 0..5 {
+            let dumper = db.dump_in_background();
+
+            db.dump_db()?;
+            dumper.join().unwrap()?;
+
+            assert_eq!(Database::<u32>::from(path.clone())?.items.len(), 10_000);
+        }
+
+        assert_eq!(std::fs::read_dir(&dir)?.count(), 1);
+
+        std::fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+
+    /// Tests refreshing only copying again after changes, including pins,
+    /// renames and snapshots of another database
+    #[test]
+    fn snapshot_refresh() -> Result<(), error::DatabaseError> {
+        let mut db = Database::new("Refresh test", None, false);
+
+        db.add_item(1u32)?;
+
+        let mut snapshot = db.snapshot();
+        let copy = Arc::clone(&snapshot.db);
+
+        assert!(!snapshot.refresh(&db));
+        assert!(Arc::ptr_eq(&snapshot.db, &copy));
+
+        db.move_to_front(&1)?;
+
+        assert!(snapshot.refresh(&db));
+
+        db.label = String::from("Renamed");
+
+        assert!(snapshot.refresh(&db));
+        assert_eq!(snapshot.db().label, "Renamed");
+        assert!(!snapshot.is_current(&db.clone()));
+
+        db.add_item(2)?;
+
+        assert!(snapshot.refresh(&db));
+        assert_eq!(snapshot.len(), 2);
+        Ok(())
+    }
+
+    /// Tests snapshots and refreshes sharing items with the live database
+    /// until it changes
+    #[test]
+    fn snapshot_shares_items() -> Result<(), error::DatabaseError> {
+        let mut db = Database::new("Sharing test", None, false);
+
+        for item in 0..100u32 {
+            db.add_item(item)?;
+        }
+
+        let mut snapshot = db.snapshot();
+
+        assert!(snapshot.db().items.ptr_eq(&db.items));
+
+        db.add_item(100)?;
+
+        assert!(!snapshot.db().items.ptr_eq(&db.items));
+        assert_eq!(snapshot.len(), 100);
+        assert!(snapshot.refresh(&db));
+        assert!(snapshot.db().items.ptr_eq(&db.items));
+        assert!(snapshot.clone().db().items.ptr_eq(&db.items));
+        Ok(())
+    }
+
+    /// Tests snapshots sharing indexes and history with the live database
+    /// until it changes them
+    #[test]
+    fn snapshot_shares_state() -> Result<(), error::DatabaseError> {
+        let mut db = Database::new("Shared state test", None, false);
+
+        db.enable_history(10);
+        db.create_prefix_index("self", |item: &String| item.clone());
+        db.add_item(String::from("Holly"))?;
+
+        let snapshot = db.snapshot();
+
+        assert!(Arc::ptr_eq(&snapshot.db().indexes.0, &db.indexes.0));
+        assert!(Arc::ptr_eq(&snapshot.db().history.0, &db.history.0));
+
+        db.add_item(String::from("Hollister"))?;
+
+        assert!(!Arc::ptr_eq(&snapshot.db().indexes.0, &db.indexes.0));
+        assert_eq!(snapshot.db().query_prefix("self", "Holl")?.len(), 1);
+        assert_eq!(db.query_prefix("self", "Holl")?.len(), 2);
+        assert!(snapshot.db().can_undo());
+        Ok(())
+    }
+}
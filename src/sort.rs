@@ -0,0 +1,159 @@
+//! Contains sorting items with custom comparators, see
+//! [crate::Database::sorted_by] and [crate::Database::top_k].
+
+use crate::compat::Vec;
+use crate::Database;
+use alloc::collections::BinaryHeap;
+use core::cmp::Ordering;
+use core::hash;
+
+/// An item found whilst looking for the top `k` items, which orders by the
+/// comparator given and then by where the item was found so ties keep the
+/// same order as [Database::sorted_by].
+struct Ranked<'a, 'c, T, F> {
+    item: &'a T,
+    position: usize,
+    compare: &'c F,
+}
+
+impl<T, F: Fn(&T, &T) -> Ordering> Ord for Ranked<'_, '_, T, F> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.compare)(self.item, other.item).then(self.position.cmp(&other.position))
+    }
+}
+
+impl<T, F: Fn(&T, &T) -> Ordering> PartialOrd for Ranked<'_, '_, T, F> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T, F: Fn(&T, &T) -> Ordering> PartialEq for Ranked<'_, '_, T, F> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<T, F: Fn(&T, &T) -> Ordering> Eq for Ranked<'_, '_, T, F> {}
+
+impl<T: hash::Hash + Eq> Database<T> {
+    /// Gets every item sorted by `compare`, with items it finds equal kept in
+    /// the same order as [Database::ordered]. Expired items are skipped.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tinydb::Database;
+    ///
+    /// fn main() {
+    ///     let mut db = Database::new("sorted_by_test", None, false);
+    ///
+    ///     for name in &["Lister", "Cat", "Rimmer"] {
+    ///         db.add_item(name.to_string()).unwrap();
+    ///     }
+    ///
+    ///     assert_eq!(db.sorted_by(|a, b| a.len().cmp(&b.len()).then(a.cmp(b))), vec!["Cat", "Lister", "Rimmer"]);
+    /// }
+    /// ```
+    pub fn sorted_by(&self, compare: impl Fn(&T, &T) -> Ordering) -> Vec<&T> {
+        let mut sorted: Vec<&T> = self
+            .ordered()
+            .filter(|item| !self.is_expired(item))
+            .collect();
+
+        sorted.sort_by(|a, b| compare(a, b));
+        sorted
+    }
+
+    /// Gets the first `k` items [Database::sorted_by] would give using
+    /// `compare`, without sorting every item. Only `k` items are held at a
+    /// time, so this suits finding a handful of items in a large database.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tinydb::Database;
+    ///
+    /// fn main() {
+    ///     let mut db = Database::new("top_k_test", None, false);
+    ///
+    ///     for (file, opened_at) in &[("a.rs", 30), ("b.rs", 10), ("c.rs", 50), ("d.rs", 20)] {
+    ///         db.add_item((file.to_string(), *opened_at)).unwrap();
+    ///     }
+    ///
+    ///     let recent = db.top_k(2, |a, b| b.1.cmp(&a.1));
+    ///
+    ///     assert_eq!(recent, vec![&(String::from("c.rs"), 50), &(String::from("a.rs"), 30)]);
+    /// }
+    /// ```
+    pub fn top_k(&self, k: usize, compare: impl Fn(&T, &T) -> Ordering) -> Vec<&T> {
+        self.top_k_where(k, |_| true, compare)
+    }
+
+    /// Gets the first `k` items matching `pred` using `compare`, the same as
+    /// [Database::top_k] but skipping items which don't match.
+    pub fn top_k_where(
+        &self,
+        k: usize,
+        mut pred: impl FnMut(&T) -> bool,
+        compare: impl Fn(&T, &T) -> Ordering,
+    ) -> Vec<&T> {
+        if k == 0 {
+            return Vec::new();
+        }
+
+        let mut heap = BinaryHeap::with_capacity(k + 1);
+        let found = self
+            .ordered()
+            .filter(|item| !self.is_expired(item) && pred(item));
+
+        for (position, item) in found.enumerate() {
+            heap.push(Ranked {
+                item,
+                position,
+                compare: &compare,
+            });
+
+            if heap.len() > k {
+                heap.pop();
+            }
+        }
+
+        heap.into_sorted_vec()
+            .into_iter()
+            .map(|ranked| ranked.item)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error;
+
+    /// Tests the top items matching a full sort, including ties and pinned
+    /// items
+    #[test]
+    fn top_k_matches_sort() -> Result<(), error::DatabaseError> {
+        let mut db = Database::new("Sort test", None, false);
+
+        for item in 0..100u32 {
+            db.add_item(item)?;
+        }
+
+        db.move_to_front(&7)?;
+
+        let by_tens = |a: &u32, b: &u32| (a / 10).cmp(&(b / 10));
+        let sorted = db.sorted_by(by_tens);
+
+        assert_eq!(sorted[0], &7);
+        assert_eq!(db.top_k(15, by_tens), sorted[..15].to_vec());
+        assert_eq!(db.top_k(1000, by_tens), sorted);
+        assert!(db.top_k(0, by_tens).is_empty());
+        assert_eq!(
+            db.top_k_where(3, |item| item % 2 == 1, |a, b| b.cmp(a)),
+            vec![&99, &97, &95]
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,400 @@
+//! Contains exporting items into SQLite, so data kept in tinydb can be moved
+//! into an SQL database once a project outgrows it. This is only available
+//! with the `sqlite` feature enabled.
+//!
+//! Items can either be written straight into an SQLite database file using
+//! [rusqlite], or as a plain text script in the SQLite dialect which has to
+//! be run against a database afterwards, for example using
+//! `sqlite3 target.db < export.sql`.
+
+use crate::value::Value;
+use crate::{error, json, Database};
+use rusqlite::types::Value as SqlValue;
+use rusqlite::Connection;
+use serde::Serialize;
+use std::convert::TryFrom;
+use std::fs::File;
+use std::hash;
+use std::io::{self, prelude::*, BufWriter};
+use std::path::PathBuf;
+
+/// Items flattened into the columns and rows of a table, alongside the type
+/// of each column if any of its values said.
+struct Table {
+    columns: Vec<(String, Option<&'static str>)>,
+    rows: Vec<Vec<(String, Value)>>,
+}
+
+impl Table {
+    /// Gets the column definitions used to create the table.
+    fn definitions(&self) -> String {
+        let definitions: Vec<String> = self
+            .columns
+            .iter()
+            .map(|(name, kind)| format!("{} {}", quote_identifier(name), kind.unwrap_or("TEXT")))
+            .collect();
+
+        definitions.join(", ")
+    }
+
+    /// Gets the value of every column for a row, in the same order as the
+    /// columns.
+    fn cells<'a>(&'a self, fields: &'a [(String, Value)]) -> impl Iterator<Item = &'a Value> {
+        self.columns.iter().map(move |(name, _)| {
+            match fields.iter().find(|(field, _)| field == name) {
+                Some((_, value)) => value,
+                None => &Value::Null,
+            }
+        })
+    }
+}
+
+impl<T: hash::Hash + Eq + Serialize> Database<T> {
+    /// Exports all items into an SQLite database file at `path`, creating it
+    /// if it doesn't exist, as a table called `table_name` containing one row
+    /// per item. Any existing table with the same name is replaced, whereas
+    /// other tables inside of the file are left alone.
+    ///
+    /// Each field of a struct-shaped item becomes a column, whereas any other
+    /// item is put into a single `value` column. Column types are taken from
+    /// the first non-null value in each column and nested fields such as
+    /// sequences or structs are stored as JSON text.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use serde::{Deserialize, Serialize};
+    /// use tinydb::Database;
+    ///
+    /// #[derive(Hash, Eq, PartialEq, Serialize, Deserialize)]
+    /// struct Crew {
+    ///     name: String,
+    ///     ranks: Vec<u8>,
+    /// }
+    ///
+    /// fn main() {
+    ///     let mut db = Database::new("crew", None, false);
+    ///
+    ///     db.add_item(Crew { name: String::from("Lister"), ranks: vec![1] }).unwrap();
+    ///     db.export_sqlite("crew.sqlite", "crew").unwrap();
+    ///     # std::fs::remove_file("crew.sqlite").unwrap();
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns [error::DatabaseError::ConversionError] if an item can't be
+    /// converted, such as a map with non-string keys, or
+    /// [error::DatabaseError::IOError] if SQLite fails to write the table.
+    pub fn export_sqlite(
+        &self,
+        path: impl Into<PathBuf>,
+        table_name: &str,
+    ) -> Result<(), error::DatabaseError> {
+        let table = self.table()?;
+        let name = quote_identifier(table_name);
+        let mut connection = Connection::open(path.into()).map_err(sqlite_error)?;
+        let transaction = connection.transaction().map_err(sqlite_error)?;
+
+        transaction
+            .execute_batch(&format!(
+                "DROP TABLE IF EXISTS {}; CREATE TABLE {} ({});",
+                name,
+                name,
+                table.definitions()
+            ))
+            .map_err(sqlite_error)?;
+
+        {
+            let placeholders = vec!["?"; table.columns.len()].join(", ");
+            let mut insert = transaction
+                .prepare(&format!("INSERT INTO {} VALUES ({})", name, placeholders))
+                .map_err(sqlite_error)?;
+
+            for fields in table.rows.iter() {
+                insert
+                    .execute(rusqlite::params_from_iter(
+                        table.cells(fields).map(sql_value),
+                    ))
+                    .map_err(sqlite_error)?;
+            }
+        }
+
+        transaction.commit().map_err(sqlite_error)
+    }
+
+    /// Exports all items to a plain text SQL script at `path`, rather than an
+    /// SQLite database file, which creates a table called `table_name`
+    /// containing one row per item, replacing the table if it already exists
+    /// when the script is run. Columns are the same as
+    /// [Database::export_sqlite].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use serde::{Deserialize, Serialize};
+    /// use tinydb::Database;
+    ///
+    /// #[derive(Hash, Eq, PartialEq, Serialize, Deserialize)]
+    /// struct Crew {
+    ///     name: String,
+    ///     ranks: Vec<u8>,
+    /// }
+    ///
+    /// fn main() {
+    ///     let mut db = Database::new("crew", None, false);
+    ///
+    ///     db.add_item(Crew { name: String::from("Lister"), ranks: vec![1] }).unwrap();
+    ///     db.export_sql_script("crew.sql", "crew").unwrap();
+    ///
+    ///     let script = std::fs::read_to_string("crew.sql").unwrap();
+    ///
+    ///     assert!(script.contains("INSERT INTO \"crew\" VALUES ('Lister', '[1]');"));
+    ///     # std::fs::remove_file("crew.sql").unwrap();
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns [error::DatabaseError::ConversionError] if an item can't be
+    /// converted, such as a map with non-string keys.
+    pub fn export_sql_script(
+        &self,
+        path: impl Into<PathBuf>,
+        table_name: &str,
+    ) -> Result<(), error::DatabaseError> {
+        let table = self.table()?;
+        let name = quote_identifier(table_name);
+        let mut writer = BufWriter::new(File::create(path.into())?);
+
+        writeln!(writer, "BEGIN TRANSACTION;")?;
+        writeln!(writer, "DROP TABLE IF EXISTS {};", name)?;
+        writeln!(writer, "CREATE TABLE {} ({});", name, table.definitions())?;
+
+        for fields in table.rows.iter() {
+            let literals: Vec<String> = table.cells(fields).map(literal).collect();
+
+            writeln!(
+                writer,
+                "INSERT INTO {} VALUES ({});",
+                name,
+                literals.join(", ")
+            )?;
+        }
+
+        writeln!(writer, "COMMIT;")?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Flattens every item which hasn't expired into a [Table].
+    fn table(&self) -> Result<Table, error::DatabaseError> {
+        let mut columns: Vec<(String, Option<&'static str>)> = Vec::new();
+        let mut rows = Vec::with_capacity(self.items.len());
+
+        for item in self.ordered().filter(|item| !self.is_expired(item)) {
+            let fields = match Value::from_item(item) {
+                Ok(Value::Map(fields)) => fields,
+                Ok(value) => vec![(String::from("value"), value)],
+                Err(err) => return Err(error::DatabaseError::ConversionError(err.0)),
+            };
+
+            for (name, value) in fields.iter() {
+                match columns.iter_mut().find(|(column, _)| column == name) {
+                    Some((_, kind @ None)) => *kind = column_type(value),
+                    Some(_) => (),
+                    None => columns.push((name.clone(), column_type(value))),
+                }
+            }
+
+            rows.push(fields);
+        }
+
+        if columns.is_empty() {
+            columns.push((String::from("value"), None));
+        }
+
+        Ok(Table { columns, rows })
+    }
+}
+
+/// Turns an error from SQLite into an [error::DatabaseError::IOError].
+fn sqlite_error(err: rusqlite::Error) -> error::DatabaseError {
+    io::Error::other(err).into()
+}
+
+/// Converts a value into one SQLite can bind, using the same types as
+/// [column_type]. Unsigned integers too large for SQLite are stored as text
+/// so they aren't rounded.
+fn sql_value(value: &Value) -> SqlValue {
+    match value {
+        Value::Null => SqlValue::Null,
+        Value::Bool(value) => SqlValue::Integer(*value as i64),
+        Value::Int(value) => SqlValue::Integer(*value),
+        Value::UInt(value) => match i64::try_from(*value) {
+            Ok(value) => SqlValue::Integer(value),
+            Err(_) => SqlValue::Text(value.to_string()),
+        },
+        Value::Float(value) if value.is_finite() => SqlValue::Real(*value),
+        Value::Float(_) => SqlValue::Null,
+        Value::String(value) => SqlValue::Text(value.clone()),
+        Value::Bytes(bytes) => SqlValue::Blob(bytes.clone()),
+        Value::Seq(_) | Value::Map(_) => SqlValue::Text(json::write(value)),
+    }
+}
+
+/// Gets the SQLite column type for a value, or [Option::None] if it's null and
+/// so says nothing about the column.
+fn column_type(value: &Value) -> Option<&'static str> {
+    match value {
+        Value::Null => None,
+        Value::Bool(_) | Value::Int(_) | Value::UInt(_) => Some("INTEGER"),
+        Value::Float(_) => Some("REAL"),
+        Value::Bytes(_) => Some("BLOB"),
+        Value::String(_) | Value::Seq(_) | Value::Map(_) => Some("TEXT"),
+    }
+}
+
+/// Writes a value as an SQLite literal.
+fn literal(value: &Value) -> String {
+    match value {
+        Value::Null => String::from("NULL"),
+        Value::Bool(value) => String::from(if *value { "1" } else { "0" }),
+        Value::Int(value) => value.to_string(),
+        Value::UInt(value) => value.to_string(),
+        Value::Float(value) if value.is_finite() => value.to_string(),
+        Value::Float(_) => String::from("NULL"),
+        Value::String(value) => quote_string(value),
+        Value::Bytes(bytes) => {
+            let hex: String = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+
+            format!("X'{}'", hex)
+        }
+        Value::Seq(_) | Value::Map(_) => quote_string(&json::write(value)),
+    }
+}
+
+/// Quotes a string literal, doubling any single quotes inside of it.
+fn quote_string(string: &str) -> String {
+    format!("'{}'", string.replace('\'', "''"))
+}
+
+/// Quotes a table or column name, doubling any double quotes inside of it.
+fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+    use std::fs;
+
+    /// Item with nested and optional fields
+    #[derive(Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
+    struct Crew {
+        name: String,
+        alive: bool,
+        nickname: Option<String>,
+        ranks: Vec<u8>,
+    }
+
+    /// Tests the generated script for struct-shaped items
+    #[test]
+    fn sql_export() -> Result<(), error::DatabaseError> {
+        let path = PathBuf::from("sql_export.sql");
+        let mut db = Database::new("SQL test", None, false);
+
+        db.add_item(Crew {
+            name: String::from("Rimmer's hologram"),
+            alive: false,
+            nickname: None,
+            ranks: vec![1, 2],
+        })?;
+        db.export_sql_script(path.clone(), "crew \"members\"")?;
+
+        assert_eq!(
+            fs::read_to_string(&path)?,
+            concat!(
+                "BEGIN TRANSACTION;\n",
+                "DROP TABLE IF EXISTS \"crew \"\"members\"\"\";\n",
+                "CREATE TABLE \"crew \"\"members\"\"\" (\"name\" TEXT, \"alive\" INTEGER, ",
+                "\"nickname\" TEXT, \"ranks\" TEXT);\n",
+                "INSERT INTO \"crew \"\"members\"\"\" VALUES ('Rimmer''s hologram', 0, NULL, '[1,2]');\n",
+                "COMMIT;\n"
+            )
+        );
+
+        fs::remove_file(path)?;
+        Ok(())
+    }
+
+    /// Tests items which aren't structs going into a single column
+    #[test]
+    fn sql_export_values() -> Result<(), error::DatabaseError> {
+        let path = PathBuf::from("sql_export_values.sql");
+        let mut db = Database::new("SQL test", None, false);
+
+        db.add_item(-5i64)?;
+        db.export_sql_script(path.clone(), "numbers")?;
+
+        let script = fs::read_to_string(&path)?;
+
+        assert!(script.contains("CREATE TABLE \"numbers\" (\"value\" INTEGER);"));
+        assert!(script.contains("INSERT INTO \"numbers\" VALUES (-5);"));
+
+        fs::remove_file(path)?;
+        Ok(())
+    }
+
+    /// Tests rows written into an SQLite file being read back
+    #[test]
+    fn sqlite_export() -> Result<(), error::DatabaseError> {
+        let path = PathBuf::from("sqlite_export.sqlite");
+        let mut db = Database::new("SQLite test", None, false);
+
+        db.add_item(Crew {
+            name: String::from("Lister"),
+            alive: true,
+            nickname: Some(String::from("Davey")),
+            ranks: vec![3],
+        })?;
+        db.add_item(Crew {
+            name: String::from("Rimmer"),
+            alive: false,
+            nickname: None,
+            ranks: vec![1, 2],
+        })?;
+        db.export_sqlite(path.clone(), "crew")?;
+        db.export_sqlite(path.clone(), "crew")?;
+
+        let connection = Connection::open(&path).map_err(sqlite_error)?;
+        let mut select = connection
+            .prepare("SELECT name, alive, nickname, ranks FROM crew ORDER BY name")
+            .map_err(sqlite_error)?;
+        let rows: Vec<(String, bool, Option<String>, String)> = select
+            .query_map([], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+            })
+            .and_then(|rows| rows.collect())
+            .map_err(sqlite_error)?;
+
+        assert_eq!(
+            rows,
+            vec![
+                (
+                    String::from("Lister"),
+                    true,
+                    Some(String::from("Davey")),
+                    String::from("[3]")
+                ),
+                (String::from("Rimmer"), false, None, String::from("[1,2]")),
+            ]
+        );
+
+        drop(select);
+        drop(connection);
+        fs::remove_file(path)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,310 @@
+//! Contains running statistics which can be tracked against a [crate::Database]
+//! without rescanning all items every time they are needed.
+
+use crate::compat::{HashMap, String};
+use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
+use core::fmt;
+
+/// The kind of running statistic to keep for a tracked field, used inside of
+/// [crate::Database::track_stat].
+///
+/// Every kind keeps a count and a sum, [Stat::MinMaxMean] additionally keeps
+/// an ordered tally of values so the minimum and maximum stay correct when
+/// items are removed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Stat {
+    /// Only count how many items have been tracked.
+    Count,
+
+    /// Keep a running sum (and count) of the tracked field.
+    Sum,
+
+    /// Keep a running mean (and therefore sum and count) of the tracked field.
+    Mean,
+
+    /// Keep everything [Stat::Mean] does alongside the minimum and maximum.
+    MinMaxMean,
+}
+
+/// A snapshot of a tracked statistic, returned from [crate::Database::stat].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StatSummary {
+    /// Amount of items currently contributing to this statistic.
+    pub count: usize,
+
+    /// Sum of every tracked value.
+    pub sum: f64,
+
+    /// Smallest tracked value, only avalible with [Stat::MinMaxMean].
+    pub min: Option<f64>,
+
+    /// Largest tracked value, only avalible with [Stat::MinMaxMean].
+    pub max: Option<f64>,
+}
+
+impl StatSummary {
+    /// Gets the mean of all tracked values or [Option::None] if no items are
+    /// currently being tracked.
+    pub fn mean(&self) -> Option<f64> {
+        if self.count == 0 {
+            None
+        } else {
+            Some(self.sum / self.count as f64)
+        }
+    }
+}
+
+/// A single statistic being tracked, with the extractor used to get the
+/// relevant value from each item.
+struct TrackedStat<T> {
+    kind: Stat,
+    extractor: Arc<dyn Fn(&T) -> f64 + Send + Sync>,
+    count: usize,
+    sum: f64,
+    values: BTreeMap<u64, usize>,
+}
+
+impl<T> TrackedStat<T> {
+    fn insert(&mut self, item: &T) {
+        let value = (self.extractor)(item);
+
+        self.count += 1;
+        self.sum += value;
+
+        if self.kind == Stat::MinMaxMean {
+            *self.values.entry(ordered_bits(value)).or_insert(0) += 1;
+        }
+    }
+
+    fn remove(&mut self, item: &T) {
+        let value = (self.extractor)(item);
+
+        self.count -= 1;
+        self.sum -= value;
+
+        if self.kind == Stat::MinMaxMean {
+            let key = ordered_bits(value);
+
+            if let Some(amount) = self.values.get_mut(&key) {
+                *amount -= 1;
+
+                if *amount == 0 {
+                    self.values.remove(&key);
+                }
+            }
+        }
+    }
+
+    fn summary(&self) -> StatSummary {
+        StatSummary {
+            count: self.count,
+            sum: self.sum,
+            min: self
+                .values
+                .keys()
+                .next()
+                .map(|bits| from_ordered_bits(*bits)),
+            max: self
+                .values
+                .keys()
+                .next_back()
+                .map(|bits| from_ordered_bits(*bits)),
+        }
+    }
+}
+
+impl<T> Clone for TrackedStat<T> {
+    fn clone(&self) -> Self {
+        TrackedStat {
+            kind: self.kind,
+            extractor: Arc::clone(&self.extractor),
+            count: self.count,
+            sum: self.sum,
+            values: self.values.clone(),
+        }
+    }
+}
+
+/// All statistics currently being tracked for a database, kept up-to-date on
+/// each mutation.
+///
+/// Tracked values are derived from the items themselves so two trackers are
+/// considered equal if they track the same kinds of statistic with the same
+/// extractors.
+pub(crate) struct StatTracker<T> {
+    stats: HashMap<String, TrackedStat<T>>,
+}
+
+impl<T> StatTracker<T> {
+    /// Starts tracking a new statistic, feeding in all items which already
+    /// exist.
+    pub(crate) fn track<'a>(
+        &mut self,
+        name: String,
+        kind: Stat,
+        extractor: Arc<dyn Fn(&T) -> f64 + Send + Sync>,
+        existing: impl Iterator<Item = &'a T>,
+    ) where
+        T: 'a,
+    {
+        let mut stat = TrackedStat {
+            kind,
+            extractor,
+            count: 0,
+            sum: 0.0,
+            values: BTreeMap::new(),
+        };
+
+        for item in existing {
+            stat.insert(item);
+        }
+
+        self.stats.insert(name, stat);
+    }
+
+    /// Stops tracking a statistic, returning if it was being tracked.
+    pub(crate) fn untrack(&mut self, name: &str) -> bool {
+        self.stats.remove(name).is_some()
+    }
+
+    /// Checks if no statistics are being tracked.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.stats.is_empty()
+    }
+
+    /// Gets the current summary of a statistic by name.
+    pub(crate) fn get(&self, name: &str) -> Option<StatSummary> {
+        self.stats.get(name).map(TrackedStat::summary)
+    }
+
+    /// Records an item which has just been added to the database.
+    pub(crate) fn insert(&mut self, item: &T) {
+        for stat in self.stats.values_mut() {
+            stat.insert(item);
+        }
+    }
+
+    /// Records an item which has just been removed from the database.
+    pub(crate) fn remove(&mut self, item: &T) {
+        for stat in self.stats.values_mut() {
+            stat.remove(item);
+        }
+    }
+
+    /// Makes sure every statistic has counted `len` items, see
+    /// [crate::testing::check_invariants].
+    #[cfg(feature = "testing")]
+    pub(crate) fn check_consistent(&self, len: usize) -> Result<(), String> {
+        match self.stats.iter().find(|(_, stat)| stat.count != len) {
+            Some((name, stat)) => Err(format!(
+                "statistic `{}` has counted {} items rather than {}",
+                name, stat.count, len
+            )),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<T> Default for StatTracker<T> {
+    fn default() -> Self {
+        StatTracker {
+            stats: HashMap::new(),
+        }
+    }
+}
+
+impl<T> Clone for StatTracker<T> {
+    fn clone(&self) -> Self {
+        StatTracker {
+            stats: self.stats.clone(),
+        }
+    }
+}
+
+impl<T> fmt::Debug for StatTracker<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(self.stats.iter().map(|(name, stat)| (name, stat.summary())))
+            .finish()
+    }
+}
+
+impl<T> PartialEq for StatTracker<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.stats.len() == other.stats.len()
+            && self
+                .stats
+                .iter()
+                .all(|(name, stat)| match other.stats.get(name) {
+                    Some(other_stat) => {
+                        stat.kind == other_stat.kind
+                            && Arc::ptr_eq(&stat.extractor, &other_stat.extractor)
+                    }
+                    None => false,
+                })
+    }
+}
+
+impl<T> Eq for StatTracker<T> {}
+
+/// Converts a float into bits which sort in the same order as the float itself.
+pub(crate) fn ordered_bits(value: f64) -> u64 {
+    let bits = value.to_bits();
+
+    if bits >> 63 == 1 {
+        !bits
+    } else {
+        bits | (1 << 63)
+    }
+}
+
+/// Reverses [ordered_bits] back into the original float.
+pub(crate) fn from_ordered_bits(bits: u64) -> f64 {
+    if bits >> 63 == 1 {
+        f64::from_bits(bits & !(1 << 63))
+    } else {
+        f64::from_bits(!bits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests that [ordered_bits] keeps the order of floats, including negatives
+    #[test]
+    fn ordered_bits_order() {
+        let values = [-300.5, -1.0, -0.0, 0.0, 2.25, 1e9];
+
+        for pair in values.windows(2) {
+            assert!(ordered_bits(pair[0]) <= ordered_bits(pair[1]));
+        }
+
+        for value in values.iter() {
+            assert_eq!(from_ordered_bits(ordered_bits(*value)), *value);
+        }
+    }
+
+    /// Tests min/max staying correct after removal
+    #[test]
+    fn min_max_removal() {
+        let mut tracker: StatTracker<i32> = StatTracker::default();
+        let existing = [5, 1, 9];
+
+        tracker.track(
+            String::from("num"),
+            Stat::MinMaxMean,
+            Arc::new(|n| *n as f64),
+            existing.iter(),
+        );
+        tracker.remove(&1);
+
+        let summary = tracker.get("num").unwrap();
+
+        assert_eq!(summary.count, 2);
+        assert_eq!(summary.min, Some(5.0));
+        assert_eq!(summary.max, Some(9.0));
+        assert_eq!(summary.mean(), Some(7.0));
+    }
+}
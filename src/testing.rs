@@ -0,0 +1,138 @@
+//! Contains helpers for testing code which uses a [Database], enabled with
+//! the `testing` feature.
+//!
+//! This includes making throwaway databases and checking that everything
+//! tracked alongside the items of a database, such as indexes, statistics
+//! and pins, is still in sync with them. Enabling the `proptest` feature as
+//! well implements [proptest::arbitrary::Arbitrary] for [Database], so
+//! databases can be generated by property tests.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use tinydb::testing::{assert_invariants, scratch_with};
+//!
+//! fn main() {
+//!     let mut db = scratch_with(vec![1, 2, 3]);
+//!
+//!     db.track_ids();
+//!     db.for_each_mut(|item| *item *= 10).unwrap();
+//!
+//!     assert_invariants(&db);
+//! }
+//! ```
+
+use crate::Database;
+use std::hash;
+
+/// Label given to databases made by [scratch], which is where they'd be
+/// saved if dumped.
+pub const SCRATCH_LABEL: &str = "scratch";
+
+/// Makes an empty in-memory database without a save path, which allows
+/// duplicates to be added without an error.
+pub fn scratch<T: hash::Hash + Eq>() -> Database<T> {
+    Database::new(SCRATCH_LABEL, None, false)
+}
+
+/// Makes an in-memory database like [scratch] holding `items`, with
+/// duplicates being dropped.
+pub fn scratch_with<T: hash::Hash + Eq>(items: impl IntoIterator<Item = T>) -> Database<T> {
+    let mut db = scratch();
+
+    for item in items {
+        db.add_item(item)
+            .expect("scratch databases accept every item");
+    }
+
+    db
+}
+
+/// Checks that everything tracked alongside the items of a database is in
+/// sync with them, returning what's wrong otherwise. This covers indexes,
+/// statistics, ids, pins, expiries and duplicate counts.
+///
+/// A database only ever changed through its own methods should always pass,
+/// so this is mostly useful after a long run of random operations.
+pub fn check_invariants<T: hash::Hash + Eq>(db: &Database<T>) -> Result<(), String> {
+    let len = db.items.len();
+    let contains = |item: &T| db.items.contains(item);
+
+    db.indexes.check_consistent(len, &contains)?;
+    db.stats.check_consistent(len)?;
+    db.handles.check_consistent(len, &contains)?;
+    db.order.check_consistent(&contains)?;
+    db.expiries.check_consistent(&contains)?;
+    db.dupes.check_consistent(&contains)
+}
+
+/// Panics with what's wrong if [check_invariants] fails.
+pub fn assert_invariants<T: hash::Hash + Eq>(db: &Database<T>) {
+    if let Err(problem) = check_invariants(db) {
+        panic!("database `{}` is inconsistent: {}", db.label, problem);
+    }
+}
+
+#[cfg(feature = "proptest")]
+impl<T> proptest::arbitrary::Arbitrary for Database<T>
+where
+    T: proptest::arbitrary::Arbitrary + hash::Hash + Eq + 'static,
+{
+    type Parameters = ();
+    type Strategy = proptest::strategy::BoxedStrategy<Self>;
+
+    /// Generates [scratch] databases of up to 32 items, which may or may not
+    /// have [Database::strict_dupes] enabled.
+    fn arbitrary_with(_args: ()) -> Self::Strategy {
+        use proptest::prelude::*;
+
+        (proptest::collection::vec(any::<T>(), 0..32), any::<bool>())
+            .prop_map(|(items, strict_dupes)| {
+                let mut db = scratch_with(items);
+
+                db.strict_dupes = strict_dupes;
+                db
+            })
+            .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{error, stats};
+
+    /// Tests tracked state staying consistent through mixed operations
+    #[test]
+    fn invariants_hold() -> Result<(), error::DatabaseError> {
+        let mut db = scratch_with(0..20u32);
+
+        db.track_ids();
+        db.track_stat("value", stats::Stat::MinMaxMean, |item| *item as f64);
+        db.create_range_index("value", |item: &u32| *item as f64);
+        db.move_to_front(&3)?;
+        db.move_to_back(&4)?;
+
+        for item in (0..20).step_by(3) {
+            db.remove_item(&item)?;
+        }
+
+        db.for_each_mut(|item| *item += 1)?;
+
+        assert_eq!(check_invariants(&db), Ok(()));
+
+        db.stats.insert(&100);
+
+        assert!(check_invariants(&db).is_err());
+        Ok(())
+    }
+
+    #[cfg(feature = "proptest")]
+    proptest::proptest! {
+        /// Tests generated databases passing every check
+        #[test]
+        fn arbitrary_consistent(db: Database<u8>) {
+            assert_invariants(&db);
+        }
+    }
+}
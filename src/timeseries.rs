@@ -0,0 +1,313 @@
+//! Contains [TimeSeries], a database of values recorded at points in time
+//! such as a small log of metrics.
+
+use crate::{error, meta, metrics, Database};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::borrow::Borrow;
+use std::hash;
+use std::ops::{Bound, RangeBounds};
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+/// Name of the range index every [TimeSeries] keeps over its timestamps.
+const TIME_INDEX: &str = "timeseries";
+
+/// A value alongside when it was recorded, which is what a [TimeSeries]
+/// stores as items.
+///
+/// Each point is given its own id when appended and only the id takes part
+/// in comparisons and hashing, so the same value can be recorded any number
+/// of times, even at the same time.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Point<T> {
+    id: u64,
+    at: u64,
+    value: T,
+}
+
+impl<T> Point<T> {
+    /// Gets the id this point was given when appended.
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Gets when the value was recorded.
+    pub fn at(&self) -> SystemTime {
+        meta::from_millis(self.at)
+    }
+
+    /// Gets the value.
+    pub fn value(&self) -> &T {
+        &self.value
+    }
+
+    /// Unwraps the value, dropping when it was recorded.
+    pub fn into_value(self) -> T {
+        self.value
+    }
+}
+
+impl<T> PartialEq for Point<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl<T> Eq for Point<T> {}
+
+impl<T> hash::Hash for Point<T> {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+    }
+}
+
+impl<T> Borrow<u64> for Point<T> {
+    fn borrow(&self) -> &u64 {
+        &self.id
+    }
+}
+
+/// Values recorded at points in time, kept in a [Database] of [Point]s with
+/// a range index over when they were recorded so ranges of time can be
+/// found without scanning every point. Timestamps are kept to the
+/// millisecond.
+///
+/// # Examples
+///
+/// ```rust
+/// use tinydb::timeseries::TimeSeries;
+/// use std::time::{Duration, SystemTime};
+///
+/// fn main() {
+///     let mut temps: TimeSeries<i32> = TimeSeries::new("timeseries_test", None);
+///     let now = SystemTime::now();
+///
+///     temps.append_at(now - Duration::from_secs(7200), 14).unwrap();
+///     temps.append_at(now - Duration::from_secs(60), 18).unwrap();
+///     temps.append(19).unwrap();
+///
+///     let last_hour = temps.range(now - Duration::from_secs(3600)..);
+///
+///     assert_eq!(last_hour.iter().map(|point| *point.value()).collect::<Vec<_>>(), vec![18, 19]);
+///     assert_eq!(temps.prune_older_than(Duration::from_secs(3600)).unwrap(), 1);
+///     assert_eq!(temps.len(), 2);
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimeSeries<T> {
+    db: Database<Point<T>>,
+}
+
+impl<T: Clone + 'static> TimeSeries<T> {
+    /// Creates an empty series, with the same `label` and `save_path` as
+    /// [Database::new].
+    pub fn new(label: impl Into<String>, save_path: impl Into<Option<PathBuf>>) -> Self {
+        TimeSeries::from_db(Database::new(label, save_path, false))
+    }
+
+    /// Wraps a database of points, indexing when they were recorded.
+    pub fn from_db(mut db: Database<Point<T>>) -> Self {
+        db.create_range_index(TIME_INDEX, |point: &Point<T>| point.at as f64);
+
+        TimeSeries { db }
+    }
+
+    /// Records a value as of right now.
+    ///
+    /// # Errors
+    ///
+    /// Fails in the same ways as [Database::add_item].
+    pub fn append(&mut self, value: T) -> Result<(), error::DatabaseError> {
+        self.append_at(SystemTime::now(), value)
+    }
+
+    /// Records a value as of `at`, which can be before other points.
+    ///
+    /// # Errors
+    ///
+    /// Fails in the same ways as [Database::add_item].
+    pub fn append_at(&mut self, at: SystemTime, value: T) -> Result<(), error::DatabaseError> {
+        self.db.check_mutable()?;
+
+        let point = Point {
+            id: self.db.next_id(),
+            at: meta::unix_millis(at),
+            value,
+        };
+
+        self.db.add_item(point)
+    }
+
+    /// Finds every point recorded inside of `range`, such as `start..end` or
+    /// `start..`, from oldest to newest. Points recorded at the same time
+    /// are in the order they were appended.
+    pub fn range(&self, range: impl RangeBounds<SystemTime>) -> Vec<&Point<T>> {
+        let millis = |bound: Bound<&SystemTime>| match bound {
+            Bound::Included(at) => Bound::Included(meta::unix_millis(*at) as f64),
+            Bound::Excluded(at) => Bound::Excluded(meta::unix_millis(*at) as f64),
+            Bound::Unbounded => Bound::Unbounded,
+        };
+
+        let mut points = self
+            .db
+            .query_range::<f64>(
+                TIME_INDEX,
+                (millis(range.start_bound()), millis(range.end_bound())),
+            )
+            .expect("time series always have a time index");
+
+        // the index only orders by time, so ties are put back into the order
+        // they were appended in as ids are handed out in that order
+        points.sort_by_key(|point| (point.at, point.id));
+        points
+    }
+
+    /// Iterates over every point from oldest to newest.
+    pub fn iter(&self) -> impl Iterator<Item = &Point<T>> {
+        self.range(..).into_iter()
+    }
+
+    /// Gets the most recently recorded point.
+    pub fn latest(&self) -> Option<&Point<T>> {
+        self.range(..).pop()
+    }
+
+    /// Removes every point recorded more than `age` ago, returning how many
+    /// were removed.
+    ///
+    /// # Errors
+    ///
+    /// Will return [error::DatabaseError::Archived] if the database has been
+    /// archived.
+    pub fn prune_older_than(&mut self, age: Duration) -> Result<usize, error::DatabaseError> {
+        let cutoff = SystemTime::now()
+            .checked_sub(age)
+            .unwrap_or(SystemTime::UNIX_EPOCH);
+
+        self.prune_before(cutoff)
+    }
+
+    /// Removes every point recorded before `cutoff`, returning how many were
+    /// removed.
+    ///
+    /// # Errors
+    ///
+    /// Will return [error::DatabaseError::Archived] if the database has been
+    /// archived.
+    pub fn prune_before(&mut self, cutoff: SystemTime) -> Result<usize, error::DatabaseError> {
+        self.db.check_mutable()?;
+
+        let ids: Vec<u64> = self.range(..cutoff).iter().map(|point| point.id).collect();
+
+        self.db.history.begin_step();
+
+        for id in ids.iter() {
+            if let Some(point) = self.db.items.take(id) {
+                self.db.forget_item(&point);
+            }
+        }
+
+        Ok(ids.len())
+    }
+
+    /// Gets how many points are stored.
+    pub fn len(&self) -> usize {
+        self.db.items.len()
+    }
+
+    /// Checks if no points are stored.
+    pub fn is_empty(&self) -> bool {
+        self.db.items.is_empty()
+    }
+
+    /// Gets the database underneath, for anything not covered here such as
+    /// [Database::add_constraint].
+    pub fn db(&self) -> &Database<Point<T>> {
+        &self.db
+    }
+
+    /// Gets the database underneath mutably. The range index this series
+    /// keeps has to be left in place, otherwise methods of this series will
+    /// panic.
+    pub fn db_mut(&mut self) -> &mut Database<Point<T>> {
+        &mut self.db
+    }
+
+    /// Unwraps the database underneath.
+    pub fn into_db(self) -> Database<Point<T>> {
+        self.db
+    }
+}
+
+impl<T: Clone + Serialize + 'static> TimeSeries<T> {
+    /// Dumps the series using [Database::dump_db].
+    pub fn dump(&self) -> Result<metrics::DumpReport, error::DatabaseError> {
+        self.db.dump_db()
+    }
+}
+
+impl<T: Clone + DeserializeOwned + 'static> TimeSeries<T> {
+    /// Loads a series from a `.tinydb` file using [Database::from].
+    ///
+    /// # Errors
+    ///
+    /// Fails in the same ways as [Database::from].
+    pub fn from(path: impl Into<PathBuf>) -> Result<Self, error::DatabaseError> {
+        Ok(TimeSeries::from_db(Database::from(path)?))
+    }
+
+    /// Loads a series from a `.tinydb` file or creates an empty one if there
+    /// isn't a dump yet, using [Database::auto_from].
+    ///
+    /// # Errors
+    ///
+    /// Fails in the same ways as [Database::auto_from].
+    pub fn auto_from(path: impl Into<PathBuf>) -> Result<Self, error::DatabaseError> {
+        Ok(TimeSeries::from_db(Database::auto_from(path, false)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests points being found by time across a dump and old points being
+    /// pruned
+    #[test]
+    fn timeseries_range_prune() -> Result<(), error::DatabaseError> {
+        let path = PathBuf::from("timeseries_range_prune.tinydb");
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000);
+        let mut series = TimeSeries::new("Time series test", path.clone());
+
+        for (secs, value) in [(30, 3.0), (10, 1.0), (20, 2.0), (20, 2.5)] {
+            series.append_at(start + Duration::from_secs(secs), value)?;
+        }
+
+        series.dump()?;
+
+        let mut series: TimeSeries<f64> = TimeSeries::from(path.clone())?;
+        let values = |points: Vec<&Point<f64>>| {
+            points
+                .into_iter()
+                .map(|point| *point.value())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            values(series.range(start + Duration::from_secs(20)..)),
+            vec![2.0, 2.5, 3.0]
+        );
+        assert_eq!(
+            values(series.range(..=start + Duration::from_secs(20))),
+            vec![1.0, 2.0, 2.5]
+        );
+        assert_eq!(series.latest().map(Point::value), Some(&3.0));
+        assert_eq!(series.prune_before(start + Duration::from_secs(25))?, 3);
+        assert_eq!(values(series.iter().collect()), vec![3.0]);
+        assert_eq!(series.prune_older_than(Duration::from_secs(60))?, 1);
+        assert!(series.is_empty());
+
+        std::fs::remove_file(path)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,188 @@
+//! Contains [Timestamped], a wrapper recording when items were created and
+//! last updated using [chrono] datetimes, enabled with the `chrono` feature.
+//!
+//! Datetimes are serialized as RFC 3339 strings so dumps stay readable by
+//! other tools and across timezones.
+
+use crate::{error, Database};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::borrow::Borrow;
+use std::hash;
+use std::ops::RangeBounds;
+
+/// An item along with when it was created and last updated.
+///
+/// Only the inner item takes part in comparisons and hashing, so two
+/// timestamped items holding the same item are duplicates whatever their
+/// timestamps are. This also lets the inner item be used to look them up.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Timestamped<T> {
+    item: T,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+impl<T> Timestamped<T> {
+    /// Wraps an item which has been created right now.
+    pub fn new(item: T) -> Self {
+        let now = Utc::now();
+
+        Timestamped {
+            item,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    /// Gets the inner item.
+    pub fn item(&self) -> &T {
+        &self.item
+    }
+
+    /// Unwraps the inner item, dropping its timestamps.
+    pub fn into_inner(self) -> T {
+        self.item
+    }
+
+    /// Gets when the item was first created.
+    pub fn created_at(&self) -> DateTime<Utc> {
+        self.created_at
+    }
+
+    /// Gets when the item was last updated, which is when it was created if
+    /// it never has been.
+    pub fn updated_at(&self) -> DateTime<Utc> {
+        self.updated_at
+    }
+}
+
+impl<T: PartialEq> PartialEq for Timestamped<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.item == other.item
+    }
+}
+
+impl<T: Eq> Eq for Timestamped<T> {}
+
+impl<T: hash::Hash> hash::Hash for Timestamped<T> {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.item.hash(state);
+    }
+}
+
+impl<T> Borrow<T> for Timestamped<T> {
+    fn borrow(&self) -> &T {
+        &self.item
+    }
+}
+
+impl<T: hash::Hash + Eq> Database<Timestamped<T>> {
+    /// Adds an item which has been created right now, see
+    /// [Database::add_item].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use chrono::Utc;
+    /// use tinydb::timestamp::Timestamped;
+    /// use tinydb::Database;
+    ///
+    /// fn main() {
+    ///     let mut db: Database<Timestamped<String>> = Database::new("timestamp_test", None, false);
+    ///     let before = Utc::now();
+    ///
+    ///     db.add_timestamped(String::from("Lister")).unwrap();
+    ///
+    ///     assert_eq!(db.query_updated_since(before)[0].item(), "Lister");
+    /// }
+    /// ```
+    pub fn add_timestamped(&mut self, item: T) -> Result<(), error::DatabaseError> {
+        self.add_item(Timestamped::new(item))
+    }
+
+    /// Replaces an item with another, keeping when it was created and
+    /// marking it as updated right now, see [Database::update_item].
+    ///
+    /// # Errors
+    ///
+    /// Returns [error::DatabaseError::ItemNotFound] if `item` isn't inside
+    /// of the database, or fails in the same ways as [Database::update_item].
+    pub fn update_timestamped(&mut self, item: &T, new: T) -> Result<(), error::DatabaseError>
+    where
+        T: Clone,
+    {
+        let created_at = self
+            .items
+            .get(item)
+            .ok_or(error::DatabaseError::ItemNotFound)?
+            .created_at;
+
+        self.update_item(
+            &Timestamped::new(item.clone()),
+            Timestamped {
+                item: new,
+                created_at,
+                updated_at: Utc::now(),
+            },
+        )
+    }
+
+    /// Finds every item created or updated at or after `since`, in the same
+    /// order as [Database::ordered].
+    pub fn query_updated_since(&self, since: DateTime<Utc>) -> Vec<&Timestamped<T>> {
+        self.ordered()
+            .filter(|item| item.updated_at >= since && !self.is_expired(item))
+            .collect()
+    }
+
+    /// Finds every item created within a range of datetimes, in the same
+    /// order as [Database::ordered].
+    pub fn query_created_within(
+        &self,
+        range: impl RangeBounds<DateTime<Utc>>,
+    ) -> Vec<&Timestamped<T>> {
+        self.ordered()
+            .filter(|item| range.contains(&item.created_at) && !self.is_expired(item))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// Tests updates keeping their creation time and timestamps being saved
+    #[test]
+    fn timestamped_updates() -> Result<(), error::DatabaseError> {
+        let path = PathBuf::from("timestamped_updates.tinydb");
+        let mut db = Database::new("Timestamp test", path.clone(), false);
+
+        db.add_timestamped(1)?;
+
+        let between = Utc::now();
+
+        db.add_timestamped(2)?;
+
+        assert_eq!(db.query_updated_since(between).len(), 1);
+        assert_eq!(db.query_created_within(..between)[0].item(), &1);
+
+        db.update_timestamped(&1, 10)?;
+        db.dump_db()?;
+
+        let loaded: Database<Timestamped<i32>> = Database::from(path.clone())?;
+        let updated = loaded.items.get(&10).unwrap();
+
+        assert!(updated.created_at() < between && updated.updated_at() > between);
+        assert_eq!(loaded.query_updated_since(between).len(), 2);
+        assert_eq!(
+            db.update_timestamped(&1, 11),
+            Err(error::DatabaseError::ItemNotFound)
+        );
+
+        drop(loaded);
+        std::fs::remove_file(path)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,81 @@
+//! Contains optional instrumentation using [tracing](https://docs.rs/tracing)
+//! spans and events, enabled with the `tracing` feature. Without the feature
+//! everything here compiles down to nothing.
+
+#[cfg(feature = "tracing")]
+use std::time::Instant;
+
+/// Span covering a single operation, which emits an event with how long the
+/// operation took once dropped.
+#[cfg(feature = "tracing")]
+pub(crate) struct Timer {
+    span: tracing::span::EnteredSpan,
+    started: Instant,
+}
+
+/// Span covering a single operation, which does nothing without the
+/// `tracing` feature.
+#[cfg(not(feature = "tracing"))]
+pub(crate) struct Timer;
+
+/// Starts timing an operation on a database currently holding `items`.
+#[cfg(feature = "tracing")]
+pub(crate) fn start(op: &'static str, label: &str, items: usize) -> Timer {
+    let span = tracing::debug_span!("tinydb", op, label, items).entered();
+
+    Timer {
+        span,
+        started: Instant::now(),
+    }
+}
+
+/// Starts timing an operation on a database currently holding `items`.
+#[cfg(not(feature = "tracing"))]
+#[inline(always)]
+pub(crate) fn start(_op: &'static str, _label: &str, _items: usize) -> Timer {
+    Timer
+}
+
+impl Timer {
+    /// Records how many items the operation ended up affecting or returning.
+    #[cfg(feature = "tracing")]
+    pub(crate) fn items(&self, items: usize) {
+        tracing::debug!(parent: self.span.id(), items, "items");
+    }
+
+    /// Records how many items the operation ended up affecting or returning.
+    #[cfg(not(feature = "tracing"))]
+    #[inline(always)]
+    pub(crate) fn items(&self, _items: usize) {}
+
+    /// Records how many bytes were read or written.
+    #[cfg(feature = "tracing")]
+    pub(crate) fn bytes(&self, bytes: usize) {
+        tracing::debug!(parent: self.span.id(), bytes, "bytes");
+    }
+
+    /// Records how many bytes were read or written.
+    #[cfg(all(feature = "std", not(feature = "tracing")))]
+    #[inline(always)]
+    pub(crate) fn bytes(&self, _bytes: usize) {}
+}
+
+#[cfg(feature = "tracing")]
+impl Drop for Timer {
+    fn drop(&mut self) {
+        let elapsed_us = self.started.elapsed().as_micros() as u64;
+
+        tracing::debug!(parent: self.span.id(), elapsed_us, "finished");
+    }
+}
+
+/// Emits a trace-level event for an item being added or removed.
+#[cfg(feature = "tracing")]
+pub(crate) fn mutation(op: &'static str, items: usize) {
+    tracing::trace!(op, items, "tinydb mutation");
+}
+
+/// Emits a trace-level event for an item being added or removed.
+#[cfg(not(feature = "tracing"))]
+#[inline(always)]
+pub(crate) fn mutation(_op: &'static str, _items: usize) {}
@@ -0,0 +1,243 @@
+//! Contains soft-deletion, where removed items are kept aside so they can be
+//! restored later rather than being erased straight away.
+
+use crate::compat::{HashSet, Vec};
+use crate::dupes::DupePolicy;
+use crate::{error, Database};
+use core::hash;
+use serde::{Deserialize, Serialize};
+
+/// Items removed whilst soft-deletion is enabled with
+/// [Database::set_soft_delete].
+///
+/// This is saved alongside the database so deleted items can still be
+/// restored after loading a dump.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound(
+    serialize = "T: hash::Hash + Eq + Serialize",
+    deserialize = "T: hash::Hash + Eq + Deserialize<'de>"
+))]
+pub(crate) struct Trash<T: hash::Hash + Eq> {
+    /// If [Database::remove_item] should move items here.
+    pub(crate) enabled: bool,
+
+    /// Items which have been soft-deleted.
+    pub(crate) items: HashSet<T>,
+}
+
+impl<T: hash::Hash + Eq> Trash<T> {
+    /// Converts every soft-deleted item into another type.
+    #[cfg(feature = "std")]
+    pub(crate) fn map<U: hash::Hash + Eq>(self, convert: &mut impl FnMut(T) -> U) -> Trash<U> {
+        Trash {
+            enabled: self.enabled,
+            items: self.items.into_iter().map(convert).collect(),
+        }
+    }
+}
+
+impl<T: hash::Hash + Eq> Default for Trash<T> {
+    fn default() -> Self {
+        Trash {
+            enabled: false,
+            items: HashSet::new(),
+        }
+    }
+}
+
+impl<T: hash::Hash + Eq> Database<T> {
+    /// Enables or disables soft-deletion, where [Database::remove_item] moves
+    /// items aside instead of erasing them. Deleted items are hidden from all
+    /// queries but can be brought back with [Database::restore] until
+    /// [Database::purge_deleted] is called.
+    ///
+    /// This setting and any deleted items are saved when dumping. Disabling
+    /// soft-deletion keeps items which have already been deleted.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tinydb::Database;
+    ///
+    /// fn main() {
+    ///     let mut db = Database::new("soft_delete_test", None, false);
+    ///
+    ///     db.set_soft_delete(true);
+    ///     db.add_item(String::from("Lister")).unwrap();
+    ///     db.remove_item(&String::from("Lister")).unwrap();
+    ///
+    ///     assert!(!db.contains(&String::from("Lister")));
+    ///     assert_eq!(db.restore(|name| name == "Lister").unwrap(), 1);
+    ///     assert!(db.contains(&String::from("Lister")));
+    /// }
+    /// ```
+    pub fn set_soft_delete(&mut self, enabled: bool) {
+        self.trash.enabled = enabled;
+    }
+
+    /// Checks if soft-deletion has been enabled with
+    /// [Database::set_soft_delete].
+    pub fn is_soft_delete(&self) -> bool {
+        self.trash.enabled
+    }
+
+    /// Iterates over all items which have been soft-deleted and not yet
+    /// restored or purged.
+    pub fn deleted(&self) -> impl Iterator<Item = &T> {
+        self.trash.items.iter()
+    }
+
+    /// Restores all soft-deleted items matching `pred`, returning how many
+    /// were restored. Restored items go through [Database::dupe_policy] and
+    /// any constraints, unique indexes or capacity, the same as
+    /// [Database::add_item]. Items which have since been added again are
+    /// dropped from the deleted items without being counted, unless they
+    /// replace the item or raise its count.
+    ///
+    /// # Errors
+    ///
+    /// Will return [error::DatabaseError::Archived] if the database has been
+    /// archived. Items which can't be restored are kept with the deleted items
+    /// and the first error is returned once the rest have been restored.
+    pub fn restore(
+        &mut self,
+        mut pred: impl FnMut(&T) -> bool,
+    ) -> Result<usize, error::DatabaseError> {
+        self.check_mutable()?;
+        self.history.begin_step();
+
+        let deleted: Vec<T> = self.trash.items.extract_if(|item| pred(item)).collect();
+        let counting = self.dupe_policy() == DupePolicy::Count;
+        let mut restored = 0;
+        let mut rejected = None;
+
+        for item in deleted {
+            if let Err(e) = self.check_policy(&item) {
+                self.trash.items.insert(item);
+                rejected.get_or_insert(e);
+                continue;
+            }
+
+            let dupe = self.contains(&item);
+
+            if self.insert_checked(item) || (dupe && counting) {
+                restored += 1;
+            }
+        }
+
+        match rejected {
+            Some(e) => Err(e),
+            None => Ok(restored),
+        }
+    }
+
+    /// Erases all soft-deleted items for good, returning how many were
+    /// erased. Archived databases are never purged.
+    pub fn purge_deleted(&mut self) -> usize {
+        if self.check_mutable().is_err() {
+            return 0;
+        }
+
+        let purged = self.trash.items.len();
+
+        if purged > 0 {
+            self.meta.modified();
+            self.trash.items.clear();
+        }
+
+        purged
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// Tests deleted items surviving a dump before being restored and purged
+    #[test]
+    fn soft_delete_persists() -> Result<(), error::DatabaseError> {
+        let path = PathBuf::from("soft_delete_persists.tinydb");
+        let mut db = Database::new("Soft delete test", path.clone(), false);
+
+        db.set_soft_delete(true);
+
+        for value in 1..=4 {
+            db.add_item(value)?;
+        }
+
+        db.remove_item(&1)?;
+        db.remove_item(&2)?;
+        db.remove_item(&3)?;
+        db.dump_db()?;
+
+        let mut loaded: Database<i32> = Database::from(path.clone())?;
+
+        assert!(loaded.is_soft_delete());
+        assert_eq!(loaded.deleted().count(), 3);
+        assert!(loaded.query_item(|n| n, 1).is_err());
+        assert!(loaded.remove_item(&1).is_err());
+        assert_eq!(loaded.restore(|n| *n < 3)?, 2);
+        assert!(loaded.contains(&2));
+        assert_eq!(loaded.purge_deleted(), 1);
+        assert_eq!(loaded.deleted().count(), 0);
+
+        loaded.set_soft_delete(false);
+        loaded.remove_item(&4)?;
+
+        assert_eq!(loaded.deleted().count(), 0);
+
+        std::fs::remove_file(path)?;
+        Ok(())
+    }
+
+    /// Tests restored items following the capacity and dupe policy
+    #[test]
+    fn restore_checked() -> Result<(), error::DatabaseError> {
+        let mut db = Database::new("Restore checked test", None, false);
+
+        db.set_soft_delete(true);
+        db.add_item(1)?;
+        db.add_item(2)?;
+        db.remove_item(&1)?;
+        db.set_capacity(1, crate::capacity::EvictionPolicy::Reject);
+
+        assert_eq!(
+            db.restore(|_| true),
+            Err(error::DatabaseError::CapacityReached)
+        );
+        assert_eq!(db.deleted().count(), 1);
+        assert!(!db.contains(&1));
+
+        db.set_capacity(2, crate::capacity::EvictionPolicy::Reject);
+        db.add_item(1)?;
+        db.set_dupe_policy(DupePolicy::Count);
+
+        assert_eq!(db.restore(|_| true)?, 1);
+        assert_eq!(db.count_of(&1), 2);
+
+        Ok(())
+    }
+
+    /// Tests purging deleted items counting as a change worth dumping
+    #[test]
+    fn purge_marks_modified() -> Result<(), error::DatabaseError> {
+        let path = std::env::temp_dir().join(format!(
+            "purge_marks_modified_{}.tinydb",
+            std::process::id()
+        ));
+        let mut db = Database::new("Purge modified test", path.clone(), false);
+
+        db.set_soft_delete(true);
+        db.set_skip_clean_dumps(true);
+        db.add_item(1)?;
+        db.remove_item(&1)?;
+        db.dump_db()?;
+
+        assert_eq!(db.purge_deleted(), 1);
+        assert!(db.is_dirty());
+
+        std::fs::remove_file(path)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,299 @@
+//! Contains per-item expiry, allowing a [crate::Database] to be used as a small
+//! persistent cache.
+
+use crate::compat::HashMap;
+#[cfg(feature = "std")]
+use crate::error;
+use crate::meta::now_millis;
+#[cfg(feature = "std")]
+use crate::meta::{from_millis, unix_millis};
+use crate::Database;
+use core::hash;
+use core::sync::atomic::{AtomicBool, Ordering};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "std")]
+use std::time::{Duration, SystemTime};
+
+/// Expiry times of items added with [Database::add_item_with_ttl], stored as
+/// milliseconds since the unix epoch so they can be saved alongside the
+/// database.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound(
+    serialize = "T: hash::Hash + Eq + Serialize",
+    deserialize = "T: hash::Hash + Eq + Deserialize<'de>"
+))]
+pub(crate) struct Expiries<T: hash::Hash + Eq> {
+    expiries: HashMap<T, u64>,
+}
+
+impl<T: hash::Hash + Eq> Expiries<T> {
+    /// Checks if an item has expired at the given time, always `false` for
+    /// items without an expiry.
+    pub(crate) fn is_expired(&self, item: &T, now: u64) -> bool {
+        match self.expiries.get(item) {
+            Some(expiry) => *expiry <= now,
+            None => false,
+        }
+    }
+
+    /// Checks if there are any expiries to consider at all.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.expiries.is_empty()
+    }
+
+    /// Forgets about an item, used once it's been removed.
+    pub(crate) fn remove(&mut self, item: &T) {
+        self.expiries.remove(item);
+    }
+
+    /// Makes sure every item with an expiry is still inside of the database,
+    /// see [crate::testing::check_invariants].
+    #[cfg(feature = "testing")]
+    pub(crate) fn check_consistent(&self, contains: &dyn Fn(&T) -> bool) -> Result<(), String> {
+        match self.expiries.keys().all(contains) {
+            true => Ok(()),
+            false => Err(String::from(
+                "an item with an expiry isn't inside of the database",
+            )),
+        }
+    }
+
+    /// Converts every item with an expiry into another type.
+    #[cfg(feature = "std")]
+    pub(crate) fn map<U: hash::Hash + Eq>(self, convert: &mut impl FnMut(T) -> U) -> Expiries<U> {
+        Expiries {
+            expiries: self
+                .expiries
+                .into_iter()
+                .map(|(item, expiry)| (convert(item), expiry))
+                .collect(),
+        }
+    }
+}
+
+impl<T: hash::Hash + Eq> Default for Expiries<T> {
+    fn default() -> Self {
+        Expiries {
+            expiries: HashMap::new(),
+        }
+    }
+}
+
+/// If queries which come across an expired item should purge it, set with
+/// [Database::set_purge_on_query].
+///
+/// Queries only borrow the database so they can't remove anything themselves,
+/// instead they mark a purge as due which happens at the start of the next
+/// change.
+#[derive(Debug, Default)]
+pub(crate) struct LazyPurge {
+    enabled: bool,
+    due: AtomicBool,
+}
+
+impl LazyPurge {
+    /// Marks a purge as due if lazy purging is enabled, called whenever an
+    /// expired item is come across.
+    pub(crate) fn expired_seen(&self) {
+        if self.enabled {
+            self.due.store(true, Ordering::Relaxed);
+        }
+    }
+
+    /// Checks if a purge is due, resetting it so it only happens once.
+    pub(crate) fn take_due(&mut self) -> bool {
+        core::mem::take(self.due.get_mut())
+    }
+}
+
+impl Clone for LazyPurge {
+    fn clone(&self) -> Self {
+        LazyPurge {
+            enabled: self.enabled,
+            due: AtomicBool::new(self.due.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+impl<T: hash::Hash + Eq> Database<T> {
+    /// Adds a new item to the database which expires after the given `ttl`.
+    ///
+    /// Expired items are hidden from [Database::query_item] and
+    /// [Database::contains] straight away but are only removed for good once
+    /// [Database::purge_expired] is called, or lazily with
+    /// [Database::set_purge_on_query]. Adding an item which already
+    /// exists without [Database::strict_dupes] will refresh its expiry.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tinydb::Database;
+    /// use std::time::Duration;
+    ///
+    /// fn main() {
+    ///     let mut cache: Database<String> = Database::new("cache", None, false);
+    ///
+    ///     cache.add_item_with_ttl(String::from("stale"), Duration::from_secs(0)).unwrap();
+    ///     cache.add_item_with_ttl(String::from("fresh"), Duration::from_secs(60)).unwrap();
+    ///
+    ///     assert!(!cache.contains(&String::from("stale")));
+    ///     assert_eq!(cache.purge_expired(), 1);
+    ///     assert!(cache.contains(&String::from("fresh")));
+    /// }
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn add_item_with_ttl(&mut self, item: T, ttl: Duration) -> Result<(), error::DatabaseError>
+    where
+        T: Clone,
+    {
+        let expiry = unix_millis(SystemTime::now() + ttl);
+
+        self.add_item(item.clone())?;
+        self.expiries.expiries.insert(item, expiry);
+
+        Ok(())
+    }
+
+    /// Gets the time an item expires at or [Option::None] if it doesn't
+    /// expire.
+    #[cfg(feature = "std")]
+    pub fn expires_at(&self, item: &T) -> Option<SystemTime> {
+        self.expiries
+            .expiries
+            .get(item)
+            .map(|millis| from_millis(*millis))
+    }
+
+    /// Removes all items which have expired, returning how many were removed.
+    ///
+    /// Archived databases are never purged, although expired items are still
+    /// hidden from queries.
+    pub fn purge_expired(&mut self) -> usize {
+        if self.expiries.is_empty() || self.check_mutable().is_err() {
+            return 0;
+        }
+
+        self.history.begin_step();
+
+        let now = now_millis();
+        let expiries = core::mem::take(&mut self.expiries);
+        let purged = self.take_where(|item| expiries.is_expired(item, now));
+
+        self.expiries = expiries;
+
+        for item in purged.iter() {
+            self.expiries.remove(item);
+        }
+
+        purged.len()
+    }
+
+    /// Sets if expired items should be purged lazily once a query comes
+    /// across them, rather than waiting for [Database::purge_expired]. This
+    /// is disabled by default.
+    ///
+    /// As queries only borrow the database, the purge happens at the start of
+    /// the next change made to it.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tinydb::Database;
+    /// use std::time::Duration;
+    ///
+    /// fn main() {
+    ///     let mut cache: Database<String> = Database::new("lazy_purge", None, false);
+    ///
+    ///     cache.set_purge_on_query(true);
+    ///     cache.add_item_with_ttl(String::from("stale"), Duration::from_secs(0)).unwrap();
+    ///
+    ///     assert!(!cache.contains(&String::from("stale")));
+    ///
+    ///     cache.add_item(String::from("fresh")).unwrap();
+    ///
+    ///     assert_eq!(cache.items.len(), 1);
+    /// }
+    /// ```
+    pub fn set_purge_on_query(&mut self, enabled: bool) {
+        self.lazy_purge.enabled = enabled;
+        self.lazy_purge.due = AtomicBool::new(false);
+    }
+
+    /// Purges expired items if a query has come across one whilst
+    /// [Database::set_purge_on_query] is enabled.
+    pub(crate) fn purge_if_due(&mut self) {
+        if self.lazy_purge.take_due() {
+            self.purge_expired();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// Tests expiries surviving a dump and being purged afterwards
+    #[test]
+    fn expiry_persists() -> Result<(), error::DatabaseError> {
+        let path = PathBuf::from("expiry_persists.tinydb");
+        let mut db = Database::new("Expiry test", path.clone(), false);
+
+        db.add_item_with_ttl(1, Duration::from_millis(0))?;
+        db.add_item_with_ttl(2, Duration::from_secs(3600))?;
+        db.add_item(3)?;
+        db.dump_db()?;
+
+        let mut loaded: Database<i32> = Database::from(path.clone())?;
+
+        assert!(loaded.expires_at(&2).is_some());
+        assert!(loaded.expires_at(&3).is_none());
+        assert!(loaded.query_item(|n| n, 1).is_err());
+        assert_eq!(loaded.purge_expired(), 1);
+        assert_eq!(loaded.items.len(), 2);
+
+        std::fs::remove_file(path)?;
+        Ok(())
+    }
+
+    /// Tests expired items being purged before the dupe policy is applied
+    #[test]
+    fn readd_expired() -> Result<(), error::DatabaseError> {
+        let mut db = Database::new("Readd expired test", None, true);
+
+        db.add_item_with_ttl(1, Duration::from_millis(0))?;
+        db.add_item(1)?;
+
+        assert!(db.contains(&1));
+        assert!(db.expires_at(&1).is_none());
+
+        db.set_dupe_policy(crate::dupes::DupePolicy::Ignore);
+        db.add_item_with_ttl(2, Duration::from_millis(0))?;
+        db.add_item(2)?;
+
+        assert!(db.contains(&2));
+        Ok(())
+    }
+
+    /// Tests queries marking a purge as due which happens on the next change
+    #[test]
+    fn purge_on_query() -> Result<(), error::DatabaseError> {
+        let mut db = Database::new("Purge on query test", None, false);
+
+        db.add_item_with_ttl(1, Duration::from_millis(0))?;
+        db.add_item(2)?;
+        db.add_item(3)?;
+
+        assert!(db.query_item(|n| n, 1).is_err());
+        assert_eq!(db.items.len(), 3);
+
+        db.set_purge_on_query(true);
+
+        assert!(db.query_item(|n| n, 1).is_err());
+
+        db.remove_item(&3)?;
+
+        assert_eq!(db.items.len(), 1);
+        Ok(())
+    }
+}
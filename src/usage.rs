@@ -0,0 +1,164 @@
+//! Contains opt-in tracking of how queries scan a [crate::Database], used to
+//! suggest which projections would benefit most from an index.
+
+use crate::compat::{HashMap, Mutex, Vec};
+use crate::Database;
+use core::hash;
+
+/// A projection which is frequently scanned, returned from
+/// [Database::index_suggestions].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexSuggestion {
+    /// Name of the projection closure's type, which includes the function it
+    /// was defined in, e.g. `my_app::find_user::{{closure}}`.
+    pub projection: &'static str,
+
+    /// Amount of queries made using this projection.
+    pub queries: u64,
+
+    /// Total items examined by all of these queries.
+    pub items_scanned: u64,
+}
+
+impl IndexSuggestion {
+    /// Gets the average amount of items examined per query.
+    pub fn average_scanned(&self) -> u64 {
+        self.items_scanned / self.queries.max(1)
+    }
+}
+
+/// Scans recorded against each projection whilst tracking is enabled.
+///
+/// This is behind a [Mutex] as it's updated from queries which only borrow
+/// the database. Recorded usage isn't saved when dumping, isn't carried over
+/// when cloning and never takes part in comparisons.
+#[derive(Debug, Default)]
+pub(crate) struct UsageTracker {
+    scans: Option<Mutex<HashMap<&'static str, (u64, u64)>>>,
+}
+
+impl UsageTracker {
+    /// Records a query made with the projection `V` which examined `scanned`
+    /// items, doing nothing if tracking is disabled.
+    pub(crate) fn record<V>(&self, scanned: usize) {
+        if let Some(scans) = &self.scans {
+            let mut scans = scans.lock().unwrap();
+            let entry = scans.entry(core::any::type_name::<V>()).or_insert((0, 0));
+
+            entry.0 += 1;
+            entry.1 += scanned as u64;
+        }
+    }
+}
+
+impl Clone for UsageTracker {
+    fn clone(&self) -> Self {
+        UsageTracker::default()
+    }
+}
+
+impl PartialEq for UsageTracker {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for UsageTracker {}
+
+impl<T: hash::Hash + Eq> Database<T> {
+    /// Enables or disables tracking which projections are queried and how many
+    /// items they examine, which powers [Database::index_suggestions].
+    ///
+    /// Tracking is disabled by default and disabling it clears everything which
+    /// has been recorded so far.
+    pub fn set_usage_tracking(&mut self, enabled: bool) {
+        self.usage.scans = if enabled {
+            Some(self.usage.scans.take().unwrap_or_default())
+        } else {
+            None
+        };
+    }
+
+    /// Suggests projections which would benefit from being indexed, based on
+    /// queries made since [Database::set_usage_tracking] was enabled.
+    ///
+    /// Projections are identified by the name of the closure type passed to
+    /// methods like [Database::query_item], which is based on the function the
+    /// closure was written in. This means the same closure used in a loop is
+    /// counted together, as are multiple closures written in the same function.
+    /// Suggestions are sorted with those which have examined the most items
+    /// overall first.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tinydb::Database;
+    ///
+    /// fn main() {
+    ///     let mut db: Database<(u32, String)> = Database::new("Usage example", None, false);
+    ///
+    ///     db.set_usage_tracking(true);
+    ///
+    ///     for num in 0..100 {
+    ///         db.add_item((num, num.to_string())).unwrap();
+    ///     }
+    ///
+    ///     for num in 0..10 {
+    ///         db.query_item(|item| &item.0, num).unwrap();
+    ///     }
+    ///
+    ///     let suggestions = db.index_suggestions();
+    ///
+    ///     assert_eq!(suggestions.len(), 1);
+    ///     assert_eq!(suggestions[0].queries, 10);
+    /// }
+    /// ```
+    pub fn index_suggestions(&self) -> Vec<IndexSuggestion> {
+        let mut suggestions: Vec<IndexSuggestion> = match &self.usage.scans {
+            Some(scans) => scans
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|(projection, (queries, items_scanned))| IndexSuggestion {
+                    projection,
+                    queries: *queries,
+                    items_scanned: *items_scanned,
+                })
+                .collect(),
+            None => Vec::new(),
+        };
+
+        suggestions.sort_by_key(|suggestion| core::cmp::Reverse(suggestion.items_scanned));
+        suggestions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests nothing being recorded until tracking is enabled
+    #[test]
+    fn tracking_opt_in() {
+        let mut db: Database<i32> = Database::new("Usage test", None, false);
+
+        db.add_item(1).unwrap();
+        db.query_item(|n| n, 1).unwrap();
+
+        assert!(db.index_suggestions().is_empty());
+
+        db.set_usage_tracking(true);
+        db.query_item(|n| n, 1).unwrap();
+        db.query_item(|n| n, 2).ok();
+
+        let suggestions = db.index_suggestions();
+
+        assert_eq!(suggestions.len(), 1);
+        assert!(suggestions[0].projection.contains("tracking_opt_in"));
+        assert_eq!(suggestions[0].queries, 2);
+        assert_eq!(suggestions[0].items_scanned, 2);
+
+        db.set_usage_tracking(false);
+        assert!(db.index_suggestions().is_empty());
+    }
+}
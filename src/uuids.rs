@@ -0,0 +1,184 @@
+//! Contains adding items under freshly generated UUIDs and looking them up by
+//! UUID, enabled with the `uuid` feature.
+
+use crate::{error, Database};
+use std::collections::HashMap;
+use std::fmt;
+use std::hash;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Gets the UUID of an item.
+type Extractor<T> = Arc<dyn Fn(&T) -> Uuid + Send + Sync>;
+
+/// Clones an item, captured when the index is created so [Clone] isn't
+/// required everywhere.
+type Cloner<T> = Arc<dyn Fn(&T) -> T + Send + Sync>;
+
+/// Items by their UUID once [Database::index_uuids] is called, kept
+/// up-to-date on each mutation.
+///
+/// The index is derived from the items themselves so it never takes part in
+/// comparisons between databases.
+pub(crate) struct UuidIndex<T> {
+    functions: Option<(Extractor<T>, Cloner<T>)>,
+    items: HashMap<Uuid, T>,
+}
+
+impl<T: hash::Hash + Eq> UuidIndex<T> {
+    /// Records an item which has just been added to the database.
+    pub(crate) fn insert(&mut self, item: &T) {
+        if let Some((extractor, cloner)) = &self.functions {
+            self.items.insert(extractor(item), cloner(item));
+        }
+    }
+
+    /// Records an item which has just been removed from the database.
+    pub(crate) fn remove(&mut self, item: &T) {
+        if let Some((extractor, _)) = &self.functions {
+            let uuid = extractor(item);
+
+            if self.items.get(&uuid) == Some(item) {
+                self.items.remove(&uuid);
+            }
+        }
+    }
+}
+
+impl<T> Default for UuidIndex<T> {
+    fn default() -> Self {
+        UuidIndex {
+            functions: None,
+            items: HashMap::new(),
+        }
+    }
+}
+
+impl<T: hash::Hash + Eq> Clone for UuidIndex<T> {
+    fn clone(&self) -> Self {
+        let (extractor, cloner) = match &self.functions {
+            Some(functions) => functions,
+            None => return UuidIndex::default(),
+        };
+
+        UuidIndex {
+            functions: Some((Arc::clone(extractor), Arc::clone(cloner))),
+            items: self
+                .items
+                .iter()
+                .map(|(uuid, item)| (*uuid, cloner(item)))
+                .collect(),
+        }
+    }
+}
+
+impl<T> fmt::Debug for UuidIndex<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "UuidIndex({})", self.items.len())
+    }
+}
+
+impl<T: hash::Hash + Eq> Database<T> {
+    /// Adds the item made by `make` from a freshly generated random UUID,
+    /// returning the UUID. This is useful for items which hold their own
+    /// UUID as a primary key.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tinydb::{Database, Uuid};
+    ///
+    /// fn main() {
+    ///     let mut db: Database<(Uuid, String)> = Database::new("uuid_test", None, false);
+    ///
+    ///     db.index_uuids(|(uuid, _)| *uuid);
+    ///
+    ///     let uuid = db.add_with_uuid(|uuid| (uuid, String::from("Talkie Toaster"))).unwrap();
+    ///
+    ///     assert_eq!(db.get_by_uuid(&uuid).unwrap().unwrap().1, "Talkie Toaster");
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Fails in the same ways as [Database::add_item].
+    pub fn add_with_uuid(
+        &mut self,
+        make: impl FnOnce(Uuid) -> T,
+    ) -> Result<Uuid, error::DatabaseError> {
+        let uuid = Uuid::new_v4();
+
+        self.add_item(make(uuid))?;
+        Ok(uuid)
+    }
+
+    /// Starts indexing items by the UUID given by `extractor`, including
+    /// those already inside of the database, so they can be found with
+    /// [Database::get_by_uuid]. Calling this again replaces the index.
+    ///
+    /// UUIDs are expected to be unique, if several items share one only the
+    /// last added is found. Like other indexes, this isn't saved when dumping
+    /// and keeps its own copy of each item, hence [Clone] being needed.
+    pub fn index_uuids(&mut self, extractor: impl Fn(&T) -> Uuid + Send + Sync + 'static)
+    where
+        T: Clone + 'static,
+    {
+        self.uuids = UuidIndex {
+            functions: Some((Arc::new(extractor), Arc::new(T::clone))),
+            items: HashMap::with_capacity(self.items.len()),
+        };
+
+        for item in self.items.iter() {
+            self.uuids.insert(item);
+        }
+    }
+
+    /// Gets the item with a UUID from the index made by
+    /// [Database::index_uuids], without scanning every item.
+    ///
+    /// # Errors
+    ///
+    /// Returns [error::DatabaseError::IndexNotFound] if
+    /// [Database::index_uuids] hasn't been called.
+    pub fn get_by_uuid(&self, uuid: &Uuid) -> Result<Option<&T>, error::DatabaseError> {
+        if self.uuids.functions.is_none() {
+            return Err(error::DatabaseError::IndexNotFound);
+        }
+
+        Ok(self
+            .uuids
+            .items
+            .get(uuid)
+            .and_then(|copy| self.items.get(copy))
+            .filter(|item| !self.is_expired(item)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests the index following updates and removals
+    #[test]
+    fn uuid_index_updates() -> Result<(), error::DatabaseError> {
+        let mut db = Database::new("Uuid test", None, false);
+        let first = db.add_with_uuid(|uuid| (uuid, 1))?;
+
+        assert_eq!(
+            db.get_by_uuid(&first),
+            Err(error::DatabaseError::IndexNotFound)
+        );
+
+        db.index_uuids(|(uuid, _)| *uuid);
+
+        let second = db.add_with_uuid(|uuid| (uuid, 2))?;
+
+        db.update_item(&(first, 1), (first, 10))?;
+        db.remove_item(&(second, 2))?;
+
+        assert_eq!(db.get_by_uuid(&first)?, Some(&(first, 10)));
+        assert_eq!(db.get_by_uuid(&second)?, None);
+        assert_eq!(db.clone().get_by_uuid(&first)?, Some(&(first, 10)));
+        Ok(())
+    }
+}
@@ -0,0 +1,785 @@
+//! Contains a self-describing [Value] which any serde-compatible item can be
+//! converted to and from, used as the middle ground when converting items to
+//! formats other than bincode.
+
+use serde::de::{self, IntoDeserializer};
+use serde::ser::{self, Serialize};
+use std::fmt;
+
+/// A self-describing value, similar to a JSON value but keeping the
+/// distinction between signed, unsigned and floating point numbers.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Value {
+    /// Unit or an empty option.
+    Null,
+
+    /// A boolean.
+    Bool(bool),
+
+    /// A signed integer.
+    Int(i64),
+
+    /// An unsigned integer.
+    UInt(u64),
+
+    /// A floating point number.
+    Float(f64),
+
+    /// A string or character.
+    String(String),
+
+    /// Raw bytes.
+    Bytes(Vec<u8>),
+
+    /// A sequence, tuple or list.
+    Seq(Vec<Value>),
+
+    /// A struct or map, keeping the order fields were serialized in.
+    Map(Vec<(String, Value)>),
+}
+
+impl Value {
+    /// Converts any serializable item into a value.
+    pub(crate) fn from_item<T: Serialize + ?Sized>(item: &T) -> Result<Self, ValueError> {
+        item.serialize(ValueSerializer)
+    }
+
+    /// Converts this value into any deserializable item, where `lenient`
+    /// allows strings to be parsed into numbers and booleans as well as empty
+    /// strings being used as [Option::None]. This is needed for text-only
+    /// formats such as CSV.
+    pub(crate) fn into_item<T: de::DeserializeOwned>(self, lenient: bool) -> Result<T, ValueError> {
+        T::deserialize(ValueDeserializer {
+            value: self,
+            lenient,
+        })
+    }
+
+    /// Describes the kind of this value for error messages.
+    fn kind(&self) -> &'static str {
+        match self {
+            Value::Null => "null",
+            Value::Bool(_) => "boolean",
+            Value::Int(_) | Value::UInt(_) => "integer",
+            Value::Float(_) => "float",
+            Value::String(_) => "string",
+            Value::Bytes(_) => "bytes",
+            Value::Seq(_) => "sequence",
+            Value::Map(_) => "map",
+        }
+    }
+}
+
+/// An error from converting to or from a [Value].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ValueError(pub(crate) String);
+
+impl fmt::Display for ValueError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for ValueError {}
+
+impl ser::Error for ValueError {
+    fn custom<M: fmt::Display>(msg: M) -> Self {
+        ValueError(msg.to_string())
+    }
+}
+
+impl de::Error for ValueError {
+    fn custom<M: fmt::Display>(msg: M) -> Self {
+        ValueError(msg.to_string())
+    }
+}
+
+/// Serializer which converts items into a [Value].
+struct ValueSerializer;
+
+impl ser::Serializer for ValueSerializer {
+    type Ok = Value;
+    type Error = ValueError;
+    type SerializeSeq = SeqSerializer;
+    type SerializeTuple = SeqSerializer;
+    type SerializeTupleStruct = SeqSerializer;
+    type SerializeTupleVariant = SeqSerializer;
+    type SerializeMap = MapSerializer;
+    type SerializeStruct = MapSerializer;
+    type SerializeStructVariant = MapSerializer;
+
+    fn serialize_bool(self, v: bool) -> Result<Value, ValueError> {
+        Ok(Value::Bool(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Value, ValueError> {
+        Ok(Value::Int(v as i64))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Value, ValueError> {
+        Ok(Value::Int(v as i64))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Value, ValueError> {
+        Ok(Value::Int(v as i64))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Value, ValueError> {
+        Ok(Value::Int(v))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Value, ValueError> {
+        Ok(Value::UInt(v as u64))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Value, ValueError> {
+        Ok(Value::UInt(v as u64))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Value, ValueError> {
+        Ok(Value::UInt(v as u64))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Value, ValueError> {
+        Ok(Value::UInt(v))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Value, ValueError> {
+        Ok(Value::Float(v as f64))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Value, ValueError> {
+        Ok(Value::Float(v))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Value, ValueError> {
+        Ok(Value::String(v.to_string()))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Value, ValueError> {
+        Ok(Value::String(v.to_string()))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Value, ValueError> {
+        Ok(Value::Bytes(v.to_vec()))
+    }
+
+    fn serialize_none(self) -> Result<Value, ValueError> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_some<V: Serialize + ?Sized>(self, value: &V) -> Result<Value, ValueError> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Value, ValueError> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Value, ValueError> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<Value, ValueError> {
+        Ok(Value::String(variant.to_string()))
+    }
+
+    fn serialize_newtype_struct<V: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &V,
+    ) -> Result<Value, ValueError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<V: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        value: &V,
+    ) -> Result<Value, ValueError> {
+        Ok(Value::Map(vec![(
+            variant.to_string(),
+            value.serialize(self)?,
+        )]))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SeqSerializer, ValueError> {
+        Ok(SeqSerializer {
+            variant: None,
+            items: Vec::with_capacity(len.unwrap_or(0)),
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SeqSerializer, ValueError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<SeqSerializer, ValueError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SeqSerializer, ValueError> {
+        Ok(SeqSerializer {
+            variant: Some(variant),
+            items: Vec::with_capacity(len),
+        })
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<MapSerializer, ValueError> {
+        Ok(MapSerializer {
+            variant: None,
+            entries: Vec::with_capacity(len.unwrap_or(0)),
+            next_key: None,
+        })
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<MapSerializer, ValueError> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<MapSerializer, ValueError> {
+        Ok(MapSerializer {
+            variant: Some(variant),
+            entries: Vec::with_capacity(len),
+            next_key: None,
+        })
+    }
+}
+
+/// Wraps a value in a single-entry map if it belongs to an enum variant.
+fn wrap_variant(variant: Option<&'static str>, value: Value) -> Value {
+    match variant {
+        Some(variant) => Value::Map(vec![(variant.to_string(), value)]),
+        None => value,
+    }
+}
+
+/// Serializer for sequences and tuples into [Value::Seq].
+struct SeqSerializer {
+    variant: Option<&'static str>,
+    items: Vec<Value>,
+}
+
+impl ser::SerializeSeq for SeqSerializer {
+    type Ok = Value;
+    type Error = ValueError;
+
+    fn serialize_element<V: Serialize + ?Sized>(&mut self, value: &V) -> Result<(), ValueError> {
+        self.items.push(value.serialize(ValueSerializer)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value, ValueError> {
+        Ok(wrap_variant(self.variant, Value::Seq(self.items)))
+    }
+}
+
+impl ser::SerializeTuple for SeqSerializer {
+    type Ok = Value;
+    type Error = ValueError;
+
+    fn serialize_element<V: Serialize + ?Sized>(&mut self, value: &V) -> Result<(), ValueError> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Value, ValueError> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl ser::SerializeTupleStruct for SeqSerializer {
+    type Ok = Value;
+    type Error = ValueError;
+
+    fn serialize_field<V: Serialize + ?Sized>(&mut self, value: &V) -> Result<(), ValueError> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Value, ValueError> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl ser::SerializeTupleVariant for SeqSerializer {
+    type Ok = Value;
+    type Error = ValueError;
+
+    fn serialize_field<V: Serialize + ?Sized>(&mut self, value: &V) -> Result<(), ValueError> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Value, ValueError> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+/// Serializer for maps and structs into [Value::Map].
+struct MapSerializer {
+    variant: Option<&'static str>,
+    entries: Vec<(String, Value)>,
+    next_key: Option<String>,
+}
+
+impl ser::SerializeMap for MapSerializer {
+    type Ok = Value;
+    type Error = ValueError;
+
+    fn serialize_key<K: Serialize + ?Sized>(&mut self, key: &K) -> Result<(), ValueError> {
+        let key = match key.serialize(ValueSerializer)? {
+            Value::String(key) => key,
+            Value::Int(key) => key.to_string(),
+            Value::UInt(key) => key.to_string(),
+            Value::Bool(key) => key.to_string(),
+            other => {
+                return Err(ValueError(format!(
+                    "map keys must be strings or numbers, found {}",
+                    other.kind()
+                )))
+            }
+        };
+
+        self.next_key = Some(key);
+        Ok(())
+    }
+
+    fn serialize_value<V: Serialize + ?Sized>(&mut self, value: &V) -> Result<(), ValueError> {
+        let key = self
+            .next_key
+            .take()
+            .ok_or_else(|| ValueError(String::from("map value without a key")))?;
+
+        self.entries.push((key, value.serialize(ValueSerializer)?));
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value, ValueError> {
+        Ok(wrap_variant(self.variant, Value::Map(self.entries)))
+    }
+}
+
+impl ser::SerializeStruct for MapSerializer {
+    type Ok = Value;
+    type Error = ValueError;
+
+    fn serialize_field<V: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &V,
+    ) -> Result<(), ValueError> {
+        self.entries
+            .push((key.to_string(), value.serialize(ValueSerializer)?));
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value, ValueError> {
+        ser::SerializeMap::end(self)
+    }
+}
+
+impl ser::SerializeStructVariant for MapSerializer {
+    type Ok = Value;
+    type Error = ValueError;
+
+    fn serialize_field<V: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &V,
+    ) -> Result<(), ValueError> {
+        ser::SerializeStruct::serialize_field(self, key, value)
+    }
+
+    fn end(self) -> Result<Value, ValueError> {
+        ser::SerializeMap::end(self)
+    }
+}
+
+/// Deserializer which converts a [Value] into items, see [Value::into_item].
+struct ValueDeserializer {
+    value: Value,
+    lenient: bool,
+}
+
+impl ValueDeserializer {
+    /// Makes a deserializer for a nested value with the same leniency.
+    fn nested(&self, value: Value) -> ValueDeserializer {
+        ValueDeserializer {
+            value,
+            lenient: self.lenient,
+        }
+    }
+
+    /// Parses a string value if leniency is allowed, otherwise deserializes
+    /// as usual.
+    fn parse_lenient<'de, V: de::Visitor<'de>, P: std::str::FromStr>(
+        self,
+        visitor: V,
+        visit: impl FnOnce(V, P) -> Result<V::Value, ValueError>,
+    ) -> Result<V::Value, ValueError> {
+        match &self.value {
+            Value::String(text) if self.lenient => match text.trim().parse() {
+                Ok(parsed) => visit(visitor, parsed),
+                Err(_) => Err(ValueError(format!("couldn't parse {:?}", text))),
+            },
+            _ => de::Deserializer::deserialize_any(self, visitor),
+        }
+    }
+}
+
+macro_rules! deserialize_lenient {
+    ($($method:ident => $ty:ty, $visit:ident;)*) => {
+        $(
+            fn $method<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, ValueError> {
+                self.parse_lenient(visitor, |visitor, parsed: $ty| visitor.$visit(parsed))
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for ValueDeserializer {
+    type Error = ValueError;
+
+    fn deserialize_any<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, ValueError> {
+        let lenient = self.lenient;
+
+        match self.value {
+            Value::Null => visitor.visit_unit(),
+            Value::Bool(v) => visitor.visit_bool(v),
+            Value::Int(v) => visitor.visit_i64(v),
+            Value::UInt(v) => visitor.visit_u64(v),
+            Value::Float(v) => visitor.visit_f64(v),
+            Value::String(v) => visitor.visit_string(v),
+            Value::Bytes(v) => visitor.visit_byte_buf(v),
+            Value::Seq(items) => visitor.visit_seq(SeqDeserializer {
+                items: items.into_iter(),
+                lenient,
+            }),
+            Value::Map(entries) => visitor.visit_map(MapDeserializer {
+                entries: entries.into_iter(),
+                next_value: None,
+                lenient,
+            }),
+        }
+    }
+
+    deserialize_lenient! {
+        deserialize_bool => bool, visit_bool;
+        deserialize_i8 => i8, visit_i8;
+        deserialize_i16 => i16, visit_i16;
+        deserialize_i32 => i32, visit_i32;
+        deserialize_i64 => i64, visit_i64;
+        deserialize_u8 => u8, visit_u8;
+        deserialize_u16 => u16, visit_u16;
+        deserialize_u32 => u32, visit_u32;
+        deserialize_u64 => u64, visit_u64;
+        deserialize_f32 => f32, visit_f32;
+        deserialize_f64 => f64, visit_f64;
+    }
+
+    fn deserialize_option<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, ValueError> {
+        match &self.value {
+            Value::Null => visitor.visit_none(),
+            Value::String(text) if self.lenient && text.is_empty() => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_unit<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, ValueError> {
+        match &self.value {
+            Value::String(text) if self.lenient && text.is_empty() => visitor.visit_unit(),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: de::Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, ValueError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: de::Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, ValueError> {
+        let lenient = self.lenient;
+
+        match self.value {
+            Value::String(variant) => visitor.visit_enum(variant.into_deserializer()),
+            Value::Map(mut entries) if entries.len() == 1 => {
+                let (variant, content) = entries.remove(0);
+
+                visitor.visit_enum(EnumDeserializer {
+                    variant,
+                    content: ValueDeserializer {
+                        value: content,
+                        lenient,
+                    },
+                })
+            }
+            other => Err(ValueError(format!(
+                "expected an enum variant, found {}",
+                other.kind()
+            ))),
+        }
+    }
+
+    fn deserialize_ignored_any<V: de::Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> Result<V::Value, ValueError> {
+        visitor.visit_unit()
+    }
+
+    serde::forward_to_deserialize_any! {
+        i128 u128 char str string bytes byte_buf unit_struct seq tuple
+        tuple_struct map struct identifier
+    }
+}
+
+/// Gives access to the elements of a [Value::Seq].
+struct SeqDeserializer {
+    items: std::vec::IntoIter<Value>,
+    lenient: bool,
+}
+
+impl<'de> de::SeqAccess<'de> for SeqDeserializer {
+    type Error = ValueError;
+
+    fn next_element_seed<S: de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: S,
+    ) -> Result<Option<S::Value>, ValueError> {
+        match self.items.next() {
+            Some(value) => seed
+                .deserialize(ValueDeserializer {
+                    value,
+                    lenient: self.lenient,
+                })
+                .map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.items.len())
+    }
+}
+
+/// Gives access to the entries of a [Value::Map].
+struct MapDeserializer {
+    entries: std::vec::IntoIter<(String, Value)>,
+    next_value: Option<Value>,
+    lenient: bool,
+}
+
+impl<'de> de::MapAccess<'de> for MapDeserializer {
+    type Error = ValueError;
+
+    fn next_key_seed<K: de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, ValueError> {
+        match self.entries.next() {
+            Some((key, value)) => {
+                self.next_value = Some(value);
+
+                seed.deserialize(ValueDeserializer {
+                    value: Value::String(key),
+                    lenient: true,
+                })
+                .map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<S: de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: S,
+    ) -> Result<S::Value, ValueError> {
+        let value = self
+            .next_value
+            .take()
+            .ok_or_else(|| ValueError(String::from("map key without a value")))?;
+
+        seed.deserialize(ValueDeserializer {
+            value,
+            lenient: self.lenient,
+        })
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.entries.len())
+    }
+}
+
+/// Gives access to an enum variant stored as a single-entry [Value::Map].
+struct EnumDeserializer {
+    variant: String,
+    content: ValueDeserializer,
+}
+
+impl<'de> de::EnumAccess<'de> for EnumDeserializer {
+    type Error = ValueError;
+    type Variant = ValueDeserializer;
+
+    fn variant_seed<S: de::DeserializeSeed<'de>>(
+        self,
+        seed: S,
+    ) -> Result<(S::Value, ValueDeserializer), ValueError> {
+        let variant = seed.deserialize(self.content.nested(Value::String(self.variant)))?;
+
+        Ok((variant, self.content))
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for ValueDeserializer {
+    type Error = ValueError;
+
+    fn unit_variant(self) -> Result<(), ValueError> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<S: de::DeserializeSeed<'de>>(
+        self,
+        seed: S,
+    ) -> Result<S::Value, ValueError> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: de::Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, ValueError> {
+        de::Deserializer::deserialize_any(self, visitor)
+    }
+
+    fn struct_variant<V: de::Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, ValueError> {
+        de::Deserializer::deserialize_any(self, visitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::{Deserialize, Serialize};
+    use std::collections::HashMap;
+
+    /// Kind of shape used to test enum conversion
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    enum Shape {
+        Empty,
+        Circle(f64),
+        Rect { width: u32, height: u32 },
+        Line(i32, i32),
+    }
+
+    /// Item using most serde features
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct Everything {
+        name: String,
+        age: u8,
+        offset: i64,
+        nickname: Option<String>,
+        shapes: Vec<Shape>,
+        tags: HashMap<u32, bool>,
+        pair: (char, ()),
+    }
+
+    /// Tests items surviving a round-trip through [Value]
+    #[test]
+    fn value_round_trip() -> Result<(), ValueError> {
+        let item = Everything {
+            name: String::from("Kryten"),
+            age: 255,
+            offset: -3000,
+            nickname: None,
+            shapes: vec![
+                Shape::Empty,
+                Shape::Circle(1.5),
+                Shape::Rect {
+                    width: 2,
+                    height: 3,
+                },
+                Shape::Line(-1, 1),
+            ],
+            tags: vec![(4, true)].into_iter().collect(),
+            pair: ('x', ()),
+        };
+        let value = Value::from_item(&item)?;
+
+        match &value {
+            Value::Map(entries) => assert_eq!(entries[0].0, "name"),
+            _ => panic!("struct wasn't converted into a map"),
+        }
+
+        assert_eq!(value.into_item::<Everything>(false)?, item);
+        Ok(())
+    }
+
+    /// Tests lenient conversion of text-only values
+    #[test]
+    fn value_lenient() -> Result<(), ValueError> {
+        let value = Value::Map(vec![
+            (String::from("name"), Value::String(String::from("Cat"))),
+            (String::from("age"), Value::String(String::from(" 10 "))),
+            (String::from("alive"), Value::String(String::from("true"))),
+            (String::from("nickname"), Value::String(String::new())),
+        ]);
+
+        #[derive(Debug, PartialEq, Deserialize)]
+        struct Row {
+            name: String,
+            age: u32,
+            alive: bool,
+            nickname: Option<String>,
+        }
+
+        assert!(value.clone().into_item::<Row>(false).is_err());
+        assert_eq!(
+            value.into_item::<Row>(true)?,
+            Row {
+                name: String::from("Cat"),
+                age: 10,
+                alive: true,
+                nickname: None
+            }
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,159 @@
+//! Contains projecting items into lightweight views, see
+//! [crate::Database::project] and [crate::Database::cached_projection].
+
+use crate::Database;
+use std::fmt;
+use std::hash;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Views of every item in a database which are only recomputed after it
+/// changes, created with [Database::cached_projection].
+///
+/// Any added or removed item marks the whole projection as stale, after which
+/// the next call to [Projection::views] projects every item again. Pinning
+/// items or them expiring doesn't count as a change.
+pub struct Projection<T, V> {
+    project: Box<dyn Fn(&T) -> V>,
+    views: Vec<V>,
+    stale: Arc<AtomicBool>,
+}
+
+impl<T: hash::Hash + Eq, V> Projection<T, V> {
+    /// Gets the views of every item, projecting them again first if the
+    /// database has changed since this was last called. `db` should be the
+    /// database this projection was created from.
+    pub fn views(&mut self, db: &Database<T>) -> &[V] {
+        if self.stale.swap(false, Ordering::Relaxed) {
+            self.views = db.project(&self.project);
+        }
+
+        &self.views
+    }
+
+    /// Checks if the database has changed since the views were last
+    /// projected, meaning the next call to [Projection::views] projects
+    /// them again.
+    pub fn is_stale(&self) -> bool {
+        self.stale.load(Ordering::Relaxed)
+    }
+}
+
+impl<T, V> fmt::Debug for Projection<T, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Projection")
+            .field("views", &self.views.len())
+            .field("stale", &self.stale.load(Ordering::Relaxed))
+            .finish()
+    }
+}
+
+impl<T: hash::Hash + Eq> Database<T> {
+    /// Projects every item into a view, such as a small row of the fields a
+    /// UI shows, in the same order as [Database::ordered].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tinydb::Database;
+    ///
+    /// fn main() {
+    ///     let mut db = Database::new("project_test", None, false);
+    ///
+    ///     db.add_item((String::from("Lister"), vec![0u8; 1024])).unwrap();
+    ///
+    ///     let names: Vec<&str> = db.project(|(name, _)| name.as_str());
+    ///
+    ///     assert_eq!(names, vec!["Lister"]);
+    /// }
+    /// ```
+    pub fn project<'a, V>(&'a self, project: impl FnMut(&'a T) -> V) -> Vec<V> {
+        self.ordered()
+            .filter(|item| !self.is_expired(item))
+            .map(project)
+            .collect()
+    }
+
+    /// Creates a [Projection] which keeps the views of every item, only
+    /// projecting them again once this database has changed. This is useful
+    /// for UIs showing rows of heavy items every frame.
+    ///
+    /// The projection is registered like a listener from
+    /// [Database::on_change], so it isn't saved when dumping or carried over
+    /// when cloning and is unregistered once dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tinydb::Database;
+    ///
+    /// fn main() {
+    ///     let mut db = Database::new("cached_projection_test", None, false);
+    ///     let mut rows = db.cached_projection(|item: &(String, u32)| item.0.len());
+    ///
+    ///     db.add_item((String::from("Kryten"), 4)).unwrap();
+    ///
+    ///     assert_eq!(rows.views(&db), &[6]);
+    ///     assert!(!rows.is_stale());
+    /// }
+    /// ```
+    pub fn cached_projection<V>(
+        &mut self,
+        project: impl Fn(&T) -> V + 'static,
+    ) -> Projection<T, V> {
+        let stale = Arc::new(AtomicBool::new(true));
+        let listener_stale = Arc::clone(&stale);
+
+        self.listeners.push(Box::new(move |_| {
+            listener_stale.store(true, Ordering::Relaxed);
+            Arc::strong_count(&listener_stale) > 1
+        }));
+
+        Projection {
+            project: Box::new(project),
+            views: Vec::new(),
+            stale,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error;
+    use std::sync::atomic::AtomicUsize;
+
+    /// Tests views only being projected again after a change
+    #[test]
+    fn projection_recompute() -> Result<(), error::DatabaseError> {
+        static PROJECTED: AtomicUsize = AtomicUsize::new(0);
+
+        let mut db = Database::new("Projection test", None, false);
+        let mut rows = db.cached_projection(|item: &u32| {
+            PROJECTED.fetch_add(1, Ordering::Relaxed);
+            item * 2
+        });
+
+        db.add_item(1)?;
+        db.add_item(2)?;
+
+        let mut views = rows.views(&db).to_vec();
+
+        views.sort();
+        assert_eq!(views, vec![2, 4]);
+        assert_eq!(rows.views(&db).len(), 2);
+        assert_eq!(PROJECTED.load(Ordering::Relaxed), 2);
+
+        db.remove_item(&1)?;
+
+        assert!(rows.is_stale());
+        assert_eq!(rows.views(&db), &[4]);
+        assert_eq!(PROJECTED.load(Ordering::Relaxed), 3);
+
+        drop(rows);
+        db.add_item(3)?;
+
+        assert!(db.listeners.is_empty());
+        Ok(())
+    }
+}
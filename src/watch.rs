@@ -0,0 +1,380 @@
+//! Contains a file watcher which notices when a dump is rewritten by another
+//! process, enabled with the `watch` feature.
+//!
+//! Changes are reported by the operating system through [notify] rather than
+//! by polling. The directory holding the dump is watched instead of the dump
+//! itself, so dumps which are replaced by renaming over them are noticed too.
+
+use crate::{error, Database};
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher as _};
+use serde::de::DeserializeOwned;
+use std::hash;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// Watches a dump from a background thread, running a callback whenever it
+/// changes. The thread is stopped once this is dropped.
+///
+/// Callbacks are only run once the dump has gone `interval` without changing,
+/// so a dump which is still being written is waited for rather than being
+/// reported once per write. Changes to other files in the same directory
+/// don't hold this up. Dumps made by this process are noticed as well.
+#[derive(Debug)]
+pub struct Watcher {
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+    events: Option<RecommendedWatcher>,
+}
+
+impl Watcher {
+    /// Starts watching the dump at `path`, running `on_change` once it has
+    /// been rewritten and then left alone for `interval`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [error::DatabaseError::IOError] if the directory holding the
+    /// dump doesn't exist or can't be watched.
+    pub fn new(
+        path: impl Into<PathBuf>,
+        interval: Duration,
+        mut on_change: impl FnMut() + Send + 'static,
+    ) -> Result<Self, error::DatabaseError> {
+        Watcher::spawn(path.into(), interval, move || {
+            on_change();
+            true
+        })
+    }
+
+    /// Starts the watching thread, where `on_change` returns `false` if the
+    /// change should be retried after another `interval`.
+    fn spawn(
+        path: PathBuf,
+        interval: Duration,
+        mut on_change: impl FnMut() -> bool + Send + 'static,
+    ) -> Result<Self, error::DatabaseError> {
+        let (dir, path) = watched_paths(&path)?;
+        let (sender, receiver) = mpsc::channel();
+        let mut events = notify::recommended_watcher(sender).map_err(io::Error::other)?;
+
+        events
+            .watch(&dir, RecursiveMode::NonRecursive)
+            .map_err(io::Error::other)?;
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = Arc::clone(&stop);
+        let handle = thread::spawn(move || {
+            let mut changed_at: Option<Instant> = None;
+
+            while !thread_stop.load(Ordering::SeqCst) {
+                let wait = match changed_at {
+                    Some(at) => interval.saturating_sub(at.elapsed()),
+                    None => interval,
+                };
+
+                match receiver.recv_timeout(wait) {
+                    Ok(Ok(event)) => {
+                        let changed = matches!(
+                            event.kind,
+                            EventKind::Create(_) | EventKind::Modify(_) | EventKind::Any
+                        );
+
+                        if changed && event.paths.contains(&path) {
+                            changed_at = Some(Instant::now());
+                        }
+                    }
+                    Ok(Err(_)) | Err(RecvTimeoutError::Timeout) => (),
+                    Err(RecvTimeoutError::Disconnected) => break,
+                }
+
+                if changed_at.is_some_and(|at| at.elapsed() >= interval)
+                    && !thread_stop.load(Ordering::SeqCst)
+                {
+                    changed_at = match on_change() {
+                        true => None,
+                        false => Some(Instant::now()),
+                    };
+                }
+            }
+        });
+
+        Ok(Watcher {
+            stop,
+            handle: Some(handle),
+            events: Some(events),
+        })
+    }
+
+    /// Stops watching, waiting for the background thread to finish.
+    pub fn stop(self) {}
+}
+
+impl Drop for Watcher {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        self.events.take();
+
+        if let Some(handle) = self.handle.take() {
+            handle.join().ok();
+        }
+    }
+}
+
+impl<T: hash::Hash + Eq> Database<T> {
+    /// Watches the dump of this database, running `on_change` from a
+    /// background thread every time it's rewritten. See [Watcher] for more
+    /// infomation.
+    ///
+    /// # Errors
+    ///
+    /// Fails in the same ways as [Watcher::new].
+    pub fn watch(
+        &self,
+        interval: Duration,
+        on_change: impl FnMut() + Send + 'static,
+    ) -> Result<Watcher, error::DatabaseError> {
+        Watcher::new(self.smart_path_get(), interval, on_change)
+    }
+
+    /// Watches the dump of a shared database, automatically running
+    /// [Database::reload] on it every time it's rewritten by another process.
+    ///
+    /// Reloading would throw away changes which haven't been dumped yet, so
+    /// if the database is [dirty](Database::is_dirty) it isn't reloaded and
+    /// `on_conflict` is run with it instead. This can then dump, merge or
+    /// reload it as needed. Dumps made by the database itself are skipped, as
+    /// their [Database::last_dumped] matches the one it already has.
+    ///
+    /// If reloading fails, for example because the dump is only half written,
+    /// it will be tried again after another `interval`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tinydb::Database;
+    /// use std::sync::{Arc, Mutex};
+    /// use std::time::{Duration, Instant};
+    ///
+    /// fn main() {
+    ///     let db: Database<String> = Database::new("watched", None, false);
+    ///     let shared = Arc::new(Mutex::new(db));
+    ///
+    ///     let _watcher = Database::reload_on_change(&shared, Duration::from_millis(250), |db| {
+    ///         eprintln!("not reloading {} with unsaved changes", db.label)
+    ///     })
+    ///     .unwrap();
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Fails in the same ways as [Watcher::new].
+    pub fn reload_on_change(
+        shared: &Arc<Mutex<Self>>,
+        interval: Duration,
+        mut on_conflict: impl FnMut(&mut Self) + Send + 'static,
+    ) -> Result<Watcher, error::DatabaseError>
+    where
+        T: DeserializeOwned + Send + Sync + 'static,
+    {
+        let path = shared.lock().unwrap().smart_path_get();
+        let shared = Arc::clone(shared);
+
+        Watcher::spawn(path.clone(), interval, move || match shared.lock() {
+            Ok(db) if is_own_dump(&db, &path) => true,
+            Ok(mut db) if db.is_dirty() => {
+                on_conflict(&mut db);
+                true
+            }
+            Ok(mut db) => db.reload().is_ok(),
+            Err(_) => true,
+        })
+    }
+}
+
+/// Checks if the dump at `path` was last written by `db` itself, by comparing
+/// the time it was dumped at with the one saved inside of the dump.
+fn is_own_dump<T: hash::Hash + Eq>(db: &Database<T>, path: &Path) -> bool {
+    match crate::inspect::inspect(path) {
+        Ok(info) => info.last_dumped.is_some() && info.last_dumped == db.last_dumped(),
+        Err(_) => false,
+    }
+}
+
+/// Gets the directory to watch for a dump at `path` and the path events for
+/// the dump itself are reported with.
+fn watched_paths(path: &Path) -> Result<(PathBuf, PathBuf), error::DatabaseError> {
+    let dir = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let dir = dir.canonicalize()?;
+    let name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "dump path has no file name"))?;
+    let path = dir.join(name);
+
+    Ok((dir, path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Waits up to a second for `check` to pass on a shared database.
+    fn wait_for<T: hash::Hash + Eq>(
+        shared: &Mutex<Database<T>>,
+        check: impl Fn(&Database<T>) -> bool,
+    ) {
+        for _ in 0..100 {
+            if check(&shared.lock().unwrap()) {
+                break;
+            }
+
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    /// Tests a shared database being reloaded after another copy dumps
+    #[test]
+    fn reload_shared() -> Result<(), error::DatabaseError> {
+        let path = PathBuf::from("reload_shared.tinydb");
+        let mut db = Database::new("Watch test", path.clone(), false);
+
+        db.add_item(1)?;
+        db.dump_db()?;
+
+        let shared = Arc::new(Mutex::new(db));
+        let watcher = Database::reload_on_change(&shared, Duration::from_millis(10), |_| {
+            panic!("not dirty")
+        })?;
+        let mut other: Database<i32> = Database::from_unlocked(path.clone())?;
+
+        other.add_item(2)?;
+        other.dump_db()?;
+        wait_for(&shared, |db| db.contains(&2));
+        watcher.stop();
+
+        assert!(shared.lock().unwrap().contains(&2));
+
+        std::fs::remove_file(path)?;
+        Ok(())
+    }
+
+    /// Tests a shared database with unsaved changes not being reloaded
+    #[test]
+    fn dirty_not_reloaded() -> Result<(), error::DatabaseError> {
+        let path = PathBuf::from("dirty_not_reloaded.tinydb");
+        let mut db = Database::new("Watch conflict test", path.clone(), false);
+
+        db.add_item(1)?;
+        db.dump_db()?;
+
+        let mut other: Database<i32> = Database::from_unlocked(path.clone())?;
+
+        db.add_item(5)?;
+
+        let shared = Arc::new(Mutex::new(db));
+        let conflicts = Arc::new(AtomicBool::new(false));
+        let seen = Arc::clone(&conflicts);
+        let watcher = Database::reload_on_change(&shared, Duration::from_millis(10), move |_| {
+            seen.store(true, Ordering::SeqCst)
+        })?;
+
+        other.add_item(2)?;
+        other.dump_db()?;
+
+        for _ in 0..100 {
+            if conflicts.load(Ordering::SeqCst) {
+                break;
+            }
+
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        watcher.stop();
+
+        let db = shared.lock().unwrap();
+
+        assert!(conflicts.load(Ordering::SeqCst));
+        assert!(db.contains(&5));
+        assert!(!db.contains(&2));
+
+        std::fs::remove_file(path)?;
+        Ok(())
+    }
+
+    /// Tests a dump made by the watched database itself not counting as a
+    /// conflict, even though it's dirty again by the time it's noticed
+    #[test]
+    fn own_dump_skipped() -> Result<(), error::DatabaseError> {
+        let path = PathBuf::from("own_dump_skipped.tinydb");
+        let mut db = Database::new("Own dump test", path.clone(), false);
+
+        db.add_item(1)?;
+        db.dump_db()?;
+
+        let shared = Arc::new(Mutex::new(db));
+        let conflicts = Arc::new(AtomicBool::new(false));
+        let seen = Arc::clone(&conflicts);
+        let watcher = Database::reload_on_change(&shared, Duration::from_millis(50), move |_| {
+            seen.store(true, Ordering::SeqCst)
+        })?;
+
+        {
+            let mut db = shared.lock().unwrap();
+
+            db.add_item(2)?;
+            db.dump_db()?;
+            db.add_item(3)?;
+        }
+
+        thread::sleep(Duration::from_millis(300));
+        watcher.stop();
+
+        assert!(!conflicts.load(Ordering::SeqCst));
+        assert!(shared.lock().unwrap().contains(&3));
+
+        std::fs::remove_file(path)?;
+        Ok(())
+    }
+
+    /// Tests changes to other files in the directory not holding back the
+    /// callback for the dump
+    #[test]
+    fn busy_directory() -> Result<(), error::DatabaseError> {
+        let dir = std::env::temp_dir().join(format!("tinydb_busy_{}", std::process::id()));
+
+        std::fs::create_dir_all(&dir)?;
+
+        let path = dir.join("busy.tinydb");
+        let noise = dir.join("noise.txt");
+        let fired = Arc::new(AtomicBool::new(false));
+        let seen = Arc::clone(&fired);
+        let watcher = Watcher::new(path.clone(), Duration::from_millis(50), move || {
+            seen.store(true, Ordering::SeqCst)
+        })?;
+
+        std::fs::write(&path, b"dump")?;
+
+        for _ in 0..100 {
+            if fired.load(Ordering::SeqCst) {
+                break;
+            }
+
+            std::fs::write(&noise, b"noise")?;
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        watcher.stop();
+
+        assert!(fired.load(Ordering::SeqCst));
+
+        std::fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,222 @@
+//! Derive macros for records stored in [tinydb](https://docs.rs/tinydb), which
+//! are re-exported from there when its `derive` feature is enabled so this
+//! crate shouldn't need to be depended on directly.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{parse_macro_input, Data, DeriveInput, Error, Field, Fields};
+
+/// Derives `tinydb::record::Record` for a struct with named fields, alongside
+/// [PartialEq], [Eq] and [Hash] implementations which only look at the field
+/// marked with `#[tinydb(key)]`.
+///
+/// Each field also gets an extractor named `by_<field>` with the same
+/// visibility as the field, which can be passed to `Database::query_item` in
+/// place of a closure.
+///
+/// Fields marked with `#[tinydb(query)]` get a `find_by_<field>` method on
+/// `Database<Self>`, added through a generated `<Name>Queries` trait which
+/// has to be in scope to be used.
+#[proc_macro_derive(TinyRecord, attributes(tinydb))]
+pub fn derive_tiny_record(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    expand(input)
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
+}
+
+/// Expands the derive, returning an error pointing at the problem if the
+/// struct can't be used as a record.
+fn expand(input: DeriveInput) -> Result<TokenStream2, Error> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => fields.named.iter().collect::<Vec<_>>(),
+            _ => {
+                return Err(Error::new_spanned(
+                    &input.ident,
+                    "TinyRecord can only be derived for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(Error::new_spanned(
+                &input.ident,
+                "TinyRecord can only be derived for structs",
+            ))
+        }
+    };
+
+    let (key, queried) = parse_attrs(&input, &fields)?;
+    let name = &input.ident;
+    let vis = &input.vis;
+    let key_ident = &key.ident;
+    let key_ty = &key.ty;
+    let mut generics = input.generics.clone();
+
+    generics
+        .make_where_clause()
+        .predicates
+        .push(syn::parse_quote!(#key_ty: ::core::hash::Hash + ::core::cmp::Eq));
+
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let queries = if queried.is_empty() {
+        quote!()
+    } else {
+        let trait_name = format_ident!("{}Queries", name);
+        let trait_doc = format!("Typed queries for a database of [{}].", name);
+        let mut query_generics = generics.clone();
+
+        query_generics
+            .make_where_clause()
+            .predicates
+            .push(syn::parse_quote!(
+                #name #ty_generics: ::core::hash::Hash
+                    + ::core::cmp::Eq
+                    + ::serde::Serialize
+                    + ::serde::de::DeserializeOwned
+            ));
+
+        let (_, _, query_where_clause) = query_generics.split_for_impl();
+        let signatures = queried.iter().map(|field| {
+            let ident = field.ident.as_ref().unwrap();
+            let ty = &field.ty;
+            let method = format_ident!("find_by_{}", ident);
+            let doc = format!(
+                "Finds all items where the `{}` field equals `value`.",
+                ident
+            );
+
+            quote! {
+                #[doc = #doc]
+                fn #method<Q>(&self, value: Q) -> ::tinydb::__private::Vec<&#name #ty_generics>
+                where
+                    #ty: ::core::cmp::PartialEq<Q>;
+            }
+        });
+        let methods = queried.iter().map(|field| {
+            let ident = field.ident.as_ref().unwrap();
+            let ty = &field.ty;
+            let method = format_ident!("find_by_{}", ident);
+            let extractor = format_ident!("by_{}", ident);
+
+            quote! {
+                fn #method<Q>(&self, value: Q) -> ::tinydb::__private::Vec<&#name #ty_generics>
+                where
+                    #ty: ::core::cmp::PartialEq<Q>,
+                {
+                    self.find_by(#name::#extractor, value)
+                }
+            }
+        });
+
+        quote! {
+            #[doc = #trait_doc]
+            #vis trait #trait_name #impl_generics #where_clause {
+                #(#signatures)*
+            }
+
+            impl #impl_generics #trait_name #ty_generics
+                for ::tinydb::Database<#name #ty_generics> #query_where_clause
+            {
+                #(#methods)*
+            }
+        }
+    };
+    let field_names = fields
+        .iter()
+        .map(|field| field.ident.as_ref().unwrap().to_string());
+    let extractors = fields.iter().map(|field| {
+        let vis = &field.vis;
+        let ident = field.ident.as_ref().unwrap();
+        let ty = &field.ty;
+        let extractor = format_ident!("by_{}", ident);
+        let doc = format!("Extracts the `{}` field, for use in queries.", ident);
+
+        quote! {
+            #[doc = #doc]
+            #[allow(dead_code)]
+            #vis fn #extractor(item: &Self) -> &#ty {
+                &item.#ident
+            }
+        }
+    });
+
+    Ok(quote! {
+        impl #impl_generics ::core::cmp::PartialEq for #name #ty_generics #where_clause {
+            fn eq(&self, other: &Self) -> bool {
+                self.#key_ident == other.#key_ident
+            }
+        }
+
+        impl #impl_generics ::core::cmp::Eq for #name #ty_generics #where_clause {}
+
+        impl #impl_generics ::core::hash::Hash for #name #ty_generics #where_clause {
+            fn hash<H: ::core::hash::Hasher>(&self, state: &mut H) {
+                ::core::hash::Hash::hash(&self.#key_ident, state)
+            }
+        }
+
+        impl #impl_generics ::tinydb::record::Record for #name #ty_generics #where_clause {
+            type Key = #key_ty;
+
+            const FIELDS: &'static [&'static str] = &[#(#field_names),*];
+
+            fn key(&self) -> &Self::Key {
+                &self.#key_ident
+            }
+        }
+
+        impl #impl_generics #name #ty_generics #where_clause {
+            #(#extractors)*
+        }
+
+        #queries
+    })
+}
+
+/// Parses the `#[tinydb(..)]` attributes of each field, returning the single
+/// field marked as the key and all fields marked to be queried.
+fn parse_attrs<'a>(
+    input: &DeriveInput,
+    fields: &[&'a Field],
+) -> Result<(&'a Field, Vec<&'a Field>), Error> {
+    let mut key = None;
+    let mut queried = Vec::new();
+
+    for field in fields {
+        for attr in field
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("tinydb"))
+        {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("query") {
+                    queried.push(*field);
+                    return Ok(());
+                }
+
+                if !meta.path.is_ident("key") {
+                    return Err(meta.error("unknown tinydb attribute, expected `key` or `query`"));
+                }
+
+                if key.is_some() {
+                    return Err(meta.error("only one field can be marked with #[tinydb(key)]"));
+                }
+
+                key = Some(*field);
+                Ok(())
+            })?;
+        }
+    }
+
+    let key = key.ok_or_else(|| {
+        Error::new_spanned(
+            &input.ident,
+            "TinyRecord needs one field marked with #[tinydb(key)]",
+        )
+    })?;
+
+    Ok((key, queried))
+}